@@ -0,0 +1,80 @@
+//! Plugin configuration, read from the validator's Geyser config file.
+//!
+//! ```json
+//! {
+//!   "libpath": "/path/to/libyukti_geyser.so",
+//!   "format": "json",
+//!   "publish_startup": true,
+//!   "kafka": {
+//!     "topic": "yukti.accounts",
+//!     "producer": { "bootstrap.servers": "localhost:9092" }
+//!   }
+//! }
+//! ```
+//!
+//! `program_id` defaults to the opinion-trading program id. Without a `kafka`
+//! section updates are written to the validator log, which is handy locally.
+
+use std::collections::HashMap;
+
+use agave_geyser_plugin_interface::geyser_plugin_interface::{GeyserPluginError, Result};
+use anchor_lang::prelude::Pubkey;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    /// Base58 id of the program whose accounts are published
+    #[serde(default = "default_program_id")]
+    pub program_id: String,
+
+    #[serde(default)]
+    pub format: Format,
+
+    /// Publish the accounts replayed from the snapshot at validator startup
+    #[serde(default = "default_publish_startup")]
+    pub publish_startup: bool,
+
+    pub kafka: Option<KafkaConfig>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    #[default]
+    Json,
+    Protobuf,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KafkaConfig {
+    pub topic: String,
+
+    /// librdkafka producer properties, e.g. `bootstrap.servers`
+    #[serde(default)]
+    pub producer: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|err| GeyserPluginError::ConfigFileReadError {
+            msg: err.to_string(),
+        })
+    }
+
+    pub fn program_id(&self) -> Result<Pubkey> {
+        self.program_id
+            .parse()
+            .map_err(|_| GeyserPluginError::ConfigFileReadError {
+                msg: format!("invalid program_id {}", self.program_id),
+            })
+    }
+}
+
+fn default_program_id() -> String {
+    opinion_trading::ID.to_string()
+}
+
+fn default_publish_startup() -> bool {
+    true
+}
@@ -0,0 +1,901 @@
+//! Decode raw opinion-trading account data into normalized records.
+//!
+//! Accounts are matched on their Anchor discriminator and decoded with the
+//! program crate's own types, so a layout change in the program is a compile
+//! error or a failing test here rather than silently wrong output.
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use opinion_trading::{
+    AccessGate, BidLedger, BidStatus, DeadlineExtension, EpochMetric, EventStatus, FeeTier,
+    IncentiveCurve, LedgerBid, OddsHistory, OddsSnapshot, PayoutMode, PayoutTable, PollCategory,
+    PollKind, PollStatus, PriceComparison, ResolutionSource, ResolverCouncil, TreasuryWithdrawal,
+    LEDGER_CAPACITY, ODDS_HISTORY_CAPACITY,
+};
+
+use crate::record::{self, Account};
+
+/// Decode account data owned by the program. `None` for unknown
+/// discriminators and for data that does not parse (e.g. mid-initialization).
+pub fn decode_account(data: &[u8]) -> Option<Account> {
+    let discriminator = data.get(..8)?;
+
+    if discriminator == opinion_trading::Poll::DISCRIMINATOR {
+        let poll = opinion_trading::Poll::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::Poll(Box::new(record::Poll {
+            authority: poll.authority.to_string(),
+            poll_id: poll.poll_id,
+            title_hash: hex(&poll.title_hash),
+            total_pool: poll.total_pool,
+            end_timestamp: poll.end_timestamp,
+            status: poll_status(poll.status).to_string(),
+            next_bid_index: poll.next_bid_index,
+            accrued_fees: poll.accrued_fees,
+            adapter: poll.adapter.map(|adapter| adapter.to_string()),
+            mint: poll.mint.map(|mint| mint.to_string()),
+            outcomes: poll
+                .outcomes
+                .into_iter()
+                .map(|outcome| record::Outcome {
+                    text_hash: hex(&outcome.text_hash),
+                    stake: outcome.stake,
+                    odds: outcome.odds,
+                    total_potential_liability: outcome.total_potential_liability,
+                })
+                .collect(),
+            winning_outcome: poll.winner.map(u32::from),
+            payout_mode: payout_mode(poll.payout_mode).to_string(),
+            resolution: poll.resolution.map(resolution),
+            dispute_period: poll.dispute_period,
+            settlement_proposed_at: poll.settlement_proposed_at,
+            arbiter: poll.arbiter.map(|arbiter| arbiter.to_string()),
+            challenged: poll.challenged,
+            fee_bps: poll.fee_bps.into(),
+            min_bet: poll.min_bet,
+            max_bet: poll.max_bet,
+            finalized_at: poll.finalized_at,
+            creation_bond: poll.creation_bond,
+            creator_fee_bps: poll.creator_fee_bps.into(),
+            creator_fees: poll.creator_fees,
+            start_timestamp: poll.start_timestamp,
+            initial_end_timestamp: poll.initial_end_timestamp,
+            deadline_extension: poll.deadline_extension.map(deadline_extension),
+            extension_count: poll.extension_count.into(),
+            virtual_liquidity: poll.virtual_liquidity,
+            lp_liquidity: poll.lp_liquidity,
+            lp_fee_bps: poll.lp_fee_bps.into(),
+            lp_fees: poll.lp_fees,
+            outcome_tokens: poll.outcome_tokens,
+            claim_period: poll.claim_period,
+            pending_authority: poll.pending_authority.map(|authority| authority.to_string()),
+            max_total_pool: poll.max_total_pool,
+            max_stake_per_option: poll.max_stake_per_option,
+            max_stake_per_user: poll.max_stake_per_user,
+            settlement_commitment: poll.settlement_commitment.map(|hash| hex(&hash)),
+            committed_at: poll.committed_at,
+            referral_fee_bps: poll.referral_fee_bps.into(),
+            fee_tiers: fee_tiers(poll.fee_tiers),
+            kind: poll_kind(&poll.kind).to_string(),
+            scalar_bounds: scalar_bounds(poll.kind),
+            crank_bounty: poll.crank_bounty,
+            event_seq: poll.event_seq,
+            gate: poll.gate.map(access_gate),
+            council: poll.council.map(resolver_council),
+            result_signer: poll.result_signer.map(|signer| signer.to_string()),
+            community_votes: poll.community_votes,
+            total_fees: poll.total_fees,
+            odds_history: poll.odds_history,
+            version: poll.version.into(),
+            incentives: poll.incentives.map(incentive_curve),
+            resolution_deadline: poll.resolution_deadline,
+            swept_fees: poll.swept_fees,
+            fee_share_bps: poll.fee_share_bps.into(),
+            cancel_window: poll.cancel_window,
+            cancel_fee_bps: poll.cancel_fee_bps.into(),
+            max_price_impact_bps: poll.max_price_impact_bps.into(),
+            bid_counts: poll.bid_counts,
+            claimed_count: poll.claimed_count,
+            min_seconds_between_bids: poll.min_seconds_between_bids,
+            registry_page: poll.registry_page,
+            damping_window: poll.damping_window,
+            damping_bps: poll.damping_bps.into(),
+            vig_bps: poll.vig_bps.into(),
+            treasury_swept: poll.treasury_swept,
+        })))
+    } else if discriminator == opinion_trading::Bid::DISCRIMINATOR {
+        let bid = opinion_trading::Bid::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::Bid(record::Bid {
+            bettor: bid.bettor.to_string(),
+            poll: bid.poll.to_string(),
+            amount: bid.amount,
+            odds_at_purchase: bid.odds_at_purchase,
+            potential_win: bid.potential_win,
+            status: bid_status(bid.status).to_string(),
+            timestamp: bid.timestamp,
+            index: bid.index,
+            outcome: bid.option.into(),
+            referrer: bid.referrer.map(|referrer| referrer.to_string()),
+            unclaimed: bid.unclaimed,
+            version: bid.version.into(),
+            claimed_amount: bid.claimed_amount,
+            fee_paid: bid.fee_paid,
+            claimed_at: bid.claimed_at,
+            fee_adjust_bps: bid.fee_adjust_bps.into(),
+            memo: bid.memo.map(|memo| hex(&memo)),
+            placed_at: bid.placed_at,
+            credit: bid.credit,
+            usd_notional_at_purchase: bid.usd_notional_at_purchase,
+        }))
+    } else if discriminator == opinion_trading::MarketAdapter::DISCRIMINATOR {
+        let adapter = opinion_trading::MarketAdapter::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::MarketAdapter(record::MarketAdapter {
+            program: adapter.program.to_string(),
+            approved: adapter.approved,
+            event_seq: adapter.event_seq,
+        }))
+    } else if discriminator == opinion_trading::Challenge::DISCRIMINATOR {
+        let challenge = opinion_trading::Challenge::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::Challenge(record::Challenge {
+            poll: challenge.poll.to_string(),
+            challenger: challenge.challenger.to_string(),
+            proposed_winner: challenge.proposed_winner.into(),
+            bond: challenge.bond,
+            created_at: challenge.created_at,
+        }))
+    } else if discriminator == opinion_trading::Config::DISCRIMINATOR {
+        let config = opinion_trading::Config::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::Config(record::Config {
+            admin: config.admin.to_string(),
+            treasury: config.treasury.to_string(),
+            fee_bps: config.fee_bps.into(),
+            min_bet: config.min_bet,
+            max_bet: config.max_bet,
+            creation_bond: config.creation_bond,
+            creator_fee_bps: config.creator_fee_bps.into(),
+            lp_fee_bps: config.lp_fee_bps.into(),
+            claim_period: config.claim_period,
+            pending_admin: config.pending_admin.map(|admin| admin.to_string()),
+            paused: config.paused,
+            referral_fee_bps: config.referral_fee_bps.into(),
+            fee_tiers: fee_tiers(config.fee_tiers),
+            event_seq: config.event_seq,
+            usd_feed_id: hex(&config.usd_feed_id),
+            max_bid_usd: config.max_bid_usd,
+            epoch_id: config.epoch_id,
+            epoch_start: config.epoch_start,
+            epoch_end: config.epoch_end,
+        }))
+    } else if discriminator == opinion_trading::LpPosition::DISCRIMINATOR {
+        let position = opinion_trading::LpPosition::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::LpPosition(record::LpPosition {
+            poll: position.poll.to_string(),
+            provider: position.provider.to_string(),
+            amount: position.amount,
+            fees_claimed: position.fees_claimed,
+            withdrawn: position.withdrawn,
+        }))
+    } else if discriminator == opinion_trading::UserProfile::DISCRIMINATOR {
+        let profile = opinion_trading::UserProfile::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::UserProfile(record::UserProfile {
+            user: profile.user.to_string(),
+            bids_placed: profile.bids_placed,
+            volume: profile.volume,
+            wins: profile.wins,
+            losses: profile.losses,
+            claims: profile.claims,
+            net_pnl: profile.net_pnl,
+            claim_delegate: profile.claim_delegate.map(|delegate| delegate.to_string()),
+            event_seq: profile.event_seq,
+            epoch_id: profile.epoch_id,
+            epoch_volume: profile.epoch_volume,
+            epoch_pnl: profile.epoch_pnl,
+        }))
+    } else if discriminator == opinion_trading::UserStake::DISCRIMINATOR {
+        let user_stake = opinion_trading::UserStake::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::UserStake(record::UserStake {
+            poll: user_stake.poll.to_string(),
+            user: user_stake.user.to_string(),
+            stake: user_stake.stake,
+        }))
+    } else if discriminator == opinion_trading::UserPollPosition::DISCRIMINATOR {
+        let position = opinion_trading::UserPollPosition::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::UserPollPosition(record::UserPollPosition {
+            poll: position.poll.to_string(),
+            user: position.user.to_string(),
+            stakes: position.stakes.to_vec(),
+            bid_count: position.bid_count,
+            bid_indices: position.bid_indices,
+            last_bid_ts: position.last_bid_ts,
+        }))
+    } else if discriminator == opinion_trading::Parlay::DISCRIMINATOR {
+        let parlay = opinion_trading::Parlay::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::Parlay(record::Parlay {
+            bettor: parlay.bettor.to_string(),
+            parlay_id: parlay.parlay_id,
+            amount: parlay.amount,
+            potential_win: parlay.potential_win,
+            fee_bps: parlay.fee_bps.into(),
+            timestamp: parlay.timestamp,
+            legs: parlay
+                .legs
+                .into_iter()
+                .map(|leg| record::ParlayLeg {
+                    poll: leg.poll.to_string(),
+                    outcome: leg.option.into(),
+                    odds: leg.odds,
+                })
+                .collect(),
+        }))
+    } else if discriminator == opinion_trading::ParlayPool::DISCRIMINATOR {
+        let pool = opinion_trading::ParlayPool::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::ParlayPool(record::ParlayPool {
+            liability: pool.liability,
+            event_seq: pool.event_seq,
+        }))
+    } else if discriminator == opinion_trading::FeeVault::DISCRIMINATOR {
+        let fee_vault = opinion_trading::FeeVault::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::FeeVault(record::FeeVault {
+            total_swept: fee_vault.total_swept,
+            event_seq: fee_vault.event_seq,
+            fee_share_reserve: fee_vault.fee_share_reserve,
+        }))
+    } else if discriminator == opinion_trading::Attestation::DISCRIMINATOR {
+        let attestation = opinion_trading::Attestation::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::Attestation(record::Attestation {
+            attestor: attestation.attestor.to_string(),
+            user: attestation.user.to_string(),
+            issued_at: attestation.issued_at,
+            expires_at: attestation.expires_at,
+            event_seq: attestation.event_seq,
+        }))
+    } else if discriminator == opinion_trading::TreasuryStats::DISCRIMINATOR {
+        let stats = opinion_trading::TreasuryStats::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::TreasuryStats(record::TreasuryStats {
+            total_fees: stats.total_fees,
+            total_insurance: stats.total_insurance,
+            total_withdrawn: stats.total_withdrawn,
+            sweep_count: stats.sweep_count,
+            withdraw_delay: stats.withdraw_delay,
+            pending: stats.pending.map(treasury_withdrawal),
+            recent_sweeps: stats
+                .recent_sweeps
+                .into_iter()
+                .map(|sweep| record::FeeSweep {
+                    poll: sweep.poll.to_string(),
+                    amount: sweep.amount,
+                    timestamp: sweep.timestamp,
+                })
+                .collect(),
+            withdrawals: stats.withdrawals.into_iter().map(treasury_withdrawal).collect(),
+            event_seq: stats.event_seq,
+            total_remainders: stats.total_remainders,
+            pending_treasury: stats.pending_treasury.map(|change| record::TreasuryChange {
+                treasury: change.treasury.to_string(),
+                unlocks_at: change.unlocks_at,
+            }),
+        }))
+    } else if discriminator == opinion_trading::MarketEvent::DISCRIMINATOR {
+        let event = opinion_trading::MarketEvent::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::MarketEvent(record::MarketEvent {
+            authority: event.authority.to_string(),
+            event_id: event.event_id,
+            status: event_status(event.status).to_string(),
+            polls: event.polls.iter().map(|poll| poll.to_string()).collect(),
+            total_volume: event.total_volume,
+            event_seq: event.event_seq,
+        }))
+    } else if discriminator == opinion_trading::FeeShare::DISCRIMINATOR {
+        let share = opinion_trading::FeeShare::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::FeeShare(record::FeeShare {
+            poll: share.poll.to_string(),
+            party: share.party.to_string(),
+            share_bps: share.share_bps.into(),
+            swept_at_grant: share.swept_at_grant,
+            claimed: share.claimed,
+        }))
+    } else if discriminator == opinion_trading::UserBalance::DISCRIMINATOR {
+        let balance = opinion_trading::UserBalance::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::UserBalance(record::UserBalance {
+            user: balance.user.to_string(),
+            balance: balance.balance,
+            event_seq: balance.event_seq,
+        }))
+    } else if discriminator == opinion_trading::PollRegistry::DISCRIMINATOR {
+        let registry = opinion_trading::PollRegistry::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::PollRegistry(record::PollRegistry {
+            page: registry.page,
+            polls: registry.polls.iter().map(|poll| poll.to_string()).collect(),
+        }))
+    } else if discriminator == opinion_trading::Epoch::DISCRIMINATOR {
+        let epoch = opinion_trading::Epoch::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::Epoch(record::Epoch {
+            epoch_id: epoch.epoch_id,
+            start_timestamp: epoch.start_timestamp,
+            end_timestamp: epoch.end_timestamp,
+            metric: epoch_metric(epoch.metric).to_string(),
+            reward_pool: epoch.reward_pool,
+            weights: epoch.weights.into_iter().map(u32::from).collect(),
+            leaderboard: epoch
+                .leaderboard
+                .into_iter()
+                .map(|entry| record::EpochEntry {
+                    user: entry.user.to_string(),
+                    score: entry.score,
+                    reward: entry.reward,
+                    claimed: entry.claimed,
+                })
+                .collect(),
+            finalized: epoch.finalized,
+            paid: epoch.paid,
+            event_seq: epoch.event_seq,
+        }))
+    } else if discriminator == opinion_trading::BetCredit::DISCRIMINATOR {
+        let credit = opinion_trading::BetCredit::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::BetCredit(record::BetCredit {
+            user: credit.user.to_string(),
+            balance: credit.balance,
+            granted: credit.granted,
+            event_seq: credit.event_seq,
+        }))
+    } else if discriminator == opinion_trading::PromoVault::DISCRIMINATOR {
+        let vault = opinion_trading::PromoVault::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::PromoVault(record::PromoVault {
+            outstanding: vault.outstanding,
+            total_granted: vault.total_granted,
+            total_returned: vault.total_returned,
+            event_seq: vault.event_seq,
+        }))
+    } else if discriminator == opinion_trading::ResolutionNote::DISCRIMINATOR {
+        let note = opinion_trading::ResolutionNote::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::ResolutionNote(record::ResolutionNote {
+            poll: note.poll.to_string(),
+            author: note.author.to_string(),
+            uri: note.uri,
+            evidence_hash: hex(&note.evidence_hash),
+            created_at: note.created_at,
+        }))
+    } else if discriminator == opinion_trading::InsuranceFund::DISCRIMINATOR {
+        let fund = opinion_trading::InsuranceFund::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::InsuranceFund(record::InsuranceFund {
+            fee_bps: fund.fee_bps.into(),
+            total_covered: fund.total_covered,
+            event_seq: fund.event_seq,
+        }))
+    } else if discriminator == opinion_trading::CommunityVote::DISCRIMINATOR {
+        let vote = opinion_trading::CommunityVote::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::CommunityVote(record::CommunityVote {
+            poll: vote.poll.to_string(),
+            voter: vote.voter.to_string(),
+            outcome: vote.option.into(),
+            weight: vote.weight,
+        }))
+    } else if discriminator == opinion_trading::PendingOrder::DISCRIMINATOR {
+        let order = opinion_trading::PendingOrder::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::PendingOrder(record::PendingOrder {
+            poll: order.poll.to_string(),
+            bettor: order.bettor.to_string(),
+            order_id: order.order_id,
+            amount: order.amount,
+            outcome: order.option.into(),
+            max_odds: order.max_odds,
+            created_at: order.created_at,
+        }))
+    } else if discriminator == opinion_trading::PollTemplate::DISCRIMINATOR {
+        let template = opinion_trading::PollTemplate::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::PollTemplate(Box::new(record::PollTemplate {
+            authority: template.authority.to_string(),
+            id_prefix: template.id_prefix,
+            title: template.title,
+            outcomes: template.outcomes,
+            mint: template.mint.map(|mint| mint.to_string()),
+            payout_mode: payout_mode(template.payout_mode).to_string(),
+            resolution: template.resolution.map(resolution),
+            dispute_period: template.dispute_period,
+            arbiter: template.arbiter.map(|arbiter| arbiter.to_string()),
+            deadline_extension: template.deadline_extension.map(deadline_extension),
+            virtual_liquidity: template.virtual_liquidity,
+            kind: poll_kind(&template.kind).to_string(),
+            scalar_bounds: scalar_bounds(template.kind),
+            first_start: template.first_start,
+            period: template.period,
+            duration: template.duration,
+            event_seq: template.event_seq,
+            min_bet: template.limits.min_bet,
+            max_bet: template.limits.max_bet,
+            fee_bps: template.limits.fee_bps.map(u32::from),
+        })))
+    } else if discriminator == opinion_trading::PollMetadata::DISCRIMINATOR {
+        let metadata = opinion_trading::PollMetadata::try_deserialize(&mut &data[..]).ok()?;
+        Some(Account::PollMetadata(record::PollMetadata {
+            poll: metadata.poll.to_string(),
+            category: poll_category(metadata.category).to_string(),
+            uri: metadata.uri,
+            tags: metadata.tags,
+            resolution_criteria: metadata.resolution_criteria,
+        }))
+    } else if discriminator == BidLedger::DISCRIMINATOR {
+        decode_bid_ledger(data.get(8..BidLedger::LEN)?).map(Account::BidLedger)
+    } else if discriminator == OddsHistory::DISCRIMINATOR {
+        decode_odds_history(data.get(8..OddsHistory::LEN)?).map(Account::OddsHistory)
+    } else if discriminator == PayoutTable::DISCRIMINATOR {
+        let header: PayoutTable =
+            bytemuck::try_pod_read_unaligned(data.get(8..PayoutTable::HEADER_LEN)?).ok()?;
+        Some(Account::PayoutTable(record::PayoutTable {
+            poll: header.poll.to_string(),
+            bid_count: header.bid_count,
+            computed_count: header.computed_count,
+        }))
+    } else {
+        None
+    }
+}
+
+/// Read the ledger header and its filled records without copying the whole
+/// (unaligned) account onto the stack
+fn decode_bid_ledger(body: &[u8]) -> Option<record::BidLedger> {
+    const RECORDS_OFFSET: usize = 40; // poll (32) + count (4) + padding (4)
+
+    let poll = anchor_lang::prelude::Pubkey::try_from(body.get(..32)?).ok()?;
+    let count = u32::from_le_bytes(body.get(32..36)?.try_into().ok()?);
+    let filled = (count as usize).min(LEDGER_CAPACITY);
+
+    let records = body
+        .get(RECORDS_OFFSET..)?
+        .chunks_exact(std::mem::size_of::<LedgerBid>())
+        .take(filled)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let entry: LedgerBid = bytemuck::try_pod_read_unaligned(chunk).ok()?;
+            Some(record::LedgerBid {
+                index: index as u32,
+                bettor: entry.bettor.to_string(),
+                amount: entry.amount,
+                potential_win: entry.potential_win,
+                odds_at_purchase: entry.odds_at_purchase,
+                timestamp: entry.timestamp,
+                status: ledger_status(entry.status)?.to_string(),
+                outcome: entry.option.into(),
+                fee_adjust_bps: entry.fee_adjust_bps.into(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(record::BidLedger {
+        poll: poll.to_string(),
+        count,
+        records,
+    })
+}
+
+/// Read the odds history header and its snapshots, oldest first
+fn decode_odds_history(body: &[u8]) -> Option<record::OddsHistory> {
+    const SNAPSHOTS_OFFSET: usize = 48; // poll (32) + interval (8) + head (4) + count (4)
+
+    let poll = anchor_lang::prelude::Pubkey::try_from(body.get(..32)?).ok()?;
+    let interval = i64::from_le_bytes(body.get(32..40)?.try_into().ok()?);
+    let head = u32::from_le_bytes(body.get(40..44)?.try_into().ok()?) as usize;
+    let count = u32::from_le_bytes(body.get(44..48)?.try_into().ok()?) as usize;
+    let count = count.min(ODDS_HISTORY_CAPACITY);
+    // A full ring starts at its head; a partial one at slot 0
+    let oldest = match count {
+        ODDS_HISTORY_CAPACITY => head % ODDS_HISTORY_CAPACITY,
+        _ => 0,
+    };
+
+    let slots: Vec<&[u8]> = body
+        .get(SNAPSHOTS_OFFSET..)?
+        .chunks_exact(std::mem::size_of::<OddsSnapshot>())
+        .collect();
+    let snapshots = (0..count)
+        .map(|i| {
+            let chunk = slots.get((oldest + i) % ODDS_HISTORY_CAPACITY)?;
+            let snapshot: OddsSnapshot = bytemuck::try_pod_read_unaligned(chunk).ok()?;
+            Some(record::OddsSnapshot {
+                timestamp: snapshot.timestamp,
+                total_pool: snapshot.total_pool,
+                odds: snapshot.odds.iter().map(|&odds| odds.into()).collect(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(record::OddsHistory {
+        poll: poll.to_string(),
+        interval,
+        snapshots,
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn poll_status(status: PollStatus) -> &'static str {
+    match status {
+        PollStatus::Active => "active",
+        PollStatus::Settled => "settled",
+        PollStatus::Cancelled => "cancelled",
+        PollStatus::PendingSettlement => "pending_settlement",
+        PollStatus::Closed => "closed",
+        PollStatus::Voided => "voided",
+        PollStatus::Scheduled => "scheduled",
+        PollStatus::Expired => "expired",
+        PollStatus::Suspended => "suspended",
+        PollStatus::Finalized => "finalized",
+    }
+}
+
+fn event_status(status: EventStatus) -> &'static str {
+    match status {
+        EventStatus::Open => "open",
+        EventStatus::Settled => "settled",
+    }
+}
+
+fn epoch_metric(metric: EpochMetric) -> &'static str {
+    match metric {
+        EpochMetric::Volume => "volume",
+        EpochMetric::Pnl => "pnl",
+    }
+}
+
+fn poll_kind(kind: &PollKind) -> &'static str {
+    match kind {
+        PollKind::Categorical => "categorical",
+        PollKind::Scalar { .. } => "scalar",
+        PollKind::Jackpot => "jackpot",
+    }
+}
+
+fn scalar_bounds(kind: PollKind) -> Vec<i64> {
+    match kind {
+        PollKind::Scalar { bounds } => bounds,
+        PollKind::Categorical | PollKind::Jackpot => Vec::new(),
+    }
+}
+
+fn resolution(source: ResolutionSource) -> record::Resolution {
+    record::Resolution {
+        feed_id: hex(&source.feed_id),
+        threshold: source.threshold,
+        exponent: source.exponent,
+        comparison: price_comparison(source.comparison).to_string(),
+    }
+}
+
+fn deadline_extension(extension: DeadlineExtension) -> record::DeadlineExtension {
+    record::DeadlineExtension {
+        window: extension.window,
+        duration: extension.duration,
+        max_extensions: extension.max_extensions.into(),
+    }
+}
+
+fn incentive_curve(curve: IncentiveCurve) -> record::IncentiveCurve {
+    record::IncentiveCurve {
+        early_window: curve.early_window,
+        early_bonus_bps: curve.early_bonus_bps.into(),
+        late_window: curve.late_window,
+        late_fee_bps: curve.late_fee_bps.into(),
+    }
+}
+
+fn access_gate(gate: AccessGate) -> record::AccessGate {
+    match gate {
+        AccessGate::Allowlist { root } => record::AccessGate {
+            kind: "allowlist".to_string(),
+            root: Some(hex(&root)),
+            mint: None,
+            min_amount: 0,
+            attestor: None,
+        },
+        AccessGate::TokenHolding { mint, min_amount } => record::AccessGate {
+            kind: "token_holding".to_string(),
+            root: None,
+            mint: Some(mint.to_string()),
+            min_amount,
+            attestor: None,
+        },
+        AccessGate::Attestation { attestor } => record::AccessGate {
+            kind: "attestation".to_string(),
+            root: None,
+            mint: None,
+            min_amount: 0,
+            attestor: Some(attestor.to_string()),
+        },
+    }
+}
+
+fn resolver_council(council: ResolverCouncil) -> record::ResolverCouncil {
+    record::ResolverCouncil {
+        votes: council
+            .resolvers
+            .iter()
+            .zip(&council.votes)
+            .filter_map(|(resolver, vote)| {
+                vote.map(|outcome| record::ResolverVote {
+                    resolver: resolver.to_string(),
+                    outcome: outcome.into(),
+                })
+            })
+            .collect(),
+        resolvers: council.resolvers.iter().map(ToString::to_string).collect(),
+        threshold: council.threshold.into(),
+    }
+}
+
+fn poll_category(category: PollCategory) -> &'static str {
+    match category {
+        PollCategory::Other => "other",
+        PollCategory::Sports => "sports",
+        PollCategory::Politics => "politics",
+        PollCategory::Crypto => "crypto",
+        PollCategory::Finance => "finance",
+        PollCategory::Entertainment => "entertainment",
+        PollCategory::Science => "science",
+        PollCategory::Weather => "weather",
+    }
+}
+
+fn payout_mode(mode: PayoutMode) -> &'static str {
+    match mode {
+        PayoutMode::FixedOdds => "fixed_odds",
+        PayoutMode::PariMutuel => "pari_mutuel",
+    }
+}
+
+fn fee_tiers(tiers: Vec<FeeTier>) -> Vec<record::FeeTier> {
+    tiers
+        .into_iter()
+        .map(|tier| record::FeeTier {
+            min_volume: tier.min_volume,
+            fee_bps: tier.fee_bps.into(),
+        })
+        .collect()
+}
+
+fn treasury_withdrawal(withdrawal: TreasuryWithdrawal) -> record::TreasuryWithdrawal {
+    record::TreasuryWithdrawal {
+        amount: withdrawal.amount,
+        destination: withdrawal.destination.to_string(),
+        timestamp: withdrawal.timestamp,
+    }
+}
+
+fn price_comparison(comparison: PriceComparison) -> &'static str {
+    match comparison {
+        PriceComparison::Above => "above",
+        PriceComparison::AtOrAbove => "at_or_above",
+        PriceComparison::Below => "below",
+        PriceComparison::AtOrBelow => "at_or_below",
+    }
+}
+
+fn bid_status(status: BidStatus) -> &'static str {
+    match status {
+        BidStatus::Active => "active",
+        BidStatus::Won => "won",
+        BidStatus::Lost => "lost",
+        BidStatus::Refunded => "refunded",
+        BidStatus::Exited => "exited",
+        BidStatus::PartiallyClaimed => "partially_claimed",
+        BidStatus::Cancelled => "cancelled",
+    }
+}
+
+/// `LedgerBid::status` stores the `BidStatus` discriminant
+fn ledger_status(raw: u8) -> Option<&'static str> {
+    [
+        BidStatus::Active,
+        BidStatus::Won,
+        BidStatus::Lost,
+        BidStatus::Refunded,
+        BidStatus::Exited,
+    ]
+    .into_iter()
+    .find(|status| *status as u8 == raw)
+    .map(bid_status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::AccountSerialize;
+    use opinion_trading::logic::text_hash;
+
+    #[test]
+    fn decodes_bid_by_discriminator() {
+        let bid = opinion_trading::Bid {
+            bettor: Pubkey::new_unique(),
+            poll: Pubkey::new_unique(),
+            amount: 1_000_000,
+            option: 3,
+            odds_at_purchase: 4_000,
+            potential_win: 2_500_000,
+            status: BidStatus::PartiallyClaimed,
+            timestamp: 1_700_000_000,
+            index: 7,
+            bump: 254,
+            referrer: Some(Pubkey::new_unique()),
+            unclaimed: 1_500_000,
+            version: opinion_trading::BID_VERSION,
+            claimed_amount: 500_000,
+            fee_paid: 10_000,
+            claimed_at: 1_700_000_500,
+            fee_adjust_bps: -150,
+            memo: Some([7; 32]),
+            placed_at: 1_700_000_001,
+            credit: 250_000,
+            usd_notional_at_purchase: 152_500,
+        };
+        let mut data = Vec::with_capacity(opinion_trading::Bid::LEN);
+        bid.try_serialize(&mut data).unwrap();
+
+        let Some(Account::Bid(decoded)) = decode_account(&data) else {
+            panic!("expected a bid");
+        };
+        assert_eq!(decoded.bettor, bid.bettor.to_string());
+        assert_eq!(decoded.outcome, 3);
+        assert_eq!(decoded.status, "partially_claimed");
+        assert_eq!(decoded.unclaimed, 1_500_000);
+        assert_eq!(decoded.claimed_amount, 500_000);
+        assert_eq!(decoded.fee_paid, 10_000);
+        assert_eq!(decoded.fee_adjust_bps, -150);
+        assert_eq!(decoded.memo.as_deref(), Some("07".repeat(32).as_str()));
+        assert_eq!(decoded.index, 7);
+        assert_eq!(decoded.credit, 250_000);
+        assert_eq!(decoded.usd_notional_at_purchase, 152_500);
+        assert_eq!(decoded.version, u32::from(opinion_trading::BID_VERSION));
+        assert_eq!(decoded.referrer, bid.referrer.map(|referrer| referrer.to_string()));
+    }
+
+    #[test]
+    fn decodes_poll_outcomes() {
+        let outcome = |text: &str, stake, odds| opinion_trading::OutcomeSlot {
+            text_hash: text_hash(text),
+            stake,
+            odds,
+            total_potential_liability: 0,
+        };
+        let poll = opinion_trading::Poll {
+            authority: Pubkey::new_unique(),
+            poll_id: "cup".to_string(),
+            title_hash: text_hash("Who wins the cup?"),
+            outcomes: vec![outcome("A", 0, 500), outcome("B", 3, 7500), outcome("C", 1, 2500)],
+            total_pool: 4,
+            end_timestamp: 1_700_000_000,
+            status: PollStatus::Settled,
+            winner: Some(1),
+            vault_bump: 255,
+            bump: 254,
+            next_bid_index: 2,
+            accrued_fees: 0,
+            adapter: None,
+            mint: None,
+            payout_mode: PayoutMode::PariMutuel,
+            resolution: None,
+            dispute_period: 0,
+            settlement_proposed_at: 0,
+            arbiter: None,
+            challenged: false,
+            fee_bps: 200,
+            min_bet: 1,
+            max_bet: 10,
+            finalized_at: 1_700_000_100,
+            creation_bond: 0,
+            creator_fee_bps: 0,
+            creator_fees: 0,
+            start_timestamp: 1_699_000_000,
+            initial_end_timestamp: 1_700_000_000,
+            deadline_extension: None,
+            extension_count: 0,
+            virtual_liquidity: 0,
+            lp_liquidity: 0,
+            lp_fee_bps: 0,
+            lp_fees: 0,
+            outcome_tokens: false,
+            claim_period: 0,
+            pending_authority: None,
+            max_total_pool: None,
+            max_stake_per_option: None,
+            max_stake_per_user: None,
+            settlement_commitment: None,
+            committed_at: 0,
+            referral_fee_bps: 0,
+            fee_tiers: Vec::new(),
+            kind: opinion_trading::PollKind::Categorical,
+            crank_bounty: 0,
+            event_seq: 0,
+            gate: None,
+            council: None,
+            result_signer: None,
+            community_votes: Vec::new(),
+            total_fees: 0,
+            odds_history: false,
+            version: opinion_trading::POLL_VERSION,
+            incentives: None,
+            resolution_deadline: 0,
+            swept_fees: 0,
+            fee_share_bps: 0,
+            cancel_window: 0,
+            cancel_fee_bps: 0,
+            max_price_impact_bps: 0,
+            bid_counts: vec![0; 2],
+            claimed_count: 0,
+            min_seconds_between_bids: 0,
+            registry_page: Some(2),
+            damping_window: 0,
+            damping_bps: 0,
+            vig_bps: 0,
+            treasury_swept: 0,
+        };
+        let mut data = Vec::with_capacity(opinion_trading::Poll::LEN);
+        poll.try_serialize(&mut data).unwrap();
+
+        let Some(Account::Poll(decoded)) = decode_account(&data) else {
+            panic!("expected a poll");
+        };
+        assert_eq!(decoded.outcomes.len(), 3);
+        assert_eq!(decoded.outcomes[1].text_hash, hex(&text_hash("B")));
+        assert_eq!(decoded.outcomes[1].odds, 7500);
+        assert_eq!(decoded.winning_outcome, Some(1));
+        assert_eq!(decoded.registry_page, Some(2));
+        assert_eq!(decoded.status, "settled");
+        assert_eq!(decoded.payout_mode, "pari_mutuel");
+    }
+
+    #[test]
+    fn decodes_filled_ledger_records_only() {
+        let poll = Pubkey::new_unique();
+        let bettor = Pubkey::new_unique();
+        let mut data = vec![0u8; BidLedger::LEN];
+        data[..8].copy_from_slice(BidLedger::DISCRIMINATOR);
+        data[8..40].copy_from_slice(poll.as_ref());
+        data[40..44].copy_from_slice(&1u32.to_le_bytes());
+        // First record: bettor, amount, then option/status after four u64 fields
+        data[48..80].copy_from_slice(bettor.as_ref());
+        data[80..88].copy_from_slice(&5u64.to_le_bytes());
+        data[112] = 2;
+        data[113] = BidStatus::Refunded as u8;
+
+        let Some(Account::BidLedger(ledger)) = decode_account(&data) else {
+            panic!("expected a ledger");
+        };
+        assert_eq!(ledger.poll, poll.to_string());
+        assert_eq!(ledger.records.len(), 1);
+        assert_eq!(ledger.records[0].bettor, bettor.to_string());
+        assert_eq!(ledger.records[0].amount, 5);
+        assert_eq!(ledger.records[0].status, "refunded");
+        assert_eq!(ledger.records[0].outcome, 2);
+    }
+
+    #[test]
+    fn decodes_full_odds_history_oldest_first() {
+        let poll = Pubkey::new_unique();
+        let mut data = vec![0u8; OddsHistory::LEN];
+        data[..8].copy_from_slice(OddsHistory::DISCRIMINATOR);
+        data[8..40].copy_from_slice(poll.as_ref());
+        data[40..48].copy_from_slice(&60i64.to_le_bytes());
+        data[48..52].copy_from_slice(&1u32.to_le_bytes());
+        data[52..56].copy_from_slice(&(ODDS_HISTORY_CAPACITY as u32).to_le_bytes());
+        // Slot 0 was overwritten last; slot 1 is now the oldest
+        data[56..64].copy_from_slice(&999i64.to_le_bytes());
+        data[88..96].copy_from_slice(&1i64.to_le_bytes());
+        data[104..106].copy_from_slice(&7_000u16.to_le_bytes());
+
+        let Some(Account::OddsHistory(history)) = decode_account(&data) else {
+            panic!("expected an odds history");
+        };
+        assert_eq!(history.poll, poll.to_string());
+        assert_eq!(history.interval, 60);
+        assert_eq!(history.snapshots.len(), ODDS_HISTORY_CAPACITY);
+        assert_eq!(history.snapshots[0].timestamp, 1);
+        assert_eq!(history.snapshots[0].odds[0], 7_000);
+        assert_eq!(history.snapshots[ODDS_HISTORY_CAPACITY - 1].timestamp, 999);
+    }
+
+    #[test]
+    fn ignores_unknown_and_short_data() {
+        assert!(decode_account(&[0u8; 4]).is_none());
+        assert!(decode_account(&[0u8; 64]).is_none());
+    }
+}
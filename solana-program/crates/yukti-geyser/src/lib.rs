@@ -0,0 +1,157 @@
+//! Geyser plugin for the Yukti opinion-trading program.
+//!
+//! Filters validator account updates down to accounts owned by the program,
+//! decodes them with the program crate's own types (see `decode`), and
+//! publishes normalized records as JSON or Protobuf (see `record` and
+//! `proto/yukti_accounts.proto`) to Kafka, keyed by account pubkey.
+//!
+//! Load it by pointing the validator's `--geyser-plugin-config` at a file
+//! described in `config`.
+
+pub mod config;
+pub mod decode;
+pub mod record;
+pub mod sink;
+
+use agave_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, Result,
+};
+use anchor_lang::prelude::Pubkey;
+use prost::Message;
+
+use config::{Config, Format};
+use record::AccountUpdate;
+use sink::Sink;
+
+#[derive(Default)]
+pub struct YuktiGeyserPlugin {
+    state: Option<State>,
+}
+
+struct State {
+    program_id: Pubkey,
+    format: Format,
+    publish_startup: bool,
+    sink: Sink,
+}
+
+impl std::fmt::Debug for YuktiGeyserPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("YuktiGeyserPlugin")
+            .field("program_id", &self.state.as_ref().map(|state| state.program_id))
+            .finish()
+    }
+}
+
+impl GeyserPlugin for YuktiGeyserPlugin {
+    fn name(&self) -> &'static str {
+        "yukti-geyser"
+    }
+
+    fn on_load(&mut self, config_file: &str, _is_reload: bool) -> Result<()> {
+        let config = Config::load(config_file)?;
+        let state = State {
+            program_id: config.program_id()?,
+            format: config.format,
+            publish_startup: config.publish_startup,
+            sink: Sink::from_config(&config)?,
+        };
+        log::info!(
+            "yukti-geyser loaded for program {} ({:?})",
+            state.program_id,
+            state.format
+        );
+        self.state = Some(state);
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        if let Some(state) = self.state.take() {
+            state.sink.flush();
+        }
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfoVersions,
+        slot: u64,
+        is_startup: bool,
+    ) -> Result<()> {
+        let Some(state) = &self.state else {
+            return Ok(());
+        };
+        if is_startup && !state.publish_startup {
+            return Ok(());
+        }
+
+        let (pubkey, owner, lamports, data, write_version) = match account {
+            ReplicaAccountInfoVersions::V0_0_1(info) => {
+                (info.pubkey, info.owner, info.lamports, info.data, info.write_version)
+            }
+            ReplicaAccountInfoVersions::V0_0_2(info) => {
+                (info.pubkey, info.owner, info.lamports, info.data, info.write_version)
+            }
+            ReplicaAccountInfoVersions::V0_0_3(info) => {
+                (info.pubkey, info.owner, info.lamports, info.data, info.write_version)
+            }
+        };
+        if owner != state.program_id.as_ref() {
+            return Ok(());
+        }
+        let Some(decoded) = decode::decode_account(data) else {
+            return Ok(());
+        };
+
+        let pubkey_str = Pubkey::try_from(pubkey)
+            .map_err(|_| GeyserPluginError::AccountsUpdateError {
+                msg: "account pubkey is not 32 bytes".to_string(),
+            })?
+            .to_string();
+        let update = AccountUpdate {
+            pubkey: pubkey_str,
+            slot,
+            write_version,
+            lamports,
+            is_startup,
+            account: Some(decoded),
+        };
+        let payload = match state.format {
+            Format::Json => serde_json::to_vec(&update).map_err(|err| {
+                GeyserPluginError::AccountsUpdateError {
+                    msg: err.to_string(),
+                }
+            })?,
+            Format::Protobuf => update.encode_to_vec(),
+        };
+
+        state.sink.publish(pubkey, &payload)
+    }
+
+    fn notify_end_of_startup(&self) -> Result<()> {
+        if let Some(state) = &self.state {
+            state.sink.flush();
+        }
+        Ok(())
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        true
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        false
+    }
+}
+
+/// Entry point the validator resolves when loading the plugin library.
+///
+/// # Safety
+///
+/// Called once by the validator's plugin manager, which takes ownership of
+/// the returned box.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    let plugin: Box<dyn GeyserPlugin> = Box::<YuktiGeyserPlugin>::default();
+    Box::into_raw(plugin)
+}
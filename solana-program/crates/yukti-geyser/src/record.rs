@@ -0,0 +1,896 @@
+//! Normalized account records, serialized as JSON or Protobuf.
+//!
+//! The Protobuf encoding matches `proto/yukti_accounts.proto`; keep the two in
+//! sync and never reuse a tag. Pubkeys are base58 strings and enums are
+//! snake_case strings in both encodings.
+
+use serde::Serialize;
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct AccountUpdate {
+    #[prost(string, tag = "1")]
+    pub pubkey: String,
+    #[prost(uint64, tag = "2")]
+    pub slot: u64,
+    #[prost(uint64, tag = "3")]
+    pub write_version: u64,
+    #[prost(uint64, tag = "4")]
+    pub lamports: u64,
+    #[prost(bool, tag = "5")]
+    pub is_startup: bool,
+    #[prost(oneof = "Account", tags = "10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33")]
+    #[serde(flatten)]
+    pub account: Option<Account>,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Oneof)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum Account {
+    #[prost(message, boxed, tag = "10")]
+    Poll(Box<Poll>),
+    #[prost(message, tag = "11")]
+    Bid(Bid),
+    #[prost(message, tag = "12")]
+    MarketAdapter(MarketAdapter),
+    #[prost(message, tag = "13")]
+    BidLedger(BidLedger),
+    #[prost(message, tag = "14")]
+    PayoutTable(PayoutTable),
+    #[prost(message, tag = "15")]
+    Challenge(Challenge),
+    #[prost(message, tag = "16")]
+    Config(Config),
+    #[prost(message, tag = "17")]
+    LpPosition(LpPosition),
+    #[prost(message, tag = "18")]
+    UserProfile(UserProfile),
+    #[prost(message, tag = "19")]
+    UserStake(UserStake),
+    #[prost(message, tag = "20")]
+    Parlay(Parlay),
+    #[prost(message, tag = "21")]
+    ParlayPool(ParlayPool),
+    #[prost(message, tag = "22")]
+    PollMetadata(PollMetadata),
+    #[prost(message, boxed, tag = "23")]
+    PollTemplate(Box<PollTemplate>),
+    #[prost(message, tag = "24")]
+    InsuranceFund(InsuranceFund),
+    #[prost(message, tag = "25")]
+    CommunityVote(CommunityVote),
+    #[prost(message, tag = "26")]
+    FeeVault(FeeVault),
+    #[prost(message, tag = "27")]
+    OddsHistory(OddsHistory),
+    #[prost(message, tag = "28")]
+    PendingOrder(PendingOrder),
+    #[prost(message, tag = "29")]
+    UserPollPosition(UserPollPosition),
+    #[prost(message, tag = "30")]
+    FeeShare(FeeShare),
+    #[prost(message, tag = "31")]
+    MarketEvent(MarketEvent),
+    #[prost(message, tag = "32")]
+    UserBalance(UserBalance),
+    #[prost(message, tag = "33")]
+    ResolutionNote(ResolutionNote),
+    #[prost(message, tag = "34")]
+    BetCredit(BetCredit),
+    #[prost(message, tag = "35")]
+    PromoVault(PromoVault),
+    #[prost(message, tag = "36")]
+    PollRegistry(PollRegistry),
+    #[prost(message, tag = "37")]
+    Epoch(Epoch),
+    #[prost(message, tag = "38")]
+    TreasuryStats(TreasuryStats),
+    #[prost(message, tag = "39")]
+    Attestation(Attestation),
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Poll {
+    #[prost(string, tag = "1")]
+    pub authority: String,
+    #[prost(string, tag = "2")]
+    pub poll_id: String,
+    #[prost(uint64, tag = "8")]
+    pub total_pool: u64,
+    #[prost(int64, tag = "11")]
+    pub end_timestamp: i64,
+    #[prost(string, tag = "12")]
+    pub status: String,
+    #[prost(uint64, tag = "14")]
+    pub next_bid_index: u64,
+    #[prost(uint64, tag = "15")]
+    pub accrued_fees: u64,
+    #[prost(string, optional, tag = "16")]
+    pub adapter: Option<String>,
+    #[prost(string, optional, tag = "17")]
+    pub mint: Option<String>,
+    #[prost(message, repeated, tag = "18")]
+    pub outcomes: Vec<Outcome>,
+    #[prost(uint32, optional, tag = "19")]
+    pub winning_outcome: Option<u32>,
+    #[prost(string, tag = "20")]
+    pub payout_mode: String,
+    #[prost(message, optional, tag = "21")]
+    pub resolution: Option<Resolution>,
+    #[prost(int64, tag = "22")]
+    pub dispute_period: i64,
+    #[prost(int64, tag = "23")]
+    pub settlement_proposed_at: i64,
+    #[prost(string, optional, tag = "24")]
+    pub arbiter: Option<String>,
+    #[prost(bool, tag = "25")]
+    pub challenged: bool,
+    #[prost(uint32, tag = "26")]
+    pub fee_bps: u32,
+    #[prost(uint64, tag = "27")]
+    pub min_bet: u64,
+    #[prost(uint64, tag = "28")]
+    pub max_bet: u64,
+    #[prost(int64, tag = "29")]
+    pub finalized_at: i64,
+    #[prost(uint64, tag = "30")]
+    pub creation_bond: u64,
+    #[prost(uint32, tag = "31")]
+    pub creator_fee_bps: u32,
+    #[prost(uint64, tag = "32")]
+    pub creator_fees: u64,
+    #[prost(int64, tag = "33")]
+    pub start_timestamp: i64,
+    #[prost(int64, tag = "34")]
+    pub initial_end_timestamp: i64,
+    #[prost(message, optional, tag = "35")]
+    pub deadline_extension: Option<DeadlineExtension>,
+    #[prost(uint32, tag = "36")]
+    pub extension_count: u32,
+    #[prost(uint64, tag = "37")]
+    pub virtual_liquidity: u64,
+    #[prost(uint64, tag = "38")]
+    pub lp_liquidity: u64,
+    #[prost(uint32, tag = "39")]
+    pub lp_fee_bps: u32,
+    #[prost(uint64, tag = "40")]
+    pub lp_fees: u64,
+    #[prost(bool, tag = "41")]
+    pub outcome_tokens: bool,
+    #[prost(int64, tag = "42")]
+    pub claim_period: i64,
+    #[prost(string, optional, tag = "43")]
+    pub pending_authority: Option<String>,
+    #[prost(uint64, optional, tag = "44")]
+    pub max_total_pool: Option<u64>,
+    #[prost(uint64, optional, tag = "45")]
+    pub max_stake_per_option: Option<u64>,
+    #[prost(uint64, optional, tag = "46")]
+    pub max_stake_per_user: Option<u64>,
+    /// Hex-encoded sha256(winning_option || salt)
+    #[prost(string, optional, tag = "47")]
+    pub settlement_commitment: Option<String>,
+    #[prost(int64, tag = "48")]
+    pub committed_at: i64,
+    #[prost(uint32, tag = "49")]
+    pub referral_fee_bps: u32,
+    #[prost(message, repeated, tag = "50")]
+    pub fee_tiers: Vec<FeeTier>,
+    #[prost(string, tag = "51")]
+    pub kind: String,
+    #[prost(int64, repeated, tag = "52")]
+    pub scalar_bounds: Vec<i64>,
+    #[prost(uint64, tag = "53")]
+    pub crank_bounty: u64,
+    #[prost(string, tag = "54")]
+    pub title_hash: String,
+    #[prost(uint64, tag = "55")]
+    pub event_seq: u64,
+    #[prost(message, optional, tag = "56")]
+    pub gate: Option<AccessGate>,
+    #[prost(message, optional, tag = "57")]
+    pub council: Option<ResolverCouncil>,
+    #[prost(string, optional, tag = "58")]
+    pub result_signer: Option<String>,
+    #[prost(uint64, repeated, tag = "59")]
+    pub community_votes: Vec<u64>,
+    #[prost(uint64, tag = "60")]
+    pub total_fees: u64,
+    #[prost(bool, tag = "61")]
+    pub odds_history: bool,
+    #[prost(uint32, tag = "62")]
+    pub version: u32,
+    #[prost(message, optional, tag = "63")]
+    pub incentives: Option<IncentiveCurve>,
+    #[prost(int64, tag = "64")]
+    pub resolution_deadline: i64,
+    #[prost(uint64, tag = "65")]
+    pub swept_fees: u64,
+    #[prost(uint32, tag = "66")]
+    pub fee_share_bps: u32,
+    #[prost(int64, tag = "67")]
+    pub cancel_window: i64,
+    #[prost(uint32, tag = "68")]
+    pub cancel_fee_bps: u32,
+    #[prost(uint32, tag = "69")]
+    pub max_price_impact_bps: u32,
+    #[prost(uint32, repeated, tag = "70")]
+    pub bid_counts: Vec<u32>,
+    #[prost(uint32, tag = "71")]
+    pub claimed_count: u32,
+    #[prost(int64, tag = "72")]
+    pub min_seconds_between_bids: i64,
+    #[prost(uint32, optional, tag = "73")]
+    pub registry_page: Option<u32>,
+    #[prost(int64, tag = "74")]
+    pub damping_window: i64,
+    #[prost(uint32, tag = "75")]
+    pub damping_bps: u32,
+    #[prost(uint32, tag = "76")]
+    pub vig_bps: u32,
+    #[prost(uint64, tag = "77")]
+    pub treasury_swept: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct ResolverCouncil {
+    #[prost(string, repeated, tag = "1")]
+    pub resolvers: Vec<String>,
+    #[prost(uint32, tag = "2")]
+    pub threshold: u32,
+    /// Votes cast so far, one per resolver who has voted
+    #[prost(message, repeated, tag = "3")]
+    pub votes: Vec<ResolverVote>,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct ResolverVote {
+    #[prost(string, tag = "1")]
+    pub resolver: String,
+    #[prost(uint32, tag = "2")]
+    pub outcome: u32,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct AccessGate {
+    #[prost(string, tag = "1")]
+    pub kind: String,
+    /// Hex-encoded Merkle root of an allowlist
+    #[prost(string, optional, tag = "2")]
+    pub root: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub mint: Option<String>,
+    #[prost(uint64, tag = "4")]
+    pub min_amount: u64,
+    #[prost(string, optional, tag = "5")]
+    pub attestor: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct FeeTier {
+    #[prost(uint64, tag = "1")]
+    pub min_volume: u64,
+    #[prost(uint32, tag = "2")]
+    pub fee_bps: u32,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Resolution {
+    /// Hex-encoded Pyth feed id
+    #[prost(string, tag = "1")]
+    pub feed_id: String,
+    #[prost(int64, tag = "2")]
+    pub threshold: i64,
+    #[prost(int32, tag = "3")]
+    pub exponent: i32,
+    #[prost(string, tag = "4")]
+    pub comparison: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct DeadlineExtension {
+    #[prost(int64, tag = "1")]
+    pub window: i64,
+    #[prost(int64, tag = "2")]
+    pub duration: i64,
+    #[prost(uint32, tag = "3")]
+    pub max_extensions: u32,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct IncentiveCurve {
+    #[prost(int64, tag = "1")]
+    pub early_window: i64,
+    #[prost(uint32, tag = "2")]
+    pub early_bonus_bps: u32,
+    #[prost(int64, tag = "3")]
+    pub late_window: i64,
+    #[prost(uint32, tag = "4")]
+    pub late_fee_bps: u32,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Outcome {
+    #[prost(uint64, tag = "2")]
+    pub stake: u64,
+    #[prost(uint64, tag = "3")]
+    pub odds: u64,
+    #[prost(uint64, tag = "4")]
+    pub total_potential_liability: u64,
+    #[prost(string, tag = "5")]
+    pub text_hash: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Bid {
+    #[prost(string, tag = "1")]
+    pub bettor: String,
+    #[prost(string, tag = "2")]
+    pub poll: String,
+    #[prost(uint64, tag = "3")]
+    pub amount: u64,
+    #[prost(uint64, tag = "5")]
+    pub odds_at_purchase: u64,
+    #[prost(uint64, tag = "6")]
+    pub potential_win: u64,
+    #[prost(string, tag = "7")]
+    pub status: String,
+    #[prost(int64, tag = "8")]
+    pub timestamp: i64,
+    #[prost(uint64, tag = "9")]
+    pub index: u64,
+    #[prost(uint32, tag = "10")]
+    pub outcome: u32,
+    #[prost(string, optional, tag = "11")]
+    pub referrer: Option<String>,
+    #[prost(uint64, tag = "12")]
+    pub unclaimed: u64,
+    #[prost(uint32, tag = "13")]
+    pub version: u32,
+    #[prost(uint64, tag = "14")]
+    pub claimed_amount: u64,
+    #[prost(uint64, tag = "15")]
+    pub fee_paid: u64,
+    #[prost(int64, tag = "16")]
+    pub claimed_at: i64,
+    #[prost(sint32, tag = "17")]
+    pub fee_adjust_bps: i32,
+    #[prost(string, optional, tag = "18")]
+    pub memo: Option<String>,
+    #[prost(int64, tag = "19")]
+    pub placed_at: i64,
+    #[prost(uint64, tag = "20")]
+    pub credit: u64,
+    #[prost(uint64, tag = "21")]
+    pub usd_notional_at_purchase: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct MarketAdapter {
+    #[prost(string, tag = "1")]
+    pub program: String,
+    #[prost(bool, tag = "2")]
+    pub approved: bool,
+    #[prost(uint64, tag = "3")]
+    pub event_seq: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct LedgerBid {
+    #[prost(uint32, tag = "1")]
+    pub index: u32,
+    #[prost(string, tag = "2")]
+    pub bettor: String,
+    #[prost(uint64, tag = "3")]
+    pub amount: u64,
+    #[prost(uint64, tag = "4")]
+    pub potential_win: u64,
+    #[prost(uint64, tag = "5")]
+    pub odds_at_purchase: u64,
+    #[prost(int64, tag = "6")]
+    pub timestamp: i64,
+    #[prost(string, tag = "8")]
+    pub status: String,
+    #[prost(uint32, tag = "9")]
+    pub outcome: u32,
+    #[prost(sint32, tag = "10")]
+    pub fee_adjust_bps: i32,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct BidLedger {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(uint32, tag = "2")]
+    pub count: u32,
+    #[prost(message, repeated, tag = "3")]
+    pub records: Vec<LedgerBid>,
+}
+
+/// Snapshots oldest first
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct OddsHistory {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(int64, tag = "2")]
+    pub interval: i64,
+    #[prost(message, repeated, tag = "3")]
+    pub snapshots: Vec<OddsSnapshot>,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct OddsSnapshot {
+    #[prost(int64, tag = "1")]
+    pub timestamp: i64,
+    #[prost(uint64, tag = "2")]
+    pub total_pool: u64,
+    #[prost(uint32, repeated, tag = "3")]
+    pub odds: Vec<u32>,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct PayoutTable {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(uint64, tag = "2")]
+    pub bid_count: u64,
+    #[prost(uint64, tag = "3")]
+    pub computed_count: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Challenge {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(string, tag = "2")]
+    pub challenger: String,
+    #[prost(uint32, tag = "3")]
+    pub proposed_winner: u32,
+    #[prost(uint64, tag = "4")]
+    pub bond: u64,
+    #[prost(int64, tag = "5")]
+    pub created_at: i64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Config {
+    #[prost(string, tag = "1")]
+    pub admin: String,
+    #[prost(string, tag = "2")]
+    pub treasury: String,
+    #[prost(uint32, tag = "3")]
+    pub fee_bps: u32,
+    #[prost(uint64, tag = "4")]
+    pub min_bet: u64,
+    #[prost(uint64, tag = "5")]
+    pub max_bet: u64,
+    #[prost(uint64, tag = "6")]
+    pub creation_bond: u64,
+    #[prost(uint32, tag = "7")]
+    pub creator_fee_bps: u32,
+    #[prost(uint32, tag = "8")]
+    pub lp_fee_bps: u32,
+    #[prost(int64, tag = "9")]
+    pub claim_period: i64,
+    #[prost(string, optional, tag = "10")]
+    pub pending_admin: Option<String>,
+    #[prost(bool, tag = "11")]
+    pub paused: bool,
+    #[prost(uint32, tag = "12")]
+    pub referral_fee_bps: u32,
+    #[prost(message, repeated, tag = "13")]
+    pub fee_tiers: Vec<FeeTier>,
+    #[prost(uint64, tag = "14")]
+    pub event_seq: u64,
+    #[prost(string, tag = "15")]
+    pub usd_feed_id: String,
+    #[prost(uint64, tag = "16")]
+    pub max_bid_usd: u64,
+    #[prost(uint32, tag = "17")]
+    pub epoch_id: u32,
+    #[prost(int64, tag = "18")]
+    pub epoch_start: i64,
+    #[prost(int64, tag = "19")]
+    pub epoch_end: i64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct LpPosition {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(string, tag = "2")]
+    pub provider: String,
+    #[prost(uint64, tag = "3")]
+    pub amount: u64,
+    #[prost(uint64, tag = "4")]
+    pub fees_claimed: u64,
+    #[prost(bool, tag = "5")]
+    pub withdrawn: bool,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct UserProfile {
+    #[prost(string, tag = "1")]
+    pub user: String,
+    #[prost(uint64, tag = "2")]
+    pub bids_placed: u64,
+    #[prost(uint64, tag = "3")]
+    pub volume: u64,
+    #[prost(uint64, tag = "4")]
+    pub wins: u64,
+    #[prost(uint64, tag = "5")]
+    pub losses: u64,
+    #[prost(uint64, tag = "6")]
+    pub claims: u64,
+    #[prost(int64, tag = "7")]
+    pub net_pnl: i64,
+    #[prost(string, optional, tag = "8")]
+    pub claim_delegate: Option<String>,
+    #[prost(uint64, tag = "9")]
+    pub event_seq: u64,
+    #[prost(uint32, tag = "10")]
+    pub epoch_id: u32,
+    #[prost(uint64, tag = "11")]
+    pub epoch_volume: u64,
+    #[prost(int64, tag = "12")]
+    pub epoch_pnl: i64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct UserStake {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(string, tag = "2")]
+    pub user: String,
+    #[prost(uint64, tag = "3")]
+    pub stake: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct UserPollPosition {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(string, tag = "2")]
+    pub user: String,
+    #[prost(uint64, repeated, tag = "3")]
+    pub stakes: Vec<u64>,
+    #[prost(uint32, tag = "4")]
+    pub bid_count: u32,
+    #[prost(uint64, repeated, tag = "5")]
+    pub bid_indices: Vec<u64>,
+    #[prost(int64, tag = "6")]
+    pub last_bid_ts: i64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct ParlayLeg {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(uint32, tag = "2")]
+    pub outcome: u32,
+    #[prost(uint64, tag = "3")]
+    pub odds: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Parlay {
+    #[prost(string, tag = "1")]
+    pub bettor: String,
+    #[prost(uint64, tag = "2")]
+    pub parlay_id: u64,
+    #[prost(uint64, tag = "3")]
+    pub amount: u64,
+    #[prost(uint64, tag = "4")]
+    pub potential_win: u64,
+    #[prost(uint32, tag = "5")]
+    pub fee_bps: u32,
+    #[prost(int64, tag = "6")]
+    pub timestamp: i64,
+    #[prost(message, repeated, tag = "7")]
+    pub legs: Vec<ParlayLeg>,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct ParlayPool {
+    #[prost(uint64, tag = "1")]
+    pub liability: u64,
+    #[prost(uint64, tag = "2")]
+    pub event_seq: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct FeeVault {
+    #[prost(uint64, tag = "1")]
+    pub total_swept: u64,
+    #[prost(uint64, tag = "2")]
+    pub event_seq: u64,
+    #[prost(uint64, tag = "3")]
+    pub fee_share_reserve: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Attestation {
+    #[prost(string, tag = "1")]
+    pub attestor: String,
+    #[prost(string, tag = "2")]
+    pub user: String,
+    #[prost(int64, tag = "3")]
+    pub issued_at: i64,
+    #[prost(int64, tag = "4")]
+    pub expires_at: i64,
+    #[prost(uint64, tag = "5")]
+    pub event_seq: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct TreasuryStats {
+    #[prost(uint64, tag = "1")]
+    pub total_fees: u64,
+    #[prost(uint64, tag = "2")]
+    pub total_insurance: u64,
+    #[prost(uint64, tag = "3")]
+    pub total_withdrawn: u64,
+    #[prost(uint64, tag = "4")]
+    pub sweep_count: u64,
+    #[prost(int64, tag = "5")]
+    pub withdraw_delay: i64,
+    #[prost(message, optional, tag = "6")]
+    pub pending: Option<TreasuryWithdrawal>,
+    #[prost(message, repeated, tag = "7")]
+    pub recent_sweeps: Vec<FeeSweep>,
+    #[prost(message, repeated, tag = "8")]
+    pub withdrawals: Vec<TreasuryWithdrawal>,
+    #[prost(uint64, tag = "9")]
+    pub event_seq: u64,
+    #[prost(uint64, tag = "10")]
+    pub total_remainders: u64,
+    #[prost(message, optional, tag = "11")]
+    pub pending_treasury: Option<TreasuryChange>,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct FeeSweep {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(uint64, tag = "2")]
+    pub amount: u64,
+    #[prost(int64, tag = "3")]
+    pub timestamp: i64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct TreasuryChange {
+    #[prost(string, tag = "1")]
+    pub treasury: String,
+    #[prost(int64, tag = "2")]
+    pub unlocks_at: i64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct TreasuryWithdrawal {
+    #[prost(uint64, tag = "1")]
+    pub amount: u64,
+    #[prost(string, tag = "2")]
+    pub destination: String,
+    #[prost(int64, tag = "3")]
+    pub timestamp: i64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct MarketEvent {
+    #[prost(string, tag = "1")]
+    pub authority: String,
+    #[prost(string, tag = "2")]
+    pub event_id: String,
+    #[prost(string, tag = "3")]
+    pub status: String,
+    #[prost(string, repeated, tag = "4")]
+    pub polls: Vec<String>,
+    #[prost(uint64, tag = "5")]
+    pub total_volume: u64,
+    #[prost(uint64, tag = "6")]
+    pub event_seq: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct FeeShare {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(string, tag = "2")]
+    pub party: String,
+    #[prost(uint32, tag = "3")]
+    pub share_bps: u32,
+    #[prost(uint64, tag = "4")]
+    pub swept_at_grant: u64,
+    #[prost(uint64, tag = "5")]
+    pub claimed: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct UserBalance {
+    #[prost(string, tag = "1")]
+    pub user: String,
+    #[prost(uint64, tag = "2")]
+    pub balance: u64,
+    #[prost(uint64, tag = "3")]
+    pub event_seq: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct PollRegistry {
+    #[prost(uint32, tag = "1")]
+    pub page: u32,
+    #[prost(string, repeated, tag = "2")]
+    pub polls: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct BetCredit {
+    #[prost(string, tag = "1")]
+    pub user: String,
+    #[prost(uint64, tag = "2")]
+    pub balance: u64,
+    #[prost(uint64, tag = "3")]
+    pub granted: u64,
+    #[prost(uint64, tag = "4")]
+    pub event_seq: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct PromoVault {
+    #[prost(uint64, tag = "1")]
+    pub outstanding: u64,
+    #[prost(uint64, tag = "2")]
+    pub total_granted: u64,
+    #[prost(uint64, tag = "3")]
+    pub total_returned: u64,
+    #[prost(uint64, tag = "4")]
+    pub event_seq: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Epoch {
+    #[prost(uint32, tag = "1")]
+    pub epoch_id: u32,
+    #[prost(int64, tag = "2")]
+    pub start_timestamp: i64,
+    #[prost(int64, tag = "3")]
+    pub end_timestamp: i64,
+    #[prost(string, tag = "4")]
+    pub metric: String,
+    #[prost(uint64, tag = "5")]
+    pub reward_pool: u64,
+    #[prost(uint32, repeated, tag = "6")]
+    pub weights: Vec<u32>,
+    #[prost(message, repeated, tag = "7")]
+    pub leaderboard: Vec<EpochEntry>,
+    #[prost(bool, tag = "8")]
+    pub finalized: bool,
+    #[prost(uint64, tag = "9")]
+    pub paid: u64,
+    #[prost(uint64, tag = "10")]
+    pub event_seq: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct EpochEntry {
+    #[prost(string, tag = "1")]
+    pub user: String,
+    #[prost(int64, tag = "2")]
+    pub score: i64,
+    #[prost(uint64, tag = "3")]
+    pub reward: u64,
+    #[prost(bool, tag = "4")]
+    pub claimed: bool,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct ResolutionNote {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(string, tag = "2")]
+    pub author: String,
+    #[prost(string, tag = "3")]
+    pub uri: String,
+    #[prost(string, tag = "4")]
+    pub evidence_hash: String,
+    #[prost(int64, tag = "5")]
+    pub created_at: i64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct InsuranceFund {
+    #[prost(uint32, tag = "1")]
+    pub fee_bps: u32,
+    #[prost(uint64, tag = "2")]
+    pub total_covered: u64,
+    #[prost(uint64, tag = "3")]
+    pub event_seq: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct CommunityVote {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(string, tag = "2")]
+    pub voter: String,
+    #[prost(uint32, tag = "3")]
+    pub outcome: u32,
+    #[prost(uint64, tag = "4")]
+    pub weight: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct PendingOrder {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(string, tag = "2")]
+    pub bettor: String,
+    #[prost(uint64, tag = "3")]
+    pub order_id: u64,
+    #[prost(uint64, tag = "4")]
+    pub amount: u64,
+    #[prost(uint32, tag = "5")]
+    pub outcome: u32,
+    #[prost(uint64, tag = "6")]
+    pub max_odds: u64,
+    #[prost(int64, tag = "7")]
+    pub created_at: i64,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct PollTemplate {
+    #[prost(string, tag = "1")]
+    pub authority: String,
+    #[prost(string, tag = "2")]
+    pub id_prefix: String,
+    #[prost(string, tag = "3")]
+    pub title: String,
+    #[prost(string, repeated, tag = "4")]
+    pub outcomes: Vec<String>,
+    #[prost(string, optional, tag = "5")]
+    pub mint: Option<String>,
+    #[prost(string, tag = "6")]
+    pub payout_mode: String,
+    #[prost(message, optional, tag = "7")]
+    pub resolution: Option<Resolution>,
+    #[prost(int64, tag = "8")]
+    pub dispute_period: i64,
+    #[prost(string, optional, tag = "9")]
+    pub arbiter: Option<String>,
+    #[prost(message, optional, tag = "10")]
+    pub deadline_extension: Option<DeadlineExtension>,
+    #[prost(uint64, tag = "11")]
+    pub virtual_liquidity: u64,
+    #[prost(string, tag = "12")]
+    pub kind: String,
+    #[prost(int64, repeated, tag = "13")]
+    pub scalar_bounds: Vec<i64>,
+    #[prost(int64, tag = "14")]
+    pub first_start: i64,
+    #[prost(int64, tag = "15")]
+    pub period: i64,
+    #[prost(int64, tag = "16")]
+    pub duration: i64,
+    #[prost(uint64, tag = "17")]
+    pub event_seq: u64,
+    #[prost(uint64, optional, tag = "18")]
+    pub min_bet: Option<u64>,
+    #[prost(uint64, optional, tag = "19")]
+    pub max_bet: Option<u64>,
+    #[prost(uint32, optional, tag = "20")]
+    pub fee_bps: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct PollMetadata {
+    #[prost(string, tag = "1")]
+    pub poll: String,
+    #[prost(string, tag = "2")]
+    pub category: String,
+    #[prost(string, tag = "3")]
+    pub uri: String,
+    #[prost(string, repeated, tag = "4")]
+    pub tags: Vec<String>,
+    #[prost(string, tag = "5")]
+    pub resolution_criteria: String,
+}
@@ -0,0 +1,96 @@
+//! Destinations for encoded account updates.
+
+use agave_geyser_plugin_interface::geyser_plugin_interface::{GeyserPluginError, Result};
+
+use crate::config::Config;
+
+pub enum Sink {
+    /// Write each update to the validator log
+    Log,
+    #[cfg(feature = "kafka")]
+    Kafka(kafka::KafkaSink),
+}
+
+impl Sink {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        match &config.kafka {
+            None => Ok(Sink::Log),
+            #[cfg(feature = "kafka")]
+            Some(kafka) => kafka::KafkaSink::new(kafka).map(Sink::Kafka),
+            #[cfg(not(feature = "kafka"))]
+            Some(_) => Err(GeyserPluginError::ConfigFileReadError {
+                msg: "built without the `kafka` feature".to_string(),
+            }),
+        }
+    }
+
+    /// Publish one update keyed by account pubkey, so a Kafka partition sees
+    /// every write of an account in order
+    #[cfg_attr(not(feature = "kafka"), allow(unused_variables))]
+    pub fn publish(&self, key: &[u8], payload: &[u8]) -> Result<()> {
+        match self {
+            Sink::Log => {
+                log::info!("yukti-geyser {}", String::from_utf8_lossy(payload));
+                Ok(())
+            }
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(sink) => sink.publish(key, payload),
+        }
+    }
+
+    pub fn flush(&self) {
+        #[cfg(feature = "kafka")]
+        if let Sink::Kafka(sink) = self {
+            sink.flush();
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use std::time::Duration;
+
+    use rdkafka::producer::{BaseRecord, DefaultProducerContext, Producer, ThreadedProducer};
+    use rdkafka::ClientConfig;
+
+    use super::*;
+    use crate::config::KafkaConfig;
+
+    pub struct KafkaSink {
+        producer: ThreadedProducer<DefaultProducerContext>,
+        topic: String,
+    }
+
+    impl KafkaSink {
+        pub fn new(config: &KafkaConfig) -> Result<Self> {
+            let mut client = ClientConfig::new();
+            for (key, value) in &config.producer {
+                client.set(key, value);
+            }
+            let producer = client
+                .create()
+                .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?;
+
+            Ok(KafkaSink {
+                producer,
+                topic: config.topic.clone(),
+            })
+        }
+
+        /// Enqueue without blocking the validator's notification thread
+        pub fn publish(&self, key: &[u8], payload: &[u8]) -> Result<()> {
+            let record = BaseRecord::to(&self.topic).key(key).payload(payload);
+            self.producer
+                .send(record)
+                .map_err(|(err, _)| GeyserPluginError::AccountsUpdateError {
+                    msg: format!("kafka enqueue failed: {err}"),
+                })
+        }
+
+        pub fn flush(&self) {
+            if let Err(err) = self.producer.flush(Duration::from_secs(10)) {
+                log::error!("yukti-geyser kafka flush failed: {err}");
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Market adapter interface.
+//!
+//! An adapter is an external program, approved by the upgrade authority via
+//! `register_adapter`, that hosts custom market logic for polls attached to it
+//! while the core keeps escrow, fees, and claims. The interface is three calls:
+//!
+//! - **quote**: on `place_bid_adapter` the core invokes the adapter's
+//!   `yukti_quote` instruction with the poll (read-only) and an
+//!   `AdapterQuoteRequest`. The adapter answers with an `AdapterQuote` via
+//!   `set_return_data`; the bid is priced at those odds.
+//! - **bet**: bettors call `place_bid_adapter` on the core; funds go to the
+//!   poll's vault exactly as with `place_bid`.
+//! - **settle**: the adapter calls `settle_poll_adapter` via CPI, signing with
+//!   its `adapter_authority` PDA for the poll.
+//!
+//! An Anchor adapter gets the matching discriminator by naming its handler
+//! `yukti_quote(ctx, request: AdapterQuoteRequest) -> Result<AdapterQuote>`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{get_return_data, invoke};
+
+use crate::ErrorCode;
+
+/// `sha256("global:yukti_quote")[..8]`
+pub const QUOTE_DISCRIMINATOR: [u8; 8] = [91, 46, 220, 151, 84, 243, 173, 174];
+
+/// Seed prefix of the PDA an adapter signs with to settle its polls
+pub const ADAPTER_AUTHORITY_SEED: &[u8] = b"yukti_adapter";
+
+/// Arguments passed to an adapter's `yukti_quote`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AdapterQuoteRequest {
+    pub poll: Pubkey,
+    pub option: u8,
+    pub amount: u64,
+}
+
+/// Adapter response: the implied probability of outcome `option`, in basis points
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AdapterQuote {
+    pub odds: u64,
+}
+
+/// The signer an adapter program uses for `settle_poll_adapter` on `poll`
+pub fn adapter_authority(adapter_program: &Pubkey, poll: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[ADAPTER_AUTHORITY_SEED, poll.as_ref()], adapter_program).0
+}
+
+/// Ask the adapter for odds on a prospective bid and return them unvalidated
+pub fn request_quote<'info>(
+    adapter_program: &AccountInfo<'info>,
+    poll: &AccountInfo<'info>,
+    option: u8,
+    amount: u64,
+) -> Result<u64> {
+    let request = AdapterQuoteRequest {
+        poll: poll.key(),
+        option,
+        amount,
+    };
+    let mut data = QUOTE_DISCRIMINATOR.to_vec();
+    request.serialize(&mut data)?;
+
+    let ix = Instruction {
+        program_id: adapter_program.key(),
+        accounts: vec![AccountMeta::new_readonly(poll.key(), false)],
+        data,
+    };
+    invoke(&ix, &[poll.clone(), adapter_program.clone()])?;
+
+    let (program_id, return_data) = get_return_data().ok_or(ErrorCode::InvalidAdapterQuote)?;
+    require_keys_eq!(program_id, adapter_program.key(), ErrorCode::InvalidAdapterQuote);
+    let quote = AdapterQuote::try_from_slice(&return_data)
+        .map_err(|_| error!(ErrorCode::InvalidAdapterQuote))?;
+
+    Ok(quote.odds)
+}
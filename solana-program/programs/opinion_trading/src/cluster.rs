@@ -0,0 +1,167 @@
+//! Cluster-specific compile-time defaults.
+//!
+//! Build with at most one of the `localnet`, `devnet`, or `mainnet` features;
+//! devnet values are used when none is set. Devnet keeps windows short for
+//! manual testing while mainnet enforces production-safe minimums, e.g.
+//! `anchor build -- --features mainnet`.
+
+use anchor_lang::prelude::*;
+
+#[cfg(any(
+    all(feature = "localnet", feature = "devnet"),
+    all(feature = "localnet", feature = "mainnet"),
+    all(feature = "devnet", feature = "mainnet"),
+))]
+compile_error!("enable only one of the `localnet`, `devnet`, and `mainnet` features");
+
+use crate::LAMPORTS_PER_SOL;
+
+#[cfg(feature = "mainnet")]
+mod profile {
+    use super::*;
+
+    pub const CLUSTER: &str = "mainnet";
+
+    // Range the config's bet limits must fall within (in lamports)
+    pub const MIN_BET_AMOUNT: u64 = LAMPORTS_PER_SOL / 100; // 0.01 SOL
+    pub const MAX_BET_AMOUNT: u64 = LAMPORTS_PER_SOL * 100; // 100 SOL
+
+    // Shortest allowed time between poll creation and its end (seconds)
+    pub const MIN_POLL_DURATION: i64 = 60 * 60; // 1 hour
+
+    // Bond posted to challenge a proposed settlement (in lamports)
+    pub const CHALLENGE_BOND: u64 = LAMPORTS_PER_SOL; // 1 SOL
+
+    // Shortest claim period the config can set: time winners and refunds have
+    // to claim before a poll can be closed or expired (seconds)
+    pub const CLOSE_GRACE_PERIOD: i64 = 30 * 24 * 60 * 60; // 30 days
+
+    // Shortest wait between committing a settlement and revealing it (seconds)
+    pub const SETTLEMENT_REVEAL_DELAY: i64 = 10 * 60; // 10 minutes
+
+    // Time after a poll ends before anyone can `crank_settle` it, so the
+    // authority gets the first chance to settle (seconds)
+    pub const CRANK_GRACE_PERIOD: i64 = 60 * 60; // 1 hour
+
+    // Time after a poll ends before its bettors can settle it by
+    // `vote_outcome` if the authority hasn't (seconds)
+    pub const COMMUNITY_VOTE_DELAY: i64 = 7 * 24 * 60 * 60; // 7 days
+
+    // Bounty a creator deposits in a SOL poll's vault for whoever cranks its
+    // settlement (in lamports). As the vault's first deposit it must cover the
+    // rent-exempt minimum of an empty account.
+    pub const CRANK_BOUNTY: u64 = LAMPORTS_PER_SOL / 1_000; // 0.001 SOL
+
+    // Tip `refund_batch` pays its cranker out of each refund it pushes (in
+    // lamports): the fee of one signature
+    pub const REFUND_TIP: u64 = 5_000;
+
+    pub const PYTH_ORACLE_PROGRAM_ID: Pubkey =
+        pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+    pub const SWITCHBOARD_PROGRAM_ID: Pubkey =
+        pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+    // Pyth pull-oracle receiver (owner of `PriceUpdateV2` accounts)
+    pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey =
+        pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+}
+
+#[cfg(feature = "localnet")]
+mod profile {
+    use super::*;
+
+    pub const CLUSTER: &str = "localnet";
+
+    // Range the config's bet limits must fall within (in lamports)
+    pub const MIN_BET_AMOUNT: u64 = LAMPORTS_PER_SOL / 1_000; // 0.001 SOL
+    pub const MAX_BET_AMOUNT: u64 = LAMPORTS_PER_SOL * 100; // 100 SOL
+
+    // Shortest allowed time between poll creation and its end (seconds)
+    pub const MIN_POLL_DURATION: i64 = 1;
+
+    // Bond posted to challenge a proposed settlement (in lamports)
+    pub const CHALLENGE_BOND: u64 = LAMPORTS_PER_SOL / 100; // 0.01 SOL
+
+    // Shortest claim period the config can set: time winners and refunds have
+    // to claim before a poll can be closed or expired (seconds)
+    pub const CLOSE_GRACE_PERIOD: i64 = 1;
+
+    // Shortest wait between committing a settlement and revealing it (seconds)
+    pub const SETTLEMENT_REVEAL_DELAY: i64 = 1;
+
+    // Time after a poll ends before anyone can `crank_settle` it, so the
+    // authority gets the first chance to settle (seconds)
+    pub const CRANK_GRACE_PERIOD: i64 = 1;
+
+    // Time after a poll ends before its bettors can settle it by
+    // `vote_outcome` if the authority hasn't (seconds)
+    pub const COMMUNITY_VOTE_DELAY: i64 = 1;
+
+    // Bounty a creator deposits in a SOL poll's vault for whoever cranks its
+    // settlement (in lamports). As the vault's first deposit it must cover the
+    // rent-exempt minimum of an empty account.
+    pub const CRANK_BOUNTY: u64 = LAMPORTS_PER_SOL / 1_000; // 0.001 SOL
+
+    // Tip `refund_batch` pays its cranker out of each refund it pushes (in
+    // lamports): the fee of one signature
+    pub const REFUND_TIP: u64 = 5_000;
+
+    // Localnet clones the devnet oracle programs
+    pub const PYTH_ORACLE_PROGRAM_ID: Pubkey =
+        pubkey!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s");
+    pub const SWITCHBOARD_PROGRAM_ID: Pubkey =
+        pubkey!("Aio4gaXjXzJNVLtzwtNVmSqGKpANtXhybbkhtAC94ji2");
+    // Pyth pull-oracle receiver (owner of `PriceUpdateV2` accounts)
+    pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey =
+        pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+}
+
+#[cfg(not(any(feature = "mainnet", feature = "localnet")))]
+mod profile {
+    use super::*;
+
+    pub const CLUSTER: &str = "devnet";
+
+    // Range the config's bet limits must fall within (in lamports)
+    pub const MIN_BET_AMOUNT: u64 = LAMPORTS_PER_SOL / 100; // 0.01 SOL
+    pub const MAX_BET_AMOUNT: u64 = LAMPORTS_PER_SOL * 100; // 100 SOL
+
+    // Shortest allowed time between poll creation and its end (seconds)
+    pub const MIN_POLL_DURATION: i64 = 60; // 1 minute
+
+    // Bond posted to challenge a proposed settlement (in lamports)
+    pub const CHALLENGE_BOND: u64 = LAMPORTS_PER_SOL / 10; // 0.1 SOL
+
+    // Shortest claim period the config can set: time winners and refunds have
+    // to claim before a poll can be closed or expired (seconds)
+    pub const CLOSE_GRACE_PERIOD: i64 = 60 * 60; // 1 hour
+
+    // Shortest wait between committing a settlement and revealing it (seconds)
+    pub const SETTLEMENT_REVEAL_DELAY: i64 = 60; // 1 minute
+
+    // Time after a poll ends before anyone can `crank_settle` it, so the
+    // authority gets the first chance to settle (seconds)
+    pub const CRANK_GRACE_PERIOD: i64 = 5 * 60; // 5 minutes
+
+    // Time after a poll ends before its bettors can settle it by
+    // `vote_outcome` if the authority hasn't (seconds)
+    pub const COMMUNITY_VOTE_DELAY: i64 = 60 * 60; // 1 hour
+
+    // Bounty a creator deposits in a SOL poll's vault for whoever cranks its
+    // settlement (in lamports). As the vault's first deposit it must cover the
+    // rent-exempt minimum of an empty account.
+    pub const CRANK_BOUNTY: u64 = LAMPORTS_PER_SOL / 1_000; // 0.001 SOL
+
+    // Tip `refund_batch` pays its cranker out of each refund it pushes (in
+    // lamports): the fee of one signature
+    pub const REFUND_TIP: u64 = 5_000;
+
+    pub const PYTH_ORACLE_PROGRAM_ID: Pubkey =
+        pubkey!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s");
+    pub const SWITCHBOARD_PROGRAM_ID: Pubkey =
+        pubkey!("Aio4gaXjXzJNVLtzwtNVmSqGKpANtXhybbkhtAC94ji2");
+    // Pyth pull-oracle receiver (owner of `PriceUpdateV2` accounts)
+    pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey =
+        pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+}
+
+pub use profile::*;
@@ -1,626 +1,11842 @@
+// `initialize_poll` takes one argument per poll setting, and Anchor generates
+// client and CPI helpers with the same signature outside the program module
+#![allow(clippy::too_many_arguments)]
+
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use anchor_spl::token_2022_extensions::transfer_fee as token_transfer_fee;
+use anchor_spl::token_interface::{self, TokenInterface};
+
+/// `require!` that also logs the offending values on failure, as one line in a
+/// stable format support tooling can parse from transaction logs:
+/// `error_context code=<ErrorCode variant> key=value key=value ...`
+macro_rules! require_ctx {
+    ($cond:expr, $code:expr, $($key:ident = $value:expr),+ $(,)?) => {
+        if !($cond) {
+            msg!(
+                concat!("error_context code={:?}", $(" ", stringify!($key), "={}"),+),
+                $code,
+                $($value),+
+            );
+            return Err(error!($code));
+        }
+    };
+}
+
+/// `emit!` that goes through the event CPI instead when built with the
+/// `cpi-events` feature, so indexers can rebuild the event from inner
+/// instructions when logs are truncated. The instruction's accounts struct
+/// carries `#[cfg_attr(feature = "cpi-events", event_cpi)]`.
+macro_rules! emit_event {
+    ($ctx:ident, $event:expr) => {{
+        #[cfg(feature = "cpi-events")]
+        emit_event_cpi(
+            &$ctx.accounts.event_authority,
+            $ctx.bumps.event_authority,
+            &$event,
+        )?;
+        #[cfg(not(feature = "cpi-events"))]
+        emit!($event);
+    }};
+}
+
+/// Emit the events `place_new_bid` returns through `emit_event!`
+macro_rules! emit_placed_bid {
+    ($ctx:ident, $placed:expr) => {{
+        let PlacedBid {
+            placed,
+            referred,
+            partially_filled,
+            state,
+        } = $placed;
+        emit_event!($ctx, placed);
+        if let Some(referred) = referred {
+            emit_event!($ctx, referred);
+        }
+        if let Some(partially_filled) = partially_filled {
+            emit_event!($ctx, partially_filled);
+        }
+        emit_event!($ctx, state);
+    }};
+}
+
+/// Emit the events of a claim's side payments through `emit_cpi!`
+macro_rules! emit_claim_events {
+    ($ctx:ident, $events:expr) => {{
+        let ctx = &$ctx;
+        let ClaimEvents {
+            referral,
+            shortfall,
+            credit_returned,
+        } = $events;
+        if let Some(event) = referral {
+            emit_cpi!(event);
+        }
+        if let Some(event) = shortfall {
+            emit_cpi!(event);
+        }
+        if let Some(event) = credit_returned {
+            emit_cpi!(event);
+        }
+    }};
+}
+
+pub mod adapter;
+pub mod cluster;
+pub mod logic;
+pub mod math;
+pub mod oracle;
+pub mod pda;
+pub mod signature;
+#[cfg(feature = "no-entrypoint")]
+pub mod sim;
 
 declare_id!("3YaSKpdV7iGrjUKAy6mKEFCSNV3bTyZVncceD34Bun1C");
 
-// Platform fee: 2% on winning payouts
-const PLATFORM_FEE_BPS: u64 = 200; // 200 basis points = 2%
+// Highest platform fee the config can charge on winning payouts
+const MAX_FEE_BPS: u16 = 1_000; // 1000 basis points = 10%
 const BPS_DENOMINATOR: u64 = 10_000;
 
 // 1 SOL in lamports (avoid importing native_token to keep compatibility)
 const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
 
-// Minimum and maximum bet amounts (in lamports)
-const MIN_BET_AMOUNT: u64 = LAMPORTS_PER_SOL / 100; // 0.01 SOL
-const MAX_BET_AMOUNT: u64 = LAMPORTS_PER_SOL * 100; // 100 SOL
+// Layout versions written to new `Poll` and `Bid` accounts. Accounts from
+// before versioning read as version 0 until `migrate_poll` / `migrate_bid`.
+pub const POLL_VERSION: u8 = 13;
+pub const BID_VERSION: u8 = 7;
+
+// Maximum number of bids accepted by a single place_bids_multi call
+const MAX_BIDS_PER_TX: usize = 10;
+
+// Maximum number of bids refunded by a single refund_batch call
+const MAX_REFUNDS_PER_TX: usize = 10;
+
+// Number of packed bids a single BidLedger account holds
+pub const LEDGER_CAPACITY: usize = 1024;
+
+// Snapshots an OddsHistory account holds before overwriting the oldest
+pub const ODDS_HISTORY_CAPACITY: usize = 256;
+
+// Bounds on the odds a bid can be priced at (basis points): 5% to 95%
+const MIN_ODDS_BPS: u64 = 500;
+const MAX_ODDS_BPS: u64 = 9500;
+
+// Furthest a poll's end time can be pushed past the end it was created with
+// (seconds): 7 days
+const MAX_END_EXTENSION: i64 = 7 * 24 * 60 * 60;
+
+// Largest virtual stake per outcome a poll can price with (base units)
+const MAX_VIRTUAL_LIQUIDITY: u64 = 1_000_000 * LAMPORTS_PER_SOL;
+
+// Decimals of outcome-token mints, matching lamports
+const OUTCOME_TOKEN_DECIMALS: u8 = 9;
+
+// Longest dispute window a poll can set on its settlement (seconds): 7 days
+const MAX_DISPUTE_PERIOD: i64 = 7 * 24 * 60 * 60;
+
+// Longest a poll can give its authority to settle after it ends before anyone
+// can cancel it with `expire_poll` (seconds): 90 days
+const MAX_RESOLUTION_DEADLINE: i64 = 90 * 24 * 60 * 60;
+
+// Longest a poll can let bettors cancel their bids after placing them
+// (seconds), and the most it can charge for a cancellation: 1 hour, 10%
+const MAX_CANCEL_WINDOW: i64 = 60 * 60;
+const MAX_CANCEL_FEE_BPS: u16 = 1_000;
+
+// Longest cooldown a poll can make a user wait between bids (seconds): 1 day
+const MAX_BID_COOLDOWN: i64 = 24 * 60 * 60;
+
+// Longest window before the end in which a poll damps odds movement
+// (seconds): 7 days
+const MAX_DAMPING_WINDOW: i64 = 7 * 24 * 60 * 60;
+
+// Largest margin a poll can build into the odds it prices bids at: 10%
+const MAX_VIG_BPS: u16 = 1_000;
+
+// Most volume fee tiers the config can schedule
+const MAX_FEE_TIERS: usize = 4;
+
+// Bounds on the number of legs in a parlay
+const MIN_PARLAY_LEGS: usize = 2;
+const MAX_PARLAY_LEGS: usize = 5;
+
+// Bounds on the number of outcomes a poll can offer
+const MIN_OUTCOMES: usize = 2;
+pub const MAX_OUTCOMES: usize = 8;
+
+// Most bid indices a `UserPollPosition` lists
+pub const MAX_POSITION_BIDS: usize = 16;
+
+// Polls listed on one `PollRegistry` page
+pub const REGISTRY_PAGE_SIZE: usize = 100;
+
+// Most polls a `MarketEvent` groups, so `settle_event` fits in one transaction
+pub const MAX_EVENT_POLLS: usize = 8;
+
+// Most ranks an `Epoch` splits its reward pool across
+pub const MAX_EPOCH_WINNERS: usize = 10;
+
+// Time after an epoch ends for scores to be submitted before it can be
+// finalized, and before the next epoch can open (seconds): 1 day
+const EPOCH_SUBMISSION_PERIOD: i64 = 24 * 60 * 60;
+
+// Most bucket bounds a scalar poll can have: one fewer than its outcomes
+const MAX_SCALAR_BOUNDS: usize = MAX_OUTCOMES - 1;
+
+// Longest poll id prefix a template can have (bytes), leaving room in the
+// 64-byte poll id for "-" and any round number
+const MAX_TEMPLATE_PREFIX_LEN: usize = 43;
+
+// Limits on a poll's metadata (bytes, except the tag count)
+const MAX_METADATA_URI_LEN: usize = 200;
+const MAX_METADATA_TAGS: usize = 8;
+const MAX_METADATA_TAG_LEN: usize = 32;
+const MAX_RESOLUTION_CRITERIA_LEN: usize = 1000;
+
+// Most resolvers a poll's settlement council can have
+const MAX_RESOLVERS: usize = 5;
+
+// Share of a poll's pool that must vote before `vote_outcome` settles it, and
+// share of those votes the winning outcome needs (basis points)
+const COMMUNITY_QUORUM_BPS: u64 = 3_000;
+const COMMUNITY_SUPERMAJORITY_BPS: u64 = 6_667;
+
+// Longest the treasury timelock can hold a withdrawal (seconds): 30 days
+const MAX_TREASURY_DELAY: i64 = 30 * 24 * 60 * 60;
+
+// Sweeps and withdrawals a `TreasuryStats` keeps, dropping the oldest
+pub const TREASURY_HISTORY_LEN: usize = 16;
 
 #[program]
 pub mod opinion_trading {
     use super::*;
 
-    /// Initialize a new prediction poll/market
-    /// This creates the on-chain state and escrow vault for the poll
-    pub fn initialize_poll(
-        ctx: Context<InitializePoll>,
-        poll_id: String,
-        title: String,
-        option_a_text: String,
-        option_b_text: String,
-        end_timestamp: i64,
+    /// Create the program config (program upgrade authority only, once)
+    /// The signer becomes the config admin.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        treasury: Pubkey,
+        fee_bps: u16,
+        min_bet: u64,
+        max_bet: u64,
+        creation_bond: u64,
+        creator_fee_bps: u16,
+        lp_fee_bps: u16,
+        referral_fee_bps: u16,
+        claim_period: i64,
     ) -> Result<()> {
-        require!(poll_id.len() <= 64, ErrorCode::PollIdTooLong);
-        require!(title.len() <= 256, ErrorCode::TitleTooLong);
-        require!(option_a_text.len() <= 128, ErrorCode::OptionTextTooLong);
-        require!(option_b_text.len() <= 128, ErrorCode::OptionTextTooLong);
-        require!(
-            end_timestamp > Clock::get()?.unix_timestamp,
-            ErrorCode::InvalidEndTime
-        );
+        logic::validate_config(
+            fee_bps,
+            creator_fee_bps,
+            lp_fee_bps,
+            referral_fee_bps,
+            min_bet,
+            max_bet,
+            claim_period,
+        )?;
 
-        let poll = &mut ctx.accounts.poll;
-        poll.authority = ctx.accounts.authority.key();
-        poll.poll_id = poll_id;
-        poll.title = title;
-        poll.option_a_text = option_a_text;
-        poll.option_b_text = option_b_text;
-        poll.option_a_stake = 0;
-        poll.option_b_stake = 0;
-        poll.total_pool = 0;
-        poll.option_a_odds = 5000; // 50% initial odds (in basis points)
-        poll.option_b_odds = 5000; // 50% initial odds
-        poll.end_timestamp = end_timestamp;
-        poll.status = PollStatus::Active;
-        poll.winner = None;
-        poll.vault_bump = ctx.bumps.vault;
-        poll.bump = ctx.bumps.poll;
-        poll.next_bid_index = 0;
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.pending_admin = None;
+        config.paused = false;
+        config.treasury = treasury;
+        config.fee_bps = fee_bps;
+        config.min_bet = min_bet;
+        config.max_bet = max_bet;
+        config.bump = ctx.bumps.config;
+        config.creation_bond = creation_bond;
+        config.creator_fee_bps = creator_fee_bps;
+        config.lp_fee_bps = lp_fee_bps;
+        config.referral_fee_bps = referral_fee_bps;
+        config.claim_period = claim_period;
+        config.fee_tiers = Vec::new();
+        config.usd_feed_id = [0; 32];
+        config.max_bid_usd = 0;
+        config.epoch_id = 0;
+        config.epoch_start = 0;
+        config.epoch_end = 0;
 
-        emit!(PollCreated {
-            poll: poll.key(),
-            authority: poll.authority,
-            poll_id: poll.poll_id.clone(),
-            title: poll.title.clone(),
-            end_timestamp: poll.end_timestamp,
+        // Always logged: `event_cpi` would clash with the `program` account
+        emit!(ConfigUpdated {
+            header: event_header(&mut config.event_seq)?,
+            admin: config.admin,
+            treasury,
+            fee_bps,
+            min_bet,
+            max_bet,
+            creation_bond,
+            creator_fee_bps,
+            lp_fee_bps,
+            referral_fee_bps,
+            claim_period,
         });
 
         Ok(())
     }
 
-    /// Place a bid on a poll option with AMM odds adjustment
-    pub fn place_bid(
-        ctx: Context<PlaceBid>,
-        amount: u64,
-        option: BidOption,
-        timestamp: i64,
-        bid_index: u64,
+    /// Replace the program config (config admin only), except the treasury,
+    /// which `change_treasury` moves behind the treasury timelock.
+    /// Fees, bet limits, the creation bond, and the claim period apply to polls
+    /// created afterwards; existing polls keep the terms they were created with.
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        admin: Pubkey,
+        fee_bps: u16,
+        min_bet: u64,
+        max_bet: u64,
+        creation_bond: u64,
+        creator_fee_bps: u16,
+        lp_fee_bps: u16,
+        referral_fee_bps: u16,
+        claim_period: i64,
     ) -> Result<()> {
-        let poll = &mut ctx.accounts.poll;
-        // Ensure provided bid index matches poll's next index
-        require!(bid_index == poll.next_bid_index, ErrorCode::InvalidBidIndex);
+        logic::validate_config(
+            fee_bps,
+            creator_fee_bps,
+            lp_fee_bps,
+            referral_fee_bps,
+            min_bet,
+            max_bet,
+            claim_period,
+        )?;
 
-        // Validations
-        require!(
-            poll.status == PollStatus::Active,
-            ErrorCode::PollNotActive
-        );
-        require!(
-            Clock::get()?.unix_timestamp < poll.end_timestamp,
-            ErrorCode::PollEnded
-        );
-        require!(
-            amount >= MIN_BET_AMOUNT && amount <= MAX_BET_AMOUNT,
-            ErrorCode::InvalidBetAmount
-        );
+        let config = &mut ctx.accounts.config;
+        // A direct admin change drops any transfer still waiting for acceptance
+        if admin != config.admin {
+            config.pending_admin = None;
+        }
+        config.admin = admin;
+        config.fee_bps = fee_bps;
+        config.min_bet = min_bet;
+        config.max_bet = max_bet;
+        config.creation_bond = creation_bond;
+        config.creator_fee_bps = creator_fee_bps;
+        config.lp_fee_bps = lp_fee_bps;
+        config.referral_fee_bps = referral_fee_bps;
+        config.claim_period = claim_period;
 
-        // Get current odds before updating
-        let current_odds = match option {
-            BidOption::OptionA => poll.option_a_odds,
-            BidOption::OptionB => poll.option_b_odds,
-        };
+        emit_event!(ctx, ConfigUpdated {
+            header: event_header(&mut config.event_seq)?,
+            admin,
+            treasury: config.treasury,
+            fee_bps,
+            min_bet,
+            max_bet,
+            creation_bond,
+            creator_fee_bps,
+            lp_fee_bps,
+            referral_fee_bps,
+            claim_period,
+        });
 
-        // Calculate potential win based on current odds
-        // potential_win = (amount * BPS_DENOMINATOR) / odds
-        let potential_win = (amount as u128)
-            .checked_mul(BPS_DENOMINATOR as u128)
-            .unwrap()
-            .checked_div(current_odds as u128)
-            .unwrap() as u64;
+        Ok(())
+    }
 
-        // Transfer SOL from bettor to vault (escrow)
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.bettor.to_account_info(),
-                to: ctx.accounts.vault.to_account_info(),
-            },
-        );
-        anchor_lang::system_program::transfer(cpi_context, amount)?;
+    /// Propose a new config admin (config admin only), who takes over once
+    /// they call `accept_admin_transfer`; `None` withdraws the proposal
+    pub fn propose_admin_transfer(
+        ctx: Context<ProposeAdminTransfer>,
+        new_admin: Option<Pubkey>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let admin = config.admin;
 
-        // Update poll state
-        match option {
-            BidOption::OptionA => {
-                poll.option_a_stake = poll.option_a_stake.checked_add(amount).unwrap();
-            }
-            BidOption::OptionB => {
-                poll.option_b_stake = poll.option_b_stake.checked_add(amount).unwrap();
-            }
-        }
-        poll.total_pool = poll.total_pool.checked_add(amount).unwrap();
+        logic::propose_transfer(&mut config.pending_admin, admin, new_admin)?;
 
-        // Update AMM odds using Constant Product Market Maker formula
-        update_amm_odds(poll)?;
+        emit_event!(ctx, AuthorityTransferProposed {
+            header: event_header(&mut config.event_seq)?,
+            account: config.key(),
+            authority: admin,
+            pending_authority: new_admin,
+        });
 
-        // Initialize bid account
-        let bid = &mut ctx.accounts.bid;
-        bid.bettor = ctx.accounts.bettor.key();
-        bid.poll = poll.key();
-        bid.amount = amount;
-        bid.option = option;
-        bid.odds_at_purchase = current_odds;
-        bid.potential_win = potential_win;
-        bid.status = BidStatus::Active;
-        bid.timestamp = timestamp;
-        bid.index = bid_index;
-        bid.bump = ctx.bumps.bid;
+        Ok(())
+    }
 
-        emit!(BidPlaced {
-            bid: bid.key(),
-            bettor: bid.bettor,
-            poll: poll.key(),
-            amount,
-            option,
-            odds: current_odds,
-            potential_win,
-        });
+    /// Become the config admin (the proposed admin only)
+    pub fn accept_admin_transfer(ctx: Context<AcceptAdminTransfer>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let admin = ctx.accounts.pending_admin.key();
 
-        // Increment next bid index for poll
-        poll.next_bid_index = poll.next_bid_index.checked_add(1).unwrap();
+        logic::accept_transfer(&mut config.pending_admin, admin)?;
+        let previous = config.admin;
+        config.admin = admin;
+
+        emit_event!(ctx, AuthorityTransferred {
+            header: event_header(&mut config.event_seq)?,
+            account: config.key(),
+            previous_authority: previous,
+            authority: admin,
+        });
 
         Ok(())
     }
 
-    /// Settle the poll and declare a winner (admin only)
-    pub fn settle_poll(ctx: Context<SettlePoll>, winning_option: BidOption) -> Result<()> {
-        let poll = &mut ctx.accounts.poll;
-
-        require!(
-            ctx.accounts.authority.key() == poll.authority,
-            ErrorCode::Unauthorized
-        );
-        require!(
-            poll.status == PollStatus::Active,
-            ErrorCode::PollNotActive
-        );
-        require!(
-            Clock::get()?.unix_timestamp >= poll.end_timestamp,
-            ErrorCode::PollNotEnded
-        );
+    /// Replace the volume fee schedule (config admin only)
+    /// Like the other fee settings, it applies to polls created afterwards.
+    pub fn set_fee_tiers(ctx: Context<UpdateConfig>, fee_tiers: Vec<FeeTier>) -> Result<()> {
+        logic::validate_fee_tiers(&fee_tiers)?;
 
-        poll.status = PollStatus::Settled;
-        poll.winner = Some(winning_option);
+        let config = &mut ctx.accounts.config;
+        config.fee_tiers = fee_tiers;
 
-        emit!(PollSettled {
-            poll: poll.key(),
-            winner: winning_option,
-            total_pool: poll.total_pool,
+        emit_event!(ctx, FeeTiersUpdated {
+            header: event_header(&mut config.event_seq)?,
+            admin: config.admin,
+            fee_tiers: config.fee_tiers.clone(),
         });
 
         Ok(())
     }
 
-    /// Claim winnings for a winning bid (with 2% platform fee)
-    pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
-        let bid = &mut ctx.accounts.bid;
-        let poll = &ctx.accounts.poll;
+    /// Set the Pyth SOL/USD feed bids are valued with and the micro-USD
+    /// limit per SOL bid (config admin only; 0 lifts the limit). While a
+    /// limit is set, SOL bids go through `place_bid`,
+    /// `place_bid_from_balance`, or `place_bid_with_credit` with a price.
+    pub fn set_usd_limits(
+        ctx: Context<UpdateConfig>,
+        usd_feed_id: [u8; 32],
+        max_bid_usd: u64,
+    ) -> Result<()> {
+        logic::validate_usd_limits(&usd_feed_id, max_bid_usd)?;
 
-        require!(
-            bid.bettor == ctx.accounts.bettor.key(),
-            ErrorCode::Unauthorized
-        );
-        require!(
-            poll.status == PollStatus::Settled,
-            ErrorCode::PollNotSettled
-        );
-        require!(
-            bid.status == BidStatus::Active,
-            ErrorCode::BidAlreadyClaimed
-        );
+        let config = &mut ctx.accounts.config;
+        config.usd_feed_id = usd_feed_id;
+        config.max_bid_usd = max_bid_usd;
 
-        // Check if this bid won
-        let did_win = match (bid.option, poll.winner) {
-            (BidOption::OptionA, Some(BidOption::OptionA)) => true,
-            (BidOption::OptionB, Some(BidOption::OptionB)) => true,
-            _ => false,
-        };
+        emit_event!(ctx, UsdLimitsUpdated {
+            header: event_header(&mut config.event_seq)?,
+            admin: config.admin,
+            usd_feed_id,
+            max_bid_usd,
+        });
 
-        require!(did_win, ErrorCode::BidDidNotWin);
+        Ok(())
+    }
 
-        // Calculate payout: potential_win - platform_fee (2%)
-        let platform_fee = (bid.potential_win as u128)
-            .checked_mul(PLATFORM_FEE_BPS as u128)
-            .unwrap()
-            .checked_div(BPS_DENOMINATOR as u128)
-            .unwrap() as u64;
+    /// Emergency stop (config admin only): every poll rejects bids and
+    /// settlement until `unpause`, while claims, refunds, and exits stay open
+    pub fn pause(ctx: Context<SetPaused>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.paused = true;
 
-        let payout = bid.potential_win.checked_sub(platform_fee).unwrap();
+        emit_event!(ctx, PauseChanged {
+            header: event_header(&mut config.event_seq)?,
+            admin: config.admin,
+            paused: true,
+        });
 
-        // Transfer winnings from vault to bettor
-        let poll_id = poll.poll_id.as_bytes();
-        let seeds = &[
-            b"vault",
-            poll_id,
-            &[poll.vault_bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
+        Ok(())
+    }
 
-        let cpi_context = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.vault.to_account_info(),
-                to: ctx.accounts.bettor.to_account_info(),
-            },
-            signer_seeds,
+    /// Lift an emergency stop (config admin only)
+    pub fn unpause(ctx: Context<SetPaused>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.paused = false;
+
+        emit_event!(ctx, PauseChanged {
+            header: event_header(&mut config.event_seq)?,
+            admin: config.admin,
+            paused: false,
+        });
+
+        Ok(())
+    }
+
+    /// Initialize a new prediction poll/market
+    /// This creates the on-chain state and escrow vault for the poll. Passing a
+    /// mint makes it a token market whose vault is the poll's associated token
+    /// account; otherwise it is denominated in SOL. `payout_mode` is fixed for
+    /// the poll's lifetime. A `resolution` source makes it an oracle market
+    /// settled by `settle_poll_with_oracle` instead of the authority. A nonzero
+    /// `dispute_period` holds every settlement open to challenges for that many
+    /// seconds; challenges are decided by `arbiter` (the authority if `None`).
+    /// Anyone can create a poll and becomes its authority. The creator posts
+    /// the config's creation bond, held on the poll account until it is
+    /// closed or slashed, and earns the config's creator share of the
+    /// platform fee. The fee, creator share, and bet limits are copied from
+    /// the config. A future `start_timestamp` creates the poll `Scheduled`: it
+    /// rejects bids until then and opens on the first bid after it (or via
+    /// `open_poll`); 0 or any past time opens it immediately. A
+    /// `deadline_extension` pushes the end back when bids arrive just before it.
+    /// `virtual_liquidity` is a virtual stake added to every outcome when
+    /// pricing, so early bids move the odds smoothly; 0 prices on raw stakes.
+    /// A `PollKind::Scalar` poll's outcomes are buckets of a number, settled
+    /// by `settle_scalar` or an oracle price rather than by picking a winner.
+    /// A nonzero `resolution_deadline` lets anyone cancel the poll with
+    /// `expire_poll` if it is still unsettled that many seconds after it ends.
+    pub fn initialize_poll(
+        ctx: Context<InitializePoll>,
+        poll_id: String,
+        title: String,
+        outcomes: Vec<String>,
+        end_timestamp: i64,
+        payout_mode: PayoutMode,
+        resolution: Option<ResolutionSource>,
+        dispute_period: i64,
+        arbiter: Option<Pubkey>,
+        start_timestamp: i64,
+        deadline_extension: Option<DeadlineExtension>,
+        virtual_liquidity: u64,
+        kind: PollKind,
+        limits: PollLimits,
+        incentives: Option<IncentiveCurve>,
+        resolution_deadline: i64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.mint.is_some() == ctx.accounts.token_vault.is_some(),
+            ErrorCode::InvalidTokenVault
         );
-        anchor_lang::system_program::transfer(cpi_context, payout)?;
+        if let Some(mint) = &ctx.accounts.mint {
+            logic::validate_stake_mint(&mint.to_account_info().try_borrow_data()?)?;
+        }
 
-        // Transfer platform fee to treasury
-        let fee_context = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.vault.to_account_info(),
-                to: ctx.accounts.treasury.to_account_info(),
+        let created = create_poll(
+            &mut ctx.accounts.poll,
+            &ctx.accounts.vault,
+            &ctx.accounts.config,
+            ctx.accounts.mint.as_ref().map(|mint| mint.key()),
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            (ctx.bumps.poll, ctx.bumps.vault),
+            PollParams {
+                poll_id,
+                title,
+                outcomes,
+                end_timestamp,
+                payout_mode,
+                resolution,
+                dispute_period,
+                arbiter,
+                start_timestamp,
+                deadline_extension,
+                virtual_liquidity,
+                kind,
+                limits,
+                incentives,
+                resolution_deadline,
             },
-            signer_seeds,
-        );
-        anchor_lang::system_program::transfer(fee_context, platform_fee)?;
+            None,
+        )?;
+        let key = ctx.accounts.poll.key();
+        logic::list_poll(&mut ctx.accounts.poll, key, &mut ctx.accounts.registry)?;
+        emit_event!(ctx, created);
 
-        // Mark bid as claimed
-        bid.status = BidStatus::Won;
+        Ok(())
+    }
 
-        emit!(WinningsClaimed {
-            bid: bid.key(),
-            bettor: bid.bettor,
-            payout,
-            platform_fee,
+    /// Save a recurring market as a template at `["poll_template", id_prefix]`.
+    /// Round `n` of the template is the poll `"{id_prefix}-{n}"`, opening at
+    /// `first_start + n * period` and ending `duration` seconds later, with
+    /// the template's text, outcomes, mint, and settlement parameters.
+    pub fn create_poll_template(
+        ctx: Context<CreatePollTemplate>,
+        id_prefix: String,
+        title: String,
+        outcomes: Vec<String>,
+        payout_mode: PayoutMode,
+        resolution: Option<ResolutionSource>,
+        dispute_period: i64,
+        arbiter: Option<Pubkey>,
+        deadline_extension: Option<DeadlineExtension>,
+        virtual_liquidity: u64,
+        kind: PollKind,
+        limits: PollLimits,
+        first_start: i64,
+        period: i64,
+        duration: i64,
+    ) -> Result<()> {
+        logic::validate_poll_metadata(&id_prefix, &title, &outcomes)?;
+        logic::validate_template_prefix(&id_prefix)?;
+        logic::validate_poll_kind(&kind, payout_mode, outcomes.len())?;
+        logic::validate_resolution_source(resolution.as_ref(), &kind, outcomes.len())?;
+        logic::validate_dispute_period(dispute_period)?;
+        logic::validate_deadline_extension(deadline_extension.as_ref())?;
+        logic::validate_virtual_liquidity(virtual_liquidity)?;
+        logic::validate_template_schedule(first_start, period, duration)?;
+
+        let template = &mut ctx.accounts.template;
+        template.authority = ctx.accounts.authority.key();
+        template.id_prefix = id_prefix;
+        template.title = title;
+        template.outcomes = outcomes;
+        if let Some(mint) = &ctx.accounts.mint {
+            logic::validate_stake_mint(&mint.to_account_info().try_borrow_data()?)?;
+        }
+        template.mint = ctx.accounts.mint.as_ref().map(|mint| mint.key());
+        template.payout_mode = payout_mode;
+        template.resolution = resolution;
+        template.dispute_period = dispute_period;
+        template.arbiter = arbiter;
+        template.deadline_extension = deadline_extension;
+        template.virtual_liquidity = virtual_liquidity;
+        template.kind = kind;
+        template.limits = limits;
+        template.first_start = first_start;
+        template.period = period;
+        template.duration = duration;
+        template.bump = ctx.bumps.template;
+
+        emit_event!(ctx, PollTemplateCreated {
+            header: event_header(&mut template.event_seq)?,
+            template: template.key(),
+            authority: template.authority,
+            id_prefix: template.id_prefix.clone(),
+            first_start,
+            period,
+            duration,
         });
 
         Ok(())
     }
 
-    /// Cancel a poll and refund all bettors (admin only, emergency use)
-    pub fn cancel_poll(ctx: Context<CancelPoll>) -> Result<()> {
-        let poll = &mut ctx.accounts.poll;
-
+    /// Create round `round` of a template (template authority only). Rounds
+    /// can be created in any order, ahead of time, but not once they'd end
+    /// before the minimum duration; each round's poll id can only be used once.
+    pub fn create_poll_from_template(
+        ctx: Context<CreatePollFromTemplate>,
+        round: u64,
+    ) -> Result<()> {
+        let template = &ctx.accounts.template;
         require!(
-            ctx.accounts.authority.key() == poll.authority,
-            ErrorCode::Unauthorized
+            ctx.accounts.mint.as_ref().map(|mint| mint.key()) == template.mint,
+            ErrorCode::DenominationMismatch
         );
         require!(
-            poll.status == PollStatus::Active,
-            ErrorCode::PollNotActive
+            ctx.accounts.mint.is_some() == ctx.accounts.token_vault.is_some(),
+            ErrorCode::InvalidTokenVault
         );
+        let (poll_id, start_timestamp, end_timestamp) = logic::template_round(template, round)?;
 
-        poll.status = PollStatus::Cancelled;
+        let created = create_poll(
+            &mut ctx.accounts.poll,
+            &ctx.accounts.vault,
+            &ctx.accounts.config,
+            template.mint,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            (ctx.bumps.poll, ctx.bumps.vault),
+            PollParams {
+                poll_id,
+                title: template.title.clone(),
+                outcomes: template.outcomes.clone(),
+                end_timestamp,
+                payout_mode: template.payout_mode,
+                resolution: template.resolution,
+                dispute_period: template.dispute_period,
+                arbiter: template.arbiter,
+                start_timestamp,
+                deadline_extension: template.deadline_extension,
+                virtual_liquidity: template.virtual_liquidity,
+                kind: template.kind.clone(),
+                limits: template.limits,
+                incentives: None,
+                resolution_deadline: 0,
+            },
+            Some(template.key()),
+        )?;
+        let key = ctx.accounts.poll.key();
+        logic::list_poll(&mut ctx.accounts.poll, key, &mut ctx.accounts.registry)?;
+        emit_event!(ctx, created);
 
-        emit!(PollCancelled {
-            poll: poll.key(),
-            total_pool: poll.total_pool,
+        Ok(())
+    }
+
+    /// Close a template and return its rent (template authority only). Polls
+    /// already created from it are unaffected.
+    pub fn close_poll_template(ctx: Context<ClosePollTemplate>) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+
+        emit_event!(ctx, PollTemplateClosed {
+            header: event_header(&mut template.event_seq)?,
+            template: template.key(),
         });
 
         Ok(())
     }
 
-    /// Claim refund for a cancelled poll
-    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
-        let bid = &mut ctx.accounts.bid;
-        let poll = &ctx.accounts.poll;
-
-        require!(
-            bid.bettor == ctx.accounts.bettor.key(),
-            ErrorCode::Unauthorized
-        );
-        require!(
-            poll.status == PollStatus::Cancelled,
-            ErrorCode::PollNotCancelled
-        );
-        require!(
-            bid.status == BidStatus::Active,
-            ErrorCode::BidAlreadyClaimed
-        );
+    /// Create page `page` of the poll registry (anyone). Pages are created in
+    /// order, so a client can read them from 0 until one is missing; creating
+    /// page `n` takes page `n - 1` as `previous`.
+    pub fn init_registry_page(ctx: Context<InitRegistryPage>, page: u32) -> Result<()> {
+        logic::validate_registry_page(
+            page,
+            ctx.accounts.previous.as_ref().map(|previous| previous.page),
+        )?;
 
-        let refund_amount = bid.amount;
+        let registry = &mut ctx.accounts.registry;
+        registry.page = page;
+        registry.bump = ctx.bumps.registry;
+        Ok(())
+    }
 
-        // Transfer refund from vault to bettor
-        let poll_id = poll.poll_id.as_bytes();
-        let seeds = &[
-            b"vault",
-            poll_id,
-            &[poll.vault_bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
+    /// List a poll created before the registry on a page with room (anyone)
+    pub fn list_poll(ctx: Context<ListPoll>) -> Result<()> {
+        let key = ctx.accounts.poll.key();
+        logic::list_poll(&mut ctx.accounts.poll, key, &mut ctx.accounts.registry)
+    }
 
-        let cpi_context = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.vault.to_account_info(),
-                to: ctx.accounts.bettor.to_account_info(),
-            },
-            signer_seeds,
-        );
-        anchor_lang::system_program::transfer(cpi_context, refund_amount)?;
+    /// Move the end time of a poll that has not ended, e.g. for a postponed
+    /// event (authority only). Bounded by the minimum duration and
+    /// `MAX_END_EXTENSION` past the poll's original end.
+    pub fn update_end_time(ctx: Context<UpdateEndTime>, end_timestamp: i64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
 
-        // Mark bid as refunded
-        bid.status = BidStatus::Refunded;
+        let previous =
+            logic::update_end_time(poll, end_timestamp, Clock::get()?.unix_timestamp)?;
 
-        emit!(RefundClaimed {
-            bid: bid.key(),
-            bettor: bid.bettor,
-            amount: refund_amount,
+        emit_event!(ctx, PollEndTimeUpdated {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            previous_end_timestamp: previous,
+            end_timestamp,
         });
 
         Ok(())
     }
-}
 
-/// Update AMM odds using Constant Product Market Maker algorithm
-/// Formula: odds_a = (stake_a / total_pool) with smoothing
-fn update_amm_odds(poll: &mut Poll) -> Result<()> {
-    let total = poll.total_pool;
+    /// Cap a poll's total pool, the stake on any one outcome, and each user's
+    /// total stake (authority only, before the poll ends); `None` lifts a cap
+    pub fn set_poll_caps(
+        ctx: Context<SetPollCaps>,
+        max_total_pool: Option<u64>,
+        max_stake_per_option: Option<u64>,
+        max_stake_per_user: Option<u64>,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
 
-    if total == 0 {
-        poll.option_a_odds = 5000; // 50%
-        poll.option_b_odds = 5000; // 50%
-        return Ok(());
-    }
+        logic::set_caps(poll, max_total_pool, max_stake_per_option, max_stake_per_user)?;
 
-    // Calculate raw probabilities
-    let stake_a = poll.option_a_stake as u128;
-    let stake_b = poll.option_b_stake as u128;
-    let total_u128 = total as u128;
+        emit_event!(ctx, PollCapsUpdated {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            max_total_pool,
+            max_stake_per_option,
+            max_stake_per_user,
+        });
 
-    // Probability = stake / total (in basis points)
-    let prob_a = (stake_a * BPS_DENOMINATOR as u128) / total_u128;
-    let prob_b = (stake_b * BPS_DENOMINATOR as u128) / total_u128;
+        Ok(())
+    }
 
-    // Apply smoothing to prevent extreme odds (keep between 5% and 95%)
-    let min_odds = 500u128; // 5%
-    let max_odds = 9500u128; // 95%
+    /// Let bettors cancel a bid within `cancel_window` seconds of placing it
+    /// for `cancel_fee_bps` of the stake (authority only, before the poll
+    /// ends); a window of 0 turns cancellation off. Applies to bids already
+    /// placed as well as later ones.
+    pub fn set_cancel_window(
+        ctx: Context<SetPollCaps>,
+        cancel_window: i64,
+        cancel_fee_bps: u16,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
 
-    poll.option_a_odds = prob_a.max(min_odds).min(max_odds) as u64;
-    poll.option_b_odds = prob_b.max(min_odds).min(max_odds) as u64;
+        logic::set_cancel_window(poll, cancel_window, cancel_fee_bps)?;
 
-    Ok(())
-}
+        emit_event!(ctx, CancelWindowUpdated {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            cancel_window,
+            cancel_fee_bps,
+        });
 
-// =============================================================================
-// ACCOUNT STRUCTS
-// =============================================================================
+        Ok(())
+    }
 
-#[derive(Accounts)]
+    /// Limit how far one bid may move its outcome's odds, in basis points
+    /// (authority only, before the poll ends); 0 lifts the limit. Single bids
+    /// above it are partially filled.
+    pub fn set_max_price_impact(
+        ctx: Context<SetPollCaps>,
+        max_price_impact_bps: u16,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::set_max_price_impact(poll, max_price_impact_bps)?;
+
+        emit_event!(ctx, PriceImpactLimitUpdated {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            max_price_impact_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Damp odds movement in the last `damping_window` seconds before the end
+    /// (authority only, before the poll ends). The share held back rises to
+    /// `damping_bps` at the end, and late bids are priced that much closer to
+    /// the odds they'd have moved to. A window of 0 turns damping off.
+    pub fn set_odds_damping(
+        ctx: Context<SetPollCaps>,
+        damping_window: i64,
+        damping_bps: u16,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::set_odds_damping(poll, damping_window, damping_bps)?;
+
+        emit_event!(ctx, OddsDampingUpdated {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            damping_window,
+            damping_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Build a `vig_bps` margin into the odds later bids are priced at
+    /// (authority only, before the poll ends); 0 prices bids at the AMM odds.
+    /// Bids already placed keep their locked-in potential win.
+    pub fn set_poll_vig(ctx: Context<SetPollCaps>, vig_bps: u16) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::set_poll_vig(poll, vig_bps)?;
+
+        emit_event!(ctx, PollVigUpdated {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            vig_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Make each user wait `min_seconds_between_bids` between bids on the poll,
+    /// counted from their last bid or increase (authority only, before the poll
+    /// ends); 0 lifts the cooldown
+    pub fn set_bid_cooldown(ctx: Context<SetPollCaps>, min_seconds_between_bids: i64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::set_bid_cooldown(poll, min_seconds_between_bids)?;
+
+        emit_event!(ctx, BidCooldownUpdated {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            min_seconds_between_bids,
+        });
+
+        Ok(())
+    }
+
+    /// Restrict who may bid on a poll to an allowlist, to holders of a token,
+    /// or to holders of an attestor's credential, or lift the gate with
+    /// `None` (authority only). Bids already placed stay. Send it in the same
+    /// transaction as `initialize_poll` to gate a poll from its first bid.
+    pub fn set_poll_gate(ctx: Context<SetPollGate>, gate: Option<AccessGate>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::set_gate(poll, gate)?;
+
+        emit_event!(ctx, PollGateUpdated {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            gate,
+        });
+
+        Ok(())
+    }
+
+    /// Issue `user` a credential admitting them to polls gated on the signing
+    /// attestor, valid until `expires_at` (0 for no expiry). Issuing it again
+    /// renews it.
+    pub fn issue_attestation(ctx: Context<IssueAttestation>, expires_at: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        logic::validate_attestation_expiry(expires_at, now)?;
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.attestor = ctx.accounts.attestor.key();
+        attestation.user = ctx.accounts.user.key();
+        attestation.issued_at = now;
+        attestation.expires_at = expires_at;
+        attestation.bump = ctx.bumps.attestation;
+
+        emit_event!(ctx, AttestationIssued {
+            header: event_header(&mut attestation.event_seq)?,
+            attestor: attestation.attestor,
+            user: attestation.user,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw a credential, returning its rent to the attestor (attestor
+    /// only). Bids already placed with it stay.
+    pub fn revoke_attestation(ctx: Context<RevokeAttestation>) -> Result<()> {
+        let attestation = &mut ctx.accounts.attestation;
+
+        emit_event!(ctx, AttestationRevoked {
+            header: event_header(&mut attestation.event_seq)?,
+            attestor: attestation.attestor,
+            user: attestation.user,
+        });
+
+        Ok(())
+    }
+
+    /// Hand a poll's settlement to `threshold` of up to `MAX_RESOLVERS`
+    /// resolvers (authority only). Only before the first bid, and only once:
+    /// bettors see who settles the poll before they bet.
+    pub fn set_resolvers(
+        ctx: Context<SetResolvers>,
+        resolvers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::set_resolvers(poll, resolvers.clone(), threshold)?;
+
+        emit_event!(ctx, ResolversSet {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            resolvers,
+            threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Hand a poll's settlement to an off-chain results key (authority only):
+    /// it then settles only through `settle_with_signature`. Only before the
+    /// first bid, and only once.
+    pub fn set_result_signer(ctx: Context<SetResolvers>, signer: Pubkey) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::set_result_signer(poll, signer)?;
+
+        emit_event!(ctx, ResultSignerSet {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            signer,
+        });
+
+        Ok(())
+    }
+
+    /// Create or replace a poll's metadata (authority only). The account is
+    /// resized to fit, so the authority pays or is refunded the rent difference.
+    pub fn set_poll_metadata(
+        ctx: Context<SetPollMetadata>,
+        category: PollCategory,
+        uri: String,
+        tags: Vec<String>,
+        resolution_criteria: String,
+    ) -> Result<()> {
+        logic::validate_metadata(&uri, &tags, &resolution_criteria)?;
+
+        // `init_if_needed` sizes a new account; an existing one is resized here
+        resize_account(
+            &ctx.accounts.metadata.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program,
+            PollMetadata::space(&uri, &tags, &resolution_criteria),
+        )?;
+
+        let poll = &mut ctx.accounts.poll;
+        let metadata = &mut ctx.accounts.metadata;
+        metadata.poll = poll.key();
+        metadata.bump = ctx.bumps.metadata;
+        metadata.category = category;
+        metadata.uri = uri;
+        metadata.tags = tags;
+        metadata.resolution_criteria = resolution_criteria;
+
+        emit_event!(ctx, PollMetadataUpdated {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            metadata: metadata.key(),
+            category,
+            uri: metadata.uri.clone(),
+            tags: metadata.tags.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Close a poll's metadata and return its rent to the authority
+    pub fn close_poll_metadata(ctx: Context<ClosePollMetadata>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        emit_event!(ctx, PollMetadataClosed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            metadata: ctx.accounts.metadata.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Propose a new poll authority (authority only), who takes over settlement,
+    /// fees, and closing once they call `accept_authority_transfer`; `None`
+    /// withdraws the proposal
+    pub fn propose_authority_transfer(
+        ctx: Context<ProposeAuthorityTransfer>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let authority = poll.authority;
+
+        logic::propose_transfer(&mut poll.pending_authority, authority, new_authority)?;
+
+        emit_event!(ctx, AuthorityTransferProposed {
+            header: event_header(&mut poll.event_seq)?,
+            account: poll.key(),
+            authority,
+            pending_authority: new_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Become a poll's authority (the proposed authority only)
+    pub fn accept_authority_transfer(ctx: Context<AcceptAuthorityTransfer>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let authority = ctx.accounts.pending_authority.key();
+
+        logic::accept_transfer(&mut poll.pending_authority, authority)?;
+        let previous = poll.authority;
+        poll.authority = authority;
+
+        emit_event!(ctx, AuthorityTransferred {
+            header: event_header(&mut poll.event_seq)?,
+            account: poll.key(),
+            previous_authority: previous,
+            authority,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit house liquidity into a fixed-odds SOL poll before it ends
+    /// The deposit backs locked-in wins, so thin markets can take bids; the
+    /// LP earns the poll's LP share of the platform fee and the pool's residual
+    /// after payouts, and absorbs any shortfall.
+    pub fn provide_liquidity(ctx: Context<ProvideLiquidity>, amount: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::provide_liquidity(poll, amount, Clock::get()?.unix_timestamp)?;
+
+        deposit_to_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.provider.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            poll,
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.lp_position;
+        if position.provider == Pubkey::default() {
+            position.poll = poll.key();
+            position.provider = ctx.accounts.provider.key();
+            position.bump = ctx.bumps.lp_position;
+        }
+        position.amount = math::safe_add(position.amount, amount)?;
+
+        emit_event!(ctx, LiquidityProvided {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            provider: position.provider,
+            amount,
+            lp_liquidity: poll.lp_liquidity,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw an LP position's share of a settled, cancelled, or voided SOL
+    /// poll: its share of the residual once, and LP fees accrued since its
+    /// last withdrawal on every call
+    pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let position = &mut ctx.accounts.lp_position;
+
+        let amount = logic::lp_withdrawal(poll, position)?;
+
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.provider.to_account_info(),
+            poll,
+            amount,
+        )?;
+
+        emit_cpi!(LiquidityWithdrawn {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            provider: position.provider,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Crank: open a scheduled poll whose start time has passed (anyone)
+    pub fn open_poll(ctx: Context<OpenPoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            poll.status == PollStatus::Scheduled,
+            ErrorCode::PollNotScheduled
+        );
+        logic::open_scheduled(poll, Clock::get()?.unix_timestamp)?;
+
+        emit_event!(ctx, PollOpened {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            start_timestamp: poll.start_timestamp,
+        });
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Place a bid on one of the poll's outcomes with AMM odds adjustment.
+    /// On an allowlisted poll, `allowlist_proof` proves the bettor is on it;
+    /// otherwise pass an empty proof. An optional `memo` tags the bid with
+    /// the frontend or campaign it came from; it is stored on the bid and
+    /// echoed in `BidPlaced`. An optional `sol_usd_price` values the stake
+    /// in USD, which the config's USD limit requires.
+    pub fn place_bid(
+        ctx: Context<PlaceBid>,
+        amount: u64,
+        option: u8,
+        timestamp: i64,
+        bid_index: u64,
+        allowlist_proof: Vec<[u8; 32]>,
+        memo: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let placed = place_new_bid(
+            NewBidAccounts {
+                poll: &mut accounts.poll,
+                config: &accounts.config,
+                vault: &accounts.vault,
+                bid: &mut accounts.bid,
+                profile: &mut accounts.profile,
+                user_stake: &mut accounts.user_stake,
+                position: &mut accounts.position,
+                gate_token: accounts.gate_token.as_deref(),
+                attestation: accounts.attestation.as_deref(),
+                odds_history: accounts.odds_history.as_ref(),
+                sol_usd_price: accounts.sol_usd_price.as_ref(),
+                bettor: accounts.bettor.key(),
+                referrer: accounts.referrer.as_ref().map(|referrer| referrer.key()),
+                bumps: [
+                    ctx.bumps.bid,
+                    ctx.bumps.profile,
+                    ctx.bumps.user_stake,
+                    ctx.bumps.position,
+                ],
+            },
+            StakeSource::Payer {
+                payer: &accounts.payer,
+                system_program: &accounts.system_program,
+            },
+            NewBid {
+                amount,
+                option,
+                timestamp,
+                bid_index,
+                allowlist_proof: &allowlist_proof,
+                memo,
+            },
+        )?;
+        emit_placed_bid!(ctx, placed);
+
+        Ok(())
+    }
+
+    /// Place a bid like `place_bid`, with the stake drawn from the bettor's
+    /// `UserBalance` instead of a transfer. `payer` only funds the new
+    /// accounts' rent, so a relayer can sponsor the transaction.
+    pub fn place_bid_from_balance(
+        ctx: Context<PlaceBidFromBalance>,
+        amount: u64,
+        option: u8,
+        timestamp: i64,
+        bid_index: u64,
+        allowlist_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let placed = place_new_bid(
+            NewBidAccounts {
+                poll: &mut accounts.poll,
+                config: &accounts.config,
+                vault: &accounts.vault,
+                bid: &mut accounts.bid,
+                profile: &mut accounts.profile,
+                user_stake: &mut accounts.user_stake,
+                position: &mut accounts.position,
+                gate_token: accounts.gate_token.as_deref(),
+                attestation: accounts.attestation.as_deref(),
+                odds_history: accounts.odds_history.as_ref(),
+                sol_usd_price: accounts.sol_usd_price.as_ref(),
+                bettor: accounts.bettor.key(),
+                referrer: None,
+                bumps: [
+                    ctx.bumps.bid,
+                    ctx.bumps.profile,
+                    ctx.bumps.user_stake,
+                    ctx.bumps.position,
+                ],
+            },
+            StakeSource::Balance(&mut accounts.balance),
+            NewBid {
+                amount,
+                option,
+                timestamp,
+                bid_index,
+                allowlist_proof: &allowlist_proof,
+                memo: None,
+            },
+        )?;
+        emit_placed_bid!(ctx, placed);
+
+        Ok(())
+    }
+
+    /// Grant `user` `amount` lamports of bet credit at `["credit", user]`
+    /// (config admin only). The promo vault backs it from spare lamports
+    /// returned by earlier credit bids, and the admin pays in the rest.
+    pub fn grant_credit(ctx: Context<GrantCredit>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidBetAmount);
+
+        let available = balance_above_rent(
+            &ctx.accounts.promo_vault.to_account_info(),
+            PromoVault::LEN,
+        )?;
+        let top_up = logic::credit_top_up(&ctx.accounts.promo_vault, available, amount);
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: ctx.accounts.promo_vault.to_account_info(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+
+        let promo_vault = &mut ctx.accounts.promo_vault;
+        promo_vault.bump = ctx.bumps.promo_vault;
+        let credit = &mut ctx.accounts.credit;
+        logic::init_credit(credit, ctx.accounts.user.key(), ctx.bumps.credit);
+        logic::grant_credit(promo_vault, credit, amount)?;
+
+        emit_event!(ctx, CreditGranted {
+            header: event_header(&mut credit.event_seq)?,
+            user: credit.user,
+            amount,
+            balance: credit.balance,
+            top_up,
+        });
+
+        Ok(())
+    }
+
+    /// Place a bid staked from the bettor's `BetCredit`; the stake moves from
+    /// the promo vault. The bid pays out as usual, except that its credit
+    /// principal goes back to the promo vault before the bettor is paid.
+    pub fn place_bid_with_credit(
+        ctx: Context<PlaceBidWithCredit>,
+        amount: u64,
+        option: u8,
+        timestamp: i64,
+        bid_index: u64,
+        allowlist_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let placed = place_new_bid(
+            NewBidAccounts {
+                poll: &mut accounts.poll,
+                config: &accounts.config,
+                vault: &accounts.vault,
+                bid: &mut accounts.bid,
+                profile: &mut accounts.profile,
+                user_stake: &mut accounts.user_stake,
+                position: &mut accounts.position,
+                gate_token: accounts.gate_token.as_deref(),
+                attestation: accounts.attestation.as_deref(),
+                odds_history: accounts.odds_history.as_ref(),
+                sol_usd_price: accounts.sol_usd_price.as_ref(),
+                bettor: accounts.bettor.key(),
+                referrer: None,
+                bumps: [
+                    ctx.bumps.bid,
+                    ctx.bumps.profile,
+                    ctx.bumps.user_stake,
+                    ctx.bumps.position,
+                ],
+            },
+            StakeSource::Credit {
+                promo_vault: &mut accounts.promo_vault,
+                credit: &mut accounts.credit,
+            },
+            NewBid {
+                amount,
+                option,
+                timestamp,
+                bid_index,
+                allowlist_proof: &allowlist_proof,
+                memo: None,
+            },
+        )?;
+        emit_placed_bid!(ctx, placed);
+
+        Ok(())
+    }
+
+    /// Add `amount` lamports to the caller's `UserBalance` at
+    /// `["balance", user]`, creating it on the first deposit
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidBetAmount);
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.balance.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let balance = &mut ctx.accounts.balance;
+        logic::init_balance(balance, ctx.accounts.user.key(), ctx.bumps.balance);
+        logic::credit_balance(balance, amount)?;
+
+        emit_event!(ctx, BalanceDeposited {
+            header: event_header(&mut balance.event_seq)?,
+            user: balance.user,
+            amount,
+            balance: balance.balance,
+        });
+
+        Ok(())
+    }
+
+    /// Pay `amount` of the caller's `UserBalance` back to them (works while
+    /// the program is paused)
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let balance = &mut ctx.accounts.balance;
+        logic::debit_balance(balance, amount)?;
+
+        **balance.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.user.try_borrow_mut_lamports()? += amount;
+
+        emit_event!(ctx, BalanceWithdrawn {
+            header: event_header(&mut balance.event_seq)?,
+            user: balance.user,
+            amount,
+            balance: balance.balance,
+        });
+
+        Ok(())
+    }
+
+    /// Add to an active bid on its outcome at the current odds instead of
+    /// placing a new Bid account; the bid's odds become the blended odds
+    pub fn increase_bid(ctx: Context<IncreaseBid>, amount: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let bid = &mut ctx.accounts.bid;
+
+        logic::validate_no_usd_limit(&ctx.accounts.config)?;
+        let now = Clock::get()?.unix_timestamp;
+        let odds = logic::increase_bid(poll, bid, amount, now)?;
+
+        let user_stake = &mut ctx.accounts.user_stake;
+        logic::init_user_stake(user_stake, poll.key(), bid.bettor, ctx.bumps.user_stake);
+        logic::record_user_stake(poll, user_stake, amount)?;
+
+        let position = &mut ctx.accounts.position;
+        logic::init_position(position, poll.key(), bid.bettor, ctx.bumps.position);
+        logic::record_bid_time(poll, position, now)?;
+        logic::record_position(position, bid.option, amount, None)?;
+
+        deposit_to_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.bettor.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            poll,
+            amount,
+        )?;
+
+        emit_event!(ctx, BidIncreased {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            poll: poll.key(),
+            amount,
+            odds,
+            total_amount: bid.amount,
+            potential_win: bid.potential_win,
+            odds_at_purchase: bid.odds_at_purchase,
+        });
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Assign an active bid to another wallet before settlement (bettor only)
+    pub fn transfer_bid(ctx: Context<TransferBid>, new_bettor: Pubkey) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let previous_bettor = bid.bettor;
+
+        logic::transfer_bid(&ctx.accounts.poll, bid, new_bettor)?;
+
+        emit_event!(ctx, BidTransferred {
+            header: event_header(&mut ctx.accounts.poll.event_seq)?,
+            bid: bid.key(),
+            poll: bid.poll,
+            previous_bettor,
+            bettor: new_bettor,
+        });
+
+        Ok(())
+    }
+
+    /// Place several bids, possibly across different polls, in one
+    /// transaction, each placed as `place_bid` places one without a referrer
+    /// or memo
+    /// remaining_accounts: [poll, vault, bid, user_stake, position,
+    /// odds_history] for each entry, in entry order; `odds_history` is the
+    /// program ID for a poll without one
+    pub fn place_bids_multi<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceBidsMulti<'info>>,
+        entries: Vec<BidEntry>,
+        timestamp: i64,
+    ) -> Result<()> {
+        require_ctx!(
+            !entries.is_empty() && entries.len() <= MAX_BIDS_PER_TX,
+            ErrorCode::InvalidBatchSize,
+            count = entries.len(),
+            max = MAX_BIDS_PER_TX
+        );
+        require_ctx!(
+            ctx.remaining_accounts.len() == entries.len() * 6,
+            ErrorCode::InvalidRemainingAccounts,
+            provided = ctx.remaining_accounts.len(),
+            expected = entries.len() * 6
+        );
+
+        let bettor = ctx.accounts.bettor.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+
+        for (entry, accounts) in entries.iter().zip(ctx.remaining_accounts.chunks(6)) {
+            let (poll_info, vault_info, bid_info) = (&accounts[0], &accounts[1], &accounts[2]);
+            let (user_stake_info, position_info, odds_history_info) =
+                (&accounts[3], &accounts[4], &accounts[5]);
+
+            // Deserialized per entry so repeated polls see earlier entries' writes
+            let mut poll: Account<'info, Poll> = Account::try_from(poll_info)?;
+            let poll_key = poll.key();
+
+            let vault_key = Pubkey::create_program_address(
+                &[b"vault", poll_key.as_ref(), &[poll.vault_bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(ErrorCode::InvalidVault))?;
+            require_keys_eq!(vault_info.key(), vault_key, ErrorCode::InvalidVault);
+            let vault = SystemAccount::try_from(vault_info)?;
+
+            let odds_history = (odds_history_info.key != ctx.program_id)
+                .then(|| AccountLoader::<OddsHistory>::try_from(odds_history_info))
+                .transpose()?;
+            if let Some(odds_history) = &odds_history {
+                require_keys_eq!(
+                    odds_history.load()?.poll,
+                    poll_key,
+                    ErrorCode::OddsHistoryMismatch
+                );
+            }
+
+            logic::validate_ungated(&poll)?;
+
+            let bid_bump = create_bid_account(
+                &system_program,
+                &bettor,
+                bid_info,
+                &poll_key,
+                entry.bid_index,
+                ctx.program_id,
+            )?;
+            let mut bid: Account<'info, Bid> = Account::try_from_unchecked(bid_info)?;
+            let (mut user_stake, user_stake_bump) = load_or_create_pda::<UserStake>(
+                &system_program,
+                &bettor,
+                user_stake_info,
+                &[b"user_stake", poll_key.as_ref(), bettor.key.as_ref()],
+                UserStake::LEN,
+                ctx.program_id,
+            )?;
+            let (mut position, position_bump) = load_or_create_pda::<UserPollPosition>(
+                &system_program,
+                &bettor,
+                position_info,
+                &[b"pos", poll_key.as_ref(), bettor.key.as_ref()],
+                UserPollPosition::LEN,
+                ctx.program_id,
+            )?;
+
+            let placed = place_new_bid(
+                NewBidAccounts {
+                    poll: &mut poll,
+                    config: &ctx.accounts.config,
+                    vault: &vault,
+                    bid: &mut bid,
+                    profile: &mut ctx.accounts.profile,
+                    user_stake: &mut user_stake,
+                    position: &mut position,
+                    gate_token: None,
+                    attestation: None,
+                    odds_history: odds_history.as_ref(),
+                    sol_usd_price: ctx.accounts.sol_usd_price.as_ref(),
+                    bettor: bettor.key(),
+                    referrer: None,
+                    bumps: [bid_bump, ctx.bumps.profile, user_stake_bump, position_bump],
+                },
+                StakeSource::Payer {
+                    payer: &ctx.accounts.bettor,
+                    system_program: &ctx.accounts.system_program,
+                },
+                NewBid {
+                    amount: entry.amount,
+                    option: entry.option,
+                    timestamp,
+                    bid_index: entry.bid_index,
+                    allowlist_proof: &[],
+                    memo: None,
+                },
+            )?;
+
+            emit_placed_bid!(ctx, placed);
+            bid.exit(ctx.program_id)?;
+            user_stake.exit(ctx.program_id)?;
+            position.exit(ctx.program_id)?;
+            poll.exit(ctx.program_id)?;
+        }
+
+        #[cfg(feature = "heap-audit")]
+        log_heap_usage("place_bids_multi");
+
+        Ok(())
+    }
+
+    /// Escrow `amount` in a `PendingOrder` PDA that `match_order` turns into a
+    /// bid on `option` once its odds come down to `max_odds`. The order also
+    /// escrows the bid's rent. Access gates are checked here, when the bettor
+    /// signs.
+    pub fn place_limit_bid(
+        ctx: Context<PlaceLimitBid>,
+        order_id: u64,
+        amount: u64,
+        option: u8,
+        max_odds: u64,
+        allowlist_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let poll = &mut ctx.accounts.poll;
+        logic::validate_gate(
+            poll,
+            ctx.accounts.bettor.key(),
+            &allowlist_proof,
+            ctx.accounts.gate_token.as_deref().map(logic::TokenHolding::from),
+            ctx.accounts.attestation.as_deref(),
+            now,
+        )?;
+        logic::validate_limit_order(poll, amount, option, max_odds, now)?;
+        logic::validate_no_usd_limit(&ctx.accounts.config)?;
+
+        let escrow = math::safe_add(amount, Rent::get()?.minimum_balance(Bid::LEN))?;
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.bettor.to_account_info(),
+                to: ctx.accounts.order.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, escrow)?;
+
+        let order = &mut ctx.accounts.order;
+        order.poll = poll.key();
+        order.bettor = ctx.accounts.bettor.key();
+        order.order_id = order_id;
+        order.amount = amount;
+        order.option = option;
+        order.max_odds = max_odds;
+        order.created_at = now;
+        order.bump = ctx.bumps.order;
+
+        emit_event!(ctx, LimitOrderPlaced {
+            header: event_header(&mut poll.event_seq)?,
+            order: order.key(),
+            poll: poll.key(),
+            bettor: order.bettor,
+            amount,
+            option,
+            max_odds,
+        });
+
+        Ok(())
+    }
+
+    /// Fill a limit order whose outcome's odds have come down to its limit
+    /// with a bid at `bid_index` (the poll's next index). Anyone can crank it:
+    /// the cranker pays the bid's rent and is repaid from the order's escrow,
+    /// and the order's own rent goes back to the bettor.
+    pub fn match_order(ctx: Context<MatchOrder>, bid_index: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let order = &ctx.accounts.order;
+        require_ctx!(
+            bid_index == poll.next_bid_index,
+            ErrorCode::InvalidBidIndex,
+            provided = bid_index,
+            expected = poll.next_bid_index
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let fee_adjust_bps = logic::fee_adjust_bps(poll, now);
+        logic::validate_no_usd_limit(&ctx.accounts.config)?;
+        let (current_odds, potential_win) = logic::match_order(poll, order, now)?;
+        record_odds_history(poll, ctx.accounts.odds_history.as_ref(), now)?;
+
+        // Stake to the vault, the bid's rent back to the cranker
+        let bid_rent = Rent::get()?.minimum_balance(Bid::LEN);
+        **order.to_account_info().try_borrow_mut_lamports()? -= order.amount + bid_rent;
+        **ctx.accounts.vault.try_borrow_mut_lamports()? += order.amount;
+        **ctx.accounts.cranker.try_borrow_mut_lamports()? += bid_rent;
+
+        let bid = &mut ctx.accounts.bid;
+        bid.bettor = order.bettor;
+        bid.poll = poll.key();
+        bid.amount = order.amount;
+        bid.option = order.option;
+        bid.odds_at_purchase = current_odds;
+        bid.potential_win = potential_win;
+        bid.status = BidStatus::Active;
+        bid.timestamp = now;
+        bid.index = bid_index;
+        bid.bump = ctx.bumps.bid;
+        bid.version = BID_VERSION;
+        bid.placed_at = now;
+        bid.fee_adjust_bps = fee_adjust_bps;
+
+        emit_event!(ctx, OrderMatched {
+            header: event_header(&mut poll.event_seq)?,
+            order: order.key(),
+            bid: bid.key(),
+            cranker: ctx.accounts.cranker.key(),
+        });
+        emit_event!(ctx, BidPlaced {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            poll: poll.key(),
+            amount: bid.amount,
+            option: bid.option,
+            odds: current_odds,
+            potential_win,
+            memo: None,
+            usd_notional: 0,
+            shares: potential_win,
+        });
+
+        poll.next_bid_index = math::safe_add(poll.next_bid_index, 1)?;
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Cancel a pending limit order, returning its escrow and rent to the
+    /// bettor
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        let order = &ctx.accounts.order;
+
+        emit_event!(ctx, OrderCancelled {
+            header: event_header(&mut ctx.accounts.poll.event_seq)?,
+            order: order.key(),
+            poll: order.poll,
+            bettor: order.bettor,
+        });
+
+        Ok(())
+    }
+
+    /// Record `poll`'s odds and pool at most every `interval` seconds in an
+    /// `OddsHistory` PDA that `place_bid` and `place_bid_token` append to
+    /// (authority only, before the first bid)
+    pub fn enable_odds_history(ctx: Context<EnableOddsHistory>, interval: i64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let mut history = ctx.accounts.odds_history.load_init()?;
+        history.poll = poll.key();
+        logic::enable_odds_history(poll, &mut history, interval, Clock::get()?.unix_timestamp)?;
+
+        emit_event!(ctx, OddsHistoryEnabled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            odds_history: ctx.accounts.odds_history.key(),
+            interval,
+        });
+
+        Ok(())
+    }
+
+    /// Close a resolved poll's odds history, returning the rent to the
+    /// authority
+    pub fn close_odds_history(ctx: Context<CloseOddsHistory>) -> Result<()> {
+        logic::validate_resolved(&ctx.accounts.poll)
+    }
+
+    /// Attach a bid ledger to a poll (admin only)
+    /// The ledger account is pre-allocated by the client at `BidLedger::LEN` bytes
+    pub fn init_bid_ledger(ctx: Context<InitBidLedger>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            ctx.accounts.ledger.to_account_info().data_len() == BidLedger::LEN,
+            ErrorCode::InvalidLedgerSize
+        );
+
+        let mut ledger = ctx.accounts.ledger.load_init()?;
+        ledger.poll = poll.key();
+        ledger.count = 0;
+
+        emit_event!(ctx, BidLedgerCreated {
+            header: event_header(&mut poll.event_seq)?,
+            ledger: ctx.accounts.ledger.key(),
+            poll: poll.key(),
+            capacity: LEDGER_CAPACITY as u32,
+        });
+
+        Ok(())
+    }
+
+    /// Place a bid recorded as a packed entry in a bid ledger instead of its own PDA
+    pub fn place_bid_ledger(
+        ctx: Context<PlaceBidLedger>,
+        amount: u64,
+        option: u8,
+        timestamp: i64,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let mut ledger = ctx.accounts.ledger.load_mut()?;
+
+        let index = ledger.count;
+        require_ctx!(
+            (index as usize) < LEDGER_CAPACITY,
+            ErrorCode::LedgerFull,
+            count = index,
+            capacity = LEDGER_CAPACITY
+        );
+
+        logic::validate_no_user_cap(poll)?;
+        logic::validate_no_bid_cooldown(poll)?;
+        logic::validate_no_usd_limit(&ctx.accounts.config)?;
+        logic::validate_ungated(poll)?;
+
+        // Validate, record stake, and update AMM odds
+        let now = Clock::get()?.unix_timestamp;
+        let fee_adjust_bps = logic::fee_adjust_bps(poll, now);
+        let (current_odds, potential_win) = logic::record_bid(poll, amount, option, now)?;
+
+        // Transfer SOL from bettor to vault (escrow)
+        deposit_to_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.bettor.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            poll,
+            amount,
+        )?;
+
+        let profile = &mut ctx.accounts.profile;
+        logic::init_profile(profile, ctx.accounts.bettor.key(), ctx.bumps.profile);
+        logic::profile_bid(profile, &ctx.accounts.config, amount, now)?;
+
+        ledger.records[index as usize] = LedgerBid {
+            bettor: ctx.accounts.bettor.key(),
+            amount,
+            potential_win,
+            odds_at_purchase: current_odds,
+            timestamp,
+            option,
+            status: BidStatus::Active as u8,
+            fee_adjust_bps,
+            _padding: [0; 4],
+        };
+        ledger.count = index.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+        emit_event!(ctx, LedgerBidPlaced {
+            header: event_header(&mut poll.event_seq)?,
+            ledger: ctx.accounts.ledger.key(),
+            index,
+            bettor: ctx.accounts.bettor.key(),
+            poll: poll.key(),
+            amount,
+            option,
+            odds: current_odds,
+            potential_win,
+        });
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Claim winnings for a winning ledger entry (minus the poll's platform
+    /// fee), settled as `claim_winnings` settles a bid
+    pub fn claim_winnings_ledger(ctx: Context<ClaimWinningsLedger>, index: u32) -> Result<()> {
+        let record = {
+            let ledger = ctx.accounts.ledger.load()?;
+            require_ctx!(
+                index < ledger.count,
+                ErrorCode::InvalidLedgerIndex,
+                index = index,
+                count = ledger.count
+            );
+            ledger.records[index as usize]
+        };
+
+        require!(
+            record.bettor == ctx.accounts.bettor.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(
+            ctx.accounts.poll.status == PollStatus::Settled,
+            ErrorCode::PollNotSettled
+        );
+        require!(
+            record.status == BidStatus::Active as u8,
+            ErrorCode::BidAlreadyClaimed
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut bid = record.to_bid(ctx.accounts.poll.key(), index);
+        let accounts = &mut *ctx.accounts;
+        let settled = settle_claim(
+            ClaimAccounts {
+                poll: &mut accounts.poll,
+                config: &accounts.config,
+                vault: &accounts.vault,
+                profile: &mut accounts.profile,
+                profile_bump: ctx.bumps.profile,
+                referrer: None,
+                insurance_fund: accounts.insurance_fund.as_mut(),
+                promo_vault: None,
+                system_program: &accounts.system_program,
+            },
+            accounts.ledger.key(),
+            &mut bid,
+            None,
+            None,
+            0,
+            now,
+        )?;
+
+        transfer_from_vault(
+            &accounts.system_program,
+            &accounts.vault,
+            &accounts.bettor.to_account_info(),
+            &accounts.poll,
+            settled.payout,
+        )?;
+        accounts.ledger.load_mut()?.records[index as usize].status = bid.status as u8;
+
+        emit_claim_events!(ctx, settled.events);
+        let poll = &mut ctx.accounts.poll;
+        emit_cpi!(LedgerWinningsClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            ledger: ctx.accounts.ledger.key(),
+            index,
+            bettor: record.bettor,
+            payout: settled.payout,
+            platform_fee: settled.platform_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Claim refund for a ledger entry of a cancelled poll
+    pub fn claim_refund_ledger(ctx: Context<ClaimLedger>, index: u32) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let mut ledger = ctx.accounts.ledger.load_mut()?;
+        require_ctx!(
+            index < ledger.count,
+            ErrorCode::InvalidLedgerIndex,
+            index = index,
+            count = ledger.count
+        );
+        let record = &mut ledger.records[index as usize];
+
+        require!(
+            record.bettor == ctx.accounts.bettor.key(),
+            ErrorCode::Unauthorized
+        );
+        logic::validate_refundable(poll)?;
+        require!(
+            record.status == BidStatus::Active as u8,
+            ErrorCode::BidAlreadyClaimed
+        );
+
+        let refund_amount = logic::refund_amount(poll, record.amount)?;
+
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.bettor.to_account_info(),
+            poll,
+            refund_amount,
+        )?;
+
+        record.status = BidStatus::Refunded as u8;
+
+        emit_cpi!(LedgerRefundClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            ledger: ctx.accounts.ledger.key(),
+            index,
+            bettor: record.bettor,
+            amount: refund_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Record why the poll's authority is about to settle it the way it will:
+    /// an evidence URI and a hash of the evidence, at
+    /// `["resolution_note", poll]`. Authority settlements require it, and it
+    /// can be revised until the poll is settled.
+    pub fn attach_resolution_note(
+        ctx: Context<AttachResolutionNote>,
+        uri: String,
+        evidence_hash: [u8; 32],
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        logic::validate_resolution_note(poll, &uri)?;
+
+        let note = &mut ctx.accounts.resolution_note;
+        note.poll = poll.key();
+        note.author = ctx.accounts.authority.key();
+        note.uri = uri;
+        note.evidence_hash = evidence_hash;
+        note.created_at = Clock::get()?.unix_timestamp;
+        note.bump = ctx.bumps.resolution_note;
+
+        emit_event!(ctx, ResolutionNoteAttached {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            note: note.key(),
+            uri: note.uri.clone(),
+            evidence_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Settle the poll and declare a winner (admin only)
+    pub fn settle_poll(ctx: Context<SettlePoll>, winning_option: u8) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        // Adapter markets are settled by their adapter via `settle_poll_adapter`
+        require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+        // Oracle markets are settled from the feed via `settle_poll_with_oracle`
+        require!(poll.resolution.is_none(), ErrorCode::OracleMarket);
+        // Scalar markets are settled by value via `settle_scalar`
+        require!(!matches!(poll.kind, PollKind::Scalar { .. }), ErrorCode::ScalarMarket);
+        // Council markets are settled by vote via `vote_settlement`
+        logic::validate_authority_settlement(poll)?;
+
+        let evidence = resolution_evidence(ctx.accounts.resolution_note.as_ref())?;
+        logic::settle(poll, winning_option, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(PollSettled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: winning_option,
+            total_pool: poll.total_pool,
+            pending: poll.status == PollStatus::PendingSettlement,
+            evidence: Some(evidence),
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Vote for the winner of an ended poll with a resolver council (resolvers
+    /// only). A resolver can change their vote until `threshold` votes agree;
+    /// that vote settles the poll like `settle_poll`.
+    pub fn vote_settlement(ctx: Context<VoteSettlement>, winning_option: u8) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let resolver = ctx.accounts.resolver.key();
+
+        let votes = logic::vote_settlement(
+            poll,
+            resolver,
+            winning_option,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        emit_cpi!(SettlementVoted {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            resolver,
+            winner: winning_option,
+            votes,
+        });
+        if poll.winner.is_some() {
+            emit_cpi!(PollSettled {
+                header: event_header(&mut poll.event_seq)?,
+                poll: poll.key(),
+                winner: winning_option,
+                total_pool: poll.total_pool,
+                pending: poll.status == PollStatus::PendingSettlement,
+                evidence: None,
+            });
+        }
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Settle an ended poll with a result signed by its result signer (anyone
+    /// can submit). The previous instruction must be an ed25519 program
+    /// instruction verifying the signature over
+    /// `signature::result_message(poll, winning_option)`.
+    pub fn settle_with_signature(
+        ctx: Context<SettleWithSignature>,
+        winning_option: u8,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let signed = signature::read_signed_message(&ctx.accounts.instructions)?;
+        logic::validate_signed_result(poll, poll.key(), winning_option, &signed)?;
+        logic::settle(poll, winning_option, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(PollSettled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: winning_option,
+            total_pool: poll.total_pool,
+            pending: poll.status == PollStatus::PendingSettlement,
+            evidence: None,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Vote for `option` with an active bid's stake on a poll its authority
+    /// left unsettled for `cluster::COMMUNITY_VOTE_DELAY` after it ended (the
+    /// bid's bettor only, once per bid). The poll settles, without a dispute
+    /// window, once the votes reach a quorum of its pool and a supermajority
+    /// agrees.
+    pub fn vote_outcome(ctx: Context<VoteOutcome>, option: u8) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let bid = &ctx.accounts.bid;
+
+        let votes = logic::vote_outcome(poll, bid, option, Clock::get()?.unix_timestamp)?;
+
+        let vote = &mut ctx.accounts.community_vote;
+        vote.poll = poll.key();
+        vote.voter = bid.bettor;
+        vote.option = option;
+        vote.weight = bid.amount;
+        vote.bump = ctx.bumps.community_vote;
+
+        emit_cpi!(OutcomeVoted {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            bid: bid.key(),
+            voter: bid.bettor,
+            option,
+            weight: bid.amount,
+            votes,
+        });
+        if poll.winner.is_some() {
+            emit_cpi!(PollSettled {
+                header: event_header(&mut poll.event_seq)?,
+                poll: poll.key(),
+                winner: option,
+                total_pool: poll.total_pool,
+                pending: false,
+                evidence: None,
+            });
+        }
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Close a community vote once its poll is resolved, returning the rent to
+    /// the voter
+    pub fn close_community_vote(ctx: Context<CloseCommunityVote>) -> Result<()> {
+        logic::validate_resolved(&ctx.accounts.poll)
+    }
+
+    /// Settle a scalar poll with its final `value` (authority only): the bucket
+    /// the value falls in wins
+    pub fn settle_scalar(ctx: Context<SettlePoll>, value: i64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+        require!(poll.resolution.is_none(), ErrorCode::OracleMarket);
+        logic::validate_authority_settlement(poll)?;
+
+        let evidence = resolution_evidence(ctx.accounts.resolution_note.as_ref())?;
+        let winning_option = logic::scalar_outcome(poll, value)?;
+        logic::settle(poll, winning_option, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(PollSettled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: winning_option,
+            total_pool: poll.total_pool,
+            pending: poll.status == PollStatus::PendingSettlement,
+            evidence: Some(evidence),
+        });
+        emit_cpi!(ScalarValueSubmitted {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            value,
+            winner: winning_option,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Commit to the winner of an ended poll as `sha256(winning_option || salt)`
+    /// (authority only) so the result isn't visible before it takes effect
+    pub fn commit_settlement(ctx: Context<SettlePoll>, commitment: [u8; 32]) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+        require!(poll.resolution.is_none(), ErrorCode::OracleMarket);
+        require!(!matches!(poll.kind, PollKind::Scalar { .. }), ErrorCode::ScalarMarket);
+        logic::validate_authority_settlement(poll)?;
+
+        logic::commit_settlement(poll, commitment, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(SettlementCommitted {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            commitment,
+            committed_at: poll.committed_at,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a committed poll by revealing its winner and salt, at least
+    /// `SETTLEMENT_REVEAL_DELAY` seconds after the commitment
+    pub fn reveal_settlement(
+        ctx: Context<SettlePoll>,
+        winning_option: u8,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let evidence = resolution_evidence(ctx.accounts.resolution_note.as_ref())?;
+        logic::reveal_settlement(poll, winning_option, &salt, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(SettlementRevealed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: winning_option,
+            salt,
+        });
+        emit_cpi!(PollSettled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: winning_option,
+            total_pool: poll.total_pool,
+            pending: poll.status == PollStatus::PendingSettlement,
+            evidence: Some(evidence),
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Settle the poll as a draw (admin only): no outcome wins and every bid
+    /// is refunded its stake through the refund instructions
+    pub fn settle_poll_void(ctx: Context<SettlePoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        // Adapter and oracle markets are settled by their adapter or feed
+        require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+        require!(poll.resolution.is_none(), ErrorCode::OracleMarket);
+
+        let evidence = resolution_evidence(ctx.accounts.resolution_note.as_ref())?;
+        logic::settle_void(poll, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(PollVoided {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            total_pool: poll.total_pool,
+            evidence,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Settle an oracle market from a Pyth price published within
+    /// `oracle::ORACLE_PRICE_WINDOW` seconds after the poll ended
+    /// Permissionless: the outcome is decided by the price, not the caller
+    pub fn settle_poll_with_oracle(ctx: Context<SettlePollWithOracle>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let price = oracle::read_price(&ctx.accounts.price_update)?;
+        let winning_option = logic::oracle_outcome(poll, &price)?;
+
+        logic::settle(poll, winning_option, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(PollSettled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: winning_option,
+            total_pool: poll.total_pool,
+            pending: poll.status == PollStatus::PendingSettlement,
+            evidence: None,
+        });
+        emit_cpi!(OraclePriceUsed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            price_update: ctx.accounts.price_update.key(),
+            price: price.price,
+            exponent: price.exponent,
+            publish_time: price.publish_time,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Settle an ended oracle or committed poll once `cluster::CRANK_GRACE_PERIOD`
+    /// has passed (anyone can crank), paying the caller the poll's crank
+    /// bounty from the vault. Oracle markets need `price_update`; committed
+    /// polls need the `reveal` matching the commitment.
+    pub fn crank_settle(ctx: Context<CrankSettle>, reveal: Option<SettlementReveal>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let now = Clock::get()?.unix_timestamp;
+
+        logic::validate_crank(poll, now)?;
+
+        let winning_option = if poll.resolution.is_some() {
+            let price_update = ctx
+                .accounts
+                .price_update
+                .as_ref()
+                .ok_or(ErrorCode::CrankInputMissing)?;
+            let price = oracle::read_price(price_update)?;
+            let winning_option = logic::oracle_outcome(poll, &price)?;
+            logic::settle(poll, winning_option, now)?;
+
+            emit_cpi!(OraclePriceUsed {
+                header: event_header(&mut poll.event_seq)?,
+                poll: poll.key(),
+                price_update: price_update.key(),
+                price: price.price,
+                exponent: price.exponent,
+                publish_time: price.publish_time,
+            });
+            winning_option
+        } else {
+            let reveal = reveal.ok_or(ErrorCode::CrankInputMissing)?;
+            logic::reveal_settlement(poll, reveal.winning_option, &reveal.salt, now)?;
+
+            emit_cpi!(SettlementRevealed {
+                header: event_header(&mut poll.event_seq)?,
+                poll: poll.key(),
+                winner: reveal.winning_option,
+                salt: reveal.salt,
+            });
+            reveal.winning_option
+        };
+
+        emit_cpi!(PollSettled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: winning_option,
+            total_pool: poll.total_pool,
+            pending: poll.status == PollStatus::PendingSettlement,
+            evidence: None,
+        });
+
+        let bounty = poll.crank_bounty;
+        if bounty > 0 {
+            transfer_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                &ctx.accounts.keeper.to_account_info(),
+                poll,
+                bounty,
+            )?;
+            poll.crank_bounty = 0;
+        }
+
+        emit_cpi!(SettlementCranked {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            keeper: ctx.accounts.keeper.key(),
+            winner: winning_option,
+            bounty,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Challenge a pending settlement by posting `cluster::CHALLENGE_BOND`
+    /// lamports; the poll then waits for `resolve_challenge`
+    pub fn challenge_settlement(ctx: Context<ChallengeSettlement>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let now = Clock::get()?.unix_timestamp;
+
+        logic::challenge(poll, now)?;
+
+        // Bond is held in the challenge account on top of its rent
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.challenger.to_account_info(),
+                to: ctx.accounts.challenge.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, cluster::CHALLENGE_BOND)?;
+
+        let challenge = &mut ctx.accounts.challenge;
+        challenge.poll = poll.key();
+        challenge.challenger = ctx.accounts.challenger.key();
+        challenge.proposed_winner = poll.winner.unwrap();
+        challenge.bond = cluster::CHALLENGE_BOND;
+        challenge.created_at = now;
+        challenge.bump = ctx.bumps.challenge;
+
+        emit_cpi!(SettlementChallenged {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            challenger: challenge.challenger,
+            proposed_winner: challenge.proposed_winner,
+            bond: challenge.bond,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Finalize an unchallenged settlement after its dispute window
+    /// Permissionless: the proposed winner stands
+    pub fn finalize_settlement(ctx: Context<FinalizeSettlement>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::finalize_settlement(poll, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(SettlementFinalized {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: poll.winner.unwrap(),
+            overturned: false,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Decide a challenged settlement (arbiter, or authority if none is set)
+    /// The bond returns to the challenger if the proposed winner is overturned
+    /// and goes to the poll authority otherwise; the challenge's rent always
+    /// returns to the challenger.
+    pub fn resolve_challenge(ctx: Context<ResolveChallenge>, winning_option: u8) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let overturned =
+            logic::resolve_challenge(poll, winning_option, Clock::get()?.unix_timestamp)?;
+
+        if !overturned {
+            let bond = ctx.accounts.challenge.bond;
+            **ctx.accounts.challenge.to_account_info().try_borrow_mut_lamports()? -= bond;
+            **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += bond;
+        }
+        ctx.accounts
+            .challenge
+            .close(ctx.accounts.challenger.to_account_info())?;
+
+        emit_cpi!(SettlementFinalized {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: winning_option,
+            overturned,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Approve `delegate` to claim the caller's winnings with
+    /// `claim_winnings`, e.g. a backend that pays out bettors who don't come
+    /// back. The payout still goes to the bettor's wallet. Replaces any
+    /// earlier delegate.
+    pub fn approve_claim_delegate(ctx: Context<SetClaimDelegate>, delegate: Pubkey) -> Result<()> {
+        set_claim_delegate(ctx, Some(delegate))
+    }
+
+    /// Revoke the caller's claim delegate
+    pub fn revoke_claim_delegate(ctx: Context<SetClaimDelegate>) -> Result<()> {
+        set_claim_delegate(ctx, None)
+    }
+
+    /// Claim winnings for a winning bid (minus the poll's platform fee) to
+    /// `destination`. The bettor signs, or their approved `claim_delegate`
+    /// does and `destination` must be the bettor's wallet. The fee stays in
+    /// the vault and is accrued on the poll for `sweep_fees`. `amount` claims
+    /// only part of the payout; the first claim locks in the payout and fee,
+    /// and later claims draw down the rest.
+    pub fn claim_winnings(ctx: Context<ClaimWinnings>, amount: Option<u64>) -> Result<()> {
+        require!(
+            ctx.accounts.poll.status == PollStatus::Settled,
+            ErrorCode::PollNotSettled
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let accounts = &mut *ctx.accounts;
+        let settled = settle_claim(
+            ClaimAccounts {
+                poll: &mut accounts.poll,
+                config: &accounts.config,
+                vault: &accounts.vault,
+                profile: &mut accounts.profile,
+                profile_bump: ctx.bumps.profile,
+                referrer: accounts.referrer.as_deref(),
+                insurance_fund: accounts.insurance_fund.as_mut(),
+                promo_vault: accounts.promo_vault.as_mut(),
+                system_program: &accounts.system_program,
+            },
+            accounts.bid.key(),
+            &mut accounts.bid,
+            None,
+            amount,
+            0,
+            now,
+        )?;
+
+        // Transfer winnings from vault to the destination
+        transfer_from_vault(
+            &accounts.system_program,
+            &accounts.vault,
+            &accounts.destination.to_account_info(),
+            &accounts.poll,
+            settled.payout,
+        )?;
+
+        emit_claim_events!(ctx, settled.events);
+        let poll = &mut ctx.accounts.poll;
+        let bid = &ctx.accounts.bid;
+        emit_cpi!(WinningsClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            payout: settled.payout,
+            platform_fee: settled.platform_fee,
+            destination: ctx.accounts.destination.key(),
+            unclaimed: bid.unclaimed,
+            claim_delegate: ctx.accounts.claim_delegate.as_ref().map(|delegate| delegate.key()),
+        });
+
+        Ok(())
+    }
+
+    /// Claim several winning bids of one bettor on a poll in one transaction,
+    /// each paid and charged as `claim_winnings` would
+    /// remaining_accounts: the bettor's Bid accounts on this poll, each
+    /// referred bid followed by its referrer. Bids already claimed or that
+    /// lost are skipped; at least one must pay out. The total is paid in a
+    /// single transfer and reported by one aggregated event.
+    pub fn claim_winnings_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimWinningsBatch<'info>>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.poll.status == PollStatus::Settled,
+            ErrorCode::PollNotSettled
+        );
+        require!(
+            !ctx.remaining_accounts.is_empty(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let poll_key = ctx.accounts.poll.key();
+        let bettor = ctx.accounts.bettor.key();
+        let mut claimed_count: u32 = 0;
+        let mut payout_total: u64 = 0;
+        let mut fee_total: u64 = 0;
+
+        let mut remaining = ctx.remaining_accounts.iter();
+        while let Some(bid_info) = remaining.next() {
+            let mut bid: Account<'info, Bid> = Account::try_from(bid_info)?;
+
+            require_keys_eq!(bid.poll, poll_key, ErrorCode::BidPollMismatch);
+            require_keys_eq!(bid.bettor, bettor, ErrorCode::Unauthorized);
+            // A referred bid is followed by its referrer
+            let referrer = match bid.referrer {
+                Some(_) => Some(remaining.next().ok_or(ErrorCode::ReferrerRequired)?),
+                None => None,
+            };
+
+            // Skip claimed and losing bids so a bettor can pass all of theirs
+            if bid.status != BidStatus::Active || ctx.accounts.poll.winner != Some(bid.option) {
+                continue;
+            }
+
+            let accounts = &mut *ctx.accounts;
+            let settled = settle_claim(
+                ClaimAccounts {
+                    poll: &mut accounts.poll,
+                    config: &accounts.config,
+                    vault: &accounts.vault,
+                    profile: &mut accounts.profile,
+                    profile_bump: ctx.bumps.profile,
+                    referrer,
+                    insurance_fund: accounts.insurance_fund.as_mut(),
+                    promo_vault: accounts.promo_vault.as_mut(),
+                    system_program: &accounts.system_program,
+                },
+                bid.key(),
+                &mut bid,
+                None,
+                None,
+                payout_total,
+                now,
+            )?;
+            // Written back immediately so a bid listed twice is skipped
+            bid.exit(ctx.program_id)?;
+            emit_claim_events!(ctx, settled.events);
+
+            claimed_count = claimed_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+            payout_total = math::safe_add(payout_total, settled.payout)?;
+            fee_total = math::safe_add(fee_total, settled.platform_fee)?;
+        }
+        require!(claimed_count > 0, ErrorCode::BidDidNotWin);
+
+        let poll = &mut ctx.accounts.poll;
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.bettor.to_account_info(),
+            poll,
+            payout_total,
+        )?;
+
+        emit_cpi!(WinningsBatchClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            bettor,
+            claimed_count,
+            payout: payout_total,
+            platform_fee: fee_total,
+        });
+
+        #[cfg(feature = "heap-audit")]
+        log_heap_usage("claim_winnings_batch");
+
+        Ok(())
+    }
+
+    /// Create the payout table for a settled poll, sized for every bid placed
+    /// Limited to polls whose table fits in a single account allocation
+    pub fn init_payout_table(ctx: Context<InitPayoutTable>) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+
+        require!(
+            poll.status == PollStatus::Settled,
+            ErrorCode::PollNotSettled
+        );
+
+        let mut table = ctx.accounts.payout_table.load_init()?;
+        table.poll = poll.key();
+        table.bid_count = poll.next_bid_index;
+        table.computed_count = 0;
+
+        Ok(())
+    }
+
+    /// Crank: compute final payouts for a batch of Bid PDAs into the payout table
+    /// remaining_accounts: Bid accounts of this poll, in any order
+    pub fn compute_payouts<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ComputePayouts<'info>>,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            poll.status == PollStatus::Settled,
+            ErrorCode::PollNotSettled
+        );
+
+        let table_info = ctx.accounts.payout_table.to_account_info();
+        let mut data = table_info.try_borrow_mut_data()?;
+        let (table, entries) = split_payout_table(&mut data)?;
+
+        let mut processed: u32 = 0;
+        for bid_info in ctx.remaining_accounts.iter() {
+            let bid: Account<'info, Bid> = Account::try_from(bid_info)?;
+            require_keys_eq!(bid.poll, poll.key(), ErrorCode::BidPollMismatch);
+
+            let entry = entries
+                .get_mut(bid.index as usize)
+                .ok_or(ErrorCode::InvalidBidIndex)?;
+            if entry.computed != 0 {
+                continue;
+            }
+
+            // Exited and cancelled bids were already paid out and hold no claim
+            let (payout, platform_fee) = if matches!(
+                bid.status,
+                BidStatus::Exited | BidStatus::Cancelled
+            ) {
+                (0, 0)
+            } else {
+                logic::settled_payout(
+                    poll,
+                    bid.option,
+                    bid.amount,
+                    bid.potential_win,
+                    bid.fee_adjust_bps,
+                )?
+            };
+            entry.payout = payout;
+            entry.platform_fee = platform_fee;
+            entry.computed = 1;
+
+            table.computed_count = math::safe_add(table.computed_count, 1)?;
+            processed = processed.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        emit_event!(ctx, PayoutsComputed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            processed,
+            computed_count: table.computed_count,
+            bid_count: table.bid_count,
+        });
+
+        #[cfg(feature = "heap-audit")]
+        log_heap_usage("compute_payouts");
+
+        Ok(())
+    }
+
+    /// Claim winnings using the gross win precomputed by `compute_payouts`,
+    /// paid and charged as `claim_winnings` would
+    pub fn claim_winnings_precomputed(ctx: Context<ClaimWinningsPrecomputed>) -> Result<()> {
+        require!(
+            ctx.accounts.poll.status == PollStatus::Settled,
+            ErrorCode::PollNotSettled
+        );
+
+        // The table holds the payout and fee at the poll's base fee; the
+        // claim charges the bettor's tier on their sum
+        let gross_win = {
+            let table_info = ctx.accounts.payout_table.to_account_info();
+            let mut data = table_info.try_borrow_mut_data()?;
+            let (_, entries) = split_payout_table(&mut data)?;
+            let entry = entries
+                .get(ctx.accounts.bid.index as usize)
+                .ok_or(ErrorCode::InvalidBidIndex)?;
+            require!(entry.computed != 0, ErrorCode::PayoutNotComputed);
+            math::safe_add(entry.payout, entry.platform_fee)?
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        let accounts = &mut *ctx.accounts;
+        let settled = settle_claim(
+            ClaimAccounts {
+                poll: &mut accounts.poll,
+                config: &accounts.config,
+                vault: &accounts.vault,
+                profile: &mut accounts.profile,
+                profile_bump: ctx.bumps.profile,
+                referrer: accounts.referrer.as_deref(),
+                insurance_fund: accounts.insurance_fund.as_mut(),
+                promo_vault: accounts.promo_vault.as_mut(),
+                system_program: &accounts.system_program,
+            },
+            accounts.bid.key(),
+            &mut accounts.bid,
+            Some(gross_win),
+            None,
+            0,
+            now,
+        )?;
+
+        transfer_from_vault(
+            &accounts.system_program,
+            &accounts.vault,
+            &accounts.bettor.to_account_info(),
+            &accounts.poll,
+            settled.payout,
+        )?;
+
+        emit_claim_events!(ctx, settled.events);
+        let poll = &mut ctx.accounts.poll;
+        let bid = &ctx.accounts.bid;
+        emit_cpi!(WinningsClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            payout: settled.payout,
+            platform_fee: settled.platform_fee,
+            destination: ctx.accounts.bettor.key(),
+            unclaimed: 0,
+            claim_delegate: None,
+        });
+
+        Ok(())
+    }
+
+    /// Sweep fees accrued by claims and exits from the vault to the fee vault
+    /// (authority only), less the insurance fund's share. Cancelled polls can
+    /// hold exit fees.
+    pub fn sweep_fees(ctx: Context<SweepFees>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            matches!(
+                poll.status,
+                PollStatus::Settled | PollStatus::Cancelled | PollStatus::Voided
+            ),
+            ErrorCode::PollNotSettled
+        );
+
+        let amount = poll.accrued_fees;
+        require!(amount > 0, ErrorCode::NoFeesToSweep);
+
+        let insurance = logic::insurance_share(amount, ctx.accounts.insurance_fund.fee_bps)?;
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.fee_vault.to_account_info(),
+            poll,
+            amount - insurance,
+        )?;
+        if insurance > 0 {
+            transfer_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                &ctx.accounts.insurance_fund.to_account_info(),
+                poll,
+                insurance,
+            )?;
+        }
+
+        poll.accrued_fees = 0;
+        poll.swept_fees = math::safe_add(poll.swept_fees, amount - insurance)?;
+        let fee_vault = &mut ctx.accounts.fee_vault;
+        fee_vault.total_swept = math::safe_add(fee_vault.total_swept, amount - insurance)?;
+        logic::reserve_fee_shares(fee_vault, poll, amount - insurance)?;
+        let key = poll.key();
+        logic::record_sweep(
+            &mut ctx.accounts.treasury_stats,
+            poll,
+            key,
+            amount,
+            insurance,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        emit_cpi!(FeesSwept {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            destination: fee_vault.key(),
+            amount,
+            insurance,
+        });
+
+        Ok(())
+    }
+
+    /// Create the fee vault that `sweep_fees` pays into (config admin only,
+    /// once)
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+        let fee_vault = &mut ctx.accounts.fee_vault;
+        fee_vault.total_swept = 0;
+        fee_vault.fee_share_reserve = 0;
+        fee_vault.bump = ctx.bumps.fee_vault;
+        Ok(())
+    }
+
+    /// Create the `TreasuryStats` ledger that `sweep_fees` records into, with
+    /// `withdraw_delay` seconds of timelock on withdrawals (config admin only,
+    /// once)
+    pub fn initialize_treasury_stats(
+        ctx: Context<InitializeTreasuryStats>,
+        withdraw_delay: i64,
+    ) -> Result<()> {
+        let stats = &mut ctx.accounts.treasury_stats;
+        logic::set_treasury_timelock(stats, withdraw_delay)?;
+        stats.bump = ctx.bumps.treasury_stats;
+        Ok(())
+    }
+
+    /// Raise the treasury timelock to `withdraw_delay` seconds (config admin
+    /// only). Queued withdrawals keep the time they unlock at.
+    pub fn set_treasury_timelock(
+        ctx: Context<SetTreasuryTimelock>,
+        withdraw_delay: i64,
+    ) -> Result<()> {
+        let stats = &mut ctx.accounts.treasury_stats;
+        logic::set_treasury_timelock(stats, withdraw_delay)?;
+
+        emit_cpi!(TreasuryTimelockSet {
+            header: event_header(&mut stats.event_seq)?,
+            withdraw_delay,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw `amount` of the swept fees in the fee vault to `destination`,
+    /// which must be the config's `treasury` (config admin only). Without a
+    /// timelock it's paid at once; otherwise it's queued for
+    /// `execute_treasury_withdrawal`.
+    pub fn withdraw_treasury(
+        ctx: Context<WithdrawTreasury>,
+        amount: u64,
+        destination: Pubkey,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let stats = &mut ctx.accounts.treasury_stats;
+        let unlocks_at = logic::queue_treasury_withdrawal(
+            stats,
+            amount,
+            destination,
+            ctx.accounts.config.treasury,
+            now,
+        )?;
+
+        emit_cpi!(TreasuryWithdrawalQueued {
+            header: event_header(&mut stats.event_seq)?,
+            amount,
+            destination,
+            unlocks_at,
+        });
+
+        if unlocks_at <= now {
+            let event = pay_treasury_withdrawal(
+                &ctx.accounts.fee_vault,
+                &ctx.accounts.destination,
+                stats,
+                now,
+            )?;
+            emit_cpi!(event);
+        }
+
+        Ok(())
+    }
+
+    /// Pay the queued treasury withdrawal once its timelock has passed
+    /// (anyone; it only goes to the destination it was queued for)
+    pub fn execute_treasury_withdrawal(ctx: Context<ExecuteTreasuryWithdrawal>) -> Result<()> {
+        let event = pay_treasury_withdrawal(
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.destination,
+            &mut ctx.accounts.treasury_stats,
+            Clock::get()?.unix_timestamp,
+        )?;
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    /// Drop the queued treasury withdrawal (config admin only)
+    pub fn cancel_treasury_withdrawal(ctx: Context<CancelTreasuryWithdrawal>) -> Result<()> {
+        let stats = &mut ctx.accounts.treasury_stats;
+        let withdrawal = logic::cancel_treasury_withdrawal(stats)?;
+
+        emit_cpi!(TreasuryWithdrawalCancelled {
+            header: event_header(&mut stats.event_seq)?,
+            amount: withdrawal.amount,
+            destination: withdrawal.destination,
+        });
+
+        Ok(())
+    }
+
+    /// Point the config treasury at `treasury` (config admin only). Like a
+    /// withdrawal, it takes effect at once without a timelock and is otherwise
+    /// queued for `execute_treasury_change`.
+    pub fn change_treasury(ctx: Context<ChangeTreasury>, treasury: Pubkey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let config = &mut ctx.accounts.config;
+        let stats = &mut ctx.accounts.treasury_stats;
+        let unlocks_at = logic::queue_treasury_change(stats, treasury, config.treasury, now)?;
+
+        emit_cpi!(TreasuryChangeQueued {
+            header: event_header(&mut stats.event_seq)?,
+            treasury,
+            unlocks_at,
+        });
+
+        if unlocks_at <= now {
+            let previous = config.treasury;
+            config.treasury = logic::execute_treasury_change(stats, now)?;
+            emit_cpi!(TreasuryChanged {
+                header: event_header(&mut stats.event_seq)?,
+                previous,
+                treasury,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Apply the queued treasury change once its timelock has passed (anyone)
+    pub fn execute_treasury_change(ctx: Context<ExecuteTreasuryChange>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let stats = &mut ctx.accounts.treasury_stats;
+        let previous = config.treasury;
+        config.treasury = logic::execute_treasury_change(stats, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(TreasuryChanged {
+            header: event_header(&mut stats.event_seq)?,
+            previous,
+            treasury: config.treasury,
+        });
+
+        Ok(())
+    }
+
+    /// Drop the queued treasury change (config admin only)
+    pub fn cancel_treasury_change(ctx: Context<ChangeTreasury>) -> Result<()> {
+        let stats = &mut ctx.accounts.treasury_stats;
+        let change = stats.pending_treasury.take().ok_or(ErrorCode::NoTreasuryChange)?;
+
+        emit_cpi!(TreasuryChangeCancelled {
+            header: event_header(&mut stats.event_seq)?,
+            treasury: change.treasury,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the poll creator's share of the fees accrued by claims and exits
+    /// (authority only)
+    pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            matches!(
+                poll.status,
+                PollStatus::Settled | PollStatus::Cancelled | PollStatus::Voided
+            ),
+            ErrorCode::PollNotSettled
+        );
+
+        let amount = poll.creator_fees;
+        require!(amount > 0, ErrorCode::NoFeesToSweep);
+
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.authority.to_account_info(),
+            poll,
+            amount,
+        )?;
+
+        poll.creator_fees = 0;
+
+        emit_cpi!(CreatorFeesClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            creator: ctx.accounts.authority.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Grant `party` (a creator, LP, or referrer) `share_bps` of the fees a
+    /// SOL poll sweeps into the fee vault from now on (config admin only).
+    /// The grants on one poll add up to at most the whole sweep.
+    pub fn grant_fee_share(ctx: Context<GrantFeeShare>, share_bps: u16) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let swept_at_grant = logic::grant_fee_share(poll, share_bps)?;
+
+        let fee_share = &mut ctx.accounts.fee_share;
+        fee_share.poll = poll.key();
+        fee_share.party = ctx.accounts.party.key();
+        fee_share.share_bps = share_bps;
+        fee_share.swept_at_grant = swept_at_grant;
+        fee_share.claimed = 0;
+        fee_share.bump = ctx.bumps.fee_share;
+
+        emit_event!(ctx, FeeShareGranted {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            party: fee_share.party,
+            share_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a fee share what it has earned from the poll's sweeps since the
+    /// last claim, out of the fee vault (the share's party only)
+    pub fn claim_fee_share(ctx: Context<ClaimFeeShare>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let amount = logic::claim_fee_share(&mut ctx.accounts.fee_share, poll.swept_fees)?;
+
+        let fee_vault_info = ctx.accounts.fee_vault.to_account_info();
+        let available = balance_above_rent(&fee_vault_info, FeeVault::LEN)?;
+        require_ctx!(
+            amount <= available,
+            ErrorCode::InsufficientFeeVault,
+            amount = amount,
+            available = available
+        );
+
+        **fee_vault_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.party.try_borrow_mut_lamports()? += amount;
+        logic::release_fee_share(&mut ctx.accounts.fee_vault, amount);
+
+        emit_event!(ctx, FeeShareClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            party: ctx.accounts.party.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Close a bid that no longer holds a claim and return its rent to the bettor
+    pub fn close_bid(ctx: Context<CloseBid>) -> Result<()> {
+        let bid = &ctx.accounts.bid;
+        let poll = &ctx.accounts.poll;
+        logic::validate_bid_close(poll, bid.status, bid.option)?;
+
+        // A bid still active on a settled poll lost
+        let profile = &mut ctx.accounts.profile;
+        logic::init_profile(profile, bid.bettor, ctx.bumps.profile);
+        let active_loser = bid.status == BidStatus::Active && poll.status == PollStatus::Settled;
+        if bid.status == BidStatus::Lost || active_loser {
+            let now = Clock::get()?.unix_timestamp;
+            logic::profile_loss(profile, &ctx.accounts.config, bid.amount, now)?;
+        }
+
+        Ok(())
+    }
+
+    /// Mark a bid that lost a settled poll as `Lost` (anyone can crank)
+    pub fn mark_bid_lost(ctx: Context<MarkBidLost>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+
+        logic::mark_lost(&ctx.accounts.poll, bid)?;
+
+        emit_event!(ctx, BidMarkedLost {
+            header: event_header(&mut ctx.accounts.poll.event_seq)?,
+            bid: bid.key(),
+            poll: bid.poll,
+            bettor: bid.bettor,
+        });
+
+        Ok(())
+    }
+
+    /// Expire a settled, cancelled, or voided poll once its claim period is
+    /// over (anyone can crank). Whatever is left in the vault (unclaimed
+    /// winnings and refunds, fees, rounding dust) goes to the fee vault and is
+    /// counted in `TreasuryStats`, and every later claim is rejected.
+    pub fn sweep_expired(ctx: Context<SweepExpired>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let now = Clock::get()?.unix_timestamp;
+
+        logic::expire(poll, now)?;
+
+        let swept = sweep_remainder(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &mut ctx.accounts.fee_vault,
+            &mut ctx.accounts.treasury_stats,
+            poll,
+            ctx.accounts.vault.lamports(),
+            now,
+        )?;
+
+        emit_cpi!(PollExpired {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            treasury: ctx.accounts.fee_vault.key(),
+            swept,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Close a settled, cancelled, voided, or expired poll after its claim period (admin only)
+    /// An unpaid crank bounty is returned to the authority. Whatever else is
+    /// left in the vault (unswept fees, unclaimed payouts, rounding dust) goes
+    /// to the fee vault and is counted in `TreasuryStats`, and the poll
+    /// shrinks to a `Closed` tombstone with the freed rent returned to the
+    /// authority.
+    pub fn close_poll(ctx: Context<ClosePoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let now = Clock::get()?.unix_timestamp;
+
+        logic::validate_poll_close(poll, now)?;
+        let key = poll.key();
+        logic::unlist_poll(poll, key, ctx.accounts.registry.as_deref_mut())?;
+
+        // `sweep_expired` may already have emptied the vault, bounty included.
+        // The bounty goes last so the vault never drops below rent-exemption
+        // without being emptied.
+        let bounty = poll.crank_bounty.min(ctx.accounts.vault.lamports());
+        let swept = sweep_remainder(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &mut ctx.accounts.fee_vault,
+            &mut ctx.accounts.treasury_stats,
+            poll,
+            ctx.accounts.vault.lamports() - bounty,
+            now,
+        )?;
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.authority.to_account_info(),
+            poll,
+            bounty,
+        )?;
+        poll.crank_bounty = 0;
+
+        let reclaimed = tombstone_poll(poll, &ctx.accounts.authority.to_account_info())?;
+
+        emit_cpi!(PollClosed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            swept,
+            reclaimed,
+            crank_bounty: bounty,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Finalize a settled poll once every winning bid is paid in full (anyone
+    /// can crank), without waiting out its claim period. An unpaid crank
+    /// bounty is returned to the authority, the rest of the vault (rounding
+    /// dust) goes to the fee vault and is counted in `TreasuryStats`, and the
+    /// poll becomes `Finalized`.
+    pub fn finalize_poll(ctx: Context<FinalizePoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::finalize(poll)?;
+        let key = poll.key();
+        logic::unlist_poll(poll, key, ctx.accounts.registry.as_deref_mut())?;
+
+        // The bounty goes last, as in `close_poll`
+        let bounty = poll.crank_bounty.min(ctx.accounts.vault.lamports());
+        let swept = sweep_remainder(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &mut ctx.accounts.fee_vault,
+            &mut ctx.accounts.treasury_stats,
+            poll,
+            ctx.accounts.vault.lamports() - bounty,
+            Clock::get()?.unix_timestamp,
+        )?;
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.authority.to_account_info(),
+            poll,
+            bounty,
+        )?;
+        poll.crank_bounty = 0;
+
+        emit_cpi!(PollFinalized {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            swept,
+            crank_bounty: bounty,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Cancel a poll and refund all bettors (admin only, emergency use)
+    pub fn cancel_poll(ctx: Context<CancelPoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            matches!(
+                poll.status,
+                PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+            ),
+            ErrorCode::PollNotActive
+        );
+
+        poll.status = PollStatus::Cancelled;
+        poll.finalized_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(PollCancelled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            total_pool: poll.total_pool,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Crank: cancel a poll still unsettled `resolution_deadline` seconds after
+    /// it ended (anyone), so its bettors can claim refunds
+    pub fn expire_poll(ctx: Context<ExpirePoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::expire_unresolved(poll, Clock::get()?.unix_timestamp)?;
+
+        emit_event!(ctx, PollCancelled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            total_pool: poll.total_pool,
+        });
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Halt betting on an active poll without cancelling it (poll authority
+    /// or config admin), e.g. while a match is interrupted
+    pub fn suspend_poll(ctx: Context<SuspendPoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::suspend(poll, Clock::get()?.unix_timestamp)?;
+
+        emit_event!(ctx, PollSuspended {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            by: ctx.accounts.authority.key(),
+        });
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Reopen a suspended poll (poll authority or config admin)
+    pub fn resume_poll(ctx: Context<SuspendPoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::resume(poll)?;
+
+        emit_event!(ctx, PollResumed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            by: ctx.accounts.authority.key(),
+        });
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Cancel an abusive poll and send its creation bond to the config
+    /// treasury (config admin only). Bettors are refunded as for `cancel_poll`.
+    pub fn slash_poll(ctx: Context<SlashPoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let bond = logic::slash(poll, Clock::get()?.unix_timestamp)?;
+
+        // The bond is held on the program-owned poll account, above its rent
+        let poll_info = poll.to_account_info();
+        **poll_info.try_borrow_mut_lamports()? -= bond;
+        **ctx.accounts.treasury.try_borrow_mut_lamports()? += bond;
+
+        emit_cpi!(PollSlashed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            treasury: ctx.accounts.treasury.key(),
+            bond,
+            total_pool: poll.total_pool,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Claim refund for a cancelled poll to `destination`
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let poll = &mut ctx.accounts.poll;
+
+        logic::validate_refundable(poll)?;
+        require!(
+            bid.status == BidStatus::Active,
+            ErrorCode::BidAlreadyClaimed
+        );
+
+        let refund_amount = logic::refund_amount(poll, bid.amount)?;
+        let returned = return_credit(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.promo_vault.as_mut(),
+            poll,
+            bid,
+            refund_amount,
+        )?;
+        if returned > 0 {
+            let promo_vault = ctx.accounts.promo_vault.as_mut().unwrap();
+            emit_cpi!(CreditReturned {
+                header: event_header(&mut promo_vault.event_seq)?,
+                poll: poll.key(),
+                bid: bid.key(),
+                amount: returned,
+            });
+        }
+        let refund_amount = refund_amount - returned;
+
+        // Transfer refund from vault to the destination
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.destination.to_account_info(),
+            poll,
+            refund_amount,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let profile = &mut ctx.accounts.profile;
+        logic::init_profile(profile, bid.bettor, ctx.bumps.profile);
+        logic::profile_claim(profile, &ctx.accounts.config, bid.amount, refund_amount, false, now)?;
+
+        // Mark bid as refunded
+        bid.status = BidStatus::Refunded;
+        logic::record_payment(bid, refund_amount, 0, now)?;
+
+        emit_cpi!(RefundClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            amount: refund_amount,
+            destination: ctx.accounts.destination.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Sell an active bid back to the pool before the poll ends (cash-out)
+    /// Pays the bid's potential win at its outcome's current odds, minus the
+    /// poll's platform fee, and marks the bid `Exited`.
+    pub fn exit_bid(ctx: Context<ExitBid>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            bid.status == BidStatus::Active,
+            ErrorCode::BidAlreadyClaimed
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let (payout, exit_fee) = logic::exit_position(
+            poll,
+            bid.option,
+            bid.amount,
+            bid.potential_win,
+            now,
+        )?;
+        let returned = return_credit(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.promo_vault.as_mut(),
+            poll,
+            bid,
+            payout,
+        )?;
+        if returned > 0 {
+            let promo_vault = ctx.accounts.promo_vault.as_mut().unwrap();
+            emit_cpi!(CreditReturned {
+                header: event_header(&mut promo_vault.event_seq)?,
+                poll: poll.key(),
+                bid: bid.key(),
+                amount: returned,
+            });
+        }
+        let payout = payout - returned;
+
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.bettor.to_account_info(),
+            poll,
+            payout,
+        )?;
+
+        bid.status = BidStatus::Exited;
+        logic::record_payment(bid, payout, exit_fee, now)?;
+
+        emit_cpi!(BidExited {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            bid: bid.key(),
+            bettor: bid.bettor,
+            option: bid.option,
+            payout,
+            exit_fee,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Cancel an active bid within the poll's cooling-off window, e.g. one
+    /// placed on the wrong outcome. Its stake and liability leave the
+    /// outcome, odds are repriced, and the stake is refunded less the poll's
+    /// `cancel_fee_bps`.
+    pub fn cancel_bid(ctx: Context<ExitBid>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            bid.status == BidStatus::Active,
+            ErrorCode::BidAlreadyClaimed
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let (refund, cancel_fee) = logic::cancel_position(poll, bid, now)?;
+        let returned = return_credit(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.promo_vault.as_mut(),
+            poll,
+            bid,
+            refund,
+        )?;
+        if returned > 0 {
+            let promo_vault = ctx.accounts.promo_vault.as_mut().unwrap();
+            emit_cpi!(CreditReturned {
+                header: event_header(&mut promo_vault.event_seq)?,
+                poll: poll.key(),
+                bid: bid.key(),
+                amount: returned,
+            });
+        }
+        let refund = refund - returned;
+
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.bettor.to_account_info(),
+            poll,
+            refund,
+        )?;
+
+        bid.status = BidStatus::Cancelled;
+        logic::record_payment(bid, refund, cancel_fee, now)?;
+
+        emit_cpi!(BidCancelled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            bid: bid.key(),
+            bettor: bid.bettor,
+            option: bid.option,
+            refund,
+            cancel_fee,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Push refunds for many bids of a cancelled poll in one transaction
+    /// Permissionless: funds can only move to each bid's own bettor
+    /// remaining_accounts: [bid, bettor] pairs
+    pub fn process_refunds_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessRefundsBatch<'info>>,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::validate_refundable(poll)?;
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(
+            !ctx.remaining_accounts.is_empty() && pairs.remainder().is_empty(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut refunded_count: u32 = 0;
+        let mut refunded_total: u64 = 0;
+
+        for accounts in pairs {
+            let (bid_info, bettor_info) = (&accounts[0], &accounts[1]);
+            let mut bid: Account<'info, Bid> = Account::try_from(bid_info)?;
+
+            require_keys_eq!(bid.poll, poll.key(), ErrorCode::BidPollMismatch);
+            require_keys_eq!(bid.bettor, bettor_info.key(), ErrorCode::Unauthorized);
+
+            // Skip bids already refunded so overlapping batches don't fail, and
+            // credit bids, which repay the promo vault through `claim_refund`
+            if bid.status != BidStatus::Active || bid.credit > 0 {
+                continue;
+            }
+
+            let refund_amount = logic::refund_amount(poll, bid.amount)?;
+            transfer_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                bettor_info,
+                poll,
+                refund_amount,
+            )?;
+
+            bid.status = BidStatus::Refunded;
+            logic::record_payment(&mut bid, refund_amount, 0, now)?;
+            bid.exit(ctx.program_id)?;
+
+            emit_event!(ctx, RefundClaimed {
+                header: event_header(&mut poll.event_seq)?,
+                bid: bid.key(),
+                bettor: bid.bettor,
+                amount: refund_amount,
+                destination: bid.bettor,
+            });
+
+            refunded_count = refunded_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+            refunded_total = math::safe_add(refunded_total, refund_amount)?;
+        }
+
+        emit_event!(ctx, RefundsBatchProcessed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            refunded_count,
+            refunded_total,
+        });
+
+        #[cfg(feature = "heap-audit")]
+        log_heap_usage("process_refunds_batch");
+
+        Ok(())
+    }
+
+    /// Push refunds of a cancelled or voided poll straight to its bettors, so
+    /// stakes aren't stranded waiting on each bettor to claim. Anyone can crank
+    /// it and keeps a `REFUND_TIP` out of each refund it pushes.
+    /// remaining_accounts: [bid, bettor] pairs, at most `MAX_REFUNDS_PER_TX`
+    pub fn refund_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundBatch<'info>>,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::validate_refundable(poll)?;
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(
+            !ctx.remaining_accounts.is_empty() && pairs.remainder().is_empty(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        require_ctx!(
+            pairs.len() <= MAX_REFUNDS_PER_TX,
+            ErrorCode::InvalidBatchSize,
+            count = pairs.len(),
+            max = MAX_REFUNDS_PER_TX
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut refunded_count: u32 = 0;
+        let mut refunded_total: u64 = 0;
+        let mut tips: u64 = 0;
+
+        for accounts in pairs {
+            let (bid_info, bettor_info) = (&accounts[0], &accounts[1]);
+            let mut bid: Account<'info, Bid> = Account::try_from(bid_info)?;
+
+            require_keys_eq!(bid.poll, poll.key(), ErrorCode::BidPollMismatch);
+            require_keys_eq!(bid.bettor, bettor_info.key(), ErrorCode::Unauthorized);
+
+            // Skip bids already refunded so overlapping batches don't fail, and
+            // credit bids, which repay the promo vault through `claim_refund`
+            if bid.status != BidStatus::Active || bid.credit > 0 {
+                continue;
+            }
+
+            let (refund_amount, tip) = logic::refund_tip(logic::refund_amount(poll, bid.amount)?);
+            transfer_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                bettor_info,
+                poll,
+                refund_amount,
+            )?;
+
+            bid.status = BidStatus::Refunded;
+            logic::record_payment(&mut bid, refund_amount, tip, now)?;
+            bid.exit(ctx.program_id)?;
+
+            emit_event!(ctx, RefundClaimed {
+                header: event_header(&mut poll.event_seq)?,
+                bid: bid.key(),
+                bettor: bid.bettor,
+                amount: refund_amount,
+                destination: bid.bettor,
+            });
+
+            refunded_count = refunded_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+            refunded_total = math::safe_add(refunded_total, refund_amount)?;
+            tips = math::safe_add(tips, tip)?;
+        }
+
+        // Tips go out in one transfer at the end
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.cranker.to_account_info(),
+            poll,
+            tips,
+        )?;
+
+        emit_event!(ctx, RefundsPushed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            cranker: ctx.accounts.cranker.key(),
+            refunded_count,
+            refunded_total,
+            tips,
+        });
+
+        Ok(())
+    }
+
+    /// Place a bid on a token market; the stake moves from the bettor's token
+    /// account into the poll's token vault
+    pub fn place_bid_token(
+        ctx: Context<PlaceBidToken>,
+        amount: u64,
+        option: u8,
+        timestamp: i64,
+        bid_index: u64,
+        allowlist_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let poll = &mut ctx.accounts.poll;
+        logic::validate_gate(
+            poll,
+            ctx.accounts.bettor.key(),
+            &allowlist_proof,
+            ctx.accounts.gate_token.as_deref().map(logic::TokenHolding::from),
+            ctx.accounts.attestation.as_deref(),
+            now,
+        )?;
+        require_ctx!(
+            bid_index == poll.next_bid_index,
+            ErrorCode::InvalidBidIndex,
+            provided = bid_index,
+            expected = poll.next_bid_index
+        );
+
+        // Validate, record stake, and update AMM odds, trimming the bid to what
+        // fits the poll's caps, liability, and price impact limit
+        let requested = amount;
+        let amount = logic::fill_amount(poll, amount, option, now)?;
+        // Stake only what reaches the vault after the mint's transfer fee
+        let stake = amount - mint_transfer_fee(&ctx.accounts.mint, amount)?;
+        let fee_adjust_bps = logic::fee_adjust_bps(poll, now);
+        let (current_odds, potential_win) = logic::record_bid(poll, stake, option, now)?;
+        record_odds_history(poll, ctx.accounts.odds_history.as_ref(), now)?;
+
+        let user_stake = &mut ctx.accounts.user_stake;
+        logic::init_user_stake(
+            user_stake,
+            poll.key(),
+            ctx.accounts.bettor.key(),
+            ctx.bumps.user_stake,
+        );
+        logic::record_user_stake(poll, user_stake, stake)?;
+
+        let position = &mut ctx.accounts.position;
+        logic::init_position(
+            position,
+            poll.key(),
+            ctx.accounts.bettor.key(),
+            ctx.bumps.position,
+        );
+        logic::record_bid_time(poll, position, now)?;
+        logic::record_position(position, option, stake, Some(bid_index))?;
+
+        // Transfer tokens from bettor to vault (escrow)
+        transfer_tokens_to_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.bettor_token,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.bettor,
+            amount,
+        )?;
+
+        let bid = &mut ctx.accounts.bid;
+        bid.bettor = ctx.accounts.bettor.key();
+        bid.poll = poll.key();
+        bid.amount = stake;
+        bid.option = option;
+        bid.odds_at_purchase = current_odds;
+        bid.potential_win = potential_win;
+        bid.status = BidStatus::Active;
+        bid.timestamp = timestamp;
+        bid.index = bid_index;
+        bid.bump = ctx.bumps.bid;
+        bid.version = BID_VERSION;
+        bid.placed_at = now;
+        bid.fee_adjust_bps = fee_adjust_bps;
+        bid.referrer = None;
+        bid.memo = None;
+
+        emit_event!(ctx, BidPlaced {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            poll: poll.key(),
+            amount: stake,
+            option,
+            odds: current_odds,
+            potential_win,
+            memo: None,
+            usd_notional: 0,
+            shares: potential_win,
+        });
+        if amount < requested {
+            emit_event!(ctx, BidPartiallyFilled {
+                header: event_header(&mut poll.event_seq)?,
+                bid: bid.key(),
+                requested,
+                filled: amount,
+            });
+        }
+
+        poll.next_bid_index = math::safe_add(poll.next_bid_index, 1)?;
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Add to an active bid on a token market at the current odds
+    pub fn increase_bid_token(ctx: Context<IncreaseBidToken>, amount: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let bid = &mut ctx.accounts.bid;
+
+        let stake = amount - mint_transfer_fee(&ctx.accounts.mint, amount)?;
+        let now = Clock::get()?.unix_timestamp;
+        let odds = logic::increase_bid(poll, bid, stake, now)?;
+
+        let user_stake = &mut ctx.accounts.user_stake;
+        logic::init_user_stake(user_stake, poll.key(), bid.bettor, ctx.bumps.user_stake);
+        logic::record_user_stake(poll, user_stake, stake)?;
+
+        let position = &mut ctx.accounts.position;
+        logic::init_position(position, poll.key(), bid.bettor, ctx.bumps.position);
+        logic::record_bid_time(poll, position, now)?;
+        logic::record_position(position, bid.option, stake, None)?;
+
+        transfer_tokens_to_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.bettor_token,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.bettor,
+            amount,
+        )?;
+
+        emit_event!(ctx, BidIncreased {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            poll: poll.key(),
+            amount: stake,
+            odds,
+            total_amount: bid.amount,
+            potential_win: bid.potential_win,
+            odds_at_purchase: bid.odds_at_purchase,
+        });
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Claim winnings for a winning bid on a token market (minus the poll's platform fee)
+    pub fn claim_winnings_token(ctx: Context<ClaimWinningsToken>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            poll.status == PollStatus::Settled,
+            ErrorCode::PollNotSettled
+        );
+        require!(
+            bid.status == BidStatus::Active,
+            ErrorCode::BidAlreadyClaimed
+        );
+        require!(poll.winner == Some(bid.option), ErrorCode::BidDidNotWin);
+
+        let (payout, platform_fee) = logic::settled_payout(
+            poll,
+            bid.option,
+            bid.amount,
+            bid.potential_win,
+            bid.fee_adjust_bps,
+        )?;
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.bettor_token,
+            poll,
+            payout,
+        )?;
+
+        logic::accrue_fee(poll, platform_fee)?;
+        logic::count_claim(poll)?;
+        bid.status = BidStatus::Won;
+        logic::record_payment(bid, payout, platform_fee, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(WinningsClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            payout,
+            platform_fee,
+            destination: ctx.accounts.bettor_token.key(),
+            unclaimed: 0,
+            claim_delegate: None,
+        });
+
+        Ok(())
+    }
+
+    /// Claim refund for a bid on a cancelled token market
+    pub fn claim_refund_token(ctx: Context<ClaimRefundToken>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let poll = &mut ctx.accounts.poll;
+
+        logic::validate_refundable(poll)?;
+        require!(
+            bid.status == BidStatus::Active,
+            ErrorCode::BidAlreadyClaimed
+        );
+
+        let refund_amount = logic::refund_amount(poll, bid.amount)?;
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.bettor_token,
+            poll,
+            refund_amount,
+        )?;
+
+        bid.status = BidStatus::Refunded;
+        logic::record_payment(bid, refund_amount, 0, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(RefundClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            amount: refund_amount,
+            destination: ctx.accounts.bettor_token.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Close a settled, cancelled, or voided token market after the claim grace period
+    /// (admin only); the token vault is emptied into a config treasury token
+    /// account and closed along with the poll's rent
+    pub fn close_poll_token(ctx: Context<ClosePollToken>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::validate_poll_close(poll, Clock::get()?.unix_timestamp)?;
+        let key = poll.key();
+        logic::unlist_poll(poll, key, ctx.accounts.registry.as_deref_mut())?;
+
+        let swept = ctx.accounts.vault_token.amount;
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.treasury_token,
+            poll,
+            swept,
+        )?;
+
+        close_token_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.authority.to_account_info(),
+            poll,
+        )?;
+
+        let reclaimed = tombstone_poll(poll, &ctx.accounts.authority.to_account_info())?;
+
+        emit_cpi!(PollClosed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            swept,
+            reclaimed,
+            crank_bounty: 0,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Expire a token market once its claim period is over (anyone can crank)
+    pub fn sweep_expired_token(ctx: Context<SweepExpiredToken>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::expire(poll, Clock::get()?.unix_timestamp)?;
+
+        let swept = ctx.accounts.vault_token.amount;
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.treasury_token,
+            poll,
+            swept,
+        )?;
+
+        emit_cpi!(PollExpired {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            treasury: ctx.accounts.treasury_token.owner,
+            swept,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Finalize a settled token market once every winning bid is paid in full
+    /// (anyone can crank). The token vault's dust goes to a config treasury
+    /// token account, and the vault is closed with its rent returned to the
+    /// poll authority.
+    pub fn finalize_poll_token(ctx: Context<FinalizePollToken>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::finalize(poll)?;
+        let key = poll.key();
+        logic::unlist_poll(poll, key, ctx.accounts.registry.as_deref_mut())?;
+
+        let swept = ctx.accounts.vault_token.amount;
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.treasury_token,
+            poll,
+            swept,
+        )?;
+        close_token_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.authority.to_account_info(),
+            poll,
+        )?;
+
+        emit_cpi!(PollFinalized {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            swept,
+            crank_bounty: 0,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Sell an active bid on a token market back to the pool before the poll ends
+    pub fn exit_bid_token(ctx: Context<ExitBidToken>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            bid.status == BidStatus::Active,
+            ErrorCode::BidAlreadyClaimed
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let (payout, exit_fee) = logic::exit_position(
+            poll,
+            bid.option,
+            bid.amount,
+            bid.potential_win,
+            now,
+        )?;
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.bettor_token,
+            poll,
+            payout,
+        )?;
+
+        bid.status = BidStatus::Exited;
+        logic::record_payment(bid, payout, exit_fee, now)?;
+
+        emit_cpi!(BidExited {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            bid: bid.key(),
+            bettor: bid.bettor,
+            option: bid.option,
+            payout,
+            exit_fee,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Sweep fees accrued on a token market to a config treasury token account (admin only)
+    pub fn sweep_fees_token(ctx: Context<SweepFeesToken>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            matches!(
+                poll.status,
+                PollStatus::Settled | PollStatus::Cancelled | PollStatus::Voided
+            ),
+            ErrorCode::PollNotSettled
+        );
+
+        let amount = poll.accrued_fees;
+        require!(amount > 0, ErrorCode::NoFeesToSweep);
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.treasury_token,
+            poll,
+            amount,
+        )?;
+
+        poll.accrued_fees = 0;
+
+        emit_cpi!(FeesSwept {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            destination: ctx.accounts.treasury_token.key(),
+            amount,
+            insurance: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the poll creator's share of the fees accrued on a token market to a
+    /// creator token account (authority only)
+    pub fn claim_creator_fees_token(ctx: Context<ClaimCreatorFeesToken>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            matches!(
+                poll.status,
+                PollStatus::Settled | PollStatus::Cancelled | PollStatus::Voided
+            ),
+            ErrorCode::PollNotSettled
+        );
+
+        let amount = poll.creator_fees;
+        require!(amount > 0, ErrorCode::NoFeesToSweep);
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.creator_token,
+            poll,
+            amount,
+        )?;
+
+        poll.creator_fees = 0;
+
+        emit_cpi!(CreatorFeesClaimed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            creator: ctx.accounts.authority.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit house liquidity into a fixed-odds token market before it ends
+    pub fn provide_liquidity_token(ctx: Context<ProvideLiquidityToken>, amount: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let liquidity = amount - mint_transfer_fee(&ctx.accounts.mint, amount)?;
+        logic::provide_liquidity(poll, liquidity, Clock::get()?.unix_timestamp)?;
+
+        transfer_tokens_to_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.provider_token,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.provider,
+            amount,
+        )?;
+
+        let position = &mut ctx.accounts.lp_position;
+        if position.provider == Pubkey::default() {
+            position.poll = poll.key();
+            position.provider = ctx.accounts.provider.key();
+            position.bump = ctx.bumps.lp_position;
+        }
+        position.amount = math::safe_add(position.amount, liquidity)?;
+
+        emit_event!(ctx, LiquidityProvided {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            provider: position.provider,
+            amount: liquidity,
+            lp_liquidity: poll.lp_liquidity,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw an LP position's share of a settled, cancelled, or voided
+    /// token market to a provider token account
+    pub fn withdraw_liquidity_token(ctx: Context<WithdrawLiquidityToken>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let position = &mut ctx.accounts.lp_position;
+
+        let amount = logic::lp_withdrawal(poll, position)?;
+
+        transfer_tokens_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token,
+            &ctx.accounts.provider_token,
+            poll,
+            amount,
+        )?;
+
+        emit_cpi!(LiquidityWithdrawn {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            provider: position.provider,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Switch a poll with no bids to outcome tokens (authority only): bids mint
+    /// a transferable SPL token per outcome instead of creating Bid accounts
+    pub fn enable_outcome_tokens(ctx: Context<EnableOutcomeTokens>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::enable_outcome_tokens(poll)?;
+
+        emit_event!(ctx, OutcomeTokensEnabled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Bet on an outcome-token market: the stake goes to the vault and the
+    /// bettor receives the outcome's tokens, minted by the poll PDA
+    pub fn place_bid_outcome_token(
+        ctx: Context<PlaceBidOutcomeToken>,
+        amount: u64,
+        option: u8,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let now = Clock::get()?.unix_timestamp;
+        logic::validate_no_user_cap(poll)?;
+        logic::validate_no_bid_cooldown(poll)?;
+        logic::validate_no_usd_limit(&ctx.accounts.config)?;
+        logic::validate_ungated(poll)?;
+        let (odds, tokens) = logic::record_outcome_token_bid(poll, amount, option, now)?;
+
+        deposit_to_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.bettor.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            poll,
+            amount,
+        )?;
+
+        let poll_id = poll.poll_id.as_bytes();
+        let seeds = &[
+            b"poll",
+            poll_id,
+            &[poll.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.outcome_mint.to_account_info(),
+                to: ctx.accounts.bettor_outcome_token.to_account_info(),
+                authority: poll.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::mint_to(cpi_context, tokens)?;
+
+        emit_event!(ctx, OutcomeTokensMinted {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            bettor: ctx.accounts.bettor.key(),
+            option,
+            amount,
+            odds,
+            tokens,
+        });
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Burn outcome tokens of a final poll for their payout: winnings (minus
+    /// the poll's platform fee) once settled, or a refund if cancelled or voided
+    pub fn redeem_outcome_tokens(
+        ctx: Context<RedeemOutcomeTokens>,
+        option: u8,
+        tokens: u64,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        let (payout, platform_fee) = logic::redeem_outcome_tokens(poll, option, tokens)?;
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.outcome_mint.to_account_info(),
+                from: ctx.accounts.holder_outcome_token.to_account_info(),
+                authority: ctx.accounts.holder.to_account_info(),
+            },
+        );
+        token::burn(cpi_context, tokens)?;
+
+        transfer_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.holder.to_account_info(),
+            poll,
+            payout,
+        )?;
+
+        emit_cpi!(OutcomeTokensRedeemed {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            holder: ctx.accounts.holder.key(),
+            option,
+            tokens,
+            payout,
+            platform_fee,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Approve an adapter program to host markets (upgrade authority only)
+    pub fn register_adapter(ctx: Context<RegisterAdapter>) -> Result<()> {
+        let market_adapter = &mut ctx.accounts.market_adapter;
+        market_adapter.program = ctx.accounts.adapter_program.key();
+        market_adapter.approved = true;
+        market_adapter.bump = ctx.bumps.market_adapter;
+
+        // Always logged: `event_cpi` would clash with the `program` account
+        emit!(AdapterApprovalChanged {
+            header: event_header(&mut market_adapter.event_seq)?,
+            program: market_adapter.program,
+            approved: true,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke or restore an adapter's approval (upgrade authority only)
+    /// Revoking blocks new bids and settlement; claims and refunds still work
+    pub fn set_adapter_approval(ctx: Context<SetAdapterApproval>, approved: bool) -> Result<()> {
+        let market_adapter = &mut ctx.accounts.market_adapter;
+        market_adapter.approved = approved;
+
+        // Always logged: `event_cpi` would clash with the `program` account
+        emit!(AdapterApprovalChanged {
+            header: event_header(&mut market_adapter.event_seq)?,
+            program: market_adapter.program,
+            approved,
+        });
+
+        Ok(())
+    }
+
+    /// Hand pricing and settlement of a fresh poll to an approved adapter (admin only)
+    pub fn attach_adapter(ctx: Context<AttachAdapter>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(
+            matches!(poll.status, PollStatus::Active | PollStatus::Scheduled),
+            ErrorCode::PollNotActive
+        );
+        require!(poll.resolution.is_none(), ErrorCode::OracleMarket);
+        require!(!poll.outcome_tokens, ErrorCode::OutcomeTokenMarket);
+        // Adapter quotes are fixed odds; a pari-mutuel pool has none to lock in
+        require!(
+            poll.payout_mode == PayoutMode::FixedOdds,
+            ErrorCode::AdapterPayoutMode
+        );
+        // Bids already priced by the AMM can't be re-priced by the adapter
+        require_ctx!(
+            poll.next_bid_index == 0 && poll.total_pool == 0,
+            ErrorCode::PollHasBids,
+            next_bid_index = poll.next_bid_index,
+            total_pool = poll.total_pool
+        );
+
+        poll.adapter = Some(ctx.accounts.market_adapter.program);
+
+        emit_event!(ctx, PollAdapterAttached {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            adapter: ctx.accounts.market_adapter.program,
+        });
+
+        Ok(())
+    }
+
+    /// Place a bid on an adapter market, priced by the adapter's `yukti_quote`
+    pub fn place_bid_adapter(
+        ctx: Context<PlaceBidAdapter>,
+        amount: u64,
+        option: u8,
+        timestamp: i64,
+        bid_index: u64,
+    ) -> Result<()> {
+        require_ctx!(
+            bid_index == ctx.accounts.poll.next_bid_index,
+            ErrorCode::InvalidBidIndex,
+            provided = bid_index,
+            expected = ctx.accounts.poll.next_bid_index
+        );
+
+        let quoted_odds = adapter::request_quote(
+            &ctx.accounts.adapter_program.to_account_info(),
+            &ctx.accounts.poll.to_account_info(),
+            option,
+            amount,
+        )?;
+        require_ctx!(
+            (MIN_ODDS_BPS..=MAX_ODDS_BPS).contains(&quoted_odds),
+            ErrorCode::InvalidAdapterQuote,
+            odds = quoted_odds,
+            min = MIN_ODDS_BPS,
+            max = MAX_ODDS_BPS
+        );
+
+        let poll = &mut ctx.accounts.poll;
+        let now = Clock::get()?.unix_timestamp;
+        logic::validate_no_user_cap(poll)?;
+        logic::validate_no_bid_cooldown(poll)?;
+        logic::validate_no_usd_limit(&ctx.accounts.config)?;
+        logic::validate_ungated(poll)?;
+        let fee_adjust_bps = logic::fee_adjust_bps(poll, now);
+        let potential_win = logic::record_bid_at_odds(poll, amount, option, quoted_odds, now)?;
+        logic::count_bid(poll, option)?;
+
+        // Transfer SOL from bettor to vault (escrow)
+        deposit_to_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.bettor.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            poll,
+            amount,
+        )?;
+
+        let bid = &mut ctx.accounts.bid;
+        bid.bettor = ctx.accounts.bettor.key();
+        bid.poll = poll.key();
+        bid.amount = amount;
+        bid.option = option;
+        bid.odds_at_purchase = quoted_odds;
+        bid.potential_win = potential_win;
+        bid.status = BidStatus::Active;
+        bid.timestamp = timestamp;
+        bid.index = bid_index;
+        bid.bump = ctx.bumps.bid;
+        bid.version = BID_VERSION;
+        bid.placed_at = now;
+        bid.fee_adjust_bps = fee_adjust_bps;
+        bid.referrer = None;
+        bid.memo = None;
+
+        emit_event!(ctx, BidPlaced {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            poll: poll.key(),
+            amount,
+            option,
+            odds: quoted_odds,
+            potential_win,
+            memo: None,
+            usd_notional: 0,
+            shares: potential_win,
+        });
+
+        poll.next_bid_index = math::safe_add(poll.next_bid_index, 1)?;
+
+        emit_event!(ctx, poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Settle an adapter market; called by the adapter via CPI, signed by its
+    /// `adapter::adapter_authority` PDA for the poll
+    pub fn settle_poll_adapter(
+        ctx: Context<SettlePollAdapter>,
+        winning_option: u8,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        logic::settle(poll, winning_option, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(PollSettled {
+            header: event_header(&mut poll.event_seq)?,
+            poll: poll.key(),
+            winner: winning_option,
+            total_pool: poll.total_pool,
+            pending: poll.status == PollStatus::PendingSettlement,
+            evidence: None,
+        });
+
+        emit_cpi!(poll_state_updated(poll)?);
+
+        Ok(())
+    }
+
+    /// Create the house pool that backs parlay wins (config admin only, once)
+    pub fn initialize_parlay_pool(ctx: Context<InitializeParlayPool>) -> Result<()> {
+        let pool = &mut ctx.accounts.parlay_pool;
+        pool.liability = 0;
+        pool.bump = ctx.bumps.parlay_pool;
+        Ok(())
+    }
+
+    /// Add `amount` lamports of backing to the parlay pool (anyone)
+    pub fn fund_parlay_pool(ctx: Context<FundParlayPool>, amount: u64) -> Result<()> {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.parlay_pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        emit_event!(ctx, ParlayPoolFunded {
+            header: event_header(&mut ctx.accounts.parlay_pool.event_seq)?,
+            funder: ctx.accounts.funder.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Move `amount` of the parlay pool's free balance, beyond rent and what
+    /// open parlays could win, to the treasury (config admin only)
+    pub fn withdraw_parlay_pool(ctx: Context<WithdrawParlayPool>, amount: u64) -> Result<()> {
+        let pool_info = ctx.accounts.parlay_pool.to_account_info();
+        let available = parlay_pool_available(&pool_info, ctx.accounts.parlay_pool.liability)?;
+        require_ctx!(
+            amount <= available,
+            ErrorCode::InsufficientParlayPool,
+            amount = amount,
+            available = available
+        );
+
+        **pool_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.treasury.try_borrow_mut_lamports()? += amount;
+
+        emit_cpi!(ParlayPoolWithdrawn {
+            header: event_header(&mut ctx.accounts.parlay_pool.event_seq)?,
+            treasury: ctx.accounts.treasury.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Stake `amount` on 2 to 5 legs, one per poll, that must all win. The
+    /// legs' polls are passed in `remaining_accounts`, in the order of
+    /// `options`. The stake goes to the parlay pool, which backs the win at
+    /// the product of the legs' current odds; the polls' pools are untouched.
+    pub fn place_parlay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceParlay<'info>>,
+        parlay_id: u64,
+        amount: u64,
+        options: Vec<u8>,
+        timestamp: i64,
+    ) -> Result<()> {
+        require_ctx!(
+            ctx.remaining_accounts.len() == options.len(),
+            ErrorCode::InvalidRemainingAccounts,
+            provided = ctx.remaining_accounts.len(),
+            expected = options.len()
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let legs = options
+            .iter()
+            .zip(ctx.remaining_accounts)
+            .map(|(&option, poll_info)| {
+                let poll: Account<'info, Poll> = Account::try_from(poll_info)?;
+                logic::parlay_leg(&poll, poll.key(), option, amount, now)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let potential_win = logic::parlay_potential_win(amount, &legs)?;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.bettor.to_account_info(),
+                    to: ctx.accounts.parlay_pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        // The pool must cover this win on top of every open parlay's
+        let pool_info = ctx.accounts.parlay_pool.to_account_info();
+        let pool = &mut ctx.accounts.parlay_pool;
+        let available = parlay_pool_available(&pool_info, pool.liability)?;
+        require_ctx!(
+            potential_win <= available,
+            ErrorCode::InsufficientParlayPool,
+            potential_win = potential_win,
+            available = available
+        );
+        pool.liability = math::safe_add(pool.liability, potential_win)?;
+
+        let parlay = &mut ctx.accounts.parlay;
+        parlay.bettor = ctx.accounts.bettor.key();
+        parlay.parlay_id = parlay_id;
+        parlay.amount = amount;
+        parlay.potential_win = potential_win;
+        parlay.fee_bps = ctx.accounts.config.fee_bps;
+        parlay.timestamp = timestamp;
+        parlay.legs = legs;
+        parlay.bump = ctx.bumps.parlay;
+
+        emit_event!(ctx, ParlayPlaced {
+            header: event_header(&mut pool.event_seq)?,
+            parlay: parlay.key(),
+            bettor: parlay.bettor,
+            amount,
+            potential_win,
+            legs: parlay.legs.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Pay a parlay whose legs have all won or been voided and close it
+    /// (bettor only). Every leg's poll is passed in `remaining_accounts`, in
+    /// leg order.
+    pub fn claim_parlay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimParlay<'info>>,
+    ) -> Result<()> {
+        let parlay = &ctx.accounts.parlay;
+        require_ctx!(
+            ctx.remaining_accounts.len() == parlay.legs.len(),
+            ErrorCode::InvalidRemainingAccounts,
+            provided = ctx.remaining_accounts.len(),
+            expected = parlay.legs.len()
+        );
+
+        let results = parlay
+            .legs
+            .iter()
+            .zip(ctx.remaining_accounts)
+            .map(|(leg, poll_info)| {
+                require_keys_eq!(poll_info.key(), leg.poll, ErrorCode::ParlayLegMismatch);
+                let poll: Account<'info, Poll> = Account::try_from(poll_info)?;
+                Ok(logic::parlay_leg_result(&poll, leg))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (payout, platform_fee) = logic::parlay_payout(parlay, &results)?;
+
+        // The fee stays in the pool as house revenue
+        let pool = &mut ctx.accounts.parlay_pool;
+        pool.liability = math::safe_sub(pool.liability, parlay.potential_win)?;
+        **pool.to_account_info().try_borrow_mut_lamports()? -= payout;
+        **ctx.accounts.bettor.try_borrow_mut_lamports()? += payout;
+
+        emit_cpi!(ParlayClaimed {
+            header: event_header(&mut pool.event_seq)?,
+            parlay: parlay.key(),
+            bettor: parlay.bettor,
+            payout,
+            platform_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Close a parlay with a leg that lost, releasing its backing in the pool
+    /// (permissionless crank; the rent returns to the bettor)
+    pub fn mark_parlay_lost(ctx: Context<MarkParlayLost>) -> Result<()> {
+        let parlay = &ctx.accounts.parlay;
+        let poll = &ctx.accounts.poll;
+        let leg = parlay
+            .legs
+            .iter()
+            .find(|leg| leg.poll == poll.key())
+            .ok_or(ErrorCode::ParlayLegMismatch)?;
+        require!(
+            logic::parlay_leg_result(poll, leg) == logic::ParlayLegResult::Lost,
+            ErrorCode::ParlayNotLost
+        );
+
+        let pool = &mut ctx.accounts.parlay_pool;
+        pool.liability = math::safe_sub(pool.liability, parlay.potential_win)?;
+
+        emit_cpi!(ParlayLost {
+            header: event_header(&mut pool.event_seq)?,
+            parlay: parlay.key(),
+            bettor: parlay.bettor,
+            poll: poll.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Create the insurance fund that covers vault shortfalls on claims, taking
+    /// `fee_bps` of swept platform fees (config admin only, once)
+    pub fn initialize_insurance_fund(
+        ctx: Context<InitializeInsuranceFund>,
+        fee_bps: u16,
+    ) -> Result<()> {
+        require_ctx!(
+            u64::from(fee_bps) <= BPS_DENOMINATOR,
+            ErrorCode::InvalidFeeBps,
+            fee_bps = fee_bps,
+            max = BPS_DENOMINATOR
+        );
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.fee_bps = fee_bps;
+        fund.total_covered = 0;
+        fund.bump = ctx.bumps.insurance_fund;
+        Ok(())
+    }
+
+    /// Change the insurance fund's share of swept platform fees (config admin
+    /// only). Applies to sweeps from then on.
+    pub fn set_insurance_fee(ctx: Context<ManageInsuranceFund>, fee_bps: u16) -> Result<()> {
+        require_ctx!(
+            u64::from(fee_bps) <= BPS_DENOMINATOR,
+            ErrorCode::InvalidFeeBps,
+            fee_bps = fee_bps,
+            max = BPS_DENOMINATOR
+        );
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.fee_bps = fee_bps;
+
+        emit_event!(ctx, InsuranceFeeSet {
+            header: event_header(&mut fund.event_seq)?,
+            fee_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Add `amount` lamports to the insurance fund (config admin only)
+    pub fn deposit_insurance(ctx: Context<ManageInsuranceFund>, amount: u64) -> Result<()> {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: ctx.accounts.insurance_fund.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        emit_event!(ctx, InsuranceDeposited {
+            header: event_header(&mut ctx.accounts.insurance_fund.event_seq)?,
+            admin: ctx.accounts.admin.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Move `amount` of the insurance fund's balance beyond rent to the
+    /// treasury (config admin only)
+    pub fn withdraw_insurance(ctx: Context<WithdrawInsurance>, amount: u64) -> Result<()> {
+        let fund_info = ctx.accounts.insurance_fund.to_account_info();
+        let available = balance_above_rent(&fund_info, InsuranceFund::LEN)?;
+        require_ctx!(
+            amount <= available,
+            ErrorCode::InsufficientInsuranceFund,
+            amount = amount,
+            available = available
+        );
+
+        **fund_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.treasury.try_borrow_mut_lamports()? += amount;
+
+        emit_cpi!(InsuranceWithdrawn {
+            header: event_header(&mut ctx.accounts.insurance_fund.event_seq)?,
+            treasury: ctx.accounts.treasury.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade a poll written by an earlier release to `POLL_VERSION` in place
+    /// (anyone). The payer covers the rent of any room the new layout needs,
+    /// and a poll from before version 12 has its SOL moved from its
+    /// `["vault", poll_id]` vault to `["vault", poll]`.
+    pub fn migrate_poll(ctx: Context<MigratePoll>) -> Result<()> {
+        let poll_info = ctx.accounts.poll.to_account_info();
+        make_room_for_version::<Poll>(
+            &poll_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program,
+            Poll::LEN,
+        )?;
+
+        let mut poll = Poll::try_deserialize(&mut &poll_info.try_borrow_data()?[..])?;
+        let from_version = logic::migrate_poll(&mut poll)?;
+        if from_version < 12 {
+            poll.vault_bump = move_legacy_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.legacy_vault.to_account_info(),
+                &ctx.accounts.vault.to_account_info(),
+                &poll,
+                poll_info.key(),
+            )?;
+        }
+
+        emit_event!(ctx, AccountMigrated {
+            header: event_header(&mut poll.event_seq)?,
+            account: poll_info.key(),
+            poll: poll_info.key(),
+            from_version,
+            version: poll.version,
+        });
+
+        let mut data = poll_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data;
+        poll.try_serialize(&mut writer)
+    }
+
+    /// Upgrade a bid written by an earlier release to `BID_VERSION` in place
+    /// (anyone), once its poll is readable. The payer covers the rent of any
+    /// room the new layout needs.
+    pub fn migrate_bid(ctx: Context<MigrateBid>) -> Result<()> {
+        let bid_info = ctx.accounts.bid.to_account_info();
+        make_room_for_version::<Bid>(
+            &bid_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program,
+            Bid::LEN,
+        )?;
+
+        let mut bid = Bid::try_deserialize(&mut &bid_info.try_borrow_data()?[..])?;
+        let poll = &mut ctx.accounts.poll;
+        require_keys_eq!(bid.poll, poll.key(), ErrorCode::BidPollMismatch);
+        let from_version = logic::migrate_bid(&mut bid)?;
+
+        emit_event!(ctx, AccountMigrated {
+            header: event_header(&mut poll.event_seq)?,
+            account: bid_info.key(),
+            poll: poll.key(),
+            from_version,
+            version: bid.version,
+        });
+
+        let mut data = bid_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data;
+        bid.try_serialize(&mut writer)
+    }
+
+    /// Create an event at `["event", event_id]` grouping related polls (one
+    /// match, several markets) under the caller as their shared resolution
+    /// authority
+    pub fn create_event(ctx: Context<CreateEvent>, event_id: String) -> Result<()> {
+        logic::validate_event_id(&event_id)?;
+
+        let event = &mut ctx.accounts.event;
+        event.authority = ctx.accounts.authority.key();
+        event.event_id = event_id;
+        event.status = EventStatus::Open;
+        event.polls = Vec::new();
+        event.total_volume = 0;
+        event.event_seq = 0;
+        event.bump = ctx.bumps.event;
+
+        emit_event!(ctx, MarketEventCreated {
+            header: event_header(&mut event.event_seq)?,
+            event: event.key(),
+            authority: event.authority,
+            event_id: event.event_id.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Register a poll under an open event (authority of both, which must be
+    /// the same)
+    pub fn add_event_poll(ctx: Context<AddEventPoll>) -> Result<()> {
+        let event = &mut ctx.accounts.event;
+        let poll = &ctx.accounts.poll;
+
+        logic::add_event_poll(event, poll.key(), poll)?;
+
+        emit_event!(ctx, EventPollAdded {
+            header: event_header(&mut event.event_seq)?,
+            event: event.key(),
+            poll: poll.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Settle every poll of an event in one pass (event authority only), each
+    /// like `settle_poll` with its entry of `winners`. Polls already settled,
+    /// cancelled, or voided on their own are skipped.
+    /// remaining_accounts: the event's polls, in the order they were added
+    pub fn settle_event<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleEvent<'info>>,
+        winners: Vec<u8>,
+    ) -> Result<()> {
+        let event = &mut ctx.accounts.event;
+        require!(event.status == EventStatus::Open, ErrorCode::EventSettled);
+        require_ctx!(
+            winners.len() == event.polls.len(),
+            ErrorCode::InvalidBatchSize,
+            count = winners.len(),
+            expected = event.polls.len()
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut settled = 0u8;
+        let mut total_volume = 0u64;
+        for (poll_info, &winner) in event_polls(event, ctx.remaining_accounts)?.zip(&winners) {
+            let mut poll: Account<'info, Poll> = Account::try_from(poll_info)?;
+            total_volume = math::safe_add(total_volume, poll.total_pool)?;
+            if !logic::event_poll_unresolved(&poll) {
+                continue;
+            }
+            // The authority may have moved since the poll was added
+            require_keys_eq!(poll.authority, event.authority, ErrorCode::Unauthorized);
+            require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+            require!(poll.resolution.is_none(), ErrorCode::OracleMarket);
+            require!(!matches!(poll.kind, PollKind::Scalar { .. }), ErrorCode::ScalarMarket);
+            logic::validate_authority_settlement(&poll)?;
+
+            logic::settle(&mut poll, winner, now)?;
+            settled += 1;
+
+            emit_cpi!(PollSettled {
+                header: event_header(&mut poll.event_seq)?,
+                poll: poll.key(),
+                winner,
+                total_pool: poll.total_pool,
+                pending: poll.status == PollStatus::PendingSettlement,
+                evidence: None,
+            });
+            emit_cpi!(poll_state_updated(&mut poll)?);
+            poll.exit(ctx.program_id)?;
+        }
+
+        event.total_volume = total_volume;
+        event.status = EventStatus::Settled;
+
+        emit_cpi!(EventSettled {
+            header: event_header(&mut event.event_seq)?,
+            event: event.key(),
+            settled,
+            total_volume: event.total_volume,
+        });
+
+        Ok(())
+    }
+
+    /// Crank: refresh an event's combined volume from its polls (anyone)
+    /// remaining_accounts: the event's polls, in the order they were added
+    pub fn sync_event<'info>(ctx: Context<'_, '_, 'info, 'info, SyncEvent<'info>>) -> Result<()> {
+        let event = &mut ctx.accounts.event;
+
+        let mut total_volume = 0u64;
+        for poll_info in event_polls(event, ctx.remaining_accounts)? {
+            let poll: Account<'info, Poll> = Account::try_from(poll_info)?;
+            total_volume = math::safe_add(total_volume, poll.total_pool)?;
+        }
+        event.total_volume = total_volume;
+
+        emit_event!(ctx, EventSynced {
+            header: event_header(&mut event.event_seq)?,
+            event: event.key(),
+            total_volume: event.total_volume,
+        });
+
+        Ok(())
+    }
+
+    /// Open leaderboard epoch `epoch_id` at `["epoch", epoch_id]` (config
+    /// admin only), funding its reward pool from the admin. Bids, claims, and
+    /// closed losing bids between `start_timestamp` and `end_timestamp` count
+    /// toward users' scores by `metric`, and the top `weights.len()` share the
+    /// pool by `weights`. Epochs are numbered from 1 and open one at a time.
+    pub fn init_epoch(
+        ctx: Context<InitEpoch>,
+        epoch_id: u32,
+        start_timestamp: i64,
+        end_timestamp: i64,
+        metric: EpochMetric,
+        weights: Vec<u16>,
+        reward_pool: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let config = &mut ctx.accounts.config;
+        logic::validate_epoch(
+            config,
+            epoch_id,
+            start_timestamp,
+            end_timestamp,
+            &weights,
+            reward_pool,
+            now,
+        )?;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: ctx.accounts.epoch.to_account_info(),
+                },
+            ),
+            reward_pool,
+        )?;
+
+        config.epoch_id = epoch_id;
+        config.epoch_start = start_timestamp;
+        config.epoch_end = end_timestamp;
+
+        let epoch = &mut ctx.accounts.epoch;
+        epoch.epoch_id = epoch_id;
+        epoch.start_timestamp = start_timestamp;
+        epoch.end_timestamp = end_timestamp;
+        epoch.metric = metric;
+        epoch.reward_pool = reward_pool;
+        epoch.weights = weights;
+        epoch.leaderboard = Vec::new();
+        epoch.finalized = false;
+        epoch.paid = 0;
+        epoch.event_seq = 0;
+        epoch.bump = ctx.bumps.epoch;
+
+        emit_event!(ctx, EpochOpened {
+            header: event_header(&mut epoch.event_seq)?,
+            epoch: epoch.key(),
+            epoch_id,
+            start_timestamp,
+            end_timestamp,
+            metric,
+            reward_pool,
+        });
+
+        Ok(())
+    }
+
+    /// Enter a user's score for an ended epoch from their profile (anyone can
+    /// crank, until `EPOCH_SUBMISSION_PERIOD` after the end). The score takes
+    /// its rank on the leaderboard if it beats the lowest one there.
+    pub fn submit_epoch_score(ctx: Context<SubmitEpochScore>) -> Result<()> {
+        let epoch = &mut ctx.accounts.epoch;
+        let profile = &ctx.accounts.profile;
+        let now = Clock::get()?.unix_timestamp;
+        let (score, rank) = logic::submit_epoch_score(epoch, profile, now)?;
+
+        emit_event!(ctx, EpochScoreSubmitted {
+            header: event_header(&mut epoch.event_seq)?,
+            epoch: epoch.key(),
+            user: profile.user,
+            score,
+            rank,
+        });
+
+        Ok(())
+    }
+
+    /// Fix each ranked user's reward once scores can no longer be submitted
+    /// (anyone can crank). The share of unfilled ranks and rounding dust goes
+    /// to the treasury.
+    pub fn finalize_epoch(ctx: Context<FinalizeEpoch>) -> Result<()> {
+        let epoch = &mut ctx.accounts.epoch;
+        let now = Clock::get()?.unix_timestamp;
+        let returned = logic::finalize_epoch(epoch, now)?;
+
+        **epoch.to_account_info().try_borrow_mut_lamports()? -= returned;
+        **ctx.accounts.treasury.try_borrow_mut_lamports()? += returned;
+
+        emit_event!(ctx, EpochFinalized {
+            header: event_header(&mut epoch.event_seq)?,
+            epoch: epoch.key(),
+            winners: epoch.leaderboard.len() as u8,
+            distributed: epoch.reward_pool - returned,
+            returned,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the caller their reward from a finalized epoch, once
+    pub fn claim_epoch_reward(ctx: Context<ClaimEpochReward>) -> Result<()> {
+        let epoch = &mut ctx.accounts.epoch;
+        let user = &ctx.accounts.user;
+        let (rank, amount) = logic::claim_epoch_reward(epoch, user.key())?;
+
+        **epoch.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **user.try_borrow_mut_lamports()? += amount;
+
+        emit_event!(ctx, EpochRewardClaimed {
+            header: event_header(&mut epoch.event_seq)?,
+            epoch: epoch.key(),
+            user: user.key(),
+            rank,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Read-only view: return a versioned snapshot of the poll via return data
+    /// so CPI callers don't depend on the `Poll` account layout
+    pub fn get_poll_state(ctx: Context<GetPollState>) -> Result<PollState> {
+        let poll = &ctx.accounts.poll;
+
+        Ok(PollState {
+            version: VIEW_VERSION,
+            poll: poll.key(),
+            authority: poll.authority,
+            status: poll.status,
+            winner: poll.winner,
+            outcome_stakes: poll.outcomes.iter().map(|outcome| outcome.stake).collect(),
+            outcome_odds: poll.outcomes.iter().map(|outcome| outcome.odds).collect(),
+            total_pool: poll.total_pool,
+            end_timestamp: poll.end_timestamp,
+            next_bid_index: poll.next_bid_index,
+            accrued_fees: poll.accrued_fees,
+            adapter: poll.adapter,
+            mint: poll.mint,
+            payout_mode: poll.payout_mode,
+            resolution: poll.resolution,
+            dispute_period: poll.dispute_period,
+            settlement_proposed_at: poll.settlement_proposed_at,
+            arbiter: poll.arbiter,
+            challenged: poll.challenged,
+            fee_bps: poll.fee_bps,
+            min_bet: poll.min_bet,
+            max_bet: poll.max_bet,
+            finalized_at: poll.finalized_at,
+            creation_bond: poll.creation_bond,
+            creator_fee_bps: poll.creator_fee_bps,
+            creator_fees: poll.creator_fees,
+            start_timestamp: poll.start_timestamp,
+            initial_end_timestamp: poll.initial_end_timestamp,
+            deadline_extension: poll.deadline_extension,
+            extension_count: poll.extension_count,
+            virtual_liquidity: poll.virtual_liquidity,
+            lp_liquidity: poll.lp_liquidity,
+            lp_fee_bps: poll.lp_fee_bps,
+            lp_fees: poll.lp_fees,
+            outcome_tokens: poll.outcome_tokens,
+            claim_period: poll.claim_period,
+            pending_authority: poll.pending_authority,
+            max_total_pool: poll.max_total_pool,
+            max_stake_per_option: poll.max_stake_per_option,
+            max_stake_per_user: poll.max_stake_per_user,
+            settlement_commitment: poll.settlement_commitment,
+            referral_fee_bps: poll.referral_fee_bps,
+            fee_tiers: poll.fee_tiers.clone(),
+            kind: poll.kind.clone(),
+            crank_bounty: poll.crank_bounty,
+            event_seq: poll.event_seq,
+            gate: poll.gate,
+            council: poll.council.clone(),
+            result_signer: poll.result_signer,
+            community_votes: poll.community_votes.clone(),
+            total_fees: poll.total_fees,
+            odds_history: poll.odds_history,
+            incentives: poll.incentives,
+            resolution_deadline: poll.resolution_deadline,
+            swept_fees: poll.swept_fees,
+            fee_share_bps: poll.fee_share_bps,
+            cancel_window: poll.cancel_window,
+            cancel_fee_bps: poll.cancel_fee_bps,
+            max_price_impact_bps: poll.max_price_impact_bps,
+            bid_counts: poll.bid_counts.clone(),
+            claimed_count: poll.claimed_count,
+            min_seconds_between_bids: poll.min_seconds_between_bids,
+            registry_page: poll.registry_page,
+            damping_window: poll.damping_window,
+            damping_bps: poll.damping_bps,
+            vig_bps: poll.vig_bps,
+        })
+    }
+
+    /// Read-only view: return a versioned snapshot of the bid via return data
+    pub fn get_bid_state(ctx: Context<GetBidState>) -> Result<BidState> {
+        let bid = &ctx.accounts.bid;
+
+        Ok(BidState {
+            version: VIEW_VERSION,
+            bid: bid.key(),
+            bettor: bid.bettor,
+            poll: bid.poll,
+            amount: bid.amount,
+            option: bid.option,
+            odds_at_purchase: bid.odds_at_purchase,
+            potential_win: bid.potential_win,
+            status: bid.status,
+            timestamp: bid.timestamp,
+            index: bid.index,
+            referrer: bid.referrer,
+            unclaimed: bid.unclaimed,
+            claimed_amount: bid.claimed_amount,
+            fee_paid: bid.fee_paid,
+            claimed_at: bid.claimed_at,
+            fee_adjust_bps: bid.fee_adjust_bps,
+            memo: bid.memo,
+            placed_at: bid.placed_at,
+            credit: bid.credit,
+            usd_notional_at_purchase: bid.usd_notional_at_purchase,
+        })
+    }
+
+    /// Read-only view: quote a bid of `amount` on `option` via return data,
+    /// priced and validated as `place_bid` would, so simulating it gives an
+    /// authoritative quote. Pass the bettor's profile to apply their fee tier.
+    pub fn quote_bid(ctx: Context<QuoteBid>, amount: u64, option: u8) -> Result<BidQuote> {
+        let volume = ctx.accounts.profile.as_ref().map_or(0, |profile| profile.volume);
+        let now = Clock::get()?.unix_timestamp;
+
+        logic::quote_bid(&ctx.accounts.poll, amount, option, volume, now)
+    }
+
+    /// Read-only view: return each outcome's implied probability, share price,
+    /// and shares outstanding via return data, for frontends quoting prices
+    /// per share instead of odds
+    pub fn get_market_state(ctx: Context<GetMarketState>) -> Result<MarketState> {
+        let poll = &ctx.accounts.poll;
+        logic::market_state(poll, poll.key())
+    }
+}
+
+/// Log how much of the 32KB heap the current instruction has consumed.
+///
+/// The default bump allocator never frees; it allocates downward from the end
+/// of the heap region and keeps its cursor in the region's first word, so the
+/// bytes in use are `heap_end - cursor`. Enabled by the `heap-audit` feature
+/// to profile batch instructions that loop over deserialized accounts.
+#[cfg(feature = "heap-audit")]
+fn log_heap_usage(label: &str) {
+    #[cfg(target_os = "solana")]
+    {
+        use anchor_lang::solana_program::entrypoint::{HEAP_LENGTH, HEAP_START_ADDRESS};
+
+        // SAFETY: the runtime maps the heap region at HEAP_START_ADDRESS and the
+        // allocator stores its cursor there; this is a plain read of that word.
+        let cursor = unsafe { *(HEAP_START_ADDRESS as *const usize) };
+        let heap_end = HEAP_START_ADDRESS as usize + HEAP_LENGTH;
+        let used = if cursor == 0 { 0 } else { heap_end - cursor };
+        msg!("heap-audit {}: {} of {} bytes", label, used, HEAP_LENGTH);
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    msg!("heap-audit {}: only measured on-chain", label);
+}
+
+/// The evidence of an authority settlement, from the poll's resolution note
+fn resolution_evidence(note: Option<&Account<ResolutionNote>>) -> Result<ResolutionEvidence> {
+    let note = note.ok_or(ErrorCode::ResolutionNoteRequired)?;
+    Ok(ResolutionEvidence {
+        note: note.key(),
+        uri: note.uri.clone(),
+        evidence_hash: note.evidence_hash,
+    })
+}
+
+/// Move lamports out of a poll's escrow vault, signing with the vault PDA seeds.
+///
+/// The vault is a system-owned PDA, so the program cannot debit it by editing
+/// lamports directly (only the owning program may do that); a signed
+/// `system_program::transfer` is required. Zero-amount transfers are skipped
+/// to save the CPI.
+fn transfer_from_vault<'info>(
+    system_program: &Program<'info, System>,
+    vault: &SystemAccount<'info>,
+    to: &AccountInfo<'info>,
+    poll: &Account<'info, Poll>,
+    amount: u64,
+) -> Result<()> {
+    // Token markets hold their escrow in the token vault
+    require!(poll.mint.is_none(), ErrorCode::DenominationMismatch);
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let poll_key = poll.key();
+    let seeds = &[
+        b"vault",
+        poll_key.as_ref(),
+        &[poll.vault_bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_context = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        anchor_lang::system_program::Transfer {
+            from: vault.to_account_info(),
+            to: to.clone(),
+        },
+        signer_seeds,
+    );
+    anchor_lang::system_program::transfer(cpi_context, amount)
+}
+
+/// Move a poll's SOL from the vault its `poll_id` derived before poll version
+/// 12 to the one its address derives, returning the new vault's bump. The old
+/// vault is left empty.
+fn move_legacy_vault<'info>(
+    system_program: &Program<'info, System>,
+    legacy_vault: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    poll: &Poll,
+    poll_key: Pubkey,
+) -> Result<u8> {
+    let legacy_key = Pubkey::create_program_address(
+        &[b"vault", poll.poll_id.as_bytes(), &[poll.vault_bump]],
+        &crate::ID,
+    )
+    .map_err(|_| error!(ErrorCode::InvalidVault))?;
+    require_keys_eq!(legacy_vault.key(), legacy_key, ErrorCode::InvalidVault);
+    let (vault_key, vault_bump) =
+        Pubkey::find_program_address(&[b"vault", poll_key.as_ref()], &crate::ID);
+    require_keys_eq!(vault.key(), vault_key, ErrorCode::InvalidVault);
+
+    let amount = legacy_vault.lamports();
+    if amount > 0 {
+        let poll_id = poll.poll_id.as_bytes();
+        let seeds = &[b"vault", poll_id, &[poll.vault_bump]];
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: legacy_vault.clone(),
+                    to: vault.clone(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+    }
+    Ok(vault_bump)
+}
+
+/// The accounts a winning claim pays or updates besides the bid and its
+/// destination
+struct ClaimAccounts<'a, 'info> {
+    poll: &'a mut Account<'info, Poll>,
+    config: &'a Config,
+    vault: &'a SystemAccount<'info>,
+    profile: &'a mut Account<'info, UserProfile>,
+    profile_bump: u8,
+    referrer: Option<&'a AccountInfo<'info>>,
+    insurance_fund: Option<&'a mut Account<'info, InsuranceFund>>,
+    promo_vault: Option<&'a mut Account<'info, PromoVault>>,
+    system_program: &'a Program<'info, System>,
+}
+
+/// What `settle_claim` leaves for the caller to pay and emit
+struct SettledClaim {
+    /// Lamports owed to the claim's destination, after any credit returned
+    payout: u64,
+    platform_fee: u64,
+    events: ClaimEvents,
+}
+
+/// The events of a claim's side payments, in the order `emit_claim_events!`
+/// emits them
+struct ClaimEvents {
+    referral: Option<ReferralPaid>,
+    shortfall: Option<ShortfallCovered>,
+    credit_returned: Option<CreditReturned>,
+}
+
+/// Claim `amount` of a winning bid's payout (all of it when `None`), the same
+/// way on every claim path. The first claim splits the bid's gross win
+/// (`gross_win` if precomputed) at the bettor's volume fee tier, pays the
+/// referrer's share of the fee and accrues the rest, and counts the win on
+/// the profile. Each claim then draws down `unclaimed`, covers a vault short
+/// of the payout plus the `pending` lamports the caller still owes from it
+/// out of the insurance fund, and returns a credit bid's principal to the
+/// promo vault. `bid_key` names the bid in events, which for a ledger entry
+/// is the ledger's address.
+fn settle_claim<'info>(
+    accounts: ClaimAccounts<'_, 'info>,
+    bid_key: Pubkey,
+    bid: &mut Bid,
+    gross_win: Option<u64>,
+    amount: Option<u64>,
+    pending: u64,
+    now: i64,
+) -> Result<SettledClaim> {
+    let ClaimAccounts {
+        poll,
+        config,
+        vault,
+        profile,
+        profile_bump,
+        referrer,
+        insurance_fund,
+        mut promo_vault,
+        system_program,
+    } = accounts;
+    require!(
+        matches!(bid.status, BidStatus::Active | BidStatus::PartiallyClaimed),
+        ErrorCode::BidAlreadyClaimed
+    );
+
+    // The payout and fee are settled on the first claim only
+    let mut platform_fee = 0;
+    let mut referral = None;
+    if bid.status == BidStatus::Active {
+        require!(poll.winner == Some(bid.option), ErrorCode::BidDidNotWin);
+        logic::init_profile(profile, bid.bettor, profile_bump);
+
+        // Calculate payout: gross win - platform_fee, at the bettor's volume tier
+        let gross_win = match gross_win {
+            Some(gross_win) => gross_win,
+            None => logic::gross_win(poll, bid.amount, bid.potential_win)?,
+        };
+        let payout;
+        (payout, platform_fee) =
+            logic::tiered_split(poll, gross_win, bid.fee_adjust_bps, profile.volume)?;
+        bid.unclaimed = payout;
+
+        // Referred bids must name their referrer. An emptied referrer account
+        // can't take a payment below rent exemption, so its share stays with
+        // the treasury.
+        let referrer = match bid.referrer {
+            Some(referrer_key) => {
+                let referrer = referrer.ok_or(ErrorCode::ReferrerRequired)?;
+                require_keys_eq!(referrer.key(), referrer_key, ErrorCode::ReferrerMismatch);
+                Some(referrer).filter(|referrer| referrer.lamports() > 0)
+            }
+            None => None,
+        };
+
+        // Accrue platform fee; the referrer's share is paid now and the rest is
+        // swept to the treasury separately
+        if let Some(referrer) = referrer {
+            let referral_fee = logic::accrue_referred_fee(poll, platform_fee)?;
+            transfer_from_vault(system_program, vault, referrer, poll, referral_fee)?;
+            referral = Some(ReferralPaid {
+                header: event_header(&mut poll.event_seq)?,
+                bid: bid_key,
+                referrer: referrer.key(),
+                amount: referral_fee,
+            });
+        } else {
+            logic::accrue_fee(poll, platform_fee)?;
+        }
+
+        logic::profile_claim(profile, config, bid.amount, payout, true, now)?;
+    }
+
+    // Marks the bid `Won` once nothing is left to claim
+    let payout = logic::claim_part(bid, amount)?;
+    logic::record_payment(bid, payout, platform_fee, now)?;
+    if bid.status == BidStatus::Won {
+        logic::count_claim(poll)?;
+    }
+
+    // A vault left short by fixed-odds wins draws the difference from the
+    // insurance fund
+    let available = match &insurance_fund {
+        Some(fund) => balance_above_rent(&fund.to_account_info(), InsuranceFund::LEN)?,
+        None => 0,
+    };
+    let vault_balance = vault.lamports().saturating_sub(pending);
+    let cover = logic::insurance_cover(vault_balance, payout, available)?;
+    let mut shortfall = None;
+    if let Some(fund) = insurance_fund.filter(|_| cover > 0) {
+        **fund.to_account_info().try_borrow_mut_lamports()? -= cover;
+        **vault.try_borrow_mut_lamports()? += cover;
+        fund.total_covered = math::safe_add(fund.total_covered, cover)?;
+        shortfall = Some(ShortfallCovered {
+            header: event_header(&mut fund.event_seq)?,
+            poll: poll.key(),
+            bid: bid_key,
+            amount: cover,
+        });
+    }
+
+    // A credit bid's principal goes back to the promo vault first
+    let returned = return_credit(
+        system_program,
+        vault,
+        promo_vault.as_deref_mut(),
+        poll,
+        bid,
+        payout,
+    )?;
+    let mut credit_returned = None;
+    if let Some(promo_vault) = promo_vault.filter(|_| returned > 0) {
+        credit_returned = Some(CreditReturned {
+            header: event_header(&mut promo_vault.event_seq)?,
+            poll: poll.key(),
+            bid: bid_key,
+            amount: returned,
+        });
+    }
+
+    Ok(SettledClaim {
+        payout: math::safe_sub(payout, returned)?,
+        platform_fee,
+        events: ClaimEvents {
+            referral,
+            shortfall,
+            credit_returned,
+        },
+    })
+}
+
+/// Send the part of `payment` that repays a credit bid's principal from the
+/// vault to the promo vault, returning how much went back; the rest is the
+/// bettor's. Bids placed without credit return nothing.
+fn return_credit<'info>(
+    system_program: &Program<'info, System>,
+    vault: &SystemAccount<'info>,
+    promo_vault: Option<&mut Account<'info, PromoVault>>,
+    poll: &Account<'info, Poll>,
+    bid: &mut Bid,
+    payment: u64,
+) -> Result<u64> {
+    if bid.credit == 0 {
+        return Ok(0);
+    }
+    let promo_vault = promo_vault.ok_or(ErrorCode::PromoVaultRequired)?;
+    let returned = logic::return_credit(promo_vault, bid, payment)?;
+    transfer_from_vault(
+        system_program,
+        vault,
+        &promo_vault.to_account_info(),
+        poll,
+        returned,
+    )?;
+    Ok(returned)
+}
+
+/// Approve or revoke the signer's claim delegate
+fn set_claim_delegate(ctx: Context<SetClaimDelegate>, delegate: Option<Pubkey>) -> Result<()> {
+    let profile = &mut ctx.accounts.profile;
+    logic::init_profile(profile, ctx.accounts.user.key(), ctx.bumps.profile);
+    logic::set_claim_delegate(profile, delegate)?;
+
+    emit_event!(ctx, ClaimDelegateUpdated {
+        header: event_header(&mut profile.event_seq)?,
+        user: profile.user,
+        delegate,
+    });
+
+    Ok(())
+}
+
+/// `PollStateUpdated` for `poll` as it stands, as the next event of its stream
+fn poll_state_updated(poll: &mut Account<'_, Poll>) -> Result<PollStateUpdated> {
+    Ok(PollStateUpdated {
+        header: event_header(&mut poll.event_seq)?,
+        poll: poll.key(),
+        status: poll.status,
+        winner: poll.winner,
+        outcome_stakes: poll.outcomes.iter().map(|outcome| outcome.stake).collect(),
+        outcome_odds: poll.outcomes.iter().map(|outcome| outcome.odds).collect(),
+        total_pool: poll.total_pool,
+        next_bid_index: poll.next_bid_index,
+    })
+}
+
+/// Header for the next event of the stream counted by `event_seq`
+fn event_header(event_seq: &mut u64) -> Result<EventHeader> {
+    *event_seq = math::safe_add(*event_seq, 1)?;
+    let clock = Clock::get()?;
+    Ok(EventHeader {
+        event_seq: *event_seq,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    })
+}
+
+/// Log `event` as the data of a self-invocation signed by the event
+/// authority, as `emit_cpi!` does
+#[cfg(feature = "cpi-events")]
+fn emit_event_cpi<'info>(
+    event_authority: &AccountInfo<'info>,
+    event_authority_bump: u8,
+    event: &impl anchor_lang::Event,
+) -> Result<()> {
+    let data: Vec<u8> = anchor_lang::event::EVENT_IX_TAG_LE
+        .iter()
+        .copied()
+        .chain(event.data())
+        .collect();
+    let ix = anchor_lang::solana_program::instruction::Instruction::new_with_bytes(
+        crate::ID,
+        &data,
+        vec![AccountMeta::new_readonly(event_authority.key(), true)],
+    );
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        std::slice::from_ref(event_authority),
+        &[&[b"__event_authority", &[event_authority_bump]]],
+    )
+    .map_err(Into::into)
+}
+
+/// Mark a poll closed and shrink its account to the tombstone's serialized
+/// size, moving the freed rent to `authority`. The account is kept rather than
+/// closed so the poll address cannot be re-created under the same id while
+/// bids, ledgers, and payout tables still point at it. Returns the lamports
+/// moved.
+fn tombstone_poll<'info>(
+    poll: &mut Account<'info, Poll>,
+    authority: &AccountInfo<'info>,
+) -> Result<u64> {
+    logic::close_poll(poll);
+
+    let mut data = Vec::new();
+    poll.try_serialize(&mut data)?;
+    let poll_info = poll.to_account_info();
+    poll_info.resize(data.len())?;
+
+    let rent = Rent::get()?.minimum_balance(data.len());
+    let reclaimed = poll_info.lamports().saturating_sub(rent);
+    **poll_info.try_borrow_mut_lamports()? -= reclaimed;
+    **authority.try_borrow_mut_lamports()? += reclaimed;
+    Ok(reclaimed)
+}
+
+/// Validate a new poll, take its creation bond and crank bounty from
+/// `authority`, and fill in `poll`. Returns the `PollCreated` event for the
+/// caller to emit.
+#[allow(clippy::too_many_arguments)]
+fn create_poll<'info>(
+    poll: &mut Account<'info, Poll>,
+    vault: &SystemAccount<'info>,
+    config: &Config,
+    mint: Option<Pubkey>,
+    authority: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    (poll_bump, vault_bump): (u8, u8),
+    params: PollParams,
+    template: Option<Pubkey>,
+) -> Result<PollCreated> {
+    let PollParams {
+        poll_id,
+        title,
+        outcomes,
+        end_timestamp,
+        payout_mode,
+        resolution,
+        dispute_period,
+        arbiter,
+        start_timestamp,
+        deadline_extension,
+        virtual_liquidity,
+        kind,
+        limits,
+        incentives,
+        resolution_deadline,
+    } = params;
+    let now = Clock::get()?.unix_timestamp;
+    let start_timestamp = start_timestamp.max(now);
+    logic::validate_poll_metadata(&poll_id, &title, &outcomes)?;
+    logic::validate_poll_kind(&kind, payout_mode, outcomes.len())?;
+    logic::validate_resolution_source(resolution.as_ref(), &kind, outcomes.len())?;
+    logic::validate_dispute_period(dispute_period)?;
+    logic::validate_deadline_extension(deadline_extension.as_ref())?;
+    logic::validate_virtual_liquidity(virtual_liquidity)?;
+    logic::validate_end_timestamp(end_timestamp, start_timestamp)?;
+    let (min_bet, max_bet, fee_bps) = logic::poll_limits(config, &limits)?;
+    logic::validate_incentives(
+        incentives.as_ref(),
+        fee_bps,
+        start_timestamp,
+        end_timestamp,
+    )?;
+    logic::validate_resolution_deadline(resolution_deadline)?;
+
+    // The title and outcome labels are only logged here; the poll keeps
+    // their hashes so bids don't deserialize the text
+    let created = PollCreated {
+        // The new poll's `event_seq` starts at zero
+        header: event_header(&mut poll.event_seq)?,
+        poll: poll.key(),
+        authority: authority.key(),
+        poll_id,
+        title,
+        outcome_count: outcomes.len() as u8,
+        outcomes,
+        end_timestamp,
+        mint,
+        payout_mode,
+        resolution,
+        dispute_period,
+        arbiter,
+        fee_bps,
+        creation_bond: config.creation_bond,
+        creator_fee_bps: config.creator_fee_bps,
+        start_timestamp,
+        deadline_extension,
+        virtual_liquidity,
+        lp_fee_bps: config.lp_fee_bps,
+        referral_fee_bps: config.referral_fee_bps,
+        claim_period: config.claim_period,
+        kind,
+        // Only SOL vaults can pay a keeper in lamports
+        crank_bounty: if mint.is_none() { cluster::CRANK_BOUNTY } else { 0 },
+        template,
+        min_bet,
+        max_bet,
+        incentives,
+        resolution_deadline,
+    };
+
+    if config.creation_bond > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: authority.to_account_info(),
+                    to: poll.to_account_info(),
+                },
+            ),
+            config.creation_bond,
+        )?;
+    }
+
+    if created.crank_bounty > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: authority.to_account_info(),
+                    to: vault.to_account_info(),
+                },
+            ),
+            created.crank_bounty,
+        )?;
+    }
+
+    poll.authority = created.authority;
+    poll.poll_id = created.poll_id.clone();
+    poll.title_hash = logic::text_hash(&created.title);
+    poll.outcomes = logic::new_outcomes(&created.outcomes)?; // equal initial odds
+    poll.total_pool = 0;
+    poll.end_timestamp = end_timestamp;
+    poll.status = if start_timestamp > now {
+        PollStatus::Scheduled
+    } else {
+        PollStatus::Active
+    };
+    poll.winner = None;
+    poll.vault_bump = vault_bump;
+    poll.bump = poll_bump;
+    poll.next_bid_index = 0;
+    poll.accrued_fees = 0;
+    poll.adapter = None;
+    poll.mint = mint;
+    poll.payout_mode = payout_mode;
+    poll.resolution = resolution;
+    poll.dispute_period = dispute_period;
+    poll.settlement_proposed_at = 0;
+    poll.arbiter = arbiter;
+    poll.challenged = false;
+    poll.fee_bps = fee_bps;
+    poll.min_bet = min_bet;
+    poll.max_bet = max_bet;
+    poll.finalized_at = 0;
+    poll.creation_bond = config.creation_bond;
+    poll.creator_fee_bps = config.creator_fee_bps;
+    poll.creator_fees = 0;
+    poll.start_timestamp = start_timestamp;
+    poll.initial_end_timestamp = end_timestamp;
+    poll.deadline_extension = deadline_extension;
+    poll.extension_count = 0;
+    poll.virtual_liquidity = virtual_liquidity;
+    poll.lp_liquidity = 0;
+    poll.lp_fee_bps = config.lp_fee_bps;
+    poll.lp_fees = 0;
+    poll.referral_fee_bps = config.referral_fee_bps;
+    poll.fee_tiers = config.fee_tiers.clone();
+    poll.outcome_tokens = false;
+    poll.claim_period = config.claim_period;
+    poll.pending_authority = None;
+    poll.max_total_pool = None;
+    poll.max_stake_per_option = None;
+    poll.max_stake_per_user = None;
+    poll.settlement_commitment = None;
+    poll.committed_at = 0;
+    poll.kind = created.kind.clone();
+    poll.crank_bounty = created.crank_bounty;
+    poll.gate = None;
+    poll.council = None;
+    poll.result_signer = None;
+    poll.community_votes = Vec::new();
+    poll.total_fees = 0;
+    poll.odds_history = false;
+    poll.incentives = incentives;
+    poll.resolution_deadline = resolution_deadline;
+    poll.swept_fees = 0;
+    poll.fee_share_bps = 0;
+    poll.cancel_window = 0;
+    poll.cancel_fee_bps = 0;
+    poll.max_price_impact_bps = 0;
+    poll.bid_counts = vec![0; poll.outcomes.len()];
+    poll.claimed_count = 0;
+    poll.min_seconds_between_bids = 0;
+    poll.registry_page = None;
+    poll.damping_window = 0;
+    poll.damping_bps = 0;
+    poll.vig_bps = 0;
+    poll.treasury_swept = 0;
+    poll.version = POLL_VERSION;
+
+    Ok(created)
+}
+
+/// The poll accounts of `event` passed as `remaining`, checked to be exactly
+/// its polls in the order they were added
+fn event_polls<'a, 'info>(
+    event: &MarketEvent,
+    remaining: &'a [AccountInfo<'info>],
+) -> Result<std::slice::Iter<'a, AccountInfo<'info>>> {
+    require_ctx!(
+        remaining.len() == event.polls.len(),
+        ErrorCode::InvalidRemainingAccounts,
+        provided = remaining.len(),
+        expected = event.polls.len()
+    );
+    for (poll_info, poll) in remaining.iter().zip(&event.polls) {
+        require_keys_eq!(poll_info.key(), *poll, ErrorCode::EventPollMismatch);
+    }
+    Ok(remaining.iter())
+}
+
+/// Resize a program-owned account to `space` bytes, topping its rent up from
+/// `payer` or returning the excess to it
+fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    account.resize(space)?;
+
+    let rent = Rent::get()?.minimum_balance(space);
+    let lamports = account.lamports();
+    if rent > lamports {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, rent - lamports)?;
+    } else {
+        **account.try_borrow_mut_lamports()? -= lamports - rent;
+        **payer.try_borrow_mut_lamports()? += lamports - rent;
+    }
+    Ok(())
+}
+
+/// Give a `Poll` or `Bid` written by an earlier release room for the fields
+/// of the current layout. Such an account reads its new fields as zero from
+/// its padding, unless its data fills the account; that one grows to `space`.
+fn make_room_for_version<'info, T: AccountDeserialize + Discriminator>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let readable = {
+        let data = account.try_borrow_data()?;
+        require!(
+            data.starts_with(T::DISCRIMINATOR),
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        T::try_deserialize(&mut &data[..]).is_ok()
+    };
+    if !readable {
+        resize_account(account, payer, system_program, space)?;
+    }
+    Ok(())
+}
+
+/// Move a bettor's lamports into a SOL poll's escrow vault
+fn deposit_to_vault<'info>(
+    system_program: &Program<'info, System>,
+    from: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    poll: &Poll,
+    amount: u64,
+) -> Result<()> {
+    // Token markets take deposits through `place_bid_token`
+    require!(poll.mint.is_none(), ErrorCode::DenominationMismatch);
+
+    let cpi_context = CpiContext::new(
+        system_program.to_account_info(),
+        anchor_lang::system_program::Transfer {
+            from: from.clone(),
+            to: vault.clone(),
+        },
+    );
+    anchor_lang::system_program::transfer(cpi_context, amount)
+}
+
+/// Fee the stake mint withholds from a transfer of `amount` this epoch; 0 for
+/// classic SPL mints
+fn mint_transfer_fee(mint: &InterfaceAccount<token_interface::Mint>, amount: u64) -> Result<u64> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    logic::transfer_fee(&data, amount, Clock::get()?.epoch)
+}
+
+/// Move tokens from a signer's token account into a token poll's vault. The
+/// vault receives `amount` less any transfer fee a Token-2022 mint withholds.
+fn transfer_tokens_to_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, token_interface::Mint>,
+    from: &InterfaceAccount<'info, token_interface::TokenAccount>,
+    vault_token: &InterfaceAccount<'info, token_interface::TokenAccount>,
+    authority: &Signer<'info>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_context = CpiContext::new(
+        token_program.to_account_info(),
+        token_interface::TransferChecked {
+            from: from.to_account_info(),
+            mint: mint.to_account_info(),
+            to: vault_token.to_account_info(),
+            authority: authority.to_account_info(),
+        },
+    );
+    token_interface::transfer_checked(cpi_context, amount, mint.decimals)
+}
+
+/// Move tokens out of a token poll's vault, signing as the poll PDA that owns
+/// the vault's associated token account. Zero-amount transfers are skipped.
+/// Recipients of a Token-2022 mint with a transfer fee receive `amount` less
+/// the fee.
+fn transfer_tokens_from_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, token_interface::Mint>,
+    vault_token: &InterfaceAccount<'info, token_interface::TokenAccount>,
+    to: &InterfaceAccount<'info, token_interface::TokenAccount>,
+    poll: &Account<'info, Poll>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let poll_id = poll.poll_id.as_bytes();
+    let seeds = &[
+        b"poll",
+        poll_id,
+        &[poll.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_context = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token_interface::TransferChecked {
+            from: vault_token.to_account_info(),
+            mint: mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: poll.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token_interface::transfer_checked(cpi_context, amount, mint.decimals)
+}
+
+/// Close an emptied token vault, signing as the poll PDA, with its rent going
+/// to `destination`. Transfer fees a Token-2022 mint withheld in the vault are
+/// collected into the mint first, since the vault can't be closed holding them.
+fn close_token_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, token_interface::Mint>,
+    vault_token: &InterfaceAccount<'info, token_interface::TokenAccount>,
+    destination: &AccountInfo<'info>,
+    poll: &Account<'info, Poll>,
+) -> Result<()> {
+    if logic::has_transfer_fee(&mint.to_account_info().try_borrow_data()?)? {
+        token_transfer_fee::harvest_withheld_tokens_to_mint(
+            CpiContext::new(
+                token_program.to_account_info(),
+                token_transfer_fee::HarvestWithheldTokensToMint {
+                    token_program_id: token_program.to_account_info(),
+                    mint: mint.to_account_info(),
+                },
+            ),
+            vec![vault_token.to_account_info()],
+        )?;
+    }
+
+    let poll_id = poll.poll_id.as_bytes();
+    let seeds = &[b"poll", poll_id, &[poll.bump]];
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token_interface::CloseAccount {
+            account: vault_token.to_account_info(),
+            destination: destination.clone(),
+            authority: poll.to_account_info(),
+        },
+        &[&seeds[..]],
+    ))
+}
+
+/// Split raw payout table data into its header and per-bid entries
+fn split_payout_table(data: &mut [u8]) -> Result<(&mut PayoutTable, &mut [PayoutEntry])> {
+    require!(
+        data.len() >= PayoutTable::HEADER_LEN,
+        ErrorCode::InvalidPayoutTable
+    );
+    let (header, entries) = data.split_at_mut(PayoutTable::HEADER_LEN);
+    let table: &mut PayoutTable = bytemuck::try_from_bytes_mut(&mut header[8..])
+        .map_err(|_| error!(ErrorCode::InvalidPayoutTable))?;
+    let entries: &mut [PayoutEntry] = bytemuck::try_cast_slice_mut(entries)
+        .map_err(|_| error!(ErrorCode::InvalidPayoutTable))?;
+    Ok((table, entries))
+}
+
+/// Lamports in the parlay pool beyond its rent and the wins of open parlays
+fn parlay_pool_available(pool_info: &AccountInfo, liability: u64) -> Result<u64> {
+    let reserved = math::safe_add(Rent::get()?.minimum_balance(ParlayPool::LEN), liability)?;
+
+    Ok(pool_info.lamports().saturating_sub(reserved))
+}
+
+/// Append a snapshot to the poll's odds history if it keeps one
+fn record_odds_history(
+    poll: &Poll,
+    odds_history: Option<&AccountLoader<OddsHistory>>,
+    now: i64,
+) -> Result<()> {
+    if poll.odds_history {
+        let odds_history = odds_history.ok_or(ErrorCode::OddsHistoryRequired)?;
+        logic::record_odds(&mut *odds_history.load_mut()?, poll, now);
+    }
+    Ok(())
+}
+
+/// Micro-USD value of a SOL bid from an optional Pyth SOL/USD price update,
+/// held to the config's USD limit
+fn bid_usd_notional(
+    config: &Config,
+    sol_usd_price: Option<&UncheckedAccount>,
+    amount: u64,
+    now: i64,
+) -> Result<u64> {
+    let price = sol_usd_price.map(|info| oracle::read_price(info)).transpose()?;
+    logic::usd_bid_notional(config, amount, price.as_ref(), now)
+}
+
+/// Lamports in a program-owned account of `space` bytes beyond its rent
+fn balance_above_rent(info: &AccountInfo, space: usize) -> Result<u64> {
+    let rent = Rent::get()?.minimum_balance(space);
+    Ok(info.lamports().saturating_sub(rent))
+}
+
+/// Pay the queued treasury withdrawal out of the fee vault's balance above
+/// rent, recording it in the stats' history
+fn pay_treasury_withdrawal(
+    fee_vault: &Account<'_, FeeVault>,
+    destination: &SystemAccount<'_>,
+    stats: &mut TreasuryStats,
+    now: i64,
+) -> Result<TreasuryWithdrawn> {
+    let amount = logic::execute_treasury_withdrawal(stats, destination.key(), now)?;
+    let fee_vault_info = fee_vault.to_account_info();
+    let available = logic::treasury_available(
+        fee_vault,
+        balance_above_rent(&fee_vault_info, FeeVault::LEN)?,
+    );
+    require_ctx!(
+        amount <= available,
+        ErrorCode::InsufficientFeeVault,
+        amount = amount,
+        available = available,
+        fee_share_reserve = fee_vault.fee_share_reserve
+    );
+
+    **fee_vault_info.try_borrow_mut_lamports()? -= amount;
+    **destination.try_borrow_mut_lamports()? += amount;
+
+    Ok(TreasuryWithdrawn {
+        header: event_header(&mut stats.event_seq)?,
+        amount,
+        destination: destination.key(),
+    })
+}
+
+/// Move `amount` left in an ended poll's vault to the fee vault and count it
+/// in the treasury ledger and the poll's `treasury_swept`. Returns `amount`.
+fn sweep_remainder<'info>(
+    system_program: &Program<'info, System>,
+    vault: &SystemAccount<'info>,
+    fee_vault: &mut Account<'info, FeeVault>,
+    stats: &mut TreasuryStats,
+    poll: &mut Account<'info, Poll>,
+    amount: u64,
+    now: i64,
+) -> Result<u64> {
+    transfer_from_vault(system_program, vault, &fee_vault.to_account_info(), poll, amount)?;
+    fee_vault.total_swept = math::safe_add(fee_vault.total_swept, amount)?;
+    let key = poll.key();
+    logic::record_remainder(stats, poll, key, amount, now)?;
+    Ok(amount)
+}
+
+/// Where a new bid's stake comes from
+enum StakeSource<'a, 'info> {
+    /// A system transfer from the payer
+    Payer {
+        payer: &'a Signer<'info>,
+        system_program: &'a Program<'info, System>,
+    },
+    /// The bettor's `UserBalance`
+    Balance(&'a mut Account<'info, UserBalance>),
+    /// The bettor's `BetCredit`, spent from the promo vault
+    Credit {
+        promo_vault: &'a mut Account<'info, PromoVault>,
+        credit: &'a mut Account<'info, BetCredit>,
+    },
+}
+
+/// The accounts of the `place_bid` variants a new bid writes or checks
+struct NewBidAccounts<'a, 'info> {
+    poll: &'a mut Account<'info, Poll>,
+    config: &'a Config,
+    vault: &'a SystemAccount<'info>,
+    bid: &'a mut Account<'info, Bid>,
+    profile: &'a mut Account<'info, UserProfile>,
+    user_stake: &'a mut Account<'info, UserStake>,
+    position: &'a mut Account<'info, UserPollPosition>,
+    gate_token: Option<&'a TokenAccount>,
+    attestation: Option<&'a Attestation>,
+    odds_history: Option<&'a AccountLoader<'info, OddsHistory>>,
+    sol_usd_price: Option<&'a UncheckedAccount<'info>>,
+    bettor: Pubkey,
+    referrer: Option<Pubkey>,
+    /// Bumps of the bid, profile, user stake, and position PDAs
+    bumps: [u8; 4],
+}
+
+/// The arguments of the `place_bid` variants
+struct NewBid<'a> {
+    amount: u64,
+    option: u8,
+    timestamp: i64,
+    bid_index: u64,
+    allowlist_proof: &'a [[u8; 32]],
+    memo: Option<[u8; 32]>,
+}
+
+/// The events of a new bid, in the order `emit_placed_bid!` emits them
+struct PlacedBid {
+    placed: BidPlaced,
+    referred: Option<BidReferred>,
+    partially_filled: Option<BidPartiallyFilled>,
+    state: PollStateUpdated,
+}
+
+/// Validate, price, and record a new bid, trimmed to what fits the poll's
+/// caps, liability, and price impact limit, and move its stake from `source`
+/// into the vault
+fn place_new_bid<'info>(
+    accounts: NewBidAccounts<'_, 'info>,
+    source: StakeSource<'_, 'info>,
+    args: NewBid,
+) -> Result<PlacedBid> {
+    let NewBidAccounts {
+        poll,
+        config,
+        vault,
+        bid,
+        profile,
+        user_stake,
+        position,
+        gate_token,
+        attestation,
+        odds_history,
+        sol_usd_price,
+        bettor,
+        referrer,
+        bumps: [bid_bump, profile_bump, user_stake_bump, position_bump],
+    } = accounts;
+    let NewBid {
+        amount,
+        option,
+        timestamp,
+        bid_index,
+        allowlist_proof,
+        memo,
+    } = args;
+
+    // Every source holds lamports; token markets take bids through
+    // `place_bid_token`
+    require!(poll.mint.is_none(), ErrorCode::DenominationMismatch);
+    let now = Clock::get()?.unix_timestamp;
+    logic::validate_gate(
+        poll,
+        bettor,
+        allowlist_proof,
+        gate_token.map(logic::TokenHolding::from),
+        attestation,
+        now,
+    )?;
+    // Ensure provided bid index matches poll's next index
+    require_ctx!(
+        bid_index == poll.next_bid_index,
+        ErrorCode::InvalidBidIndex,
+        provided = bid_index,
+        expected = poll.next_bid_index
+    );
+    logic::validate_referrer(referrer, bettor)?;
+
+    // Validate, record stake, and update AMM odds, trimming the bid to what
+    // fits the poll's caps, liability, and price impact limit
+    let requested = amount;
+    let amount = logic::fill_amount(poll, amount, option, now)?;
+    let usd_notional = bid_usd_notional(config, sol_usd_price, amount, now)?;
+    let fee_adjust_bps = logic::fee_adjust_bps(poll, now);
+    let (current_odds, potential_win) = logic::record_bid(poll, amount, option, now)?;
+    record_odds_history(poll, odds_history, now)?;
+
+    let credit = match source {
+        StakeSource::Payer {
+            payer,
+            system_program,
+        } => {
+            // Transfer SOL from the payer to vault (escrow)
+            deposit_to_vault(
+                system_program,
+                &payer.to_account_info(),
+                &vault.to_account_info(),
+                poll,
+                amount,
+            )?;
+            0
+        }
+        // The balance's lamports sit on its program-owned PDA, so the stake
+        // moves without a system transfer
+        StakeSource::Balance(balance) => {
+            logic::debit_balance(balance, amount)?;
+            **balance.to_account_info().try_borrow_mut_lamports()? -= amount;
+            **vault.to_account_info().try_borrow_mut_lamports()? += amount;
+            0
+        }
+        // The promo vault is program-owned too
+        StakeSource::Credit {
+            promo_vault,
+            credit,
+        } => {
+            logic::spend_credit(promo_vault, credit, amount)?;
+            **promo_vault.to_account_info().try_borrow_mut_lamports()? -= amount;
+            **vault.to_account_info().try_borrow_mut_lamports()? += amount;
+            amount
+        }
+    };
+
+    logic::init_profile(profile, bettor, profile_bump);
+    logic::profile_bid(profile, config, amount, now)?;
+
+    logic::init_user_stake(user_stake, poll.key(), bettor, user_stake_bump);
+    logic::record_user_stake(poll, user_stake, amount)?;
+
+    logic::init_position(position, poll.key(), bettor, position_bump);
+    logic::record_bid_time(poll, position, now)?;
+    logic::record_position(position, option, amount, Some(bid_index))?;
+
+    // Initialize bid account
+    bid.bettor = bettor;
+    bid.poll = poll.key();
+    bid.amount = amount;
+    bid.option = option;
+    bid.odds_at_purchase = current_odds;
+    bid.potential_win = potential_win;
+    bid.status = BidStatus::Active;
+    bid.timestamp = timestamp;
+    bid.index = bid_index;
+    bid.bump = bid_bump;
+    bid.version = BID_VERSION;
+    bid.placed_at = now;
+    bid.fee_adjust_bps = fee_adjust_bps;
+    bid.referrer = referrer;
+    bid.memo = memo;
+    bid.credit = credit;
+    bid.usd_notional_at_purchase = usd_notional;
+
+    let placed = BidPlaced {
+        header: event_header(&mut poll.event_seq)?,
+        bid: bid.key(),
+        bettor,
+        poll: poll.key(),
+        amount,
+        option,
+        odds: current_odds,
+        potential_win,
+        memo,
+        usd_notional,
+        shares: potential_win,
+    };
+    let referred = referrer
+        .map(|referrer| -> Result<BidReferred> {
+            Ok(BidReferred {
+                header: event_header(&mut poll.event_seq)?,
+                bid: bid.key(),
+                referrer,
+            })
+        })
+        .transpose()?;
+    let partially_filled = if amount < requested {
+        Some(BidPartiallyFilled {
+            header: event_header(&mut poll.event_seq)?,
+            bid: bid.key(),
+            requested,
+            filled: amount,
+        })
+    } else {
+        None
+    };
+
+    // Increment next bid index for poll
+    poll.next_bid_index = math::safe_add(poll.next_bid_index, 1)?;
+
+    Ok(PlacedBid {
+        placed,
+        referred,
+        partially_filled,
+        state: poll_state_updated(poll)?,
+    })
+}
+
+/// Create a Bid PDA supplied through remaining_accounts, funded by the bettor.
+/// Mirrors the `init` constraint on `PlaceBid` and returns the PDA bump.
+fn create_bid_account<'info>(
+    system_program: &AccountInfo<'info>,
+    bettor: &AccountInfo<'info>,
+    bid_info: &AccountInfo<'info>,
+    poll: &Pubkey,
+    bid_index: u64,
+    program_id: &Pubkey,
+) -> Result<u8> {
+    let index_bytes = bid_index.to_le_bytes();
+    let seeds: &[&[u8]] = &[b"bid", poll.as_ref(), bettor.key.as_ref(), &index_bytes];
+    let (expected, bump) = Pubkey::find_program_address(seeds, program_id);
+    require_keys_eq!(bid_info.key(), expected, ErrorCode::InvalidBidAccount);
+
+    create_pda(system_program, bettor, bid_info, seeds, bump, Bid::LEN, program_id)?;
+    Ok(bump)
+}
+
+/// Load the PDA at `seeds` supplied through remaining_accounts, creating it
+/// funded by `payer` if it doesn't exist yet. Mirrors an `init_if_needed`
+/// constraint and returns the account and its bump.
+fn load_or_create_pda<'info, T: AccountSerialize + AccountDeserialize + Owner + Clone>(
+    system_program: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    info: &'info AccountInfo<'info>,
+    seeds: &[&[u8]],
+    space: usize,
+    program_id: &Pubkey,
+) -> Result<(Account<'info, T>, u8)> {
+    let (expected, bump) = Pubkey::find_program_address(seeds, program_id);
+    require_keys_eq!(info.key(), expected, ErrorCode::InvalidRemainingAccounts);
+
+    if info.owner == program_id {
+        return Ok((Account::try_from(info)?, bump));
+    }
+    create_pda(system_program, payer, info, seeds, bump, space, program_id)?;
+    Ok((Account::try_from_unchecked(info)?, bump))
+}
+
+/// Create the program-owned account of `space` bytes at `seeds` and `bump`,
+/// funded by `payer`
+fn create_pda<'info>(
+    system_program: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    info: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    bump: u8,
+    space: usize,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let bump = [bump];
+    let seeds = [seeds, &[&bump[..]]].concat();
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_context = CpiContext::new_with_signer(
+        system_program.clone(),
+        anchor_lang::system_program::CreateAccount {
+            from: payer.clone(),
+            to: info.clone(),
+        },
+        signer_seeds,
+    );
+    anchor_lang::system_program::create_account(
+        cpi_context,
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        program_id,
+    )
+}
+
+// =============================================================================
+// ACCOUNT STRUCTS
+// =============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Config::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::OpinionTrading>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ProposeAdminTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct AcceptAdminTransfer<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub pending_admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
 #[instruction(poll_id: String)]
 pub struct InitializePoll<'info> {
     #[account(
         init,
         payer = authority,
-        space = Poll::LEN,
-        seeds = [b"poll", poll_id.as_bytes()],
+        space = Poll::LEN,
+        seeds = [b"poll", poll_id.as_bytes()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding SOL in escrow
+    pub vault: SystemAccount<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// A registry page with room, which lists the new poll
+    #[account(
+        mut,
+        seeds = [b"poll_registry", registry.page.to_le_bytes().as_ref()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, PollRegistry>,
+
+    /// Set for token markets (e.g. USDC); omitted for SOL markets
+    pub mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub token_vault: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(id_prefix: String)]
+pub struct CreatePollTemplate<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = PollTemplate::LEN,
+        seeds = [b"poll_template", id_prefix.as_bytes()],
+        bump
+    )]
+    pub template: Account<'info, PollTemplate>,
+
+    /// Set for token markets; omitted for SOL markets
+    pub mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(round: u64)]
+pub struct CreatePollFromTemplate<'info> {
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub template: Account<'info, PollTemplate>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Poll::LEN,
+        seeds = [b"poll", logic::template_poll_id(&template.id_prefix, round).as_bytes()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for holding SOL in escrow
+    pub vault: SystemAccount<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// A registry page with room, which lists the new poll
+    #[account(
+        mut,
+        seeds = [b"poll_registry", registry.page.to_le_bytes().as_ref()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, PollRegistry>,
+
+    /// The template's mint, for token markets
+    pub mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub token_vault: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ClosePollTemplate<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub template: Account<'info, PollTemplate>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(page: u32)]
+pub struct InitRegistryPage<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = PollRegistry::LEN,
+        seeds = [b"poll_registry", page.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry: Account<'info, PollRegistry>,
+
+    /// Page `page - 1`; omitted for page 0
+    #[account(
+        seeds = [b"poll_registry", previous.page.to_le_bytes().as_ref()],
+        bump = previous.bump
+    )]
+    pub previous: Option<Account<'info, PollRegistry>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ListPoll<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"poll_registry", registry.page.to_le_bytes().as_ref()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, PollRegistry>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateEndTime<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct SetPollCaps<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct SetPollGate<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct IssueAttestation<'info> {
+    #[account(
+        init_if_needed,
+        payer = attestor,
+        space = Attestation::LEN,
+        seeds = [b"attestation", attestor.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    /// CHECK: Any wallet; only its address is recorded
+    pub user: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub attestor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct RevokeAttestation<'info> {
+    #[account(
+        mut,
+        close = attestor,
+        has_one = attestor @ ErrorCode::Unauthorized
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    #[account(mut)]
+    pub attestor: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct SetResolvers<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(category: PollCategory, uri: String, tags: Vec<String>, resolution_criteria: String)]
+pub struct SetPollMetadata<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PollMetadata::space(&uri, &tags, &resolution_criteria),
+        seeds = [b"poll_metadata", poll.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, PollMetadata>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ClosePollMetadata<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"poll_metadata", poll.key().as_ref()],
+        bump = metadata.bump
+    )]
+    pub metadata: Account<'info, PollMetadata>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ProposeAuthorityTransfer<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct AcceptAuthorityTransfer<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    pub pending_authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ProvideLiquidity<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = LpPosition::LEN,
+        seeds = [b"lp", poll.key().as_ref(), provider.key().as_ref()],
+        bump
+    )]
+    pub lp_position: Account<'info, LpPosition>,
+
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawLiquidity<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"lp", poll.key().as_ref(), provider.key().as_ref()],
+        bump = lp_position.bump
+    )]
+    pub lp_position: Account<'info, LpPosition>,
+
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct OpenPoll<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+}
+
+/// Eight accounts (poll, config, vault, bid, profile, user stake, bettor, system
+/// program) plus an optional referrer, so the hot path fits in a legacy
+/// transaction without address lookup tables
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(amount: u64, option: u8, timestamp: i64, bid_index: u64)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Bid::LEN,
+        seeds = [
+            b"bid",
+            poll.key().as_ref(),
+            bettor.key().as_ref(),
+            &bid_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserStake::LEN,
+        seeds = [b"user_stake", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserPollPosition::LEN,
+        seeds = [b"pos", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, UserPollPosition>,
+
+    /// Wallet credited with bringing the bettor; paid a share of the fee on claim
+    pub referrer: Option<SystemAccount<'info>>,
+
+    /// The bettor's token account of a token-gated poll's mint
+    pub gate_token: Option<Account<'info, TokenAccount>>,
+
+    /// The bettor's credential from an attestation-gated poll's attestor
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// The poll's odds history; required when it has one
+    #[account(
+        mut,
+        constraint = odds_history.load()?.poll == poll.key() @ ErrorCode::OddsHistoryMismatch
+    )]
+    pub odds_history: Option<AccountLoader<'info, OddsHistory>>,
+
+    /// Pyth SOL/USD price update valuing the stake in USD; required while the
+    /// config sets `max_bid_usd`
+    /// CHECK: Owner, discriminator, and verification level checked by `oracle::read_price`
+    pub sol_usd_price: Option<UncheckedAccount<'info>>,
+
+    /// Owner of the bid. Only signs, so a calling program's PDA that holds
+    /// data can bet through CPI.
+    pub bettor: Signer<'info>,
+
+    /// Funds the stake and the new accounts' rent; a wallet passes itself as
+    /// both `bettor` and `payer`
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// `PlaceBid` with the stake taken from the bettor's `UserBalance`; no
+/// referrer
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(amount: u64, option: u8, timestamp: i64, bid_index: u64)]
+pub struct PlaceBidFromBalance<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"balance", bettor.key().as_ref()],
+        bump = balance.bump
+    )]
+    pub balance: Account<'info, UserBalance>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Bid::LEN,
+        seeds = [
+            b"bid",
+            poll.key().as_ref(),
+            bettor.key().as_ref(),
+            &bid_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserStake::LEN,
+        seeds = [b"user_stake", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserPollPosition::LEN,
+        seeds = [b"pos", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, UserPollPosition>,
+
+    /// The bettor's token account of a token-gated poll's mint
+    pub gate_token: Option<Account<'info, TokenAccount>>,
+
+    /// The bettor's credential from an attestation-gated poll's attestor
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// The poll's odds history; required when it has one
+    #[account(
+        mut,
+        constraint = odds_history.load()?.poll == poll.key() @ ErrorCode::OddsHistoryMismatch
+    )]
+    pub odds_history: Option<AccountLoader<'info, OddsHistory>>,
+
+    /// Pyth SOL/USD price update valuing the stake in USD; required while the
+    /// config sets `max_bid_usd`
+    /// CHECK: Owner, discriminator, and verification level checked by `oracle::read_price`
+    pub sol_usd_price: Option<UncheckedAccount<'info>>,
+
+    /// Owner of the bid and the balance
+    pub bettor: Signer<'info>,
+
+    /// Funds the new accounts' rent, e.g. a relayer
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct GrantCredit<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = PromoVault::LEN,
+        seeds = [b"promo_vault"],
+        bump
+    )]
+    pub promo_vault: Account<'info, PromoVault>,
+
+    /// CHECK: Any wallet can be granted credit; it signs to spend it
+    pub user: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = BetCredit::LEN,
+        seeds = [b"credit", user.key().as_ref()],
+        bump
+    )]
+    pub credit: Account<'info, BetCredit>,
+
+    /// Pays whatever part of the grant the promo vault can't cover
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(amount: u64, option: u8, timestamp: i64, bid_index: u64)]
+pub struct PlaceBidWithCredit<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"promo_vault"], bump = promo_vault.bump)]
+    pub promo_vault: Account<'info, PromoVault>,
+
+    #[account(
+        mut,
+        seeds = [b"credit", bettor.key().as_ref()],
+        bump = credit.bump
+    )]
+    pub credit: Account<'info, BetCredit>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Bid::LEN,
+        seeds = [
+            b"bid",
+            poll.key().as_ref(),
+            bettor.key().as_ref(),
+            &bid_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserStake::LEN,
+        seeds = [b"user_stake", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserPollPosition::LEN,
+        seeds = [b"pos", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, UserPollPosition>,
+
+    /// The bettor's token account of a token-gated poll's mint
+    pub gate_token: Option<Account<'info, TokenAccount>>,
+
+    /// The bettor's credential from an attestation-gated poll's attestor
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// The poll's odds history; required when it has one
+    #[account(
+        mut,
+        constraint = odds_history.load()?.poll == poll.key() @ ErrorCode::OddsHistoryMismatch
+    )]
+    pub odds_history: Option<AccountLoader<'info, OddsHistory>>,
+
+    /// Pyth SOL/USD price update valuing the stake in USD; required while the
+    /// config sets `max_bid_usd`
+    /// CHECK: Owner, discriminator, and verification level checked by `oracle::read_price`
+    pub sol_usd_price: Option<UncheckedAccount<'info>>,
+
+    /// Owner of the bid and the credit
+    pub bettor: Signer<'info>,
+
+    /// Funds the new accounts' rent, e.g. a relayer
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserBalance::LEN,
+        seeds = [b"balance", user.key().as_ref()],
+        bump
+    )]
+    pub balance: Account<'info, UserBalance>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"balance", user.key().as_ref()],
+        bump = balance.bump
+    )]
+    pub balance: Account<'info, UserBalance>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct IncreaseBid<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserStake::LEN,
+        seeds = [b"user_stake", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserPollPosition::LEN,
+        seeds = [b"pos", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, UserPollPosition>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct TransferBid<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    pub bettor: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct PlaceBidsMulti<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    /// Pyth SOL/USD price update valuing each stake in USD; required while
+    /// the config sets `max_bid_usd`
+    /// CHECK: Owner, discriminator, and verification level checked by `oracle::read_price`
+    pub sol_usd_price: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceLimitBid<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = bettor,
+        space = PendingOrder::LEN,
+        seeds = [
+            b"order",
+            poll.key().as_ref(),
+            bettor.key().as_ref(),
+            &order_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub order: Account<'info, PendingOrder>,
+
+    /// The bettor's token account of a token-gated poll's mint
+    pub gate_token: Option<Account<'info, TokenAccount>>,
+
+    /// The bettor's credential from an attestation-gated poll's attestor
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(bid_index: u64)]
+pub struct MatchOrder<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = bettor,
+        has_one = poll @ ErrorCode::BidPollMismatch,
+        has_one = bettor @ ErrorCode::Unauthorized
+    )]
+    pub order: Account<'info, PendingOrder>,
+
+    #[account(
+        init,
+        payer = cranker,
+        space = Bid::LEN,
+        seeds = [
+            b"bid",
+            poll.key().as_ref(),
+            bettor.key().as_ref(),
+            &bid_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    /// The poll's odds history; required when it has one
+    #[account(
+        mut,
+        constraint = odds_history.load()?.poll == poll.key() @ ErrorCode::OddsHistoryMismatch
+    )]
+    pub odds_history: Option<AccountLoader<'info, OddsHistory>>,
+
+    /// CHECK: the order's bettor, checked by `has_one`; gets the order's rent back
+    #[account(mut)]
+    pub bettor: UncheckedAccount<'info>,
+
+    /// Pays the bid's rent, repaid from the order's escrow
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(mut, address = order.poll @ ErrorCode::BidPollMismatch)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(mut, close = bettor, has_one = bettor @ ErrorCode::Unauthorized)]
+    pub order: Account<'info, PendingOrder>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct EnableOddsHistory<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = OddsHistory::LEN,
+        seeds = [b"odds_history", poll.key().as_ref()],
+        bump
+    )]
+    pub odds_history: AccountLoader<'info, OddsHistory>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseOddsHistory<'info> {
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"odds_history", poll.key().as_ref()],
+        bump
+    )]
+    pub odds_history: AccountLoader<'info, OddsHistory>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct InitBidLedger<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(zero)]
+    pub ledger: AccountLoader<'info, BidLedger>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct PlaceBidLedger<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = ledger.load()?.poll == poll.key() @ ErrorCode::LedgerPollMismatch
+    )]
+    pub ledger: AccountLoader<'info, BidLedger>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimWinningsLedger<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = ledger.load()?.poll == poll.key() @ ErrorCode::LedgerPollMismatch
+    )]
+    pub ledger: AccountLoader<'info, BidLedger>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    /// Covers the payout if the vault is short; required only then
+    #[account(mut, seeds = [b"insurance_fund"], bump = insurance_fund.bump)]
+    pub insurance_fund: Option<Account<'info, InsuranceFund>>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimLedger<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = ledger.load()?.poll == poll.key() @ ErrorCode::LedgerPollMismatch
+    )]
+    pub ledger: AccountLoader<'info, BidLedger>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettlePoll<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The poll's resolution note; `settle_poll`, `settle_scalar`,
+    /// `reveal_settlement`, and `settle_poll_void` require it
+    #[account(seeds = [b"resolution_note", poll.key().as_ref()], bump = resolution_note.bump)]
+    pub resolution_note: Option<Account<'info, ResolutionNote>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct AttachResolutionNote<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ResolutionNote::LEN,
+        seeds = [b"resolution_note", poll.key().as_ref()],
+        bump
+    )]
+    pub resolution_note: Account<'info, ResolutionNote>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VoteSettlement<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    pub resolver: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleWithSignature<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: The instructions sysvar, read by `signature::read_signed_message`
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VoteOutcome<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    /// Created once per bid, so each bid votes once
+    #[account(
+        init,
+        payer = bettor,
+        space = CommunityVote::LEN,
+        seeds = [b"community_vote", bid.key().as_ref()],
+        bump
+    )]
+    pub community_vote: Account<'info, CommunityVote>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCommunityVote<'info> {
+    #[account(address = community_vote.poll @ ErrorCode::BidPollMismatch)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(mut, close = voter, has_one = voter @ ErrorCode::Unauthorized)]
+    pub community_vote: Account<'info, CommunityVote>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettlePollWithOracle<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Owner, discriminator, and verification level checked by `oracle::read_price`
+    pub price_update: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CrankSettle<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Owner, discriminator, and verification level checked by `oracle::read_price`;
+    /// required for oracle markets
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ChallengeSettlement<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = challenger,
+        space = Challenge::LEN,
+        seeds = [b"challenge", poll.key().as_ref()],
+        bump
+    )]
+    pub challenge: Account<'info, Challenge>,
+
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizeSettlement<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveChallenge<'info> {
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = poll.arbiter.unwrap_or(poll.authority) == arbiter.key() @ ErrorCode::Unauthorized
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"challenge", poll.key().as_ref()],
+        bump = challenge.bump,
+        has_one = challenger @ ErrorCode::Unauthorized
+    )]
+    pub challenge: Account<'info, Challenge>,
+
+    /// Receives the challenge's rent, and the bond if the challenge succeeds
+    #[account(mut)]
+    pub challenger: SystemAccount<'info>,
+
+    /// Receives the bond if the proposed winner stands
+    #[account(mut)]
+    pub authority: SystemAccount<'info>,
+
+    pub arbiter: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct SetClaimDelegate<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserProfile::LEN,
+        seeds = [b"profile", user.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// No treasury account: fees are accrued on the poll and swept by `sweep_fees`
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    /// The bid's referrer; required when the bid has one
+    #[account(mut)]
+    pub referrer: Option<SystemAccount<'info>>,
+
+    /// Covers the payout if the vault is short; required only then
+    #[account(mut, seeds = [b"insurance_fund"], bump = insurance_fund.bump)]
+    pub insurance_fund: Option<Account<'info, InsuranceFund>>,
+
+    /// Takes back a credit bid's principal; required for those bids
+    #[account(mut, seeds = [b"promo_vault"], bump = promo_vault.bump)]
+    pub promo_vault: Option<Account<'info, PromoVault>>,
+
+    /// CHECK: The bid's owner; signs unless `claim_delegate` claims for them
+    #[account(constraint = bettor.is_signer || claim_delegate.is_some() @ ErrorCode::Unauthorized)]
+    pub bettor: UncheckedAccount<'info>,
+
+    /// The bettor's approved claim delegate, signing in their place
+    #[account(
+        constraint = profile.claim_delegate == Some(claim_delegate.key()) @ ErrorCode::NotClaimDelegate
+    )]
+    pub claim_delegate: Option<Signer<'info>>,
+
+    /// Receives the payout; the bettor's own wallet, a cold wallet, or a PDA.
+    /// A delegated claim pays the bettor's wallet.
+    #[account(
+        mut,
+        constraint = claim_delegate.is_none() || destination.key() == bettor.key()
+            @ ErrorCode::ClaimDestinationMismatch
+    )]
+    pub destination: SystemAccount<'info>,
+
+    /// Funds the profile's rent if it doesn't exist yet; a wallet passes
+    /// itself as `bettor`, `destination`, and `payer`
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimWinningsBatch<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    /// Covers the payouts if the vault is short; required only then
+    #[account(mut, seeds = [b"insurance_fund"], bump = insurance_fund.bump)]
+    pub insurance_fund: Option<Account<'info, InsuranceFund>>,
+
+    /// Takes back credit bids' principal; required when a credit bid wins
+    #[account(mut, seeds = [b"promo_vault"], bump = promo_vault.bump)]
+    pub promo_vault: Option<Account<'info, PromoVault>>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitPayoutTable<'info> {
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PayoutTable::space(poll.next_bid_index),
+        seeds = [b"payouts", poll.key().as_ref()],
+        bump
+    )]
+    pub payout_table: AccountLoader<'info, PayoutTable>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ComputePayouts<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"payouts", poll.key().as_ref()],
+        bump
+    )]
+    pub payout_table: AccountLoader<'info, PayoutTable>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimWinningsPrecomputed<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"payouts", poll.key().as_ref()],
+        bump
+    )]
+    pub payout_table: AccountLoader<'info, PayoutTable>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    /// The bid's referrer; required when the bid has one
+    #[account(mut)]
+    pub referrer: Option<SystemAccount<'info>>,
+
+    /// Covers the payout if the vault is short; required only then
+    #[account(mut, seeds = [b"insurance_fund"], bump = insurance_fund.bump)]
+    pub insurance_fund: Option<Account<'info, InsuranceFund>>,
+
+    /// Takes back a credit bid's principal; required for those bids
+    #[account(mut, seeds = [b"promo_vault"], bump = promo_vault.bump)]
+    pub promo_vault: Option<Account<'info, PromoVault>>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExitBid<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    /// Takes back a credit bid's principal; required for those bids
+    #[account(mut, seeds = [b"promo_vault"], bump = promo_vault.bump)]
+    pub promo_vault: Option<Account<'info, PromoVault>>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SweepFees<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"fee_vault"], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Counts the sweep
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    /// Takes its `fee_bps` share of the sweep
+    #[account(mut, seeds = [b"insurance_fund"], bump = insurance_fund.bump)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = FeeVault::LEN,
+        seeds = [b"fee_vault"],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasuryStats<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = TreasuryStats::LEN,
+        seeds = [b"treasury_stats"],
+        bump
+    )]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetTreasuryTimelock<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"fee_vault"], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    /// Paid at once when there's no timelock; checked against the
+    /// `destination` argument
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteTreasuryWithdrawal<'info> {
+    #[account(mut, seeds = [b"fee_vault"], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    /// Must be the queued withdrawal's destination
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelTreasuryWithdrawal<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ChangeTreasury<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteTreasuryChange<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimCreatorFees<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct GrantFeeShare<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    /// CHECK: Any wallet can be granted a share; it signs to claim
+    pub party: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = FeeShare::LEN,
+        seeds = [b"fee_share", poll.key().as_ref(), party.key().as_ref()],
+        bump
+    )]
+    pub fee_share: Account<'info, FeeShare>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ClaimFeeShare<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_share", poll.key().as_ref(), party.key().as_ref()],
+        bump = fee_share.bump
+    )]
+    pub fee_share: Account<'info, FeeShare>,
+
+    #[account(mut, seeds = [b"fee_vault"], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut)]
+    pub party: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBid<'info> {
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = bettor,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct MarkBidLost<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(mut, has_one = poll @ ErrorCode::BidPollMismatch)]
+    pub bid: Account<'info, Bid>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SweepExpired<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"fee_vault"], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SweepExpiredToken<'info> {
+    #[account(
+        mut,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        constraint = treasury_token.owner == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClosePoll<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    /// The registry page listing the poll; required while it's listed
+    #[account(
+        mut,
+        seeds = [b"poll_registry", registry.page.to_le_bytes().as_ref()],
+        bump = registry.bump
+    )]
+    pub registry: Option<Account<'info, PollRegistry>>,
+
+    #[account(mut, seeds = [b"fee_vault"], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClosePollToken<'info> {
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// Writable to collect transfer fees withheld in the vault before closing it
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// The registry page listing the poll; required while it's listed
+    #[account(
+        mut,
+        seeds = [b"poll_registry", registry.page.to_le_bytes().as_ref()],
+        bump = registry.bump
+    )]
+    pub registry: Option<Account<'info, PollRegistry>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        constraint = treasury_token.owner == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizePoll<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    /// The registry page listing the poll; required while it's listed
+    #[account(
+        mut,
+        seeds = [b"poll_registry", registry.page.to_le_bytes().as_ref()],
+        bump = registry.bump
+    )]
+    pub registry: Option<Account<'info, PollRegistry>>,
+
+    #[account(mut, seeds = [b"fee_vault"], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut, seeds = [b"treasury_stats"], bump = treasury_stats.bump)]
+    pub treasury_stats: Account<'info, TreasuryStats>,
+
+    /// The poll authority, refunded any unpaid crank bounty
+    #[account(mut, address = poll.authority @ ErrorCode::Unauthorized)]
+    pub authority: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizePollToken<'info> {
+    #[account(
+        mut,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// Writable to collect transfer fees withheld in the vault before closing it
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// The registry page listing the poll; required while it's listed
+    #[account(
+        mut,
+        seeds = [b"poll_registry", registry.page.to_le_bytes().as_ref()],
+        bump = registry.bump
+    )]
+    pub registry: Option<Account<'info, PollRegistry>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        constraint = treasury_token.owner == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// The poll authority, who gets the token vault's rent
+    #[account(mut, address = poll.authority @ ErrorCode::Unauthorized)]
+    pub authority: SystemAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelPoll<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ExpirePoll<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct SuspendPoll<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// The poll's authority or the config admin
+    #[account(
+        constraint = authority.key() == poll.authority || authority.key() == config.admin
+            @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SlashPoll<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+        has_one = treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub treasury: SystemAccount<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserProfile::LEN,
+        seeds = [b"profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+
+    /// Takes back a credit bid's principal; required for those bids
+    #[account(mut, seeds = [b"promo_vault"], bump = promo_vault.bump)]
+    pub promo_vault: Option<Account<'info, PromoVault>>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    /// Receives the refund; the bettor's own wallet or a cold wallet
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ProcessRefundsBatch<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct RefundBatch<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// Anyone; paid a tip per refund pushed
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(amount: u64, option: u8, timestamp: i64, bid_index: u64)]
+pub struct PlaceBidToken<'info> {
+    #[account(
+        mut,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        token::authority = bettor
+    )]
+    pub bettor_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        init,
+        payer = bettor,
+        space = Bid::LEN,
+        seeds = [
+            b"bid",
+            poll.key().as_ref(),
+            bettor.key().as_ref(),
+            &bid_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserStake::LEN,
+        seeds = [b"user_stake", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserPollPosition::LEN,
+        seeds = [b"pos", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, UserPollPosition>,
+
+    /// The bettor's token account of a token-gated poll's mint
+    pub gate_token: Option<Account<'info, TokenAccount>>,
+
+    /// The bettor's credential from an attestation-gated poll's attestor
+    pub attestation: Option<Account<'info, Attestation>>,
+
+    /// The poll's odds history; required when it has one
+    #[account(
+        mut,
+        constraint = odds_history.load()?.poll == poll.key() @ ErrorCode::OddsHistoryMismatch
+    )]
+    pub odds_history: Option<AccountLoader<'info, OddsHistory>>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct IncreaseBidToken<'info> {
+    #[account(
+        mut,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        token::authority = bettor
+    )]
+    pub bettor_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserStake::LEN,
+        seeds = [b"user_stake", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = UserPollPosition::LEN,
+        seeds = [b"pos", poll.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, UserPollPosition>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimWinningsToken<'info> {
+    #[account(
+        mut,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        token::authority = bettor
+    )]
+    pub bettor_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    pub bettor: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExitBidToken<'info> {
+    #[account(
+        mut,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        token::authority = bettor
+    )]
+    pub bettor_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    pub bettor: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimRefundToken<'info> {
+    #[account(mut, constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch)]
+    pub poll: Account<'info, Poll>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = bettor @ ErrorCode::Unauthorized,
+        has_one = poll @ ErrorCode::BidPollMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        token::authority = bettor
+    )]
+    pub bettor_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    pub bettor: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SweepFeesToken<'info> {
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        constraint = treasury_token.owner == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimCreatorFeesToken<'info> {
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        token::authority = authority
+    )]
+    pub creator_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ProvideLiquidityToken<'info> {
+    #[account(
+        mut,
+        constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        token::authority = provider
+    )]
+    pub provider_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = LpPosition::LEN,
+        seeds = [b"lp", poll.key().as_ref(), provider.key().as_ref()],
+        bump
+    )]
+    pub lp_position: Account<'info, LpPosition>,
+
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawLiquidityToken<'info> {
+    #[account(mut, constraint = poll.mint == Some(mint.key()) @ ErrorCode::DenominationMismatch)]
+    pub poll: Account<'info, Poll>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = poll,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        token::authority = provider
+    )]
+    pub provider_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"lp", poll.key().as_ref(), provider.key().as_ref()],
+        bump = lp_position.bump
+    )]
+    pub lp_position: Account<'info, LpPosition>,
+
+    pub provider: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct EnableOutcomeTokens<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(amount: u64, option: u8)]
+pub struct PlaceBidOutcomeToken<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        seeds = [b"outcome_mint", poll.key().as_ref(), &[option]],
+        bump,
+        mint::decimals = OUTCOME_TOKEN_DECIMALS,
+        mint::authority = poll
+    )]
+    pub outcome_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        associated_token::mint = outcome_mint,
+        associated_token::authority = bettor
+    )]
+    pub bettor_outcome_token: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(option: u8)]
+pub struct RedeemOutcomeTokens<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"outcome_mint", poll.key().as_ref(), &[option]],
+        bump
+    )]
+    pub outcome_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = outcome_mint,
+        token::authority = holder
+    )]
+    pub holder_outcome_token: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAdapter<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = MarketAdapter::LEN,
+        seeds = [b"adapter", adapter_program.key().as_ref()],
+        bump
+    )]
+    pub market_adapter: Account<'info, MarketAdapter>,
+
+    #[account(executable)]
+    /// CHECK: Only required to be a program; approving it is the admin's call
+    pub adapter_program: UncheckedAccount<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::OpinionTrading>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAdapterApproval<'info> {
+    #[account(
+        mut,
+        seeds = [b"adapter", market_adapter.program.as_ref()],
+        bump = market_adapter.bump
+    )]
+    pub market_adapter: Account<'info, MarketAdapter>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::OpinionTrading>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct AttachAdapter<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"adapter", market_adapter.program.as_ref()],
+        bump = market_adapter.bump,
+        constraint = market_adapter.approved @ ErrorCode::AdapterNotApproved
+    )]
+    pub market_adapter: Account<'info, MarketAdapter>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(amount: u64, option: u8, timestamp: i64, bid_index: u64)]
+pub struct PlaceBidAdapter<'info> {
+    #[account(
+        mut,
+        constraint = poll.adapter == Some(adapter_program.key()) @ ErrorCode::AdapterMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", poll.key().as_ref()],
+        bump = poll.vault_bump
+    )]
+    /// CHECK: Vault PDA checked via seeds
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        init,
+        payer = bettor,
+        space = Bid::LEN,
+        seeds = [
+            b"bid",
+            poll.key().as_ref(),
+            bettor.key().as_ref(),
+            &bid_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        seeds = [b"adapter", adapter_program.key().as_ref()],
+        bump = market_adapter.bump,
+        constraint = market_adapter.approved @ ErrorCode::AdapterNotApproved
+    )]
+    pub market_adapter: Account<'info, MarketAdapter>,
+
+    #[account(executable)]
+    /// CHECK: Matched against the poll's adapter and its approval record
+    pub adapter_program: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettlePollAdapter<'info> {
+    #[account(
+        mut,
+        constraint = poll.adapter == Some(market_adapter.program) @ ErrorCode::AdapterMismatch
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"adapter", market_adapter.program.as_ref()],
+        bump = market_adapter.bump,
+        constraint = market_adapter.approved @ ErrorCode::AdapterNotApproved
+    )]
+    pub market_adapter: Account<'info, MarketAdapter>,
+
+    #[account(
+        constraint = adapter_authority.key()
+            == adapter::adapter_authority(&market_adapter.program, &poll.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub adapter_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeParlayPool<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ParlayPool::LEN,
+        seeds = [b"parlay_pool"],
+        bump
+    )]
+    pub parlay_pool: Account<'info, ParlayPool>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct FundParlayPool<'info> {
+    #[account(mut, seeds = [b"parlay_pool"], bump = parlay_pool.bump)]
+    pub parlay_pool: Account<'info, ParlayPool>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawParlayPool<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"parlay_pool"], bump = parlay_pool.bump)]
+    pub parlay_pool: Account<'info, ParlayPool>,
+
+    #[account(mut, address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: SystemAccount<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = InsuranceFund::LEN,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ManageInsuranceFund<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"insurance_fund"], bump = insurance_fund.bump)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// Pays deposits
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawInsurance<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"insurance_fund"], bump = insurance_fund.bump)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(mut, address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: SystemAccount<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(parlay_id: u64)]
+pub struct PlaceParlay<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"parlay_pool"], bump = parlay_pool.bump)]
+    pub parlay_pool: Account<'info, ParlayPool>,
+
+    #[account(
+        init,
+        payer = bettor,
+        space = Parlay::LEN,
+        seeds = [b"parlay", bettor.key().as_ref(), &parlay_id.to_le_bytes()],
+        bump
+    )]
+    pub parlay: Account<'info, Parlay>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimParlay<'info> {
+    #[account(mut, seeds = [b"parlay_pool"], bump = parlay_pool.bump)]
+    pub parlay_pool: Account<'info, ParlayPool>,
+
+    #[account(mut, has_one = bettor @ ErrorCode::Unauthorized, close = bettor)]
+    pub parlay: Account<'info, Parlay>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MarkParlayLost<'info> {
+    #[account(mut, seeds = [b"parlay_pool"], bump = parlay_pool.bump)]
+    pub parlay_pool: Account<'info, ParlayPool>,
+
+    #[account(mut, has_one = bettor @ ErrorCode::Unauthorized, close = bettor)]
+    pub parlay: Account<'info, Parlay>,
+
+    pub poll: Account<'info, Poll>,
+
+    #[account(mut)]
+    pub bettor: SystemAccount<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct MigratePoll<'info> {
+    /// CHECK: a `Poll` in any layout version; the discriminator is checked
+    /// before it is resized
+    #[account(mut, owner = crate::ID)]
+    pub poll: UncheckedAccount<'info>,
+
+    /// CHECK: the SOL vault derived from the poll's `poll_id`, checked
+    /// against the poll once it is deserialized
+    #[account(mut)]
+    pub legacy_vault: UncheckedAccount<'info>,
+
+    /// CHECK: the SOL vault derived from the poll's address, checked the same
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct MigrateBid<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    /// CHECK: a `Bid` in any layout version; the discriminator is checked
+    /// before it is resized
+    #[account(mut, owner = crate::ID)]
+    pub bid: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(event_id: String)]
+pub struct CreateEvent<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = MarketEvent::LEN,
+        seeds = [b"event", event_id.as_bytes()],
+        bump
+    )]
+    pub event: Account<'info, MarketEvent>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct AddEventPoll<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub event: Account<'info, MarketEvent>,
+
+    #[account(has_one = authority @ ErrorCode::Unauthorized)]
+    pub poll: Account<'info, Poll>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleEvent<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub event: Account<'info, MarketEvent>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ ErrorCode::ProgramPaused
+    )]
+    pub config: Account<'info, Config>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct SyncEvent<'info> {
+    #[account(mut)]
+    pub event: Account<'info, MarketEvent>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(epoch_id: u32)]
+pub struct InitEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Epoch::LEN,
+        seeds = [b"epoch", epoch_id.to_le_bytes().as_ref()],
         bump
     )]
+    pub epoch: Account<'info, Epoch>,
+
+    /// Pays the epoch's rent and reward pool
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct SubmitEpochScore<'info> {
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch.epoch_id.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    #[account(seeds = [b"profile", profile.user.as_ref()], bump = profile.bump)]
+    pub profile: Account<'info, UserProfile>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct FinalizeEpoch<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch.epoch_id.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    /// Receives the reward pool not won by anyone
+    #[account(mut, address = config.treasury @ ErrorCode::Unauthorized)]
+    pub treasury: SystemAccount<'info>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ClaimEpochReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch.epoch_id.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetPollState<'info> {
+    pub poll: Account<'info, Poll>,
+}
+
+#[derive(Accounts)]
+pub struct GetBidState<'info> {
+    pub bid: Account<'info, Bid>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteBid<'info> {
+    pub poll: Account<'info, Poll>,
+
+    /// The bettor's profile, if they have one
+    #[account(seeds = [b"profile", profile.user.as_ref()], bump = profile.bump)]
+    pub profile: Option<Account<'info, UserProfile>>,
+}
+
+#[derive(Accounts)]
+pub struct GetMarketState<'info> {
     pub poll: Account<'info, Poll>,
+}
+
+// =============================================================================
+// STATE STRUCTS
+// =============================================================================
+//
+// Off-chain decoders (the `yukti-geyser` plugin, indexers) identify accounts by
+// their 8-byte Anchor discriminator, `sha256("account:<Name>")[..8]`, and read
+// the Borsh (or zero-copy) layout below. Fields are only appended, never
+// reordered or resized; the size assertions after this section fail the build
+// when a layout changes so decoders are updated in the same change.
+
+#[account]
+#[derive(InitSpace)]
+pub struct Poll {
+    pub authority: Pubkey,          // 32
+    #[max_len(64)]
+    pub poll_id: String,            // 4 + 64 = 68
+    pub title_hash: [u8; 32],       // 32 (sha256 of the title; the text is in `PollCreated`)
+    #[max_len(MAX_OUTCOMES)]
+    pub outcomes: Vec<OutcomeSlot>, // 4 + 56 * MAX_OUTCOMES = 452
+    pub total_pool: u64,            // 8
+    pub end_timestamp: i64,         // 8
+    pub status: PollStatus,         // 1
+    pub winner: Option<u8>,         // 1 + 1 = 2 (outcome index)
+    pub vault_bump: u8,             // 1
+    pub bump: u8,                   // 1
+    pub next_bid_index: u64,        // 8
+    pub accrued_fees: u64,          // 8
+    pub adapter: Option<Pubkey>,    // 1 + 32 = 33
+    pub mint: Option<Pubkey>,       // 1 + 32 = 33 (None for SOL markets)
+    pub payout_mode: PayoutMode,    // 1
+    pub resolution: Option<ResolutionSource>, // 1 + 45 = 46 (None unless oracle-settled)
+    pub dispute_period: i64,        // 8 (0 = settlements are final immediately)
+    pub settlement_proposed_at: i64, // 8
+    pub arbiter: Option<Pubkey>,    // 1 + 32 = 33 (None = authority decides challenges)
+    pub challenged: bool,           // 1
+    pub fee_bps: u16,               // 2 (copied from the config at creation)
+    pub min_bet: u64,               // 8
+    pub max_bet: u64,               // 8
+    pub finalized_at: i64,          // 8 (when the poll became Settled, Cancelled, or Voided)
+    pub creation_bond: u64,         // 8 (lamports held on this account until close or slash)
+    pub creator_fee_bps: u16,       // 2 (creator's share of the platform fee)
+    pub creator_fees: u64,          // 8 (creator's unclaimed share)
+    pub start_timestamp: i64,       // 8 (when bids open)
+    pub initial_end_timestamp: i64, // 8 (end at creation; caps `update_end_time`)
+    pub deadline_extension: Option<DeadlineExtension>, // 1 + 17 = 18 (None = no anti-sniping)
+    pub extension_count: u8,        // 1
+    pub virtual_liquidity: u64,     // 8 (virtual stake per outcome in AMM pricing)
+    pub lp_liquidity: u64,          // 8 (LP deposits backing fixed-odds wins)
+    pub lp_fee_bps: u16,            // 2 (LPs' share of the platform fee)
+    pub lp_fees: u64,               // 8 (LP fees accrued so far)
+    pub outcome_tokens: bool,       // 1 (bids mint outcome tokens instead of Bid accounts)
+    pub claim_period: i64,          // 8 (time to claim once finalized; copied from the config)
+    pub pending_authority: Option<Pubkey>, // 1 + 32 = 33 (proposed by `propose_authority_transfer`)
+    pub max_total_pool: Option<u64>, // 1 + 8 = 9 (None = uncapped)
+    pub max_stake_per_option: Option<u64>, // 1 + 8 = 9
+    pub max_stake_per_user: Option<u64>, // 1 + 8 = 9 (tracked in `UserStake`)
+    pub settlement_commitment: Option<[u8; 32]>, // 1 + 32 = 33 (hash awaiting `reveal_settlement`)
+    pub committed_at: i64,          // 8
+    pub referral_fee_bps: u16,      // 2 (referrers' share of the platform fee)
+    #[max_len(MAX_FEE_TIERS)]
+    pub fee_tiers: Vec<FeeTier>,    // 4 + 10 * MAX_FEE_TIERS = 44 (volume discounts on `fee_bps`)
+    pub kind: PollKind,             // 1 + 4 + 8 * MAX_SCALAR_BOUNDS = 61
+    pub crank_bounty: u64,          // 8 (held in the vault for `crank_settle`; 0 once paid)
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the poll's last event)
+    pub gate: Option<AccessGate>,   // 1 + 41 = 42 (who may bid; anyone if None)
+    pub council: Option<ResolverCouncil>, // 1 + 179 = 180 (settles by vote instead of authority)
+    pub result_signer: Option<Pubkey>, // 1 + 32 = 33 (settles by signed result instead)
+    #[max_len(MAX_OUTCOMES)]
+    pub community_votes: Vec<u64>,  // 4 + 8 * MAX_OUTCOMES = 68 (stake voted per outcome)
+    pub total_fees: u64,            // 8 (platform fees taken by claims and exits, all shares)
+    pub odds_history: bool,         // 1 (bids append to the `OddsHistory` PDA)
+    pub version: u8,                // 1 (layout version, `POLL_VERSION` when current)
+    pub incentives: Option<IncentiveCurve>, // 1 + 20 = 21 (early-bird bonus and late fee; v2)
+    pub resolution_deadline: i64,   // 8 (seconds after the end to settle, 0 = none; v3)
+    pub swept_fees: u64,            // 8 (swept into the fee vault, after insurance; v4)
+    pub fee_share_bps: u16,         // 2 (granted to `FeeShare` accounts; v4)
+    pub cancel_window: i64,         // 8 (seconds after placing a bid to cancel it, 0 = off; v5)
+    pub cancel_fee_bps: u16,        // 2 (taken from a cancelled bid's stake; v5)
+    pub max_price_impact_bps: u16,  // 2 (most one bid may move its odds, 0 = no limit; v6)
+    #[max_len(MAX_OUTCOMES)]
+    pub bid_counts: Vec<u32>,       // 4 + 4 * MAX_OUTCOMES = 36 (bids per outcome, less exits; v7)
+    pub claimed_count: u32,         // 4 (winning bids paid in full; v7)
+    pub min_seconds_between_bids: i64, // 8 (cooldown between a user's bids, 0 = none; v8)
+    pub registry_page: Option<u32>, // 1 + 4 = 5 (`PollRegistry` page listing the poll; v9)
+    pub damping_window: i64,        // 8 (damped stretch before the end, 0 = off; v10)
+    pub damping_bps: u16,           // 2 (share of an odds move held back at the end; v10)
+    pub vig_bps: u16,               // 2 (margin built into bid odds, 0 = none; v11)
+    pub treasury_swept: u64,        // 8 (fees and vault remainders in `TreasuryStats`; v13)
+}
+
+impl Poll {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Oracle condition that settles a two-outcome poll: outcome 0 wins when the
+/// feed's price compares true against `threshold`, outcome 1 otherwise
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct ResolutionSource {
+    pub feed_id: [u8; 32],          // 32 (Pyth price feed id)
+    pub threshold: i64,             // 8 (in units of 10^exponent)
+    pub exponent: i32,              // 4 (must match the feed's)
+    pub comparison: PriceComparison, // 1
+}
+
+/// A resolution note as carried by `PollSettled` and `PollVoided`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ResolutionEvidence {
+    pub note: Pubkey,
+    pub uri: String,
+    pub evidence_hash: [u8; 32],
+}
+
+/// Winner and salt of a settlement commitment, revealed through `crank_settle`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SettlementReveal {
+    pub winning_option: u8,
+    pub salt: [u8; 32],
+}
+
+/// Resolvers that settle a poll by vote: the first outcome `threshold` of them
+/// vote for wins
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct ResolverCouncil {
+    #[max_len(MAX_RESOLVERS)]
+    pub resolvers: Vec<Pubkey>,     // 4 + 32 * MAX_RESOLVERS = 164
+    pub threshold: u8,              // 1
+    #[max_len(MAX_RESOLVERS)]
+    pub votes: Vec<Option<u8>>,     // 4 + 2 * MAX_RESOLVERS = 14 (each resolver's vote, in order)
+}
+
+/// A bid's vote at `["community_vote", bid]` on the outcome of a poll its
+/// authority left unsettled
+#[account]
+#[derive(InitSpace)]
+pub struct CommunityVote {
+    pub poll: Pubkey,               // 32
+    pub voter: Pubkey,              // 32 (the bid's bettor; gets the rent back)
+    pub option: u8,                 // 1 (outcome index)
+    pub weight: u64,                // 8 (the bid's stake)
+    pub bump: u8,                   // 1
+}
+
+impl CommunityVote {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// A limit order at `["order", poll, bettor, order_id]` escrowing a stake (plus
+/// the bid's rent) until the outcome's odds reach its limit
+#[account]
+#[derive(InitSpace)]
+pub struct PendingOrder {
+    pub poll: Pubkey,               // 32
+    pub bettor: Pubkey,             // 32
+    pub order_id: u64,              // 8 (chosen by the bettor)
+    pub amount: u64,                // 8 (stake escrowed)
+    pub option: u8,                 // 1 (outcome index)
+    pub max_odds: u64,              // 8 (highest odds it fills at, basis points)
+    pub created_at: i64,            // 8
+    pub bump: u8,                   // 1
+}
+
+impl PendingOrder {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// One leg of a parlay: an outcome of a poll and its odds when the parlay was
+/// placed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct ParlayLeg {
+    pub poll: Pubkey,               // 32
+    pub option: u8,                 // 1
+    pub odds: u64,                  // 8 (basis points)
+}
+
+/// Volume discount: bettors who have staked at least `min_volume` in total pay
+/// `fee_bps` on winnings instead of the poll's base fee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct FeeTier {
+    pub min_volume: u64,            // 8
+    pub fee_bps: u16,               // 2
+}
+
+/// Anti-sniping rule: a bid placed within `window` seconds of the end pushes
+/// the end back by `duration` seconds, at most `max_extensions` times
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct DeadlineExtension {
+    pub window: i64,                // 8
+    pub duration: i64,              // 8
+    pub max_extensions: u8,         // 1
+}
+
+/// Incentive curve: a bid placed within `early_window` seconds of the start
+/// pays `early_bonus_bps` less fee on its win, and one placed within
+/// `late_window` seconds of the end pays `late_fee_bps` more
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct IncentiveCurve {
+    pub early_window: i64,          // 8
+    pub early_bonus_bps: u16,       // 2 (at most the poll's fee, which funds it)
+    pub late_window: i64,           // 8
+    pub late_fee_bps: u16,          // 2
+}
+
+/// One outcome of a poll: its label, total stake, current AMM odds, and the
+/// sum of `potential_win` locked in by bids on it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct OutcomeSlot {
+    pub text_hash: [u8; 32],        // 32 (sha256 of the label; the text is in `PollCreated`)
+    pub stake: u64,                 // 8
+    pub odds: u64,                  // 8
+    pub total_potential_liability: u64, // 8
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Bid {
+    pub bettor: Pubkey,             // 32
+    pub poll: Pubkey,               // 32
+    pub amount: u64,                // 8
+    pub option: u8,                 // 1 (outcome index)
+    pub odds_at_purchase: u64,      // 8
+    pub potential_win: u64,         // 8
+    pub status: BidStatus,          // 1
+    pub timestamp: i64,             // 8
+    pub index: u64,                 // 8
+    pub bump: u8,                   // 1
+    pub referrer: Option<Pubkey>,   // 1 + 32 = 33 (paid a share of the fee on claim)
+    pub unclaimed: u64,             // 8 (payout left after a partial claim)
+    pub version: u8,                // 1 (layout version, `BID_VERSION` when current)
+    pub claimed_amount: u64,        // 8 (paid to the bettor by claims, refunds, and exits; v2)
+    pub fee_paid: u64,              // 8 (fees and tips taken from those payments; v2)
+    pub claimed_at: i64,            // 8 (time of the last payment, 0 before; v2)
+    pub fee_adjust_bps: i16,        // 2 (added to the poll's fee on claim, negative for an early bid; v3)
+    pub memo: Option<[u8; 32]>,     // 1 + 32 = 33 (client or campaign tag from `place_bid`; v4)
+    pub placed_at: i64,             // 8 (clock time the bid was placed, 0 before; v5)
+    pub credit: u64,                // 8 (bet credit principal still owed to the promo vault; v6)
+    pub usd_notional_at_purchase: u64, // 8 (micro-USD value of the stake when placed, 0 without a price; v7)
+}
+
+impl Bid {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Program-wide settings, a singleton at `["config"]`
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,              // 32
+    pub treasury: Pubkey,           // 32 (receives swept fees)
+    pub fee_bps: u16,               // 2
+    pub min_bet: u64,               // 8
+    pub max_bet: u64,               // 8
+    pub bump: u8,                   // 1
+    pub creation_bond: u64,         // 8 (lamports posted by each poll creator)
+    pub creator_fee_bps: u16,       // 2 (creator's share of the platform fee)
+    pub lp_fee_bps: u16,            // 2 (LPs' share of the platform fee)
+    pub claim_period: i64,          // 8 (time winners and refunds have to claim)
+    pub pending_admin: Option<Pubkey>, // 1 + 32 = 33 (proposed by `propose_admin_transfer`)
+    pub paused: bool,               // 1 (blocks bids and settlement, not claims)
+    pub referral_fee_bps: u16,      // 2 (referrers' share of the platform fee)
+    #[max_len(MAX_FEE_TIERS)]
+    pub fee_tiers: Vec<FeeTier>,    // 4 + 10 * MAX_FEE_TIERS = 44 (set by `set_fee_tiers`)
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the config's last event)
+    pub usd_feed_id: [u8; 32],      // 32 (Pyth SOL/USD feed bids are valued with, zeros when unset)
+    pub max_bid_usd: u64,           // 8 (micro-USD limit per SOL bid, 0 for none)
+    pub epoch_id: u32,              // 4 (latest leaderboard `Epoch`, 0 before the first)
+    pub epoch_start: i64,           // 8 (when that epoch starts counting)
+    pub epoch_end: i64,             // 8 (when it stops)
+}
+
+impl Config {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// An adapter program approved to host markets on top of core escrow
+#[account]
+#[derive(InitSpace)]
+pub struct MarketAdapter {
+    pub program: Pubkey,            // 32
+    pub approved: bool,             // 1
+    pub bump: u8,                   // 1
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the adapter's last event)
+}
+
+impl MarketAdapter {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// A bond posted against a poll's proposed settlement, one per poll
+#[account]
+#[derive(InitSpace)]
+pub struct Challenge {
+    pub poll: Pubkey,               // 32
+    pub challenger: Pubkey,         // 32
+    pub proposed_winner: u8,        // 1
+    pub bond: u64,                  // 8
+    pub created_at: i64,            // 8
+    pub bump: u8,                   // 1
+}
+
+impl Challenge {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// One provider's liquidity in a poll, at `["lp", poll, provider]`
+#[account]
+#[derive(InitSpace)]
+pub struct LpPosition {
+    pub poll: Pubkey,               // 32
+    pub provider: Pubkey,           // 32
+    pub amount: u64,                // 8 (total deposited)
+    pub fees_claimed: u64,          // 8 (LP fees withdrawn so far)
+    pub withdrawn: bool,            // 1 (equity share paid out)
+    pub bump: u8,                   // 1
+}
+
+impl LpPosition {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// A user's lifetime betting stats, at `["profile", user]`. Created on first
+/// use and updated by `place_bid`, `claim_winnings`, `claim_refund`, and
+/// `close_bid` (which records losses).
+#[account]
+#[derive(InitSpace)]
+pub struct UserProfile {
+    pub user: Pubkey,               // 32
+    pub bids_placed: u64,           // 8
+    pub volume: u64,                // 8 (total staked, in lamports)
+    pub wins: u64,                  // 8
+    pub losses: u64,                // 8
+    pub claims: u64,                // 8 (winnings and refunds claimed)
+    pub net_pnl: i64,               // 8 (payouts minus stakes of finished bids)
+    pub bump: u8,                   // 1
+    pub claim_delegate: Option<Pubkey>, // 1 + 32 = 33 (may claim winnings for the user, paid to them)
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the profile's last event)
+    pub epoch_id: u32,              // 4 (`Epoch` the counters below are for)
+    pub epoch_volume: u64,          // 8 (staked during that epoch)
+    pub epoch_pnl: i64,             // 8 (net P&L realized during that epoch)
+}
+
+impl UserProfile {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Lamports a user pre-funded for `place_bid_from_balance`, at
+/// `["balance", user]`. They are held on this account above its rent.
+#[account]
+#[derive(InitSpace)]
+pub struct UserBalance {
+    pub user: Pubkey,               // 32
+    pub balance: u64,               // 8 (deposits less withdrawals and stakes)
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the balance's last event)
+    pub bump: u8,                   // 1
+}
+
+impl UserBalance {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// One page of the index of live polls, at `["poll_registry", page]` (page as
+/// little-endian u32). New polls are appended to a page with room; finalized
+/// and closed polls are swapped out, so a page isn't in creation order.
+#[account]
+#[derive(InitSpace)]
+pub struct PollRegistry {
+    pub page: u32,                  // 4
+    #[max_len(REGISTRY_PAGE_SIZE)]
+    pub polls: Vec<Pubkey>,         // 4 + 32 * REGISTRY_PAGE_SIZE = 3204
+    pub bump: u8,                   // 1
+}
+
+impl PollRegistry {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Promotional credit granted to a user by `grant_credit`, at
+/// `["credit", user]`, spent by `place_bid_with_credit`. The lamports backing
+/// it sit in the promo vault.
+#[account]
+#[derive(InitSpace)]
+pub struct BetCredit {
+    pub user: Pubkey,               // 32
+    pub balance: u64,               // 8 (granted credit not yet staked)
+    pub granted: u64,               // 8 (all credit granted so far)
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the credit's last event)
+    pub bump: u8,                   // 1
+}
+
+impl BetCredit {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Vault at `["promo_vault"]` holding the lamports behind every `BetCredit`
+/// above its rent. Credit bids' principal returns here from their payouts.
+#[account]
+#[derive(InitSpace)]
+pub struct PromoVault {
+    pub outstanding: u64,           // 8 (credit granted and not yet staked)
+    pub total_granted: u64,         // 8
+    pub total_returned: u64,        // 8 (principal repaid by credit bids' payouts)
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the vault's last event)
+    pub bump: u8,                   // 1
+}
+
+impl PromoVault {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// A user's total stake on one poll, at `["user_stake", poll, user]`, checked
+/// against the poll's `max_stake_per_user`. Exits don't free up room.
+#[account]
+#[derive(InitSpace)]
+pub struct UserStake {
+    pub poll: Pubkey,               // 32
+    pub user: Pubkey,               // 32
+    pub stake: u64,                 // 8
+    pub bump: u8,                   // 1
+}
+
+impl UserStake {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// A user's bids on one poll, at `["pos", poll, user]`, so clients can load a
+/// position with direct fetches instead of `getProgramAccounts`. Lists the
+/// index of each of the user's first `MAX_POSITION_BIDS` bids; `stakes` and
+/// `bid_count` cover all of them.
+#[account]
+#[derive(InitSpace)]
+pub struct UserPollPosition {
+    pub poll: Pubkey,               // 32
+    pub user: Pubkey,               // 32
+    pub stakes: [u64; MAX_OUTCOMES], // 64 (staked per outcome)
+    pub bid_count: u32,             // 4 (bids placed, listed or not)
+    #[max_len(MAX_POSITION_BIDS)]
+    pub bid_indices: Vec<u64>,      // 4 + 8 * MAX_POSITION_BIDS = 132
+    pub bump: u8,                   // 1
+    pub last_bid_ts: i64,           // 8 (clock time of the last bid or increase)
+}
+
+impl UserPollPosition {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Related polls grouped at `["event", event_id]` under one resolution
+/// authority, e.g. the markets of one match. Named `MarketEvent` because
+/// `Event` is Anchor's trait for logged events.
+#[account]
+#[derive(InitSpace)]
+pub struct MarketEvent {
+    pub authority: Pubkey,          // 32 (authority of every poll; settles them by `settle_event`)
+    #[max_len(64)]
+    pub event_id: String,           // 4 + 64 = 68
+    pub status: EventStatus,        // 1
+    #[max_len(MAX_EVENT_POLLS)]
+    pub polls: Vec<Pubkey>,         // 4 + 32 * MAX_EVENT_POLLS = 260 (in the order added)
+    pub total_volume: u64,          // 8 (sum of the polls' pools at the last settle or sync)
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the event's last event)
+    pub bump: u8,                   // 1
+}
+
+impl MarketEvent {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// A leaderboard epoch at `["epoch", epoch_id]`. Users' activity between its
+/// start and end is scored by `metric`, and the top scorers share the reward
+/// pool, held on this account above its rent, by `weights`.
+#[account]
+#[derive(InitSpace)]
+pub struct Epoch {
+    pub epoch_id: u32,              // 4
+    pub start_timestamp: i64,       // 8
+    pub end_timestamp: i64,         // 8
+    pub metric: EpochMetric,        // 1
+    pub reward_pool: u64,           // 8
+    #[max_len(MAX_EPOCH_WINNERS)]
+    pub weights: Vec<u16>,          // 4 + 2 * MAX_EPOCH_WINNERS = 24 (pool share per rank)
+    #[max_len(MAX_EPOCH_WINNERS)]
+    pub leaderboard: Vec<EpochEntry>, // 4 + 49 * MAX_EPOCH_WINNERS = 494 (highest score first)
+    pub finalized: bool,            // 1 (rewards fixed; no more scores)
+    pub paid: u64,                  // 8 (rewards claimed so far)
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the epoch's last event)
+    pub bump: u8,                   // 1
+}
+
+impl Epoch {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// A ranked user on an `Epoch` leaderboard
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct EpochEntry {
+    pub user: Pubkey,               // 32
+    pub score: i64,                 // 8
+    pub reward: u64,                // 8 (set when the epoch is finalized)
+    pub claimed: bool,              // 1
+}
+
+/// A bet that every leg wins, at `["parlay", bettor, parlay_id]`. Closed when
+/// it is claimed or marked lost.
+#[account]
+#[derive(InitSpace)]
+pub struct Parlay {
+    pub bettor: Pubkey,             // 32
+    pub parlay_id: u64,             // 8
+    pub amount: u64,                // 8
+    pub potential_win: u64,         // 8 (gross, reserved in the parlay pool)
+    pub fee_bps: u16,               // 2 (copied from the config)
+    pub timestamp: i64,             // 8
+    #[max_len(MAX_PARLAY_LEGS)]
+    pub legs: Vec<ParlayLeg>,       // 4 + 41 * MAX_PARLAY_LEGS = 209
+    pub bump: u8,                   // 1
+}
+
+impl Parlay {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// House pool at `["parlay_pool"]` that takes parlay stakes and pays parlay
+/// wins from its own lamports
+#[account]
+#[derive(InitSpace)]
+pub struct ParlayPool {
+    pub liability: u64,             // 8 (gross wins of open parlays)
+    pub bump: u8,                   // 1
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the pool's last event)
+}
+
+impl ParlayPool {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Program-owned account at `["fee_vault"]` that swept platform fees collect
+/// in until the admin withdraws them to the treasury
+#[account]
+#[derive(InitSpace)]
+pub struct FeeVault {
+    pub total_swept: u64,           // 8 (lamports swept in so far)
+    pub bump: u8,                   // 1
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the vault's last event)
+    pub fee_share_reserve: u64,     // 8 (owed to fee shares, not yet claimed; withdrawals leave it)
+}
+
+impl FeeVault {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// A credential an attestor issued to `user`, at `["attestation", attestor,
+/// user]`, admitting them to polls gated on that attestor. Closed when
+/// revoked.
+#[account]
+#[derive(InitSpace)]
+pub struct Attestation {
+    pub attestor: Pubkey,           // 32
+    pub user: Pubkey,               // 32
+    pub issued_at: i64,             // 8 (last issued or renewed)
+    pub expires_at: i64,            // 8 (0 for no expiry)
+    pub bump: u8,                   // 1
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the credential's last event)
+}
+
+impl Attestation {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Program-wide fee ledger at `["treasury_stats"]`: what `sweep_fees` moved
+/// out of poll vaults and what `withdraw_treasury` paid out of the fee vault
+#[account]
+#[derive(InitSpace)]
+pub struct TreasuryStats {
+    pub total_fees: u64,            // 8 (lamports swept, insurance share included)
+    pub total_insurance: u64,       // 8 (part of `total_fees` paid to the insurance fund)
+    pub total_withdrawn: u64,       // 8
+    pub sweep_count: u64,           // 8
+    pub withdraw_delay: i64,        // 8 (timelock in seconds; 0 pays withdrawals at once)
+    pub pending: Option<TreasuryWithdrawal>, // 1 + 48 (`timestamp` is when it unlocks)
+    #[max_len(TREASURY_HISTORY_LEN)]
+    pub recent_sweeps: Vec<FeeSweep>, // 4 + 48 * TREASURY_HISTORY_LEN = 772 (oldest first)
+    #[max_len(TREASURY_HISTORY_LEN)]
+    pub withdrawals: Vec<TreasuryWithdrawal>, // 4 + 48 * TREASURY_HISTORY_LEN = 772 (oldest first)
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the ledger's last event)
+    pub bump: u8,                   // 1
+    pub total_remainders: u64,      // 8 (part of `total_fees` left in the vaults of ended polls)
+    pub pending_treasury: Option<TreasuryChange>, // 1 + 40 (config treasury waiting out the timelock)
+}
+
+impl TreasuryStats {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// One poll's `sweep_fees` in a `TreasuryStats` history
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct FeeSweep {
+    pub poll: Pubkey,               // 32
+    pub amount: u64,                // 8 (insurance share included)
+    pub timestamp: i64,             // 8
+}
+
+/// A treasury withdrawal, queued or paid
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct TreasuryWithdrawal {
+    pub amount: u64,                // 8
+    pub destination: Pubkey,        // 32
+    pub timestamp: i64,             // 8 (when it unlocks, or was paid)
+}
+
+/// A new config treasury queued by `change_treasury`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct TreasuryChange {
+    pub treasury: Pubkey,           // 32
+    pub unlocks_at: i64,            // 8
+}
+
+/// A party's share of the fees one SOL poll sweeps into the fee vault, at
+/// `["fee_share", poll, party]`
+#[account]
+#[derive(InitSpace)]
+pub struct FeeShare {
+    pub poll: Pubkey,               // 32
+    pub party: Pubkey,              // 32
+    pub share_bps: u16,             // 2 (of each sweep, after insurance)
+    pub swept_at_grant: u64,        // 8 (poll's `swept_fees` when granted; earlier sweeps don't count)
+    pub claimed: u64,               // 8
+    pub bump: u8,                   // 1
+}
+
+impl FeeShare {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Why a poll's authority settled it the way it did, at
+/// `["resolution_note", poll]`. It stays after settlement as the audit trail.
+#[account]
+#[derive(InitSpace)]
+pub struct ResolutionNote {
+    pub poll: Pubkey,               // 32
+    pub author: Pubkey,             // 32 (the poll's authority when attached)
+    #[max_len(MAX_METADATA_URI_LEN)]
+    pub uri: String,                // 4 + 200 (where the evidence is published)
+    pub evidence_hash: [u8; 32],    // 32 (sha256 of the evidence, e.g. an archived page)
+    pub created_at: i64,            // 8 (time of the last revision)
+    pub bump: u8,                   // 1
+}
+
+impl ResolutionNote {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Fund at `["insurance_fund"]` that tops up a vault too short to pay a
+/// claim, from its own lamports
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceFund {
+    pub fee_bps: u16,               // 2 (share of swept platform fees)
+    pub total_covered: u64,         // 8 (lamports paid into vaults so far)
+    pub bump: u8,                   // 1
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the fund's last event)
+}
+
+impl InsuranceFund {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Recurring market at `["poll_template", id_prefix]` that
+/// `create_poll_from_template` stamps rounds out of
+#[account]
+#[derive(InitSpace)]
+pub struct PollTemplate {
+    pub authority: Pubkey,          // 32
+    #[max_len(MAX_TEMPLATE_PREFIX_LEN)]
+    pub id_prefix: String,          // 4 + 43 (round n is poll "{id_prefix}-{n}")
+    #[max_len(256)]
+    pub title: String,              // 4 + 256
+    #[max_len(MAX_OUTCOMES, 128)]
+    pub outcomes: Vec<String>,      // 4 + 8 * (4 + 128)
+    pub mint: Option<Pubkey>,       // 1 + 32 = 33
+    pub payout_mode: PayoutMode,    // 1
+    pub resolution: Option<ResolutionSource>, // 1 + 45 = 46
+    pub dispute_period: i64,        // 8
+    pub arbiter: Option<Pubkey>,    // 1 + 32 = 33
+    pub deadline_extension: Option<DeadlineExtension>, // 1 + 17 = 18
+    pub virtual_liquidity: u64,     // 8
+    pub kind: PollKind,             // 1 + 4 + 7 * 8 = 61
+    pub limits: PollLimits,         // 9 + 9 + 3 = 21
+    pub first_start: i64,           // 8 (round 0 opens at this time)
+    pub period: i64,                // 8 (seconds between rounds)
+    pub duration: i64,              // 8 (seconds each round is open)
+    pub bump: u8,                   // 1
+    pub event_seq: u64,             // 8 (`EventHeader::event_seq` of the template's last event)
+}
+
+impl PollTemplate {
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+/// Optional display and resolution details of a poll at
+/// `["poll_metadata", poll]`, kept off the `Poll` that every bid loads.
+/// Sized to its contents.
+#[account]
+pub struct PollMetadata {
+    pub poll: Pubkey,               // 32
+    pub bump: u8,                   // 1
+    pub category: PollCategory,     // 1
+    pub uri: String,                // 4 + len (off-chain JSON: description, image)
+    pub tags: Vec<String>,          // 4 + (4 + len) per tag
+    pub resolution_criteria: String, // 4 + len
+}
+
+impl PollMetadata {
+    pub fn space(uri: &str, tags: &[String], resolution_criteria: &str) -> usize {
+        8 + 32
+            + 1
+            + 1
+            + 4
+            + uri.len()
+            + 4
+            + tags.iter().map(|tag| 4 + tag.len()).sum::<usize>()
+            + 4
+            + resolution_criteria.len()
+    }
+}
+
+/// Packed bid storage for high-frequency polls: one account holds
+/// `LEDGER_CAPACITY` bids, addressed by (ledger, index), instead of one PDA each
+#[account(zero_copy)]
+pub struct BidLedger {
+    pub poll: Pubkey,                          // 32
+    pub count: u32,                            // 4
+    pub _padding: [u8; 4],                     // 4
+    pub records: [LedgerBid; LEDGER_CAPACITY], // 72 * LEDGER_CAPACITY
+}
+
+impl BidLedger {
+    pub const LEN: usize = 8 + std::mem::size_of::<BidLedger>();
+}
+
+#[zero_copy]
+pub struct LedgerBid {
+    pub bettor: Pubkey,             // 32
+    pub amount: u64,                // 8
+    pub potential_win: u64,         // 8
+    pub odds_at_purchase: u64,      // 8
+    pub timestamp: i64,             // 8
+    pub option: u8,                 // 1 (outcome index)
+    pub status: u8,                 // 1 (BidStatus discriminant)
+    pub fee_adjust_bps: i16,        // 2 (added to the poll's fee on claim, as on `Bid`)
+    pub _padding: [u8; 4],          // 4
+}
+
+impl LedgerBid {
+    /// The entry as an unclaimed `Bid` of `poll`, for `settle_claim`
+    pub fn to_bid(&self, poll: Pubkey, index: u32) -> Bid {
+        Bid {
+            bettor: self.bettor,
+            poll,
+            amount: self.amount,
+            option: self.option,
+            odds_at_purchase: self.odds_at_purchase,
+            potential_win: self.potential_win,
+            status: BidStatus::Active,
+            timestamp: self.timestamp,
+            index: u64::from(index),
+            bump: 0,
+            referrer: None,
+            unclaimed: 0,
+            version: BID_VERSION,
+            claimed_amount: 0,
+            fee_paid: 0,
+            claimed_at: 0,
+            fee_adjust_bps: self.fee_adjust_bps,
+            memo: None,
+            placed_at: self.timestamp,
+            credit: 0,
+            usd_notional_at_purchase: 0,
+        }
+    }
+}
+
+/// Chart data at `["odds_history", poll]`: a ring of the poll's latest
+/// `ODDS_HISTORY_CAPACITY` snapshots, at least `interval` seconds apart
+#[account(zero_copy)]
+pub struct OddsHistory {
+    pub poll: Pubkey,               // 32
+    pub interval: i64,              // 8 (least seconds between snapshots)
+    pub head: u32,                  // 4 (slot the next snapshot goes in)
+    pub count: u32,                 // 4 (snapshots held, up to ODDS_HISTORY_CAPACITY)
+    pub snapshots: [OddsSnapshot; ODDS_HISTORY_CAPACITY], // 32 * ODDS_HISTORY_CAPACITY
+}
+
+impl OddsHistory {
+    pub const LEN: usize = 8 + std::mem::size_of::<OddsHistory>();
+}
+
+#[zero_copy]
+pub struct OddsSnapshot {
+    pub timestamp: i64,             // 8
+    pub total_pool: u64,            // 8
+    pub odds: [u16; MAX_OUTCOMES],  // 16 (basis points per outcome; 0 past the poll's outcomes)
+}
+
+/// Final per-bid payouts written at settlement by the `compute_payouts` crank.
+/// The header is followed by `bid_count` `PayoutEntry` records indexed by `Bid::index`.
+#[account(zero_copy)]
+pub struct PayoutTable {
+    pub poll: Pubkey,               // 32
+    pub bid_count: u64,             // 8
+    pub computed_count: u64,        // 8
+}
+
+impl PayoutTable {
+    pub const HEADER_LEN: usize = 8 + std::mem::size_of::<PayoutTable>();
+
+    pub fn space(bid_count: u64) -> usize {
+        Self::HEADER_LEN + bid_count as usize * std::mem::size_of::<PayoutEntry>()
+    }
+}
+
+#[zero_copy]
+pub struct PayoutEntry {
+    pub payout: u64,                // 8
+    pub platform_fee: u64,          // 8
+    pub computed: u8,               // 1
+    pub _padding: [u8; 7],          // 7
+}
+
+// Layout guards for off-chain decoders (see the STATE STRUCTS note)
+const _: () = assert!(Poll::LEN == 1587);
+const _: () = assert!(Bid::LEN == 240);
+const _: () = assert!(MarketAdapter::LEN == 50);
+const _: () = assert!(Challenge::LEN == 90);
+const _: () = assert!(Config::LEN == 259);
+const _: () = assert!(LpPosition::LEN == 90);
+const _: () = assert!(UserProfile::LEN == 150);
+const _: () = assert!(UserStake::LEN == 81);
+const _: () = assert!(UserBalance::LEN == 57);
+const _: () = assert!(BetCredit::LEN == 65);
+const _: () = assert!(PromoVault::LEN == 41);
+const _: () = assert!(PollRegistry::LEN == 3217);
+const _: () = assert!(UserPollPosition::LEN == 281);
+const _: () = assert!(MarketEvent::LEN == 386);
+const _: () = assert!(Epoch::LEN == 573);
+const _: () = assert!(Parlay::LEN == 284);
+const _: () = assert!(ParlayPool::LEN == 25);
+const _: () = assert!(InsuranceFund::LEN == 27);
+const _: () = assert!(FeeVault::LEN == 33);
+const _: () = assert!(TreasuryStats::LEN == 1699);
+const _: () = assert!(Attestation::LEN == 97);
+const _: () = assert!(FeeShare::LEN == 91);
+const _: () = assert!(ResolutionNote::LEN == 317);
+const _: () = assert!(CommunityVote::LEN == 82);
+const _: () = assert!(PendingOrder::LEN == 106);
+const _: () = assert!(PollTemplate::LEN == 1669);
+const _: () = assert!(std::mem::size_of::<LedgerBid>() == 72);
+const _: () = assert!(BidLedger::LEN == 8 + 40 + 72 * LEDGER_CAPACITY);
+const _: () = assert!(std::mem::size_of::<OddsSnapshot>() == 32);
+const _: () = assert!(OddsHistory::LEN == 8 + 48 + 32 * ODDS_HISTORY_CAPACITY);
+const _: () = assert!(PayoutTable::HEADER_LEN == 56);
+const _: () = assert!(std::mem::size_of::<PayoutEntry>() == 24);
+
+// =============================================================================
+// VIEW TYPES
+// =============================================================================
+
+/// Current layout version of `PollState`, `BidState`, and `BidQuote`. Fields
+/// are only ever appended; a breaking change bumps the version.
+pub const VIEW_VERSION: u8 = 2;
+
+/// Stable snapshot returned by `get_poll_state`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PollState {
+    pub version: u8,
+    pub poll: Pubkey,
+    pub authority: Pubkey,
+    pub status: PollStatus,
+    pub winner: Option<u8>,
+    pub outcome_stakes: Vec<u64>,
+    pub outcome_odds: Vec<u64>,
+    pub total_pool: u64,
+    pub end_timestamp: i64,
+    pub next_bid_index: u64,
+    pub accrued_fees: u64,
+    pub adapter: Option<Pubkey>,
+    pub mint: Option<Pubkey>,
+    pub payout_mode: PayoutMode,
+    pub resolution: Option<ResolutionSource>,
+    pub dispute_period: i64,
+    pub settlement_proposed_at: i64,
+    pub arbiter: Option<Pubkey>,
+    pub challenged: bool,
+    pub fee_bps: u16,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub finalized_at: i64,
+    pub creation_bond: u64,
+    pub creator_fee_bps: u16,
+    pub creator_fees: u64,
+    pub start_timestamp: i64,
+    pub initial_end_timestamp: i64,
+    pub deadline_extension: Option<DeadlineExtension>,
+    pub extension_count: u8,
+    pub virtual_liquidity: u64,
+    pub lp_liquidity: u64,
+    pub lp_fee_bps: u16,
+    pub lp_fees: u64,
+    pub outcome_tokens: bool,
+    pub claim_period: i64,
+    pub pending_authority: Option<Pubkey>,
+    pub max_total_pool: Option<u64>,
+    pub max_stake_per_option: Option<u64>,
+    pub max_stake_per_user: Option<u64>,
+    pub settlement_commitment: Option<[u8; 32]>,
+    pub referral_fee_bps: u16,
+    pub fee_tiers: Vec<FeeTier>,
+    pub kind: PollKind,
+    pub crank_bounty: u64,
+    pub event_seq: u64,
+    pub gate: Option<AccessGate>,
+    pub council: Option<ResolverCouncil>,
+    pub result_signer: Option<Pubkey>,
+    pub community_votes: Vec<u64>,
+    pub total_fees: u64,
+    pub odds_history: bool,
+    pub incentives: Option<IncentiveCurve>,
+    pub resolution_deadline: i64,
+    pub swept_fees: u64,
+    pub fee_share_bps: u16,
+    pub cancel_window: i64,
+    pub cancel_fee_bps: u16,
+    pub max_price_impact_bps: u16,
+    pub bid_counts: Vec<u32>,
+    pub claimed_count: u32,
+    pub min_seconds_between_bids: i64,
+    pub registry_page: Option<u32>,
+    pub damping_window: i64,
+    pub damping_bps: u16,
+    pub vig_bps: u16,
+}
+
+/// Stable snapshot returned by `get_bid_state`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BidState {
+    pub version: u8,
+    pub bid: Pubkey,
+    pub bettor: Pubkey,
+    pub poll: Pubkey,
+    pub amount: u64,
+    pub option: u8,
+    pub odds_at_purchase: u64,
+    pub potential_win: u64,
+    pub status: BidStatus,
+    pub timestamp: i64,
+    pub index: u64,
+    pub referrer: Option<Pubkey>,
+    pub unclaimed: u64,
+    pub claimed_amount: u64,
+    pub fee_paid: u64,
+    pub claimed_at: i64,
+    pub fee_adjust_bps: i16,
+    pub memo: Option<[u8; 32]>,
+    pub placed_at: i64,
+    pub credit: u64,
+    pub usd_notional_at_purchase: u64,
+}
+
+/// Quote returned by `quote_bid`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Debug)]
+pub struct BidQuote {
+    pub version: u8,
+    /// The part of the requested amount that would fill under the poll's
+    /// price impact limit
+    pub amount: u64,
+    pub option: u8,
+    /// Odds the bid would be priced at (basis points)
+    pub odds: u64,
+    /// Every outcome's odds once the bid is placed
+    pub post_trade_odds: Vec<u64>,
+    pub potential_win: u64,
+    /// Platform fee taken from the potential win if the bid wins
+    pub fee: u64,
+    /// `potential_win - fee`
+    pub payout: u64,
+}
+
+/// Share pricing returned by `get_market_state`. A share of an outcome pays
+/// one lamport (or token base unit) if it wins on a fixed-odds poll, so its
+/// price in basis points reads as cents per dollar.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MarketState {
+    pub version: u8,
+    pub poll: Pubkey,
+    pub status: PollStatus,
+    pub payout_mode: PayoutMode,
+    /// Each outcome's AMM odds (basis points), summing to about 10000
+    pub implied_probabilities: Vec<u64>,
+    /// What the next share of each outcome costs (basis points): its odds
+    /// with the poll's vig
+    pub share_prices: Vec<u64>,
+    /// Shares sold on each outcome, less those of cancelled or exited bids
+    pub shares: Vec<u64>,
+    pub total_pool: u64,
+    /// The most the poll pays its winners, before fees, whichever outcome
+    /// wins: the largest outcome's shares on fixed-odds polls, the whole pool
+    /// on pari-mutuel ones
+    pub max_payout: u64,
+}
+
+// =============================================================================
+// ENUMS
+// =============================================================================
+
+/// Everything a new poll is created from besides the config, from
+/// `initialize_poll` or a template round
+struct PollParams {
+    poll_id: String,
+    title: String,
+    outcomes: Vec<String>,
+    end_timestamp: i64,
+    payout_mode: PayoutMode,
+    resolution: Option<ResolutionSource>,
+    dispute_period: i64,
+    arbiter: Option<Pubkey>,
+    start_timestamp: i64,
+    deadline_extension: Option<DeadlineExtension>,
+    virtual_liquidity: u64,
+    kind: PollKind,
+    limits: PollLimits,
+    incentives: Option<IncentiveCurve>,
+    resolution_deadline: i64,
+}
+
+/// Per-poll overrides of the config's bet limits and platform fee; `None`
+/// keeps the config's value. Bets stay within the config's limits and the fee
+/// at or above the config's.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub struct PollLimits {
+    pub min_bet: Option<u64>,       // 1 + 8 = 9
+    pub max_bet: Option<u64>,       // 1 + 8 = 9
+    pub fee_bps: Option<u16>,       // 1 + 2 = 3
+}
+
+/// One leg of a `place_bids_multi` call; the poll is taken from remaining_accounts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BidEntry {
+    pub amount: u64,
+    pub option: u8,
+    pub bid_index: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PollStatus {
+    Active,
+    Settled,
+    Cancelled,
+    /// Winner proposed; claims open once the dispute window or challenge is resolved
+    PendingSettlement,
+    /// Shrunk to a tombstone by `close_poll`; only `close_bid` accepts it
+    Closed,
+    /// Settled as a draw: no outcome won and every bid is refunded
+    Voided,
+    /// Created ahead of its start time; bids open at `start_timestamp`
+    Scheduled,
+    /// Claim period over and unclaimed funds swept by `sweep_expired`; only
+    /// `close_poll` and `close_bid` accept it
+    Expired,
+    /// Betting halted by `suspend_poll` until `resume_poll`; bids, exits, and
+    /// settlement wait, while the authority can still cancel or adjust it
+    Suspended,
+    /// Every winning bid paid and the vault emptied by `finalize_poll` ahead of
+    /// the claim period; only `close_poll` and `close_bid` accept it
+    Finalized,
+}
+
+/// What an `Epoch` ranks users by
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum EpochMetric {
+    /// Total staked by bids placed during the epoch
+    Volume,
+    /// Payouts less stakes of bids claimed or closed as lost during the epoch
+    Pnl,
+}
+
+/// Lifecycle of a `MarketEvent`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum EventStatus {
+    /// Polls can be added
+    Open,
+    /// `settle_event` has settled its polls; no more can be added
+    Settled,
+}
+
+/// Who may bid on a gated poll
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum AccessGate {
+    /// Bettors prove their key is a leaf of this sha256 Merkle tree (see
+    /// `logic::allowlist_leaf`)
+    Allowlist { root: [u8; 32] },
+    /// Bettors hold at least `min_amount` of `mint` (1 for an NFT)
+    TokenHolding { mint: Pubkey, min_amount: u64 },
+    /// Bettors present an unexpired `Attestation` issued by `attestor`, such
+    /// as a KYC provider
+    Attestation { attestor: Pubkey },
+}
+
+/// Topic of a poll, for browsing; variants are only ever appended
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PollCategory {
+    Other,
+    Sports,
+    Politics,
+    Crypto,
+    Finance,
+    Entertainment,
+    Science,
+    Weather,
+}
+
+/// What a poll's outcomes are
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum PollKind {
+    /// Each outcome is a choice; the winner is picked directly
+    Categorical,
+    /// Each outcome is a bucket of a number: outcome `i` covers values from
+    /// `bounds[i - 1]` up to `bounds[i]`, and the first and last buckets are
+    /// open-ended. Settled with the final value.
+    Scalar {
+        #[max_len(MAX_SCALAR_BOUNDS)]
+        bounds: Vec<i64>,
+    },
+    /// Each outcome is a choice, and the winners split the whole pool less
+    /// fees in proportion to their stake, whatever the odds they bid at.
+    /// Must be `PayoutMode::PariMutuel`.
+    Jackpot,
+}
+
+/// How winning bids are paid once a poll settles
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PayoutMode {
+    /// Each bid is paid the `potential_win` locked in at purchase
+    FixedOdds,
+    /// Winners split the whole pool in proportion to their stake, computed at
+    /// claim time; `potential_win` is only the estimate at purchase
+    PariMutuel,
+}
+
+/// How an oracle price is compared against a `ResolutionSource` threshold
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PriceComparison {
+    Above,
+    AtOrAbove,
+    Below,
+    AtOrBelow,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum BidStatus {
+    Active,
+    Won,
+    Lost,
+    Refunded,
+    /// Sold back to the pool before the poll ended
+    Exited,
+    /// Won, with part of the payout still to claim
+    PartiallyClaimed,
+    /// Cancelled by the bettor within the poll's cooling-off window
+    Cancelled,
+}
+
+// =============================================================================
+// EVENTS
+// =============================================================================
+
+/// Leads every event. `event_seq` numbers the events of one account from 1
+/// without gaps, so an indexer that sees a jump knows it missed some: the
+/// poll's for poll and bid events, the config's for config events, the
+/// adapter's for `AdapterApprovalChanged`, and the parlay pool's for parlay
+/// events.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EventHeader {
+    pub event_seq: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+/// A poll's state after any instruction that bids on it, moves its stakes or
+/// odds, or changes its status, alongside that instruction's own events, so
+/// consumers can follow the log as a state feed. `header.event_seq` orders
+/// snapshots of the same poll.
+#[event]
+pub struct PollStateUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub status: PollStatus,
+    pub winner: Option<u8>,
+    pub outcome_stakes: Vec<u64>,
+    pub outcome_odds: Vec<u64>,
+    pub total_pool: u64,
+    pub next_bid_index: u64,
+}
+
+#[event]
+pub struct PollCreated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub authority: Pubkey,
+    pub poll_id: String,
+    pub title: String,
+    pub outcome_count: u8,
+    pub outcomes: Vec<String>,
+    pub end_timestamp: i64,
+    pub mint: Option<Pubkey>,
+    pub payout_mode: PayoutMode,
+    pub resolution: Option<ResolutionSource>,
+    pub dispute_period: i64,
+    pub arbiter: Option<Pubkey>,
+    pub fee_bps: u16,
+    pub creation_bond: u64,
+    pub creator_fee_bps: u16,
+    pub start_timestamp: i64,
+    pub deadline_extension: Option<DeadlineExtension>,
+    pub virtual_liquidity: u64,
+    pub lp_fee_bps: u16,
+    pub referral_fee_bps: u16,
+    pub claim_period: i64,
+    pub kind: PollKind,
+    pub crank_bounty: u64,
+    /// Template the poll is a round of, if any
+    pub template: Option<Pubkey>,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub incentives: Option<IncentiveCurve>,
+    pub resolution_deadline: i64,
+}
+
+#[event]
+pub struct ScalarValueSubmitted {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub value: i64,
+    pub winner: u8,
+}
+
+#[event]
+pub struct PollEndTimeUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub previous_end_timestamp: i64,
+    pub end_timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityProvided {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub provider: Pubkey,
+    pub amount: u64,
+    pub lp_liquidity: u64,
+}
+
+#[event]
+pub struct LiquidityWithdrawn {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub provider: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OutcomeTokensEnabled {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+}
+
+#[event]
+pub struct OutcomeTokensMinted {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub bettor: Pubkey,
+    pub option: u8,
+    pub amount: u64,
+    pub odds: u64,
+    pub tokens: u64,
+}
+
+#[event]
+pub struct OutcomeTokensRedeemed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub holder: Pubkey,
+    pub option: u8,
+    pub tokens: u64,
+    pub payout: u64,
+    pub platform_fee: u64,
+}
+
+#[event]
+pub struct SettlementCommitted {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub commitment: [u8; 32],
+    pub committed_at: i64,
+}
+
+#[event]
+pub struct SettlementRevealed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub winner: u8,
+    pub salt: [u8; 32],
+}
+
+#[event]
+pub struct SettlementCranked {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub keeper: Pubkey,
+    pub winner: u8,
+    pub bounty: u64,
+}
+
+#[event]
+pub struct PollGateUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub gate: Option<AccessGate>,
+}
+
+#[event]
+pub struct AttestationIssued {
+    pub header: EventHeader,
+    pub attestor: Pubkey,
+    pub user: Pubkey,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct AttestationRevoked {
+    pub header: EventHeader,
+    pub attestor: Pubkey,
+    pub user: Pubkey,
+}
+
+#[event]
+pub struct ResolversSet {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub resolvers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct ResultSignerSet {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub signer: Pubkey,
+}
+
+#[event]
+pub struct SettlementVoted {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub resolver: Pubkey,
+    pub winner: u8,
+    /// Resolvers now voting for `winner`
+    pub votes: u8,
+}
+
+#[event]
+pub struct OutcomeVoted {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub bid: Pubkey,
+    pub voter: Pubkey,
+    pub option: u8,
+    /// Stake of the voting bid
+    pub weight: u64,
+    /// Stake now voting for `option`
+    pub votes: u64,
+}
+
+#[event]
+pub struct PollCapsUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub max_total_pool: Option<u64>,
+    pub max_stake_per_option: Option<u64>,
+    pub max_stake_per_user: Option<u64>,
+}
+
+#[event]
+pub struct PollTemplateCreated {
+    pub header: EventHeader,
+    pub template: Pubkey,
+    pub authority: Pubkey,
+    pub id_prefix: String,
+    pub first_start: i64,
+    pub period: i64,
+    pub duration: i64,
+}
+
+#[event]
+pub struct PollTemplateClosed {
+    pub header: EventHeader,
+    pub template: Pubkey,
+}
+
+#[event]
+pub struct PollMetadataUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub metadata: Pubkey,
+    pub category: PollCategory,
+    pub uri: String,
+    pub tags: Vec<String>,
+}
+
+#[event]
+pub struct PollMetadataClosed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub metadata: Pubkey,
+}
+
+#[event]
+pub struct PollSuspended {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    /// The poll's authority or the config admin
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct PollResumed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct PollOpened {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub start_timestamp: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+    pub bettor: Pubkey,
+    pub poll: Pubkey,
+    pub amount: u64,
+    pub option: u8,
+    pub odds: u64,
+    pub potential_win: u64,
+    /// Client or campaign tag passed to `place_bid`
+    pub memo: Option<[u8; 32]>,
+    /// Micro-USD value of the stake at the SOL/USD price passed with the
+    /// bid; 0 without one
+    pub usd_notional: u64,
+    /// Shares bought at `odds`, each paying one lamport (or token base unit)
+    /// if the outcome wins on a fixed-odds poll: the stake over the price
+    pub shares: u64,
+}
+
+#[event]
+pub struct LimitOrderPlaced {
+    pub header: EventHeader,
+    pub order: Pubkey,
+    pub poll: Pubkey,
+    pub bettor: Pubkey,
+    pub amount: u64,
+    pub option: u8,
+    pub max_odds: u64,
+}
+
+#[event]
+pub struct OrderMatched {
+    pub header: EventHeader,
+    pub order: Pubkey,
+    pub bid: Pubkey,
+    pub cranker: Pubkey,
+}
+
+#[event]
+pub struct OrderCancelled {
+    pub header: EventHeader,
+    pub order: Pubkey,
+    pub poll: Pubkey,
+    pub bettor: Pubkey,
+}
+
+#[event]
+pub struct BidIncreased {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+    pub bettor: Pubkey,
+    pub poll: Pubkey,
+    pub amount: u64,
+    pub odds: u64,
+    pub total_amount: u64,
+    pub potential_win: u64,
+    pub odds_at_purchase: u64,
+}
+
+#[event]
+pub struct BidTransferred {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+    pub poll: Pubkey,
+    pub previous_bettor: Pubkey,
+    pub bettor: Pubkey,
+}
+
+#[event]
+pub struct BidMarkedLost {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+    pub poll: Pubkey,
+    pub bettor: Pubkey,
+}
+
+#[event]
+pub struct OddsHistoryEnabled {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub odds_history: Pubkey,
+    pub interval: i64,
+}
+
+#[event]
+pub struct BidLedgerCreated {
+    pub header: EventHeader,
+    pub ledger: Pubkey,
+    pub poll: Pubkey,
+    pub capacity: u32,
+}
+
+#[event]
+pub struct LedgerBidPlaced {
+    pub header: EventHeader,
+    pub ledger: Pubkey,
+    pub index: u32,
+    pub bettor: Pubkey,
+    pub poll: Pubkey,
+    pub amount: u64,
+    pub option: u8,
+    pub odds: u64,
+    pub potential_win: u64,
+}
+
+#[event]
+pub struct LedgerWinningsClaimed {
+    pub header: EventHeader,
+    pub ledger: Pubkey,
+    pub index: u32,
+    pub bettor: Pubkey,
+    pub payout: u64,
+    pub platform_fee: u64,
+}
+
+#[event]
+pub struct LedgerRefundClaimed {
+    pub header: EventHeader,
+    pub ledger: Pubkey,
+    pub index: u32,
+    pub bettor: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PayoutsComputed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub processed: u32,
+    pub computed_count: u64,
+    pub bid_count: u64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub header: EventHeader,
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub creation_bond: u64,
+    pub creator_fee_bps: u16,
+    pub lp_fee_bps: u16,
+    pub referral_fee_bps: u16,
+    pub claim_period: i64,
+}
+
+#[event]
+pub struct PollExpired {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    /// Where the vault balance went: the fee vault, or the treasury for token
+    /// polls
+
+    pub treasury: Pubkey,
+
+    pub swept: u64,
+}
+
+/// A poll authority or config admin proposed a successor (`None` withdraws)
+#[event]
+pub struct AuthorityTransferProposed {
+    pub header: EventHeader,
+    /// The poll or config
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub header: EventHeader,
+    /// The poll or config
+    pub account: Pubkey,
+    pub previous_authority: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct ParlayPoolFunded {
+    pub header: EventHeader,
+    pub funder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ParlayPoolWithdrawn {
+    pub header: EventHeader,
+    pub treasury: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TreasuryTimelockSet {
+    pub header: EventHeader,
+    pub withdraw_delay: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawalQueued {
+    pub header: EventHeader,
+    pub amount: u64,
+    pub destination: Pubkey,
+    /// Equal to the event's timestamp when there's no timelock
+    pub unlocks_at: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub header: EventHeader,
+    pub amount: u64,
+    pub destination: Pubkey,
+}
 
-    #[account(
-        seeds = [b"vault", poll_id.as_bytes()],
-        bump
-    )]
-    /// CHECK: Vault PDA for holding SOL in escrow
-    pub vault: SystemAccount<'info>,
+#[event]
+pub struct TreasuryWithdrawalCancelled {
+    pub header: EventHeader,
+    pub amount: u64,
+    pub destination: Pubkey,
+}
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+#[event]
+pub struct TreasuryChangeQueued {
+    pub header: EventHeader,
+    pub treasury: Pubkey,
+    /// Equal to the event's timestamp when there's no timelock
+    pub unlocks_at: i64,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct TreasuryChanged {
+    pub header: EventHeader,
+    pub previous: Pubkey,
+    pub treasury: Pubkey,
 }
 
-#[derive(Accounts)]
-#[instruction(amount: u64, option: BidOption, timestamp: i64, bid_index: u64)]
-pub struct PlaceBid<'info> {
-    #[account(mut)]
-    pub poll: Account<'info, Poll>,
+#[event]
+pub struct TreasuryChangeCancelled {
+    pub header: EventHeader,
+    pub treasury: Pubkey,
+}
 
-    #[account(
-        mut,
-        seeds = [b"vault", poll.poll_id.as_bytes()],
-        bump = poll.vault_bump
-    )]
-    /// CHECK: Vault PDA checked via seeds
-    pub vault: SystemAccount<'info>,
+#[event]
+pub struct InsuranceFeeSet {
+    pub header: EventHeader,
+    pub fee_bps: u16,
+}
 
-    #[account(
-        init,
-        payer = bettor,
-        space = Bid::LEN,
-        seeds = [
-            b"bid",
-            poll.key().as_ref(),
-            bettor.key().as_ref(),
-            &bid_index.to_le_bytes(),
-        ],
-        bump
-    )]
-    pub bid: Account<'info, Bid>,
+#[event]
+pub struct InsuranceDeposited {
+    pub header: EventHeader,
+    pub admin: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(mut)]
-    pub bettor: Signer<'info>,
+#[event]
+pub struct InsuranceWithdrawn {
+    pub header: EventHeader,
+    pub treasury: Pubkey,
+    pub amount: u64,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct ShortfallCovered {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub bid: Pubkey,
+    pub amount: u64,
 }
 
-#[derive(Accounts)]
-pub struct SettlePoll<'info> {
-    #[account(mut)]
-    pub poll: Account<'info, Poll>,
+#[event]
+pub struct ParlayPlaced {
+    pub header: EventHeader,
+    pub parlay: Pubkey,
+    pub bettor: Pubkey,
+    pub amount: u64,
+    pub potential_win: u64,
+    pub legs: Vec<ParlayLeg>,
+}
 
-    pub authority: Signer<'info>,
+#[event]
+pub struct ParlayClaimed {
+    pub header: EventHeader,
+    pub parlay: Pubkey,
+    pub bettor: Pubkey,
+    pub payout: u64,
+    pub platform_fee: u64,
 }
 
-#[derive(Accounts)]
-pub struct ClaimWinnings<'info> {
-    #[account(mut)]
-    pub poll: Account<'info, Poll>,
+#[event]
+pub struct ParlayLost {
+    pub header: EventHeader,
+    pub parlay: Pubkey,
+    pub bettor: Pubkey,
+    pub poll: Pubkey,
+}
 
-    #[account(
-        mut,
-        seeds = [b"vault", poll.poll_id.as_bytes()],
-        bump = poll.vault_bump
-    )]
-    /// CHECK: Vault PDA checked via seeds
-    pub vault: SystemAccount<'info>,
+#[event]
+pub struct FeeTiersUpdated {
+    pub header: EventHeader,
+    pub admin: Pubkey,
+    pub fee_tiers: Vec<FeeTier>,
+}
 
-    #[account(mut)]
-    pub bid: Account<'info, Bid>,
+#[event]
+pub struct UsdLimitsUpdated {
+    pub header: EventHeader,
+    pub admin: Pubkey,
+    pub usd_feed_id: [u8; 32],
+    /// Micro-USD; 0 when lifted
+    pub max_bid_usd: u64,
+}
 
-    #[account(mut)]
-    pub bettor: Signer<'info>,
+#[event]
+pub struct PauseChanged {
+    pub header: EventHeader,
+    pub admin: Pubkey,
+    pub paused: bool,
+}
 
-    #[account(mut)]
-    /// CHECK: Treasury account for platform fees
-    pub treasury: SystemAccount<'info>,
+#[event]
+pub struct AdapterApprovalChanged {
+    pub header: EventHeader,
+    pub program: Pubkey,
+    pub approved: bool,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct PollAdapterAttached {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub adapter: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct CancelPoll<'info> {
-    #[account(mut)]
-    pub poll: Account<'info, Poll>,
+#[event]
+pub struct PollSettled {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub winner: u8,
+    pub total_pool: u64,
+    /// Whether the winner is only proposed, pending the dispute window
+    pub pending: bool,
+    /// The authority's resolution note; `None` when the poll was settled by
+    /// an oracle, adapter, council, arbiter, keeper, or event
+    pub evidence: Option<ResolutionEvidence>,
+}
 
-    pub authority: Signer<'info>,
+#[event]
+pub struct SettlementChallenged {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub challenger: Pubkey,
+    pub proposed_winner: u8,
+    pub bond: u64,
 }
 
-#[derive(Accounts)]
-pub struct ClaimRefund<'info> {
-    #[account(mut)]
-    pub poll: Account<'info, Poll>,
+#[event]
+pub struct SettlementFinalized {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub winner: u8,
+    pub overturned: bool,
+}
 
-    #[account(
-        mut,
-        seeds = [b"vault", poll.poll_id.as_bytes()],
-        bump = poll.vault_bump
-    )]
-    /// CHECK: Vault PDA checked via seeds
-    pub vault: SystemAccount<'info>,
+#[event]
+pub struct OraclePriceUsed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub price_update: Pubkey,
+    pub price: i64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
 
-    #[account(mut)]
-    pub bid: Account<'info, Bid>,
+#[event]
+pub struct WinningsClaimed {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+    pub bettor: Pubkey,
+    /// Paid by this claim
+    pub payout: u64,
+    /// Accrued by the bid's first claim; zero on later partial claims
+    pub platform_fee: u64,
+    /// Wallet or token account the payout went to
+    pub destination: Pubkey,
+    /// Payout left to claim
+    pub unclaimed: u64,
+    /// Operator that claimed for the bettor, if any
+    pub claim_delegate: Option<Pubkey>,
+}
 
-    #[account(mut)]
-    pub bettor: Signer<'info>,
+#[event]
+pub struct ClaimDelegateUpdated {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    /// `None` when revoked
+    pub delegate: Option<Pubkey>,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct BidReferred {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+    pub referrer: Pubkey,
 }
 
-// =============================================================================
-// STATE STRUCTS
-// =============================================================================
+#[event]
+pub struct ReferralPaid {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+    pub referrer: Pubkey,
+    pub amount: u64,
+}
 
-#[account]
-pub struct Poll {
-    pub authority: Pubkey,          // 32
-    pub poll_id: String,            // 4 + 64 = 68
-    pub title: String,              // 4 + 256 = 260
-    pub option_a_text: String,      // 4 + 128 = 132
-    pub option_b_text: String,      // 4 + 128 = 132
-    pub option_a_stake: u64,        // 8
-    pub option_b_stake: u64,        // 8
-    pub total_pool: u64,            // 8
-    pub option_a_odds: u64,         // 8
-    pub option_b_odds: u64,         // 8
-    pub end_timestamp: i64,         // 8
-    pub status: PollStatus,         // 1
-    pub winner: Option<BidOption>,  // 1 + 1 = 2
-    pub vault_bump: u8,             // 1
-    pub bump: u8,                   // 1
-    pub next_bid_index: u64,        // 8
+#[event]
+pub struct WinningsBatchClaimed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub bettor: Pubkey,
+    pub claimed_count: u32,
+    pub payout: u64,
+    pub platform_fee: u64,
 }
 
-impl Poll {
-    pub const LEN: usize = 8 + 32 + 68 + 260 + 132 + 132 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 2 + 1 + 1 + 8;
+#[event]
+pub struct FeesSwept {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    /// The fee vault, or the treasury's token account for token markets
+    pub destination: Pubkey,
+    pub amount: u64,
+    /// Part of `amount` paid to the insurance fund instead of the treasury
+    pub insurance: u64,
 }
 
-#[account]
-pub struct Bid {
-    pub bettor: Pubkey,             // 32
-    pub poll: Pubkey,               // 32
-    pub amount: u64,                // 8
-    pub option: BidOption,          // 1
-    pub odds_at_purchase: u64,      // 8
-    pub potential_win: u64,         // 8
-    pub status: BidStatus,          // 1
-    pub timestamp: i64,             // 8
-    pub index: u64,                 // 8
-    pub bump: u8,                   // 1
+#[event]
+pub struct FeeShareGranted {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub party: Pubkey,
+    pub share_bps: u16,
+}
+
+#[event]
+pub struct FeeShareClaimed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub party: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CreatorFeesClaimed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PollSlashed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub treasury: Pubkey,
+    pub bond: u64,
+    pub total_pool: u64,
+}
+
+#[event]
+pub struct PollCancelled {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub total_pool: u64,
+}
+
+/// A bid trimmed to fit the poll's pool caps, its fixed-odds liability, or
+/// its price impact limit; only `filled` was taken
+#[event]
+pub struct BidPartiallyFilled {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+    pub requested: u64,
+    pub filled: u64,
+}
+
+#[event]
+pub struct PriceImpactLimitUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub max_price_impact_bps: u16,
+}
+
+#[event]
+pub struct CreditGranted {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    /// Paid in by the admin where the promo vault's spare lamports fell short
+    pub top_up: u64,
+}
+
+#[event]
+pub struct CreditReturned {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub bid: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OddsDampingUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub damping_window: i64,
+    pub damping_bps: u16,
+}
+
+#[event]
+pub struct PollVigUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub vig_bps: u16,
+}
+
+#[event]
+pub struct BidCooldownUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub min_seconds_between_bids: i64,
+}
+
+#[event]
+pub struct BidCancelled {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub bid: Pubkey,
+    pub bettor: Pubkey,
+    pub option: u8,
+    pub refund: u64,
+    pub cancel_fee: u64,
+}
+
+#[event]
+pub struct CancelWindowUpdated {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub cancel_window: i64,
+    pub cancel_fee_bps: u16,
+}
+
+#[event]
+pub struct BidExited {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub bid: Pubkey,
+    pub bettor: Pubkey,
+    pub option: u8,
+    pub payout: u64,
+    pub exit_fee: u64,
+}
+
+#[event]
+pub struct PollVoided {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub total_pool: u64,
+    pub evidence: ResolutionEvidence,
 }
 
-impl Bid {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 8 + 8 + 1 + 8 + 8 + 1;
+#[event]
+pub struct ResolutionNoteAttached {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    pub note: Pubkey,
+    pub uri: String,
+    pub evidence_hash: [u8; 32],
 }
 
-// =============================================================================
-// ENUMS
-// =============================================================================
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum PollStatus {
-    Active,
-    Settled,
-    Cancelled,
+/// Every winning bid of a settled poll was paid and `finalize_poll` emptied
+/// its vault
+#[event]
+pub struct PollFinalized {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    /// Vault balance moved to the fee vault, or to the treasury for token polls
+    pub swept: u64,
+    /// Unpaid crank bounty returned to the authority
+    pub crank_bounty: u64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum BidOption {
-    OptionA,
-    OptionB,
+#[event]
+pub struct PollClosed {
+    pub header: EventHeader,
+    pub poll: Pubkey,
+    /// Vault balance moved to the fee vault, or to the treasury for token polls
+    pub swept: u64,
+    /// Rent returned to the authority
+    pub reclaimed: u64,
+    /// Unpaid crank bounty returned to the authority
+    pub crank_bounty: u64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum BidStatus {
-    Active,
-    Won,
-    Lost,
-    Refunded,
+#[event]
+pub struct RefundClaimed {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+    pub bettor: Pubkey,
+    pub amount: u64,
+    /// Wallet or token account the refund went to
+    pub destination: Pubkey,
 }
 
-// =============================================================================
-// EVENTS
-// =============================================================================
-
 #[event]
-pub struct PollCreated {
+pub struct RefundsBatchProcessed {
+    pub header: EventHeader,
     pub poll: Pubkey,
-    pub authority: Pubkey,
-    pub poll_id: String,
-    pub title: String,
-    pub end_timestamp: i64,
+    pub refunded_count: u32,
+    pub refunded_total: u64,
 }
 
 #[event]
-pub struct BidPlaced {
-    pub bid: Pubkey,
-    pub bettor: Pubkey,
+pub struct RefundsPushed {
+    pub header: EventHeader,
     pub poll: Pubkey,
+    pub cranker: Pubkey,
+    pub refunded_count: u32,
+    /// Paid to bettors, after tips
+    pub refunded_total: u64,
+    pub tips: u64,
+}
+
+#[event]
+pub struct BalanceDeposited {
+    pub header: EventHeader,
+    pub user: Pubkey,
     pub amount: u64,
-    pub option: BidOption,
-    pub odds: u64,
-    pub potential_win: u64,
+    /// Balance after the deposit
+    pub balance: u64,
 }
 
 #[event]
-pub struct PollSettled {
-    pub poll: Pubkey,
-    pub winner: BidOption,
-    pub total_pool: u64,
+pub struct BalanceWithdrawn {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub amount: u64,
+    /// Balance after the withdrawal
+    pub balance: u64,
 }
 
 #[event]
-pub struct WinningsClaimed {
-    pub bid: Pubkey,
-    pub bettor: Pubkey,
-    pub payout: u64,
-    pub platform_fee: u64,
+pub struct MarketEventCreated {
+    pub header: EventHeader,
+    pub event: Pubkey,
+    pub authority: Pubkey,
+    pub event_id: String,
 }
 
 #[event]
-pub struct PollCancelled {
+pub struct EventPollAdded {
+    pub header: EventHeader,
+    pub event: Pubkey,
     pub poll: Pubkey,
-    pub total_pool: u64,
 }
 
 #[event]
-pub struct RefundClaimed {
-    pub bid: Pubkey,
-    pub bettor: Pubkey,
+pub struct EventSettled {
+    pub header: EventHeader,
+    pub event: Pubkey,
+    /// Polls settled by this call; the rest were already resolved
+    pub settled: u8,
+    pub total_volume: u64,
+}
+
+#[event]
+pub struct EventSynced {
+    pub header: EventHeader,
+    pub event: Pubkey,
+    pub total_volume: u64,
+}
+
+#[event]
+pub struct EpochOpened {
+    pub header: EventHeader,
+    pub epoch: Pubkey,
+    pub epoch_id: u32,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub metric: EpochMetric,
+    pub reward_pool: u64,
+}
+
+#[event]
+pub struct EpochScoreSubmitted {
+    pub header: EventHeader,
+    pub epoch: Pubkey,
+    pub user: Pubkey,
+    pub score: i64,
+    /// 0 for the top score
+    pub rank: u8,
+}
+
+#[event]
+pub struct EpochFinalized {
+    pub header: EventHeader,
+    pub epoch: Pubkey,
+    pub winners: u8,
+    pub distributed: u64,
+    /// Unwon share of the pool paid to the treasury
+    pub returned: u64,
+}
+
+#[event]
+pub struct EpochRewardClaimed {
+    pub header: EventHeader,
+    pub epoch: Pubkey,
+    pub user: Pubkey,
+    pub rank: u8,
     pub amount: u64,
 }
 
+#[event]
+pub struct AccountMigrated {
+    pub header: EventHeader,
+    /// The migrated `Poll` or `Bid`
+    pub account: Pubkey,
+    pub poll: Pubkey,
+    pub from_version: u8,
+    pub version: u8,
+}
+
 // =============================================================================
 // ERRORS
 // =============================================================================
@@ -636,7 +11852,7 @@ pub enum ErrorCode {
     #[msg("Option text cannot exceed 128 characters")]
     OptionTextTooLong,
 
-    #[msg("End time must be in the future")]
+    #[msg("End time must be at least the minimum poll duration in the future")]
     InvalidEndTime,
 
     #[msg("Poll is not active")]
@@ -648,7 +11864,7 @@ pub enum ErrorCode {
     #[msg("Poll has not ended yet")]
     PollNotEnded,
 
-    #[msg("Bet amount must be between 0.01 and 100 SOL")]
+    #[msg("Bet amount is outside the allowed range")]
     InvalidBetAmount,
 
     #[msg("Unauthorized action")]
@@ -663,9 +11879,534 @@ pub enum ErrorCode {
     #[msg("This bid did not win")]
     BidDidNotWin,
 
-    #[msg("Poll is not cancelled")]
+    #[msg("Poll is not cancelled or voided")]
     PollNotCancelled,
 
     #[msg("Provided bid index does not match next available index")]
     InvalidBidIndex,
-}
\ No newline at end of file
+
+    #[msg("No accrued fees to sweep")]
+    NoFeesToSweep,
+
+    #[msg("Batch must contain between 1 and 10 entries")]
+    InvalidBatchSize,
+
+    #[msg("Remaining accounts do not match the batch entries")]
+    InvalidRemainingAccounts,
+
+    #[msg("Vault account does not match the poll's vault PDA")]
+    InvalidVault,
+
+    #[msg("Bid account does not match the expected bid PDA")]
+    InvalidBidAccount,
+
+    #[msg("Ledger account has the wrong size")]
+    InvalidLedgerSize,
+
+    #[msg("Ledger belongs to a different poll")]
+    LedgerPollMismatch,
+
+    #[msg("Ledger is full")]
+    LedgerFull,
+
+    #[msg("Ledger index out of range")]
+    InvalidLedgerIndex,
+
+    #[msg("Bid belongs to a different poll")]
+    BidPollMismatch,
+
+    #[msg("Payout table data is malformed")]
+    InvalidPayoutTable,
+
+    #[msg("Payout for this bid has not been computed yet")]
+    PayoutNotComputed,
+
+    #[msg("Adapter program is not approved")]
+    AdapterNotApproved,
+
+    #[msg("Adapter does not match the poll's adapter")]
+    AdapterMismatch,
+
+    #[msg("Poll is priced and settled by its adapter")]
+    AdapterMarket,
+
+    #[msg("Adapter returned a missing or out-of-range quote")]
+    InvalidAdapterQuote,
+
+    #[msg("Poll already has bids")]
+    PollHasBids,
+
+    #[msg("Poll is denominated in a different currency")]
+    DenominationMismatch,
+
+    #[msg("Token markets need both a mint and a token vault")]
+    InvalidTokenVault,
+
+    #[msg("Poll must have between 2 and 8 outcomes")]
+    InvalidOutcomeCount,
+
+    #[msg("Outcome index is out of range")]
+    InvalidOutcome,
+
+    #[msg("Adapter markets must use fixed-odds payouts")]
+    AdapterPayoutMode,
+
+    #[msg("Bid's potential win exceeds what the pool can cover")]
+    InsufficientPoolLiquidity,
+
+    #[msg("Poll is settled by its oracle")]
+    OracleMarket,
+
+    #[msg("Poll has no oracle resolution source")]
+    NoResolutionSource,
+
+    #[msg("Oracle resolution needs a poll with exactly 2 outcomes")]
+    InvalidResolutionSource,
+
+    #[msg("Oracle account is not a fully verified Pyth price update")]
+    InvalidOracleAccount,
+
+    #[msg("Price update is for a different feed or exponent")]
+    OracleFeedMismatch,
+
+    #[msg("Price was not published within the poll's settlement window")]
+    OraclePriceOutsideWindow,
+
+    #[msg("Dispute period exceeds the maximum")]
+    InvalidDisputePeriod,
+
+    #[msg("Poll has no pending settlement")]
+    SettlementNotPending,
+
+    #[msg("Settlement has already been challenged")]
+    SettlementAlreadyChallenged,
+
+    #[msg("Dispute window has closed")]
+    DisputeWindowClosed,
+
+    #[msg("Dispute window is still open")]
+    DisputeWindowOpen,
+
+    #[msg("Challenged settlements are finalized by the arbiter")]
+    SettlementChallenged,
+
+    #[msg("Settlement has not been challenged")]
+    SettlementNotChallenged,
+
+    #[msg("Platform fee exceeds the maximum")]
+    InvalidFeeBps,
+
+    #[msg("Bet limits are out of order or outside the cluster's range")]
+    InvalidBetLimits,
+
+    #[msg("Treasury does not match the program config")]
+    InvalidTreasury,
+
+    #[msg("Bid still holds a claim on its poll")]
+    BidNotClosable,
+
+    #[msg("Poll is not settled, cancelled, or voided")]
+    PollNotFinalized,
+
+    #[msg("Claim grace period has not elapsed")]
+    ClaimGracePeriod,
+
+    #[msg("Poll has not started yet")]
+    PollNotStarted,
+
+    #[msg("Poll is not scheduled")]
+    PollNotScheduled,
+
+    #[msg("Deadline extension is out of range")]
+    InvalidDeadlineExtension,
+
+    #[msg("Virtual liquidity exceeds the maximum")]
+    InvalidVirtualLiquidity,
+
+    #[msg("Liquidity can only back fixed-odds polls")]
+    LiquidityPayoutMode,
+
+    #[msg("Liquidity amount must be positive")]
+    InvalidLiquidityAmount,
+
+    #[msg("Nothing to withdraw")]
+    NothingToWithdraw,
+
+    #[msg("New bettor must be a different, non-default key")]
+    InvalidNewBettor,
+
+    #[msg("Poll issues outcome tokens instead of bids")]
+    OutcomeTokenMarket,
+
+    #[msg("Poll does not issue outcome tokens")]
+    NotOutcomeTokenMarket,
+
+    #[msg("Token amount must be positive")]
+    InvalidTokenAmount,
+
+    #[msg("Bid's outcome won")]
+    BidNotLost,
+
+    #[msg("Claim period is shorter than the cluster minimum")]
+    InvalidClaimPeriod,
+
+    #[msg("New authority must be a different, non-default key")]
+    InvalidNewAuthority,
+
+    #[msg("Signer is not the proposed authority")]
+    NotPendingAuthority,
+
+    #[msg("Program is paused")]
+    ProgramPaused,
+
+    #[msg("Bet caps must be positive")]
+    InvalidBetCap,
+
+    #[msg("Bid exceeds the poll's pool, outcome, or per-user cap")]
+    BetCapExceeded,
+
+    #[msg("Poll caps stake per user; bid with place_bid or place_bid_token")]
+    UserStakeRequired,
+
+    #[msg("Settlement is committed; settle it with reveal_settlement")]
+    SettlementAwaitingReveal,
+
+    #[msg("Poll has no settlement commitment")]
+    NoSettlementCommitment,
+
+    #[msg("Settlement reveal delay has not passed")]
+    RevealTooEarly,
+
+    #[msg("Winner and salt don't match the settlement commitment")]
+    SettlementHashMismatch,
+
+    #[msg("Bettors can't refer themselves")]
+    InvalidReferrer,
+
+    #[msg("Bid has a referrer; pass it to claim")]
+    ReferrerRequired,
+
+    #[msg("Referrer doesn't match the bid")]
+    ReferrerMismatch,
+
+    #[msg("Fee tiers must raise volume and lower the fee at each step")]
+    InvalidFeeTiers,
+
+    #[msg("Parlay needs 2 to 5 legs on distinct polls")]
+    InvalidParlayLegs,
+
+    #[msg("Poll is not a leg of this parlay")]
+    ParlayLegMismatch,
+
+    #[msg("Parlay has legs still undecided")]
+    ParlayNotResolved,
+
+    #[msg("Parlay has a losing leg")]
+    ParlayLegLost,
+
+    #[msg("Parlay leg didn't lose")]
+    ParlayNotLost,
+
+    #[msg("Parlay pool can't cover this amount")]
+    InsufficientParlayPool,
+
+    #[msg("Scalar bounds must strictly increase, one fewer than the outcomes")]
+    InvalidScalarBounds,
+
+    #[msg("Poll is not a scalar market")]
+    NotScalarMarket,
+
+    #[msg("Scalar markets are settled with settle_scalar")]
+    ScalarMarket,
+
+    #[msg("Poll has no oracle or settlement commitment to crank")]
+    NoOnChainResolution,
+
+    #[msg("Poll can't be cranked until its grace period after the end has passed")]
+    CrankTooEarly,
+
+    #[msg("Pass the price update for oracle markets or the reveal for committed polls")]
+    CrankInputMissing,
+
+    #[msg("Claim amount must be between 1 and the bid's unclaimed payout")]
+    InvalidClaimAmount,
+
+    #[msg("Metadata URI exceeds 200 bytes")]
+    MetadataUriTooLong,
+
+    #[msg("Metadata allows at most 8 tags of 1 to 32 bytes each")]
+    InvalidMetadataTags,
+
+    #[msg("Resolution criteria exceed 1000 bytes")]
+    ResolutionCriteriaTooLong,
+
+    #[msg("Template prefix cannot exceed 43 characters")]
+    TemplatePrefixTooLong,
+
+    #[msg("Template period must be positive and rounds must fit the poll limits")]
+    InvalidTemplateSchedule,
+
+    #[msg("A token gate needs a minimum holding of at least 1")]
+    InvalidAccessGate,
+
+    #[msg("Bettor is not on the poll's allowlist")]
+    NotAllowlisted,
+
+    #[msg("Pass a token account of the bettor's holding the gate's token")]
+    GateTokenRequired,
+
+    #[msg("Bettor doesn't hold enough of the gate's token")]
+    InsufficientGateHolding,
+
+    #[msg("Gated polls only take bids through place_bid and place_bid_token")]
+    GatedPoll,
+
+    #[msg("A council needs 1 to 5 distinct resolvers and a threshold of at most their number")]
+    InvalidResolvers,
+
+    #[msg("Poll already has a resolver council or result signer")]
+    SettlerAlreadySet,
+
+    #[msg("Signer is not one of the poll's resolvers")]
+    NotResolver,
+
+    #[msg("Poll has a resolver council; settle it with vote_settlement")]
+    ResolverVoteRequired,
+
+    #[msg("Poll has a result signer; settle it with settle_with_signature")]
+    ResultSignatureRequired,
+
+    #[msg("Poll has no result signer")]
+    NoResultSigner,
+
+    #[msg("Previous instruction doesn't verify the result signer's signature over this result")]
+    InvalidResultSignature,
+
+    #[msg("Poll is not suspended")]
+    PollNotSuspended,
+
+    #[msg("The vault can't cover this payout and the insurance fund can't make up the difference")]
+    VaultShortfall,
+
+    #[msg("Insurance fund can't cover this amount")]
+    InsufficientInsuranceFund,
+
+    #[msg("Bettors can't vote on the outcome until the authority has had time to settle")]
+    CommunityVoteTooEarly,
+
+    #[msg("Only active bids can vote on a poll's outcome")]
+    BidCannotVote,
+
+    #[msg("Fee vault can't cover this amount")]
+    InsufficientFeeVault,
+
+    #[msg("Odds history interval must be positive")]
+    InvalidOddsHistoryInterval,
+
+    #[msg("This poll records odds history; pass its OddsHistory account")]
+    OddsHistoryRequired,
+
+    #[msg("Odds history belongs to a different poll")]
+    OddsHistoryMismatch,
+
+    #[msg("Limit odds must be within the odds a bid can be priced at")]
+    InvalidLimitOdds,
+
+    #[msg("The outcome's odds are above the order's limit")]
+    LimitNotReached,
+
+    #[msg("Account is already at the current layout version")]
+    AccountUpToDate,
+
+    #[msg("Incentive curve windows must not overlap, and its bonus must be funded by the fee")]
+    InvalidIncentiveCurve,
+
+    #[msg("Resolution deadline is out of range")]
+    InvalidResolutionDeadline,
+
+    #[msg("Poll has no resolution deadline")]
+    NoResolutionDeadline,
+
+    #[msg("Poll's resolution deadline has not passed")]
+    ResolutionDeadlineNotReached,
+
+    #[msg("Fee shares on a poll must be positive and add up to at most 10000 bps")]
+    InvalidFeeShare,
+
+    #[msg("Event ID exceeds maximum length")]
+    EventIdTooLong,
+
+    #[msg("Event already holds the most polls it can")]
+    EventFull,
+
+    #[msg("Poll is already part of this event")]
+    PollAlreadyInEvent,
+
+    #[msg("Event is already settled")]
+    EventSettled,
+
+    #[msg("Poll accounts must be the event's polls, in the order they were added")]
+    EventPollMismatch,
+
+    #[msg("User balance is too low")]
+    InsufficientBalance,
+
+    #[msg("Cancellation window or fee is out of range")]
+    InvalidCancelWindow,
+
+    #[msg("Bid can no longer be cancelled")]
+    CancelWindowClosed,
+
+    #[msg("Bid would move the odds more than the poll allows")]
+    PriceImpactTooHigh,
+
+    #[msg("Price impact limit is out of range")]
+    InvalidPriceImpact,
+
+    #[msg("Settlement requires the poll's resolution note")]
+    ResolutionNoteRequired,
+
+    #[msg("Resolution note needs an evidence URI within the length limit")]
+    InvalidResolutionNote,
+
+    #[msg("Mint has a Token-2022 extension token markets don't support")]
+    UnsupportedMint,
+
+    #[msg("Poll predates claim tracking and can't be finalized early")]
+    ClaimsNotTracked,
+
+    #[msg("Poll still owes winnings, fees, or house liquidity")]
+    ClaimsOutstanding,
+
+    #[msg("Bid cooldown is out of range")]
+    InvalidBidCooldown,
+
+    #[msg("Too soon after this user's last bid on the poll")]
+    BidCooldown,
+
+    #[msg("Poll has a bid cooldown; bid with place_bid or place_bid_token")]
+    PositionRequired,
+
+    #[msg("Bet credit is too low")]
+    InsufficientCredit,
+
+    #[msg("Bid was placed with bet credit; pass the promo vault")]
+    PromoVaultRequired,
+
+    #[msg("Registry page is full")]
+    RegistryPageFull,
+
+    #[msg("Registry pages must be created in order")]
+    InvalidRegistryPage,
+
+    #[msg("Poll is listed in the registry; pass its page")]
+    RegistryRequired,
+
+    #[msg("Registry page doesn't list this poll")]
+    RegistryMismatch,
+
+    #[msg("Poll is already listed in the registry")]
+    PollAlreadyListed,
+
+    #[msg("Odds damping window or share is out of range")]
+    InvalidOddsDamping,
+
+    #[msg("A USD bid limit needs a SOL/USD feed")]
+    InvalidUsdLimits,
+
+    #[msg("Config sets a USD bid limit; pass a SOL/USD price to place_bid")]
+    UsdPriceRequired,
+
+    #[msg("Price update isn't the configured SOL/USD feed")]
+    UsdFeedMismatch,
+
+    #[msg("SOL/USD price is stale or out of range")]
+    InvalidUsdPrice,
+
+    #[msg("Bid exceeds the USD limit per bid")]
+    UsdLimitExceeded,
+
+    #[msg("A user can't be their own claim delegate")]
+    InvalidClaimDelegate,
+
+    #[msg("Signer isn't the bettor's claim delegate")]
+    NotClaimDelegate,
+
+    #[msg("A delegated claim pays the bettor's wallet")]
+    ClaimDestinationMismatch,
+
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+
+    #[msg("Poll vig is out of range")]
+    InvalidVig,
+
+    #[msg("Epoch id, window, weights, or reward pool is invalid")]
+    InvalidEpoch,
+
+    #[msg("The previous epoch's scores can still be submitted")]
+    EpochStillOpen,
+
+    #[msg("Epoch scores can only be submitted for a while after it ends")]
+    EpochSubmissionClosed,
+
+    #[msg("User has no positive score for this epoch")]
+    NoEpochScore,
+
+    #[msg("User is already on the epoch leaderboard")]
+    AlreadyOnLeaderboard,
+
+    #[msg("Score is too low for the epoch leaderboard")]
+    ScoreBelowLeaderboard,
+
+    #[msg("Epoch scores can still be submitted")]
+    EpochNotFinalizable,
+
+    #[msg("Epoch is already finalized")]
+    EpochAlreadyFinalized,
+
+    #[msg("Epoch is not finalized")]
+    EpochNotFinalized,
+
+    #[msg("User is not on the epoch leaderboard")]
+    NotOnLeaderboard,
+
+    #[msg("Epoch reward already claimed")]
+    EpochRewardAlreadyClaimed,
+
+    #[msg("Treasury timelock can only be raised, up to 30 days")]
+    InvalidTreasuryTimelock,
+
+    #[msg("Treasury withdrawal amount must be positive")]
+    InvalidTreasuryWithdrawal,
+
+    #[msg("A treasury withdrawal is already queued")]
+    TreasuryWithdrawalPending,
+
+    #[msg("No treasury withdrawal is queued")]
+    NoTreasuryWithdrawal,
+
+    #[msg("Treasury withdrawal is still timelocked")]
+    TreasuryTimelocked,
+
+    #[msg("Destination does not match the treasury withdrawal")]
+    TreasuryDestinationMismatch,
+
+    #[msg("Bettor must present a credential from the poll's attestor")]
+    AttestationRequired,
+
+    #[msg("Attestation has expired")]
+    AttestationExpired,
+
+    #[msg("Attestation expiry must be in the future, or 0 for none")]
+    InvalidAttestation,
+
+    #[msg("A treasury change is already queued")]
+    TreasuryChangePending,
+
+    #[msg("No treasury change is queued")]
+    NoTreasuryChange,
+
+    #[msg("Jackpot polls must be pari-mutuel")]
+    JackpotPayoutMode,
+}
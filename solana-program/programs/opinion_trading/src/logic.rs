@@ -0,0 +1,6371 @@
+//! Pure business rules: validation, odds, fees, and payouts.
+//!
+//! Nothing here touches `AccountInfo`, sysvars, or CPIs; the caller passes the
+//! clock time in. Handlers load accounts, call into this module, and move
+//! funds, so every rule that decides *how much* or *whether* can be exercised
+//! by host-side unit tests and fuzzers with plain values.
+
+use anchor_lang::prelude::*;
+
+use solana_sha256_hasher::hashv;
+
+use crate::cluster::{
+    CLOSE_GRACE_PERIOD, COMMUNITY_VOTE_DELAY, CRANK_GRACE_PERIOD, MAX_BET_AMOUNT, MIN_BET_AMOUNT,
+    MIN_POLL_DURATION, REFUND_TIP, SETTLEMENT_REVEAL_DELAY,
+};
+use anchor_spl::token::TokenAccount;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Mint as Mint2022;
+
+use crate::math::{
+    clamped_odds, fee, mul_div, payout_at_odds, safe_add, safe_mul, safe_mul_div, safe_sub, Bps,
+    Rounding,
+};
+use crate::oracle::{OraclePrice, MIN_USD_PRICE_EXPONENT, ORACLE_PRICE_WINDOW, USD_PRICE_MAX_AGE};
+use crate::signature::{result_message, SignedMessage};
+use crate::{
+    AccessGate, Attestation, BetCredit, Bid, BidQuote, BidStatus, Config, DeadlineExtension, Epoch,
+    EpochEntry, EpochMetric, ErrorCode, EventStatus, FeeShare, FeeSweep, FeeTier, FeeVault,
+    IncentiveCurve,
+    LpPosition, MarketEvent, MarketState, OddsHistory, OddsSnapshot, OutcomeSlot, Parlay,
+    ParlayLeg, PayoutMode, PendingOrder, Poll, PollKind, PollLimits, PollRegistry, PollStatus,
+    PollTemplate, PriceComparison, PromoVault, ResolutionSource, ResolverCouncil, TreasuryChange,
+    TreasuryStats, TreasuryWithdrawal, UserBalance, UserPollPosition, UserProfile, UserStake,
+    BID_VERSION, BPS_DENOMINATOR, COMMUNITY_QUORUM_BPS, COMMUNITY_SUPERMAJORITY_BPS,
+    EPOCH_SUBMISSION_PERIOD,
+    MAX_BID_COOLDOWN, MAX_CANCEL_FEE_BPS, MAX_CANCEL_WINDOW, MAX_DAMPING_WINDOW,
+    MAX_DISPUTE_PERIOD, MAX_END_EXTENSION, MAX_EPOCH_WINNERS, MAX_EVENT_POLLS, MAX_FEE_BPS,
+    MAX_FEE_TIERS, MAX_METADATA_TAGS, MAX_METADATA_TAG_LEN, MAX_METADATA_URI_LEN, MAX_ODDS_BPS,
+    MAX_OUTCOMES, MAX_PARLAY_LEGS, MAX_POSITION_BIDS, MAX_RESOLUTION_CRITERIA_LEN,
+    MAX_RESOLUTION_DEADLINE, MAX_RESOLVERS, MAX_TEMPLATE_PREFIX_LEN, MAX_TREASURY_DELAY,
+    MAX_VIG_BPS, MAX_VIRTUAL_LIQUIDITY, MIN_ODDS_BPS, MIN_OUTCOMES, MIN_PARLAY_LEGS,
+    ODDS_HISTORY_CAPACITY, POLL_VERSION, REGISTRY_PAGE_SIZE, TREASURY_HISTORY_LEN, VIEW_VERSION,
+};
+
+/// Validate the text fields and outcome count of a new poll against their
+/// stored capacity
+pub fn validate_poll_metadata(poll_id: &str, title: &str, outcomes: &[String]) -> Result<()> {
+    require_ctx!(
+        poll_id.len() <= 64,
+        ErrorCode::PollIdTooLong,
+        len = poll_id.len(),
+        max = 64
+    );
+    require_ctx!(
+        title.len() <= 256,
+        ErrorCode::TitleTooLong,
+        len = title.len(),
+        max = 256
+    );
+    require_ctx!(
+        (MIN_OUTCOMES..=MAX_OUTCOMES).contains(&outcomes.len()),
+        ErrorCode::InvalidOutcomeCount,
+        count = outcomes.len(),
+        min = MIN_OUTCOMES,
+        max = MAX_OUTCOMES
+    );
+    for (option, text) in outcomes.iter().enumerate() {
+        require_ctx!(
+            text.len() <= 128,
+            ErrorCode::OptionTextTooLong,
+            option = option,
+            len = text.len(),
+            max = 128
+        );
+    }
+    Ok(())
+}
+
+/// Validate a template's poll id prefix, which leaves room for its rounds
+pub fn validate_template_prefix(id_prefix: &str) -> Result<()> {
+    require_ctx!(
+        id_prefix.len() <= MAX_TEMPLATE_PREFIX_LEN,
+        ErrorCode::TemplatePrefixTooLong,
+        len = id_prefix.len(),
+        max = MAX_TEMPLATE_PREFIX_LEN
+    );
+    Ok(())
+}
+
+/// Validate a template's schedule: rounds start every `period` seconds from
+/// `first_start` and each stays open for `duration`, at least the minimum poll
+/// duration
+pub fn validate_template_schedule(first_start: i64, period: i64, duration: i64) -> Result<()> {
+    require_ctx!(
+        period > 0 && first_start.checked_add(duration).is_some(),
+        ErrorCode::InvalidTemplateSchedule,
+        first_start = first_start,
+        period = period,
+        duration = duration
+    );
+    validate_end_timestamp(first_start + duration, first_start)
+}
+
+/// Poll id of a template's round `round`
+pub fn template_poll_id(id_prefix: &str, round: u64) -> String {
+    format!("{}-{}", id_prefix, round)
+}
+
+/// Poll id, start, and end of a template's round `round`
+pub fn template_round(template: &PollTemplate, round: u64) -> Result<(String, i64, i64)> {
+    let start = i64::try_from(round)
+        .ok()
+        .and_then(|round| round.checked_mul(template.period))
+        .and_then(|offset| offset.checked_add(template.first_start));
+    let end = start.and_then(|start| start.checked_add(template.duration));
+    let (Some(start), Some(end)) = (start, end) else {
+        return err!(ErrorCode::InvalidTemplateSchedule);
+    };
+    Ok((template_poll_id(&template.id_prefix, round), start, end))
+}
+
+/// Validate a poll's metadata against its limits; tags can't be empty
+pub fn validate_metadata(uri: &str, tags: &[String], resolution_criteria: &str) -> Result<()> {
+    require_ctx!(
+        uri.len() <= MAX_METADATA_URI_LEN,
+        ErrorCode::MetadataUriTooLong,
+        len = uri.len(),
+        max = MAX_METADATA_URI_LEN
+    );
+    require_ctx!(
+        tags.len() <= MAX_METADATA_TAGS
+            && tags.iter().all(|tag| (1..=MAX_METADATA_TAG_LEN).contains(&tag.len())),
+        ErrorCode::InvalidMetadataTags,
+        count = tags.len(),
+        max = MAX_METADATA_TAGS
+    );
+    require_ctx!(
+        resolution_criteria.len() <= MAX_RESOLUTION_CRITERIA_LEN,
+        ErrorCode::ResolutionCriteriaTooLong,
+        len = resolution_criteria.len(),
+        max = MAX_RESOLUTION_CRITERIA_LEN
+    );
+    Ok(())
+}
+
+/// Validate a poll's kind against its payout mode and `outcome_count`
+/// outcomes: a jackpot poll splits its pool among the winners, so it must be
+/// pari-mutuel, and a scalar poll's bounds split the number line into exactly
+/// that many buckets, so there is one fewer bound than outcomes and they
+/// strictly increase
+pub fn validate_poll_kind(
+    kind: &PollKind,
+    payout_mode: PayoutMode,
+    outcome_count: usize,
+) -> Result<()> {
+    require!(
+        *kind != PollKind::Jackpot || payout_mode == PayoutMode::PariMutuel,
+        ErrorCode::JackpotPayoutMode
+    );
+    if let PollKind::Scalar { bounds } = kind {
+        require_ctx!(
+            bounds.len() + 1 == outcome_count,
+            ErrorCode::InvalidScalarBounds,
+            bounds = bounds.len(),
+            outcome_count = outcome_count
+        );
+        require!(
+            bounds.windows(2).all(|pair| pair[0] < pair[1]),
+            ErrorCode::InvalidScalarBounds
+        );
+    }
+    Ok(())
+}
+
+/// Validate an oracle resolution source for a poll of `kind` with
+/// `outcome_count` outcomes; the oracle can decide a yes/no question or pick
+/// a scalar poll's bucket
+pub fn validate_resolution_source(
+    resolution: Option<&ResolutionSource>,
+    kind: &PollKind,
+    outcome_count: usize,
+) -> Result<()> {
+    if resolution.is_some() && !matches!(kind, PollKind::Scalar { .. }) {
+        require_ctx!(
+            outcome_count == 2,
+            ErrorCode::InvalidResolutionSource,
+            outcome_count = outcome_count
+        );
+    }
+    Ok(())
+}
+
+/// Validate a poll's anti-sniping rule: every part must be positive, and all
+/// extensions together may push the end at most `MAX_END_EXTENSION`
+pub fn validate_deadline_extension(extension: Option<&DeadlineExtension>) -> Result<()> {
+    if let Some(extension) = extension {
+        let total = extension
+            .duration
+            .checked_mul(extension.max_extensions.into());
+        require_ctx!(
+            extension.window > 0
+                && extension.duration > 0
+                && extension.max_extensions > 0
+                && total.is_some_and(|total| total <= MAX_END_EXTENSION),
+            ErrorCode::InvalidDeadlineExtension,
+            window = extension.window,
+            duration = extension.duration,
+            max_extensions = extension.max_extensions
+        );
+    }
+    Ok(())
+}
+
+/// Validate a poll's incentive curve against its fee and schedule: the early
+/// bonus is funded from the fee, the fee plus the late fee stays within
+/// `MAX_FEE_BPS`, and the early window ends before the late one starts
+pub fn validate_incentives(
+    incentives: Option<&IncentiveCurve>,
+    fee_bps: u16,
+    start_timestamp: i64,
+    end_timestamp: i64,
+) -> Result<()> {
+    if let Some(curve) = incentives {
+        let early_end = start_timestamp.checked_add(curve.early_window);
+        let late_start = end_timestamp.checked_sub(curve.late_window);
+        require_ctx!(
+            curve.early_window >= 0
+                && curve.late_window >= 0
+                && curve.early_bonus_bps <= fee_bps
+                && fee_bps.saturating_add(curve.late_fee_bps) <= MAX_FEE_BPS
+                && early_end.zip(late_start).is_some_and(|(early, late)| early <= late),
+            ErrorCode::InvalidIncentiveCurve,
+            early_window = curve.early_window,
+            early_bonus_bps = curve.early_bonus_bps,
+            late_window = curve.late_window,
+            late_fee_bps = curve.late_fee_bps
+        );
+    }
+    Ok(())
+}
+
+/// Validate a poll's resolution deadline: 0 for none, otherwise at least
+/// `CRANK_GRACE_PERIOD` so the authority and keepers get to settle first
+pub fn validate_resolution_deadline(resolution_deadline: i64) -> Result<()> {
+    require_ctx!(
+        resolution_deadline == 0
+            || (CRANK_GRACE_PERIOD..=MAX_RESOLUTION_DEADLINE).contains(&resolution_deadline),
+        ErrorCode::InvalidResolutionDeadline,
+        resolution_deadline = resolution_deadline,
+        min = CRANK_GRACE_PERIOD,
+        max = MAX_RESOLUTION_DEADLINE
+    );
+    Ok(())
+}
+
+/// Validate a poll's virtual liquidity against `MAX_VIRTUAL_LIQUIDITY`
+pub fn validate_virtual_liquidity(virtual_liquidity: u64) -> Result<()> {
+    require_ctx!(
+        virtual_liquidity <= MAX_VIRTUAL_LIQUIDITY,
+        ErrorCode::InvalidVirtualLiquidity,
+        virtual_liquidity = virtual_liquidity,
+        max = MAX_VIRTUAL_LIQUIDITY
+    );
+    Ok(())
+}
+
+/// Validate config settings: the fee is capped at `MAX_FEE_BPS`, the creator,
+/// LP, and referral shares together are at most the whole fee, the bet limits must be
+/// ordered and within the cluster's range, and the claim period is at least
+/// the cluster's `CLOSE_GRACE_PERIOD`
+pub fn validate_config(
+    fee_bps: u16,
+    creator_fee_bps: u16,
+    lp_fee_bps: u16,
+    referral_fee_bps: u16,
+    min_bet: u64,
+    max_bet: u64,
+    claim_period: i64,
+) -> Result<()> {
+    require_ctx!(
+        fee_bps <= MAX_FEE_BPS,
+        ErrorCode::InvalidFeeBps,
+        fee_bps = fee_bps,
+        max = MAX_FEE_BPS
+    );
+    require_ctx!(
+        u64::from(creator_fee_bps) + u64::from(lp_fee_bps) + u64::from(referral_fee_bps)
+            <= BPS_DENOMINATOR,
+        ErrorCode::InvalidFeeBps,
+        creator_fee_bps = creator_fee_bps,
+        lp_fee_bps = lp_fee_bps,
+        referral_fee_bps = referral_fee_bps,
+        max = BPS_DENOMINATOR
+    );
+    require_ctx!(
+        MIN_BET_AMOUNT <= min_bet && min_bet <= max_bet && max_bet <= MAX_BET_AMOUNT,
+        ErrorCode::InvalidBetLimits,
+        min_bet = min_bet,
+        max_bet = max_bet
+    );
+    require_ctx!(
+        claim_period >= CLOSE_GRACE_PERIOD,
+        ErrorCode::InvalidClaimPeriod,
+        claim_period = claim_period,
+        min = CLOSE_GRACE_PERIOD
+    );
+    Ok(())
+}
+
+/// Resolve a new poll's bet limits and platform fee from the config and the
+/// creator's overrides. The limits must be ordered and within the config's;
+/// the fee at least the config's and at most `MAX_FEE_BPS`. Returns
+/// `(min_bet, max_bet, fee_bps)`.
+pub fn poll_limits(config: &Config, limits: &PollLimits) -> Result<(u64, u64, u16)> {
+    let min_bet = limits.min_bet.unwrap_or(config.min_bet);
+    let max_bet = limits.max_bet.unwrap_or(config.max_bet);
+    let fee_bps = limits.fee_bps.unwrap_or(config.fee_bps);
+    require_ctx!(
+        config.min_bet <= min_bet && min_bet <= max_bet && max_bet <= config.max_bet,
+        ErrorCode::InvalidBetLimits,
+        min_bet = min_bet,
+        max_bet = max_bet
+    );
+    require_ctx!(
+        config.fee_bps <= fee_bps && fee_bps <= MAX_FEE_BPS,
+        ErrorCode::InvalidFeeBps,
+        fee_bps = fee_bps,
+        min = config.fee_bps,
+        max = MAX_FEE_BPS
+    );
+    Ok((min_bet, max_bet, fee_bps))
+}
+
+/// Validate a volume fee schedule: at most `MAX_FEE_TIERS` tiers, each fee at
+/// most `MAX_FEE_BPS`, with volume thresholds rising and fees falling tier by
+/// tier
+pub fn validate_fee_tiers(fee_tiers: &[FeeTier]) -> Result<()> {
+    require_ctx!(
+        fee_tiers.len() <= MAX_FEE_TIERS,
+        ErrorCode::InvalidFeeTiers,
+        count = fee_tiers.len(),
+        max = MAX_FEE_TIERS
+    );
+    require!(
+        fee_tiers.iter().all(|tier| tier.fee_bps <= MAX_FEE_BPS),
+        ErrorCode::InvalidFeeTiers
+    );
+    require!(
+        fee_tiers
+            .windows(2)
+            .all(|pair| pair[0].min_volume < pair[1].min_volume && pair[0].fee_bps > pair[1].fee_bps),
+        ErrorCode::InvalidFeeTiers
+    );
+    Ok(())
+}
+
+/// Validate the config's USD bid limit: a limit needs a SOL/USD feed to
+/// value bids against
+pub fn validate_usd_limits(usd_feed_id: &[u8; 32], max_bid_usd: u64) -> Result<()> {
+    require!(
+        max_bid_usd == 0 || *usd_feed_id != [0; 32],
+        ErrorCode::InvalidUsdLimits
+    );
+    Ok(())
+}
+
+/// Micro-USD value of `amount` lamports at a SOL/USD price, rounded down
+pub fn usd_notional(amount: u64, price: &OraclePrice) -> Result<u64> {
+    // Lamports to SOL is 10^-9 and dollars to micro-USD 10^6, so the price's
+    // exponent comes out 3 lower
+    require!(
+        price.price > 0 && (MIN_USD_PRICE_EXPONENT..=0).contains(&price.exponent),
+        ErrorCode::InvalidUsdPrice
+    );
+    let divisor = 10u64.pow((3 - price.exponent) as u32);
+    mul_div(amount, price.price as u64, divisor, Rounding::Down)
+        .ok_or(error!(ErrorCode::InvalidUsdPrice))
+}
+
+/// Value a SOL bid of `amount` in micro-USD and hold it to the config's
+/// `max_bid_usd`. Without a price the bid is valued at 0, which only a
+/// config without a limit accepts.
+pub fn usd_bid_notional(
+    config: &Config,
+    amount: u64,
+    price: Option<&OraclePrice>,
+    now: i64,
+) -> Result<u64> {
+    let Some(price) = price else {
+        require!(config.max_bid_usd == 0, ErrorCode::UsdPriceRequired);
+        return Ok(0);
+    };
+    require!(
+        config.usd_feed_id != [0; 32] && price.feed_id == config.usd_feed_id,
+        ErrorCode::UsdFeedMismatch
+    );
+    require_ctx!(
+        now.saturating_sub(price.publish_time) <= USD_PRICE_MAX_AGE,
+        ErrorCode::InvalidUsdPrice,
+        publish_time = price.publish_time,
+        now = now,
+        max_age = USD_PRICE_MAX_AGE
+    );
+
+    let notional = usd_notional(amount, price)?;
+    require_ctx!(
+        config.max_bid_usd == 0 || notional <= config.max_bid_usd,
+        ErrorCode::UsdLimitExceeded,
+        notional = notional,
+        max = config.max_bid_usd
+    );
+    Ok(notional)
+}
+
+/// Validate that a bid path without a SOL/USD price can't get around the
+/// config's USD bid limit
+pub fn validate_no_usd_limit(config: &Config) -> Result<()> {
+    require!(config.max_bid_usd == 0, ErrorCode::UsdPriceRequired);
+    Ok(())
+}
+
+/// Validate a poll's dispute period (0 makes settlements final immediately)
+pub fn validate_dispute_period(dispute_period: i64) -> Result<()> {
+    require_ctx!(
+        (0..=MAX_DISPUTE_PERIOD).contains(&dispute_period),
+        ErrorCode::InvalidDisputePeriod,
+        dispute_period = dispute_period,
+        max = MAX_DISPUTE_PERIOD
+    );
+    Ok(())
+}
+
+/// sha256 of a poll title or outcome label. Polls store only the hash; the
+/// text itself is emitted in `PollCreated`.
+pub fn text_hash(text: &str) -> [u8; 32] {
+    hashv(&[text.as_bytes()]).to_bytes()
+}
+
+/// Outcome slots for a new poll, unstaked and at equal odds
+pub fn new_outcomes(texts: &[String]) -> Result<Vec<OutcomeSlot>> {
+    let odds = amm_odds(0, 0, texts.len(), 0)?;
+    Ok(texts
+        .iter()
+        .map(|text| OutcomeSlot {
+            text_hash: text_hash(text),
+            stake: 0,
+            odds,
+            total_potential_liability: 0,
+        })
+        .collect())
+}
+
+/// Validate that a new poll opening at `opens_at` runs for at least the
+/// cluster's minimum duration
+pub fn validate_end_timestamp(end_timestamp: i64, opens_at: i64) -> Result<()> {
+    require_ctx!(
+        end_timestamp
+            >= opens_at
+                .checked_add(MIN_POLL_DURATION)
+                .ok_or(ErrorCode::MathOverflow)?,
+        ErrorCode::InvalidEndTime,
+        end_timestamp = end_timestamp,
+        opens_at = opens_at,
+        min_duration = MIN_POLL_DURATION
+    );
+    Ok(())
+}
+
+/// Move the end time of a poll that has not ended or settled yet, e.g. to
+/// make up for time spent suspended.
+/// The new end must leave the minimum duration from now (or the start time)
+/// and be at most `MAX_END_EXTENSION` past the end the poll was created with.
+/// Returns the previous end time.
+pub fn update_end_time(poll: &mut Poll, end_timestamp: i64, now: i64) -> Result<i64> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        now < poll.end_timestamp,
+        ErrorCode::PollEnded,
+        now = now,
+        end_timestamp = poll.end_timestamp
+    );
+    validate_end_timestamp(end_timestamp, now.max(poll.start_timestamp))?;
+    let latest = poll
+        .initial_end_timestamp
+        .checked_add(MAX_END_EXTENSION)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require_ctx!(
+        end_timestamp <= latest,
+        ErrorCode::InvalidEndTime,
+        end_timestamp = end_timestamp,
+        latest = latest
+    );
+
+    Ok(std::mem::replace(&mut poll.end_timestamp, end_timestamp))
+}
+
+/// Open a `Scheduled` poll once its start time has passed; other polls are
+/// left unchanged. Bids and settlement open a started poll on their own, so
+/// the `open_poll` crank is only needed to surface the status change.
+pub fn open_scheduled(poll: &mut Poll, now: i64) -> Result<()> {
+    if poll.status == PollStatus::Scheduled {
+        require_ctx!(
+            now >= poll.start_timestamp,
+            ErrorCode::PollNotStarted,
+            now = now,
+            start_timestamp = poll.start_timestamp
+        );
+        poll.status = PollStatus::Active;
+    }
+    Ok(())
+}
+
+/// Validate that `poll` accepts a bid of `amount` at `now`
+pub fn validate_bid(poll: &Poll, amount: u64, now: i64) -> Result<()> {
+    require!(
+        poll.status == PollStatus::Active,
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        now < poll.end_timestamp,
+        ErrorCode::PollEnded,
+        now = now,
+        end_timestamp = poll.end_timestamp
+    );
+    require_ctx!(
+        (poll.min_bet..=poll.max_bet).contains(&amount),
+        ErrorCode::InvalidBetAmount,
+        amount = amount,
+        min = poll.min_bet,
+        max = poll.max_bet
+    );
+    Ok(())
+}
+
+/// Validate that `option` indexes one of the poll's outcomes
+pub fn validate_outcome(poll: &Poll, option: u8) -> Result<()> {
+    require_ctx!(
+        (option as usize) < poll.outcomes.len(),
+        ErrorCode::InvalidOutcome,
+        option = option,
+        count = poll.outcomes.len()
+    );
+    Ok(())
+}
+
+/// Validate a new bid against the poll, then record its stake, count it, and
+/// update AMM odds. Returns the odds the bid was priced at and its locked-in
+/// potential win.
+pub fn record_bid(poll: &mut Poll, amount: u64, option: u8, now: i64) -> Result<(u64, u64)> {
+    let priced = price_bid(poll, amount, option, now)?;
+    count_bid(poll, option)?;
+    Ok(priced)
+}
+
+/// `record_bid` without counting a new bid, for stake added to an existing one
+fn price_bid(poll: &mut Poll, amount: u64, option: u8, now: i64) -> Result<(u64, u64)> {
+    // Adapter markets are priced by their adapter via `place_bid_adapter`
+    require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+    // Outcome-token markets mint tokens instead of creating bids
+    require!(!poll.outcome_tokens, ErrorCode::OutcomeTokenMarket);
+    validate_outcome(poll, option)?;
+
+    // Current odds, plus any late-bid premium and the vig
+    let odds = bid_odds(poll, amount, option, now)?;
+
+    let potential_win = record_bid_at_odds(poll, amount, option, odds, now)?;
+
+    Ok((odds, potential_win))
+}
+
+/// Validate a bid priced at `odds`, then record its stake and liability and
+/// update AMM odds. Returns the bid's locked-in potential win.
+///
+/// On fixed-odds polls the bid is rejected if, should its outcome win, the
+/// pool (including this bid) could not pay every win locked in on it.
+pub fn record_bid_at_odds(
+    poll: &mut Poll,
+    amount: u64,
+    option: u8,
+    odds: u64,
+    now: i64,
+) -> Result<u64> {
+    open_scheduled(poll, now)?;
+    validate_bid(poll, amount, now)?;
+    validate_outcome(poll, option)?;
+
+    let potential_win = potential_win(amount, odds)?;
+
+    // Update poll state
+    let total_pool = safe_add(poll.total_pool, amount)?;
+    let stake = safe_add(poll.outcomes[option as usize].stake, amount)?;
+    validate_pool_caps(poll, total_pool, stake)?;
+    let outcome = &mut poll.outcomes[option as usize];
+    let liability = safe_add(outcome.total_potential_liability, potential_win)?;
+    if poll.payout_mode == PayoutMode::FixedOdds {
+        require_ctx!(
+            liability <= safe_add(total_pool, poll.lp_liquidity)?,
+            ErrorCode::InsufficientPoolLiquidity,
+            option = option,
+            potential_win = potential_win,
+            liability = liability,
+            total_pool = total_pool,
+            lp_liquidity = poll.lp_liquidity
+        );
+    }
+    let odds_before = outcome.odds;
+    outcome.stake = stake;
+    outcome.total_potential_liability = liability;
+    poll.total_pool = total_pool;
+
+    // Update AMM odds using Constant Product Market Maker formula
+    reprice(poll, now)?;
+    let impact = poll.outcomes[option as usize].odds.saturating_sub(odds_before);
+    require_ctx!(
+        poll.max_price_impact_bps == 0 || impact <= u64::from(poll.max_price_impact_bps),
+        ErrorCode::PriceImpactTooHigh,
+        amount = amount,
+        impact = impact,
+        max = poll.max_price_impact_bps
+    );
+    extend_deadline(poll, now)?;
+
+    Ok(potential_win)
+}
+
+/// Set or lift (0) the most one bid may move its outcome's odds, in basis
+/// points
+pub fn set_max_price_impact(poll: &mut Poll, max_price_impact_bps: u16) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        u64::from(max_price_impact_bps) <= BPS_DENOMINATOR,
+        ErrorCode::InvalidPriceImpact,
+        max_price_impact_bps = max_price_impact_bps,
+        max = BPS_DENOMINATOR
+    );
+
+    poll.max_price_impact_bps = max_price_impact_bps;
+    Ok(())
+}
+
+/// The largest part of a bid of `amount` on `option` the poll takes: within
+/// its pool caps, within the pool's backing of the outcome's locked-in wins
+/// on fixed-odds polls, and moving the outcome's odds by at most
+/// `max_price_impact_bps`. The whole amount when it all fits; fails with the
+/// limit's error if the part is below the poll's minimum bet.
+pub fn fill_amount(poll: &Poll, amount: u64, option: u8, now: i64) -> Result<u64> {
+    validate_outcome(poll, option)?;
+    let outcome = &poll.outcomes[option as usize];
+
+    let mut room = amount;
+    if let Some(cap) = poll.max_total_pool {
+        room = room.min(cap.saturating_sub(poll.total_pool));
+    }
+    if let Some(cap) = poll.max_stake_per_option {
+        room = room.min(cap.saturating_sub(outcome.stake));
+    }
+    require_ctx!(
+        room == amount || room >= poll.min_bet,
+        ErrorCode::BetCapExceeded,
+        amount = amount,
+        fillable = room,
+        min_bet = poll.min_bet
+    );
+
+    let amount = if poll.payout_mode == PayoutMode::FixedOdds {
+        largest_fill(poll, room, ErrorCode::InsufficientPoolLiquidity, |part| {
+            let odds = bid_odds(poll, part, option, now)?;
+            let win = potential_win(part, odds)?;
+            let liability = safe_add(outcome.total_potential_liability, win)?;
+            let backing = safe_add(safe_add(poll.total_pool, part)?, poll.lp_liquidity)?;
+            Ok(liability <= backing)
+        })?
+    } else {
+        room
+    };
+
+    if poll.max_price_impact_bps == 0 {
+        return Ok(amount);
+    }
+    let max = u64::from(poll.max_price_impact_bps);
+    largest_fill(poll, amount, ErrorCode::PriceImpactTooHigh, |part| {
+        let odds = amm_odds(
+            safe_add(outcome.stake, part)?,
+            safe_add(poll.total_pool, part)?,
+            poll.outcomes.len(),
+            poll.virtual_liquidity,
+        )?;
+        Ok(odds.saturating_sub(outcome.odds) <= max)
+    })
+}
+
+/// The largest part of `amount` that `fits`, found by bisection since any
+/// smaller part of a bid that fits does too. Fails with `error` if the part is
+/// below the poll's minimum bet.
+fn largest_fill(
+    poll: &Poll,
+    amount: u64,
+    error: ErrorCode,
+    fits: impl Fn(u64) -> Result<bool>,
+) -> Result<u64> {
+    if fits(amount)? {
+        return Ok(amount);
+    }
+
+    // `low` always fits and `high` never does
+    let (mut low, mut high) = (0, amount);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if fits(mid)? {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    require_ctx!(
+        low >= poll.min_bet,
+        error,
+        amount = amount,
+        fillable = low,
+        min_bet = poll.min_bet
+    );
+    Ok(low)
+}
+
+/// Set or lift (`None`) a poll's caps on its total pool, the stake on any one
+/// outcome, and each user's total stake. Caps bound new bids only.
+pub fn set_caps(
+    poll: &mut Poll,
+    max_total_pool: Option<u64>,
+    max_stake_per_option: Option<u64>,
+    max_stake_per_user: Option<u64>,
+) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+    require!(
+        [max_total_pool, max_stake_per_option, max_stake_per_user]
+            .iter()
+            .all(|cap| *cap != Some(0)),
+        ErrorCode::InvalidBetCap
+    );
+
+    poll.max_total_pool = max_total_pool;
+    poll.max_stake_per_option = max_stake_per_option;
+    poll.max_stake_per_user = max_stake_per_user;
+    Ok(())
+}
+
+fn validate_pool_caps(poll: &Poll, total_pool: u64, stake: u64) -> Result<()> {
+    if let Some(cap) = poll.max_total_pool {
+        require_ctx!(
+            total_pool <= cap,
+            ErrorCode::BetCapExceeded,
+            total_pool = total_pool,
+            max_total_pool = cap
+        );
+    }
+    if let Some(cap) = poll.max_stake_per_option {
+        require_ctx!(
+            stake <= cap,
+            ErrorCode::BetCapExceeded,
+            stake = stake,
+            max_stake_per_option = cap
+        );
+    }
+    Ok(())
+}
+
+/// Set up a user's stake tracker on a poll the first time it is used
+pub fn init_user_stake(user_stake: &mut UserStake, poll: Pubkey, user: Pubkey, bump: u8) {
+    if user_stake.user == Pubkey::default() {
+        user_stake.poll = poll;
+        user_stake.user = user;
+        user_stake.bump = bump;
+    }
+}
+
+/// Add `amount` to a user's total stake on a poll, within its per-user cap
+pub fn record_user_stake(poll: &Poll, user_stake: &mut UserStake, amount: u64) -> Result<()> {
+    let stake = safe_add(user_stake.stake, amount)?;
+    if let Some(cap) = poll.max_stake_per_user {
+        require_ctx!(
+            stake <= cap,
+            ErrorCode::BetCapExceeded,
+            user_stake = stake,
+            max_stake_per_user = cap
+        );
+    }
+
+    user_stake.stake = stake;
+    Ok(())
+}
+
+/// Set up a user's position on a poll the first time it is used
+pub fn init_position(position: &mut UserPollPosition, poll: Pubkey, user: Pubkey, bump: u8) {
+    if position.user == Pubkey::default() {
+        position.poll = poll;
+        position.user = user;
+        position.bump = bump;
+    }
+}
+
+/// Add `amount` on `option` to a user's position. A new bid passes its index,
+/// listed while the position has room; an increase passes `None`.
+pub fn record_position(
+    position: &mut UserPollPosition,
+    option: u8,
+    amount: u64,
+    bid_index: Option<u64>,
+) -> Result<()> {
+    let stake = &mut position.stakes[option as usize];
+    *stake = safe_add(*stake, amount)?;
+
+    if let Some(bid_index) = bid_index {
+        position.bid_count = position
+            .bid_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if position.bid_indices.len() < MAX_POSITION_BIDS {
+            position.bid_indices.push(bid_index);
+        }
+    }
+    Ok(())
+}
+
+/// Validate that a bid path without a `UserStake` account can't get around
+/// the poll's per-user cap
+pub fn validate_no_user_cap(poll: &Poll) -> Result<()> {
+    require!(
+        poll.max_stake_per_user.is_none(),
+        ErrorCode::UserStakeRequired
+    );
+    Ok(())
+}
+
+/// Set the least time a user must wait between bids on the poll; 0 lifts the
+/// cooldown
+pub fn set_bid_cooldown(poll: &mut Poll, min_seconds_between_bids: i64) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        (0..=MAX_BID_COOLDOWN).contains(&min_seconds_between_bids),
+        ErrorCode::InvalidBidCooldown,
+        min_seconds_between_bids = min_seconds_between_bids,
+        max = MAX_BID_COOLDOWN
+    );
+
+    poll.min_seconds_between_bids = min_seconds_between_bids;
+    Ok(())
+}
+
+/// Set how long before the end odds movement is damped and how much of it is
+/// held back at the end, in basis points; a window of 0 turns damping off
+pub fn set_odds_damping(poll: &mut Poll, damping_window: i64, damping_bps: u16) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        (0..=MAX_DAMPING_WINDOW).contains(&damping_window)
+            && u64::from(damping_bps) <= BPS_DENOMINATOR
+            && (damping_window == 0) == (damping_bps == 0),
+        ErrorCode::InvalidOddsDamping,
+        damping_window = damping_window,
+        damping_bps = damping_bps,
+        max_window = MAX_DAMPING_WINDOW
+    );
+
+    poll.damping_window = damping_window;
+    poll.damping_bps = damping_bps;
+    Ok(())
+}
+
+/// Share of an odds move held back at `now`, in basis points: 0 until the
+/// poll's damping window opens, rising linearly to `damping_bps` at the end
+pub fn damping_bps(poll: &Poll, now: i64) -> Result<u64> {
+    if poll.damping_window == 0 {
+        return Ok(0);
+    }
+    let opens = poll.end_timestamp.saturating_sub(poll.damping_window);
+    if now <= opens {
+        return Ok(0);
+    }
+    let elapsed = (now - opens).min(poll.damping_window) as u64;
+    safe_mul_div(
+        u64::from(poll.damping_bps),
+        elapsed,
+        poll.damping_window as u64,
+        Rounding::Down,
+    )
+}
+
+/// Odds a bid of `amount` on `option` is priced at: the outcome's current
+/// odds, plus the damped share of the move the bid causes, so a late bid
+/// pays for the movement it doesn't get to make
+pub fn late_bid_odds(poll: &Poll, amount: u64, option: u8, now: i64) -> Result<u64> {
+    let outcome = &poll.outcomes[option as usize];
+    let damping = damping_bps(poll, now)?;
+    if damping == 0 {
+        return Ok(outcome.odds);
+    }
+    let target = amm_odds(
+        safe_add(outcome.stake, amount)?,
+        safe_add(poll.total_pool, amount)?,
+        poll.outcomes.len(),
+        poll.virtual_liquidity,
+    )?;
+    let premium = safe_mul_div(
+        target.saturating_sub(outcome.odds),
+        damping,
+        BPS_DENOMINATOR,
+        Rounding::Up,
+    )?;
+    safe_add(outcome.odds, premium)
+}
+
+/// Set the margin built into the odds bids are priced at, in basis points of
+/// the odds; 0 prices bids at the AMM odds
+pub fn set_poll_vig(poll: &mut Poll, vig_bps: u16) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        vig_bps <= MAX_VIG_BPS,
+        ErrorCode::InvalidVig,
+        vig_bps = vig_bps,
+        max = MAX_VIG_BPS
+    );
+
+    poll.vig_bps = vig_bps;
+    Ok(())
+}
+
+/// `odds` marked up by the poll's vig (rounded up) and capped at 100%, where
+/// a bid only wins back its stake. With a 5% vig, a 50% outcome is priced at
+/// 52.5%, so a two-outcome book sums to 105%.
+pub fn odds_with_vig(poll: &Poll, odds: u64) -> Result<u64> {
+    if poll.vig_bps == 0 {
+        return Ok(odds);
+    }
+    let margin = Bps::new(poll.vig_bps.into()).try_apply(odds, Rounding::Up)?;
+    Ok(safe_add(odds, margin)?.min(BPS_DENOMINATOR))
+}
+
+/// Odds a bid of `amount` on `option` is priced at right now: the late-bid
+/// odds marked up by the vig
+pub fn bid_odds(poll: &Poll, amount: u64, option: u8, now: i64) -> Result<u64> {
+    odds_with_vig(poll, late_bid_odds(poll, amount, option, now)?)
+}
+
+/// Update AMM odds after the pool changed. Inside the damping window each
+/// outcome moves only part of the way from its old odds.
+fn reprice(poll: &mut Poll, now: i64) -> Result<()> {
+    let damping = damping_bps(poll, now)?;
+    if damping == 0 {
+        return update_amm_odds(poll);
+    }
+    let before: Vec<u64> = poll.outcomes.iter().map(|outcome| outcome.odds).collect();
+    update_amm_odds(poll)?;
+    let kept = BPS_DENOMINATOR - damping;
+    for (outcome, before) in poll.outcomes.iter_mut().zip(before) {
+        let full_move = before.abs_diff(outcome.odds);
+        let moved = safe_mul_div(full_move, kept, BPS_DENOMINATOR, Rounding::Down)?;
+        outcome.odds = if outcome.odds >= before {
+            before + moved
+        } else {
+            before - moved
+        };
+    }
+    Ok(())
+}
+
+/// Enforce the poll's cooldown since the user's last bid or increase, then
+/// restart it from `now`. A user's first bid is never held back.
+pub fn record_bid_time(poll: &Poll, position: &mut UserPollPosition, now: i64) -> Result<()> {
+    if poll.min_seconds_between_bids > 0 && position.last_bid_ts > 0 {
+        let next_bid_at = position
+            .last_bid_ts
+            .saturating_add(poll.min_seconds_between_bids);
+        require_ctx!(
+            now >= next_bid_at,
+            ErrorCode::BidCooldown,
+            now = now,
+            next_bid_at = next_bid_at
+        );
+    }
+    position.last_bid_ts = now;
+    Ok(())
+}
+
+/// Validate that a bid path without a `UserPollPosition` account can't get
+/// around the poll's bid cooldown
+pub fn validate_no_bid_cooldown(poll: &Poll) -> Result<()> {
+    require!(
+        poll.min_seconds_between_bids == 0,
+        ErrorCode::PositionRequired
+    );
+    Ok(())
+}
+
+/// Set or lift a poll's access gate before it settles
+pub fn set_gate(poll: &mut Poll, gate: Option<AccessGate>) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+    match gate {
+        Some(AccessGate::TokenHolding { min_amount, .. }) => {
+            require!(min_amount > 0, ErrorCode::InvalidAccessGate);
+        }
+        Some(AccessGate::Attestation { attestor }) => {
+            require!(attestor != Pubkey::default(), ErrorCode::InvalidAccessGate);
+        }
+        _ => {}
+    }
+
+    poll.gate = gate;
+    Ok(())
+}
+
+/// A token account offered as proof of holding a gate's token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenHolding {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+impl From<&TokenAccount> for TokenHolding {
+    fn from(account: &TokenAccount) -> Self {
+        Self {
+            owner: account.owner,
+            mint: account.mint,
+            amount: account.amount,
+        }
+    }
+}
+
+/// Leaf of `bettor` in an allowlist Merkle tree
+pub fn allowlist_leaf(bettor: &Pubkey) -> [u8; 32] {
+    hashv(&[b"yukti_allowlist", bettor.as_ref()]).to_bytes()
+}
+
+/// Parent of two allowlist tree nodes. The pair is hashed in sorted order, so
+/// proofs carry no left/right flags.
+pub fn allowlist_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[lo, hi]).to_bytes()
+}
+
+/// Validate that `bettor` may bid on the poll: anyone on an ungated poll, a
+/// bettor whose `proof` leads to the allowlist root, one whose `holding` has
+/// enough of the gate's token, or one whose `attestation` from the gate's
+/// attestor hasn't expired
+pub fn validate_gate(
+    poll: &Poll,
+    bettor: Pubkey,
+    proof: &[[u8; 32]],
+    holding: Option<TokenHolding>,
+    attestation: Option<&Attestation>,
+    now: i64,
+) -> Result<()> {
+    match poll.gate {
+        None => Ok(()),
+        Some(AccessGate::Allowlist { root }) => {
+            let computed = proof
+                .iter()
+                .fold(allowlist_leaf(&bettor), |node, sibling| allowlist_node(&node, sibling));
+            require!(computed == root, ErrorCode::NotAllowlisted);
+            Ok(())
+        }
+        Some(AccessGate::TokenHolding { mint, min_amount }) => {
+            let holding = holding.ok_or_else(|| error!(ErrorCode::GateTokenRequired))?;
+            require!(
+                holding.owner == bettor && holding.mint == mint,
+                ErrorCode::GateTokenRequired
+            );
+            require_ctx!(
+                holding.amount >= min_amount,
+                ErrorCode::InsufficientGateHolding,
+                amount = holding.amount,
+                min_amount = min_amount
+            );
+            Ok(())
+        }
+        Some(AccessGate::Attestation { attestor }) => {
+            let attestation = attestation.ok_or_else(|| error!(ErrorCode::AttestationRequired))?;
+            require!(
+                attestation.attestor == attestor && attestation.user == bettor,
+                ErrorCode::AttestationRequired
+            );
+            require_ctx!(
+                attestation.expires_at == 0 || now < attestation.expires_at,
+                ErrorCode::AttestationExpired,
+                expires_at = attestation.expires_at,
+                now = now
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Validate a new credential's expiry: 0 for none, or a time still ahead
+pub fn validate_attestation_expiry(expires_at: i64, now: i64) -> Result<()> {
+    require!(
+        expires_at == 0 || expires_at > now,
+        ErrorCode::InvalidAttestation
+    );
+    Ok(())
+}
+
+/// Validate that a bid path without a gate check can't get around the
+/// poll's access gate
+pub fn validate_ungated(poll: &Poll) -> Result<()> {
+    require!(poll.gate.is_none(), ErrorCode::GatedPoll);
+    Ok(())
+}
+
+/// Token-2022 extensions a stake mint may not carry: a transfer hook needs
+/// accounts the token instructions don't pass, a permanent delegate could
+/// empty the vault, and a non-transferable token can't be staked at all
+const UNSUPPORTED_MINT_EXTENSIONS: [ExtensionType; 3] = [
+    ExtensionType::TransferHook,
+    ExtensionType::PermanentDelegate,
+    ExtensionType::NonTransferable,
+];
+
+/// Validate a token market's mint from its account data: any classic SPL
+/// mint, or a Token-2022 mint without an unsupported extension
+pub fn validate_stake_mint(mint_data: &[u8]) -> Result<()> {
+    let mint = StateWithExtensions::<Mint2022>::unpack(mint_data)?;
+    let unsupported = mint
+        .get_extension_types()?
+        .iter()
+        .any(|extension| UNSUPPORTED_MINT_EXTENSIONS.contains(extension));
+    require!(!unsupported, ErrorCode::UnsupportedMint);
+    Ok(())
+}
+
+/// Whether a mint withholds a fee from transfers (Token-2022's transfer fee
+/// extension)
+pub fn has_transfer_fee(mint_data: &[u8]) -> Result<bool> {
+    let mint = StateWithExtensions::<Mint2022>::unpack(mint_data)?;
+    Ok(mint.get_extension::<TransferFeeConfig>().is_ok())
+}
+
+/// Fee a mint withholds from a transfer of `amount` during `epoch`, so the
+/// recipient gets `amount` less this; 0 for classic SPL mints and Token-2022
+/// mints without a transfer fee
+pub fn transfer_fee(mint_data: &[u8], amount: u64, epoch: u64) -> Result<u64> {
+    let mint = StateWithExtensions::<Mint2022>::unpack(mint_data)?;
+    Ok(match mint.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or(ErrorCode::MathOverflow)?,
+        Err(_) => 0,
+    })
+}
+
+/// Add `amount` to an active bid, priced at its outcome's current odds like a
+/// new bid. The bid's stake and potential win grow, and `odds_at_purchase`
+/// becomes the blended odds of the whole position. Returns the odds the
+/// increase was priced at.
+pub fn increase_bid(poll: &mut Poll, bid: &mut Bid, amount: u64, now: i64) -> Result<u64> {
+    require!(
+        bid.status == BidStatus::Active,
+        ErrorCode::BidAlreadyClaimed
+    );
+
+    // The whole bid keeps the less favorable of its fee adjustments
+    bid.fee_adjust_bps = bid.fee_adjust_bps.max(fee_adjust_bps(poll, now));
+    let (odds, potential_win) = price_bid(poll, amount, bid.option, now)?;
+    bid.amount = safe_add(bid.amount, amount)?;
+    bid.potential_win = safe_add(bid.potential_win, potential_win)?;
+    bid.odds_at_purchase =
+        Bps::try_from_ratio(bid.amount, bid.potential_win, Rounding::Down)?.raw();
+    Ok(odds)
+}
+
+/// Assign an active bid to another wallet before the poll is settled, e.g. to
+/// migrate keys or gift a position. The new bettor claims or exits it.
+pub fn transfer_bid(poll: &Poll, bid: &mut Bid, new_bettor: Pubkey) -> Result<()> {
+    require!(poll.status == PollStatus::Active, ErrorCode::PollNotActive);
+    validate_ungated(poll)?;
+    require!(
+        bid.status == BidStatus::Active,
+        ErrorCode::BidAlreadyClaimed
+    );
+    require!(
+        new_bettor != bid.bettor && new_bettor != Pubkey::default(),
+        ErrorCode::InvalidNewBettor
+    );
+
+    bid.bettor = new_bettor;
+    Ok(())
+}
+
+/// Opt a SOL poll with no bids into outcome tokens: bids mint an SPL token per
+/// outcome to the bettor instead of creating Bid accounts
+pub fn enable_outcome_tokens(poll: &mut Poll) -> Result<()> {
+    require!(
+        matches!(poll.status, PollStatus::Active | PollStatus::Scheduled),
+        ErrorCode::PollNotActive
+    );
+    require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+    require!(poll.mint.is_none(), ErrorCode::DenominationMismatch);
+    require_ctx!(
+        poll.next_bid_index == 0 && poll.total_pool == 0,
+        ErrorCode::PollHasBids,
+        next_bid_index = poll.next_bid_index,
+        total_pool = poll.total_pool
+    );
+
+    poll.outcome_tokens = true;
+    Ok(())
+}
+
+/// Record a bid on an outcome-token market. Returns the odds it was priced at
+/// and the outcome tokens to mint: a fixed-odds token is a claim on one unit of
+/// gross win, a pari-mutuel token one unit of stake in the outcome.
+pub fn record_outcome_token_bid(
+    poll: &mut Poll,
+    amount: u64,
+    option: u8,
+    now: i64,
+) -> Result<(u64, u64)> {
+    require!(poll.outcome_tokens, ErrorCode::NotOutcomeTokenMarket);
+    validate_outcome(poll, option)?;
+
+    let odds = bid_odds(poll, amount, option, now)?;
+    let potential_win = record_bid_at_odds(poll, amount, option, odds, now)?;
+    let tokens = match poll.payout_mode {
+        PayoutMode::FixedOdds => potential_win,
+        PayoutMode::PariMutuel => amount,
+    };
+    Ok((odds, tokens))
+}
+
+/// Redeem `tokens` of an outcome once its poll is final. Returns
+/// (payout, platform_fee): winning tokens pay like a bid of the same stake or
+/// potential win, and tokens of a cancelled or voided poll refund their share
+/// of the outcome's stake. Every token of an outcome redeems at the same rate.
+pub fn redeem_outcome_tokens(poll: &mut Poll, option: u8, tokens: u64) -> Result<(u64, u64)> {
+    require!(poll.outcome_tokens, ErrorCode::NotOutcomeTokenMarket);
+    require!(tokens > 0, ErrorCode::InvalidTokenAmount);
+    validate_outcome(poll, option)?;
+
+    match poll.status {
+        PollStatus::Settled => {
+            require!(poll.winner == Some(option), ErrorCode::BidDidNotWin);
+            let (payout, platform_fee) = settled_payout(poll, option, tokens, tokens, 0)?;
+            accrue_fee(poll, platform_fee)?;
+            Ok((payout, platform_fee))
+        }
+        PollStatus::Cancelled | PollStatus::Voided => {
+            let outcome = &poll.outcomes[option as usize];
+            let stake = match poll.payout_mode {
+                PayoutMode::FixedOdds => safe_mul_div(
+                    tokens,
+                    outcome.stake,
+                    outcome.total_potential_liability,
+                    Rounding::Down,
+                )?,
+                PayoutMode::PariMutuel => tokens,
+            };
+            Ok((refund_amount(poll, stake)?, 0))
+        }
+        _ => Err(ErrorCode::PollNotFinalized.into()),
+    }
+}
+
+/// Anti-sniping: a bid placed within the poll's extension window of the end
+/// pushes the end back by the extension duration, until the poll's extensions
+/// are used up
+pub fn extend_deadline(poll: &mut Poll, now: i64) -> Result<()> {
+    let Some(extension) = poll.deadline_extension else {
+        return Ok(());
+    };
+    if poll.extension_count < extension.max_extensions
+        && poll.end_timestamp - now <= extension.window
+    {
+        poll.end_timestamp = poll
+            .end_timestamp
+            .checked_add(extension.duration)
+            .ok_or(ErrorCode::MathOverflow)?;
+        poll.extension_count += 1;
+    }
+    Ok(())
+}
+
+/// Sell a bid back to the pool before the poll ends. The position is worth
+/// its `potential_win` at the outcome's current odds; the poll's platform fee
+/// is taken from that value and accrued. The bid's stake and liability leave
+/// the outcome and odds are repriced. Fixed-odds polls must still cover every
+/// outcome's locked-in wins afterwards. Returns (payout, exit_fee).
+pub fn exit_position(
+    poll: &mut Poll,
+    option: u8,
+    amount: u64,
+    potential_win: u64,
+    now: i64,
+) -> Result<(u64, u64)> {
+    require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+    require!(
+        poll.status == PollStatus::Active,
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        now < poll.end_timestamp,
+        ErrorCode::PollEnded,
+        now = now,
+        end_timestamp = poll.end_timestamp
+    );
+    validate_outcome(poll, option)?;
+
+    let outcome = &mut poll.outcomes[option as usize];
+    let value = Bps::new(outcome.odds).try_apply(potential_win, Rounding::Down)?;
+    require_ctx!(
+        value <= poll.total_pool,
+        ErrorCode::InsufficientPoolLiquidity,
+        value = value,
+        total_pool = poll.total_pool
+    );
+    let total_pool = safe_sub(poll.total_pool, value)?;
+    outcome.stake = safe_sub(outcome.stake, amount)?;
+    outcome.total_potential_liability =
+        safe_sub(outcome.total_potential_liability, potential_win)?;
+    if poll.payout_mode == PayoutMode::FixedOdds {
+        let liability = poll
+            .outcomes
+            .iter()
+            .map(|outcome| outcome.total_potential_liability)
+            .max()
+            .unwrap_or(0);
+        require_ctx!(
+            liability <= safe_add(total_pool, poll.lp_liquidity)?,
+            ErrorCode::InsufficientPoolLiquidity,
+            value = value,
+            liability = liability,
+            total_pool = total_pool,
+            lp_liquidity = poll.lp_liquidity
+        );
+    }
+
+    let (payout, exit_fee) = split_platform_fee(value, poll.fee_bps)?;
+    poll.total_pool = total_pool;
+    accrue_fee(poll, exit_fee)?;
+    uncount_bid(poll, option);
+    reprice(poll, now)?;
+
+    Ok((payout, exit_fee))
+}
+
+/// Set how long after placing a bid its bettor may cancel it, and the fee
+/// taken from the stake; a window of 0 turns cancellation off
+pub fn set_cancel_window(poll: &mut Poll, cancel_window: i64, cancel_fee_bps: u16) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        (0..=MAX_CANCEL_WINDOW).contains(&cancel_window) && cancel_fee_bps <= MAX_CANCEL_FEE_BPS,
+        ErrorCode::InvalidCancelWindow,
+        cancel_window = cancel_window,
+        cancel_fee_bps = cancel_fee_bps,
+        max_window = MAX_CANCEL_WINDOW,
+        max_fee_bps = MAX_CANCEL_FEE_BPS
+    );
+
+    poll.cancel_window = cancel_window;
+    poll.cancel_fee_bps = cancel_fee_bps;
+    Ok(())
+}
+
+/// Undo a bid placed within the poll's cancel window: its stake leaves the
+/// pool and its liability the outcome, and odds are repriced. The poll's
+/// `cancel_fee_bps` of the stake (rounded up) is accrued like a platform
+/// fee. Fixed-odds polls must still cover every outcome's locked-in wins
+/// afterwards. Returns (refund, cancel_fee).
+pub fn cancel_position(poll: &mut Poll, bid: &Bid, now: i64) -> Result<(u64, u64)> {
+    require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+    require!(
+        poll.status == PollStatus::Active,
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        now < poll.end_timestamp,
+        ErrorCode::PollEnded,
+        now = now,
+        end_timestamp = poll.end_timestamp
+    );
+    // Bids from before version 5 have no placement time
+    require_ctx!(
+        poll.cancel_window > 0
+            && bid.placed_at > 0
+            && now
+                <= bid
+                    .placed_at
+                    .checked_add(poll.cancel_window)
+                    .ok_or(ErrorCode::MathOverflow)?,
+        ErrorCode::CancelWindowClosed,
+        placed_at = bid.placed_at,
+        cancel_window = poll.cancel_window,
+        now = now
+    );
+    validate_outcome(poll, bid.option)?;
+
+    // Exits can leave the pool short of the stakes
+    require_ctx!(
+        bid.amount <= poll.total_pool,
+        ErrorCode::InsufficientPoolLiquidity,
+        amount = bid.amount,
+        total_pool = poll.total_pool
+    );
+    let total_pool = safe_sub(poll.total_pool, bid.amount)?;
+    let outcome = &mut poll.outcomes[bid.option as usize];
+    outcome.stake = safe_sub(outcome.stake, bid.amount)?;
+    outcome.total_potential_liability =
+        safe_sub(outcome.total_potential_liability, bid.potential_win)?;
+    if poll.payout_mode == PayoutMode::FixedOdds {
+        let liability = poll
+            .outcomes
+            .iter()
+            .map(|outcome| outcome.total_potential_liability)
+            .max()
+            .unwrap_or(0);
+        require_ctx!(
+            liability <= safe_add(total_pool, poll.lp_liquidity)?,
+            ErrorCode::InsufficientPoolLiquidity,
+            amount = bid.amount,
+            liability = liability,
+            total_pool = total_pool,
+            lp_liquidity = poll.lp_liquidity
+        );
+    }
+
+    let (refund, cancel_fee) = split_platform_fee(bid.amount, poll.cancel_fee_bps)?;
+    poll.total_pool = total_pool;
+    accrue_fee(poll, cancel_fee)?;
+    uncount_bid(poll, bid.option);
+    reprice(poll, now)?;
+
+    Ok((refund, cancel_fee))
+}
+
+/// Accrue a platform fee taken by a claim or exit, counting it in
+/// `total_fees`: the poll creator's `creator_fee_bps` share goes to
+/// `creator_fees`, the LPs' `lp_fee_bps` share to `lp_fees` if the poll has
+/// liquidity (both rounded down), and the rest to `accrued_fees` for the
+/// treasury
+pub fn accrue_fee(poll: &mut Poll, fee: u64) -> Result<()> {
+    let creator_fee = Bps::new(poll.creator_fee_bps.into()).try_apply(fee, Rounding::Down)?;
+    let lp_fee = if poll.lp_liquidity > 0 {
+        Bps::new(poll.lp_fee_bps.into()).try_apply(fee, Rounding::Down)?
+    } else {
+        0
+    };
+    let treasury_fee = safe_sub(safe_sub(fee, creator_fee)?, lp_fee)?;
+    poll.total_fees = safe_add(poll.total_fees, fee)?;
+    poll.creator_fees = safe_add(poll.creator_fees, creator_fee)?;
+    poll.lp_fees = safe_add(poll.lp_fees, lp_fee)?;
+    poll.accrued_fees = safe_add(poll.accrued_fees, treasury_fee)?;
+    Ok(())
+}
+
+/// Accrue the platform fee of a referred bid's claim as `accrue_fee` does, less
+/// the referrer's `referral_fee_bps` share (rounded down), which comes out of
+/// the treasury's part. Returns the referrer's share for the caller to pay.
+pub fn accrue_referred_fee(poll: &mut Poll, fee: u64) -> Result<u64> {
+    let referral_fee = Bps::new(poll.referral_fee_bps.into()).try_apply(fee, Rounding::Down)?;
+    accrue_fee(poll, fee)?;
+    poll.accrued_fees = safe_sub(poll.accrued_fees, referral_fee)?;
+    Ok(referral_fee)
+}
+
+/// Validate a bid's referrer: bettors can't refer themselves
+pub fn validate_referrer(referrer: Option<Pubkey>, bettor: Pubkey) -> Result<()> {
+    require!(referrer != Some(bettor), ErrorCode::InvalidReferrer);
+    Ok(())
+}
+
+/// The insurance fund's `fee_bps` share (rounded down) of `amount` swept
+/// fees; the rest goes to the treasury
+pub fn insurance_share(amount: u64, fee_bps: u16) -> Result<u64> {
+    Bps::new(fee_bps.into()).try_apply(amount, Rounding::Down)
+}
+
+/// Grant a new fee share of `share_bps` on a SOL poll, keeping the poll's
+/// grants within the whole sweep. Returns the poll's `swept_fees` so far,
+/// which the share doesn't earn from.
+pub fn grant_fee_share(poll: &mut Poll, share_bps: u16) -> Result<u64> {
+    // Token markets sweep to the treasury's token account, not the fee vault
+    require!(poll.mint.is_none(), ErrorCode::DenominationMismatch);
+    let total = u64::from(poll.fee_share_bps) + u64::from(share_bps);
+    require_ctx!(
+        share_bps > 0 && total <= BPS_DENOMINATOR,
+        ErrorCode::InvalidFeeShare,
+        share_bps = share_bps,
+        granted = poll.fee_share_bps
+    );
+
+    poll.fee_share_bps = total as u16;
+    Ok(poll.swept_fees)
+}
+
+/// Claim what `share` has earned since its last claim: its `share_bps`
+/// (rounded down) of everything the poll swept since the grant. Returns the
+/// amount, which the caller pays out of the fee vault.
+pub fn claim_fee_share(share: &mut FeeShare, swept_fees: u64) -> Result<u64> {
+    let earned = Bps::new(share.share_bps.into())
+        .try_apply(swept_fees.saturating_sub(share.swept_at_grant), Rounding::Down)?;
+
+    let amount = earned.saturating_sub(share.claimed);
+    require!(amount > 0, ErrorCode::NoFeesToSweep);
+
+    share.claimed = earned;
+    Ok(amount)
+}
+
+/// Hold back in the fee vault the part of a sweep of `swept` from `poll` that
+/// its fee shares earn, rounded up so the reserve covers every share's claim
+pub fn reserve_fee_shares(vault: &mut FeeVault, poll: &Poll, swept: u64) -> Result<()> {
+    let owed = fee(swept, Bps::new(poll.fee_share_bps.into()))?;
+    vault.fee_share_reserve = safe_add(vault.fee_share_reserve, owed)?;
+    Ok(())
+}
+
+/// Release `amount` paid to a fee share from the fee vault's reserve
+pub fn release_fee_share(vault: &mut FeeVault, amount: u64) {
+    vault.fee_share_reserve = vault.fee_share_reserve.saturating_sub(amount);
+}
+
+/// What a treasury withdrawal can take from a fee vault holding `available`
+/// above rent: everything not reserved for fee shares
+pub fn treasury_available(vault: &FeeVault, available: u64) -> u64 {
+    available.saturating_sub(vault.fee_share_reserve)
+}
+
+/// Count a sweep of `amount` fees from the poll at `key`, `insurance` of them
+/// paid to the insurance fund, in the treasury ledger and the poll's
+/// `treasury_swept`
+pub fn record_sweep(
+    stats: &mut TreasuryStats,
+    poll: &mut Poll,
+    key: Pubkey,
+    amount: u64,
+    insurance: u64,
+    now: i64,
+) -> Result<()> {
+    poll.treasury_swept = safe_add(poll.treasury_swept, amount)?;
+    stats.total_fees = safe_add(stats.total_fees, amount)?;
+    stats.total_insurance = safe_add(stats.total_insurance, insurance)?;
+    stats.sweep_count = safe_add(stats.sweep_count, 1)?;
+    push_history(
+        &mut stats.recent_sweeps,
+        FeeSweep {
+            poll: key,
+            amount,
+            timestamp: now,
+        },
+    );
+    Ok(())
+}
+
+/// Count the `amount` left in a poll's vault when it expired, closed, or was
+/// finalized, moved to the fee vault, like a sweep without insurance. An
+/// empty vault isn't counted.
+pub fn record_remainder(
+    stats: &mut TreasuryStats,
+    poll: &mut Poll,
+    key: Pubkey,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    record_sweep(stats, poll, key, amount, 0, now)?;
+    stats.total_remainders = safe_add(stats.total_remainders, amount)?;
+    Ok(())
+}
+
+/// Append to a `TreasuryStats` history, dropping its oldest entry when full
+fn push_history<T>(history: &mut Vec<T>, entry: T) {
+    if history.len() == TREASURY_HISTORY_LEN {
+        history.remove(0);
+    }
+    history.push(entry);
+}
+
+/// Set the treasury timelock. It can only go up, so a stolen admin key can't
+/// drop it before withdrawing.
+pub fn set_treasury_timelock(stats: &mut TreasuryStats, withdraw_delay: i64) -> Result<()> {
+    require_ctx!(
+        withdraw_delay >= stats.withdraw_delay && withdraw_delay <= MAX_TREASURY_DELAY,
+        ErrorCode::InvalidTreasuryTimelock,
+        withdraw_delay = withdraw_delay,
+        current = stats.withdraw_delay
+    );
+    stats.withdraw_delay = withdraw_delay;
+    Ok(())
+}
+
+/// Queue a withdrawal of `amount` to `destination`, one at a time. Only the
+/// config's `treasury` can be paid, and changing it waits out the same
+/// timelock (`queue_treasury_change`), so a stolen admin key can't send the
+/// fees elsewhere before the timelock is up. Returns when it unlocks: `now`
+/// without a timelock.
+pub fn queue_treasury_withdrawal(
+    stats: &mut TreasuryStats,
+    amount: u64,
+    destination: Pubkey,
+    treasury: Pubkey,
+    now: i64,
+) -> Result<i64> {
+    require!(amount > 0, ErrorCode::InvalidTreasuryWithdrawal);
+    require!(destination == treasury, ErrorCode::InvalidTreasury);
+    require!(stats.pending.is_none(), ErrorCode::TreasuryWithdrawalPending);
+
+    let unlocks_at = now.saturating_add(stats.withdraw_delay);
+    stats.pending = Some(TreasuryWithdrawal {
+        amount,
+        destination,
+        timestamp: unlocks_at,
+    });
+    Ok(unlocks_at)
+}
+
+/// Take the queued withdrawal once it unlocks and record it as paid to
+/// `destination`. Returns the amount to pay.
+pub fn execute_treasury_withdrawal(
+    stats: &mut TreasuryStats,
+    destination: Pubkey,
+    now: i64,
+) -> Result<u64> {
+    let pending = stats.pending.ok_or(ErrorCode::NoTreasuryWithdrawal)?;
+    require!(
+        pending.destination == destination,
+        ErrorCode::TreasuryDestinationMismatch
+    );
+    require_ctx!(
+        now >= pending.timestamp,
+        ErrorCode::TreasuryTimelocked,
+        unlocks_at = pending.timestamp,
+        now = now
+    );
+
+    stats.pending = None;
+    stats.total_withdrawn = safe_add(stats.total_withdrawn, pending.amount)?;
+    push_history(
+        &mut stats.withdrawals,
+        TreasuryWithdrawal {
+            timestamp: now,
+            ..pending
+        },
+    );
+    Ok(pending.amount)
+}
+
+/// Drop the queued withdrawal, returning it
+pub fn cancel_treasury_withdrawal(stats: &mut TreasuryStats) -> Result<TreasuryWithdrawal> {
+    let withdrawal = stats.pending.take().ok_or(ErrorCode::NoTreasuryWithdrawal)?;
+    Ok(withdrawal)
+}
+
+/// Queue a change of the config's treasury from `current` to `treasury`, one
+/// at a time, behind the withdrawal timelock. Returns when it unlocks: `now`
+/// without a timelock.
+pub fn queue_treasury_change(
+    stats: &mut TreasuryStats,
+    treasury: Pubkey,
+    current: Pubkey,
+    now: i64,
+) -> Result<i64> {
+    require!(treasury != current, ErrorCode::InvalidTreasury);
+    require!(stats.pending_treasury.is_none(), ErrorCode::TreasuryChangePending);
+
+    let unlocks_at = now.saturating_add(stats.withdraw_delay);
+    stats.pending_treasury = Some(TreasuryChange {
+        treasury,
+        unlocks_at,
+    });
+    Ok(unlocks_at)
+}
+
+/// Take the queued treasury change once it unlocks. Returns the new treasury.
+pub fn execute_treasury_change(stats: &mut TreasuryStats, now: i64) -> Result<Pubkey> {
+    let pending = stats.pending_treasury.ok_or(ErrorCode::NoTreasuryChange)?;
+    require_ctx!(
+        now >= pending.unlocks_at,
+        ErrorCode::TreasuryTimelocked,
+        unlocks_at = pending.unlocks_at,
+        now = now
+    );
+
+    stats.pending_treasury = None;
+    Ok(pending.treasury)
+}
+
+/// Set up a `UserBalance` created by `init_if_needed`; a no-op on later
+/// deposits
+pub fn init_balance(balance: &mut UserBalance, user: Pubkey, bump: u8) {
+    if balance.user == Pubkey::default() {
+        balance.user = user;
+        balance.bump = bump;
+    }
+}
+
+/// Record a deposit of `amount` into a user's balance
+pub fn credit_balance(balance: &mut UserBalance, amount: u64) -> Result<()> {
+    balance.balance = safe_add(balance.balance, amount)?;
+    Ok(())
+}
+
+/// Take `amount` out of a user's balance for a withdrawal or a stake
+pub fn debit_balance(balance: &mut UserBalance, amount: u64) -> Result<()> {
+    require_ctx!(
+        amount <= balance.balance,
+        ErrorCode::InsufficientBalance,
+        amount = amount,
+        balance = balance.balance
+    );
+    balance.balance -= amount;
+    Ok(())
+}
+
+/// Fill in a new `BetCredit`; an existing one is left untouched
+pub fn init_credit(credit: &mut BetCredit, user: Pubkey, bump: u8) {
+    if credit.user == Pubkey::default() {
+        credit.user = user;
+        credit.bump = bump;
+    }
+}
+
+/// Lamports the admin must pay into the promo vault to back a grant of
+/// `amount`, given `available` above its rent. Spare lamports not already
+/// backing outstanding credit count first.
+pub fn credit_top_up(vault: &PromoVault, available: u64, amount: u64) -> u64 {
+    let spare = available.saturating_sub(vault.outstanding);
+    amount.saturating_sub(spare)
+}
+
+/// Record a grant of `amount` to a user's credit
+pub fn grant_credit(vault: &mut PromoVault, credit: &mut BetCredit, amount: u64) -> Result<()> {
+    credit.balance = safe_add(credit.balance, amount)?;
+    credit.granted = safe_add(credit.granted, amount)?;
+    vault.outstanding = safe_add(vault.outstanding, amount)?;
+    vault.total_granted = safe_add(vault.total_granted, amount)?;
+    Ok(())
+}
+
+/// Take `amount` out of a user's credit for a stake
+pub fn spend_credit(vault: &mut PromoVault, credit: &mut BetCredit, amount: u64) -> Result<()> {
+    require_ctx!(
+        amount <= credit.balance,
+        ErrorCode::InsufficientCredit,
+        amount = amount,
+        balance = credit.balance
+    );
+    credit.balance -= amount;
+    vault.outstanding -= amount;
+    Ok(())
+}
+
+/// The part of a `payment` on a credit bid that repays its principal to the
+/// promo vault, taken off what the bid still owes
+pub fn return_credit(vault: &mut PromoVault, bid: &mut Bid, payment: u64) -> Result<u64> {
+    let returned = payment.min(bid.credit);
+    bid.credit -= returned;
+    vault.total_returned = safe_add(vault.total_returned, returned)?;
+    Ok(returned)
+}
+
+/// Lamports the insurance fund must add to a vault holding `vault_balance`
+/// to pay `payout`, given `available` free in the fund; fails if the fund
+/// can't make up the difference
+pub fn insurance_cover(vault_balance: u64, payout: u64, available: u64) -> Result<u64> {
+    let shortfall = payout.saturating_sub(vault_balance);
+    require_ctx!(
+        shortfall <= available,
+        ErrorCode::VaultShortfall,
+        shortfall = shortfall,
+        available = available
+    );
+    Ok(shortfall)
+}
+
+/// Record `amount` of LP liquidity deposited into a fixed-odds poll before it
+/// ends. Liquidity backs locked-in wins alongside the bettors' pool but is
+/// not staked on any outcome, so it leaves the odds unchanged.
+pub fn provide_liquidity(poll: &mut Poll, amount: u64, now: i64) -> Result<()> {
+    require!(
+        matches!(poll.status, PollStatus::Active | PollStatus::Scheduled),
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        now < poll.end_timestamp,
+        ErrorCode::PollEnded,
+        now = now,
+        end_timestamp = poll.end_timestamp
+    );
+    // Pari-mutuel winners split the whole pool; there is nothing to back
+    require!(
+        poll.payout_mode == PayoutMode::FixedOdds,
+        ErrorCode::LiquidityPayoutMode
+    );
+    require!(amount > 0, ErrorCode::InvalidLiquidityAmount);
+
+    poll.lp_liquidity = safe_add(poll.lp_liquidity, amount)?;
+    Ok(())
+}
+
+/// What the LPs of a finalized poll own once every bettor is paid: the pool
+/// plus liquidity, less the winning outcome's locked-in wins (settled) or the
+/// outstanding stakes (cancelled or voided). Losses come out of this first.
+pub fn lp_equity(poll: &Poll) -> Result<u64> {
+    let backing = safe_add(poll.total_pool, poll.lp_liquidity)?;
+    let owed = match (poll.status, poll.winner) {
+        (PollStatus::Settled, Some(winner)) => {
+            poll.outcomes[winner as usize].total_potential_liability
+        }
+        (PollStatus::Cancelled | PollStatus::Voided, _) => staked(poll)?,
+        _ => return err!(ErrorCode::PollNotFinalized),
+    };
+    Ok(backing.saturating_sub(owed))
+}
+
+/// Amount an LP position can withdraw now: its share of the LP equity on the
+/// first withdrawal, plus its share of LP fees accrued since its last one.
+/// Updates the position; fails if there is nothing to withdraw.
+pub fn lp_withdrawal(poll: &Poll, position: &mut LpPosition) -> Result<u64> {
+    let equity = if position.withdrawn {
+        0
+    } else {
+        safe_mul_div(lp_equity(poll)?, position.amount, poll.lp_liquidity, Rounding::Down)?
+    };
+    let fees_earned =
+        safe_mul_div(poll.lp_fees, position.amount, poll.lp_liquidity, Rounding::Down)?;
+    let fees = fees_earned.saturating_sub(position.fees_claimed);
+
+    let amount = safe_add(equity, fees)?;
+    require!(amount > 0, ErrorCode::NothingToWithdraw);
+    position.withdrawn = true;
+    position.fees_claimed = fees_earned;
+    Ok(amount)
+}
+
+/// Refund of a bid of `amount` on a cancelled poll. Profitable exits can leave
+/// the pool and LP liquidity short of the remaining stakes, in which case
+/// every refund is scaled down by the same ratio (rounded down).
+pub fn refund_amount(poll: &Poll, amount: u64) -> Result<u64> {
+    let staked = staked(poll)?;
+    // LP liquidity absorbs the shortfall first
+    let backing = safe_add(poll.total_pool, poll.lp_liquidity)?;
+    if backing >= staked {
+        Ok(amount)
+    } else {
+        safe_mul_div(amount, backing, staked, Rounding::Down)
+    }
+}
+
+/// Split a refund that `refund_batch` pushes to a bettor into the bettor's
+/// share and the cranker's tip, which never exceeds the refund
+pub fn refund_tip(refund: u64) -> (u64, u64) {
+    let tip = REFUND_TIP.min(refund);
+    (refund - tip, tip)
+}
+
+/// Sum of the stakes still held by bids across every outcome
+fn staked(poll: &Poll) -> Result<u64> {
+    poll.outcomes
+        .iter()
+        .try_fold(0, |sum, outcome| safe_add(sum, outcome.stake))
+}
+
+/// Close an active, ended poll with outcome `winning_option` as the winner.
+/// Polls with a dispute period only propose the winner; they stay
+/// `PendingSettlement` until the window closes or a challenge is resolved.
+pub fn settle(poll: &mut Poll, winning_option: u8, now: i64) -> Result<()> {
+    open_scheduled(poll, now)?;
+    require!(
+        poll.status == PollStatus::Active,
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        now >= poll.end_timestamp,
+        ErrorCode::PollNotEnded,
+        now = now,
+        end_timestamp = poll.end_timestamp
+    );
+    validate_outcome(poll, winning_option)?;
+    require!(
+        poll.settlement_commitment.is_none(),
+        ErrorCode::SettlementAwaitingReveal
+    );
+
+    poll.winner = Some(winning_option);
+    if poll.dispute_period > 0 {
+        poll.status = PollStatus::PendingSettlement;
+        poll.settlement_proposed_at = now;
+    } else {
+        poll.status = PollStatus::Settled;
+        poll.finalized_at = now;
+    }
+
+    Ok(())
+}
+
+/// Validate that the poll's authority can still hand its settlement to a
+/// resolver council or result signer: once, before the first bid. Oracle and
+/// adapter polls settle without their authority, so they can't.
+fn validate_settler_change(poll: &Poll) -> Result<()> {
+    require!(
+        matches!(poll.status, PollStatus::Active | PollStatus::Scheduled),
+        ErrorCode::PollNotActive
+    );
+    require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+    require!(poll.resolution.is_none(), ErrorCode::OracleMarket);
+    require!(
+        poll.council.is_none() && poll.result_signer.is_none(),
+        ErrorCode::SettlerAlreadySet
+    );
+    require_ctx!(
+        poll.next_bid_index == 0 && poll.total_pool == 0,
+        ErrorCode::PollHasBids,
+        next_bid_index = poll.next_bid_index,
+        total_pool = poll.total_pool
+    );
+    Ok(())
+}
+
+/// Hand a poll with no bids to a resolver council
+pub fn set_resolvers(poll: &mut Poll, resolvers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+    validate_settler_change(poll)?;
+    require_ctx!(
+        (1..=MAX_RESOLVERS).contains(&resolvers.len())
+            && (1..=resolvers.len()).contains(&(threshold as usize)),
+        ErrorCode::InvalidResolvers,
+        count = resolvers.len(),
+        threshold = threshold
+    );
+    for (i, resolver) in resolvers.iter().enumerate() {
+        require!(
+            !resolvers[..i].contains(resolver) && *resolver != Pubkey::default(),
+            ErrorCode::InvalidResolvers
+        );
+    }
+
+    poll.council = Some(ResolverCouncil {
+        votes: vec![None; resolvers.len()],
+        resolvers,
+        threshold,
+    });
+    Ok(())
+}
+
+/// Hand a poll with no bids to a result signer: it settles only with the
+/// signer's signed result
+pub fn set_result_signer(poll: &mut Poll, signer: Pubkey) -> Result<()> {
+    validate_settler_change(poll)?;
+    require!(signer != Pubkey::default(), ErrorCode::InvalidResultSignature);
+
+    poll.result_signer = Some(signer);
+    Ok(())
+}
+
+/// Validate that the poll's authority may settle it alone
+pub fn validate_authority_settlement(poll: &Poll) -> Result<()> {
+    require!(poll.council.is_none(), ErrorCode::ResolverVoteRequired);
+    require!(
+        poll.result_signer.is_none(),
+        ErrorCode::ResultSignatureRequired
+    );
+    Ok(())
+}
+
+/// Validate the ID of a new event against its stored capacity
+pub fn validate_event_id(event_id: &str) -> Result<()> {
+    require_ctx!(
+        event_id.len() <= 64,
+        ErrorCode::EventIdTooLong,
+        len = event_id.len(),
+        max = 64
+    );
+    Ok(())
+}
+
+/// Validate a resolution note's evidence URI; the note can only be written
+/// while the poll awaits settlement
+pub fn validate_resolution_note(poll: &Poll, uri: &str) -> Result<()> {
+    require!(event_poll_unresolved(poll), ErrorCode::PollNotActive);
+    require_ctx!(
+        (1..=MAX_METADATA_URI_LEN).contains(&uri.len()),
+        ErrorCode::InvalidResolutionNote,
+        len = uri.len(),
+        max = MAX_METADATA_URI_LEN
+    );
+    Ok(())
+}
+
+/// Whether an event's poll still awaits a settlement: `settle_event` settles
+/// these and skips polls already settled, cancelled, or voided on their own
+pub fn event_poll_unresolved(poll: &Poll) -> bool {
+    matches!(
+        poll.status,
+        PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+    )
+}
+
+/// Add the unresolved poll `key` to an open event with room for it
+pub fn add_event_poll(event: &mut MarketEvent, key: Pubkey, poll: &Poll) -> Result<()> {
+    require!(event.status == EventStatus::Open, ErrorCode::EventSettled);
+    require!(event_poll_unresolved(poll), ErrorCode::PollNotActive);
+    require!(!event.polls.contains(&key), ErrorCode::PollAlreadyInEvent);
+    require_ctx!(
+        event.polls.len() < MAX_EVENT_POLLS,
+        ErrorCode::EventFull,
+        count = event.polls.len(),
+        max = MAX_EVENT_POLLS
+    );
+
+    event.polls.push(key);
+    Ok(())
+}
+
+/// Validate that `signed` is the poll's result signer settling it with
+/// `winning_option`
+pub fn validate_signed_result(
+    poll: &Poll,
+    poll_key: Pubkey,
+    winning_option: u8,
+    signed: &SignedMessage,
+) -> Result<()> {
+    require!(
+        poll.result_signer.is_some(),
+        ErrorCode::NoResultSigner
+    );
+    require!(
+        poll.result_signer == Some(signed.signer)
+            && signed.message == result_message(&poll_key, winning_option),
+        ErrorCode::InvalidResultSignature
+    );
+    Ok(())
+}
+
+/// Record `resolver`'s vote for the winner of an ended council poll, replacing
+/// any earlier vote, and settle it once `threshold` votes agree. Returns how
+/// many resolvers now vote for `winning_option`.
+pub fn vote_settlement(
+    poll: &mut Poll,
+    resolver: Pubkey,
+    winning_option: u8,
+    now: i64,
+) -> Result<u8> {
+    open_scheduled(poll, now)?;
+    require!(
+        poll.status == PollStatus::Active,
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        now >= poll.end_timestamp,
+        ErrorCode::PollNotEnded,
+        now = now,
+        end_timestamp = poll.end_timestamp
+    );
+    validate_outcome(poll, winning_option)?;
+
+    let council = poll
+        .council
+        .as_mut()
+        .ok_or_else(|| error!(ErrorCode::NotResolver))?;
+    let seat = council
+        .resolvers
+        .iter()
+        .position(|key| *key == resolver)
+        .ok_or_else(|| error!(ErrorCode::NotResolver))?;
+    council.votes[seat] = Some(winning_option);
+    let votes = council
+        .votes
+        .iter()
+        .filter(|vote| **vote == Some(winning_option))
+        .count() as u8;
+
+    if votes >= council.threshold {
+        settle(poll, winning_option, now)?;
+    }
+    Ok(votes)
+}
+
+/// Record `bid`'s stake as a vote for `option` on a poll its authority left
+/// unsettled for `COMMUNITY_VOTE_DELAY` after it ended. Once the votes reach
+/// `COMMUNITY_QUORUM_BPS` of the pool and `option` holds
+/// `COMMUNITY_SUPERMAJORITY_BPS` of them, the poll is settled for `option`,
+/// final at once: the authority it replaces would decide any challenge.
+/// Returns the stake now voting for `option`.
+pub fn vote_outcome(poll: &mut Poll, bid: &Bid, option: u8, now: i64) -> Result<u64> {
+    open_scheduled(poll, now)?;
+    require!(
+        matches!(poll.status, PollStatus::Active | PollStatus::Suspended),
+        ErrorCode::PollNotActive
+    );
+    let opens_at = poll.end_timestamp.saturating_add(COMMUNITY_VOTE_DELAY);
+    require_ctx!(
+        now >= opens_at,
+        ErrorCode::CommunityVoteTooEarly,
+        now = now,
+        opens_at = opens_at
+    );
+    validate_outcome(poll, option)?;
+    require!(bid.status == BidStatus::Active, ErrorCode::BidCannotVote);
+
+    if poll.community_votes.is_empty() {
+        poll.community_votes = vec![0; poll.outcomes.len()];
+    }
+    let tally = &mut poll.community_votes[usize::from(option)];
+    *tally = safe_add(*tally, bid.amount)?;
+    let votes = *tally;
+
+    let total = poll
+        .community_votes
+        .iter()
+        .try_fold(0, |total, &votes| safe_add(total, votes))?;
+    let quorum = Bps::new(COMMUNITY_QUORUM_BPS).try_apply(poll.total_pool, Rounding::Up)?;
+    let supermajority = Bps::new(COMMUNITY_SUPERMAJORITY_BPS).try_apply(total, Rounding::Up)?;
+    if total >= quorum && votes >= supermajority {
+        // A commitment its authority never revealed no longer matters
+        poll.settlement_commitment = None;
+        poll.winner = Some(option);
+        poll.status = PollStatus::Settled;
+        poll.finalized_at = now;
+    }
+    Ok(votes)
+}
+
+/// Validate that a poll is resolved for good, so accounts that only matter
+/// while it's open (community votes, odds history) can be closed
+pub fn validate_resolved(poll: &Poll) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Settled
+                | PollStatus::Cancelled
+                | PollStatus::Voided
+                | PollStatus::Expired
+                | PollStatus::Finalized
+                | PollStatus::Closed
+        ),
+        ErrorCode::PollNotFinalized
+    );
+    Ok(())
+}
+
+/// Settle an active, ended poll as a draw: no outcome wins and every bid is
+/// refunded its stake. Voids are final immediately, with no dispute window.
+pub fn settle_void(poll: &mut Poll, now: i64) -> Result<()> {
+    open_scheduled(poll, now)?;
+    require!(
+        poll.status == PollStatus::Active,
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        now >= poll.end_timestamp,
+        ErrorCode::PollNotEnded,
+        now = now,
+        end_timestamp = poll.end_timestamp
+    );
+    require!(
+        poll.settlement_commitment.is_none(),
+        ErrorCode::SettlementAwaitingReveal
+    );
+
+    poll.status = PollStatus::Voided;
+    poll.winner = None;
+    poll.finalized_at = now;
+    Ok(())
+}
+
+/// Commitment to a settlement: `sha256(winning_option || salt)`
+pub fn settlement_hash(winning_option: u8, salt: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&[winning_option], salt.as_slice()]).to_bytes()
+}
+
+/// Commit to the winner of an active, ended poll without revealing it.
+/// Committing again replaces the commitment and restarts the reveal delay.
+pub fn commit_settlement(poll: &mut Poll, commitment: [u8; 32], now: i64) -> Result<()> {
+    open_scheduled(poll, now)?;
+    require!(
+        poll.status == PollStatus::Active,
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        now >= poll.end_timestamp,
+        ErrorCode::PollNotEnded,
+        now = now,
+        end_timestamp = poll.end_timestamp
+    );
+
+    poll.settlement_commitment = Some(commitment);
+    poll.committed_at = now;
+    Ok(())
+}
+
+/// Settle a poll with the winner it committed to, once
+/// `SETTLEMENT_REVEAL_DELAY` has passed since the commitment
+pub fn reveal_settlement(
+    poll: &mut Poll,
+    winning_option: u8,
+    salt: &[u8; 32],
+    now: i64,
+) -> Result<()> {
+    let commitment = poll
+        .settlement_commitment
+        .ok_or(ErrorCode::NoSettlementCommitment)?;
+    let reveal_at = poll.committed_at.saturating_add(SETTLEMENT_REVEAL_DELAY);
+    require_ctx!(
+        now >= reveal_at,
+        ErrorCode::RevealTooEarly,
+        now = now,
+        reveal_at = reveal_at
+    );
+    require!(
+        settlement_hash(winning_option, salt) == commitment,
+        ErrorCode::SettlementHashMismatch
+    );
+
+    poll.settlement_commitment = None;
+    settle(poll, winning_option, now)
+}
+
+/// Check that anyone may settle a poll through `crank_settle`: it has an
+/// on-chain result (an oracle or a settlement commitment) and
+/// `CRANK_GRACE_PERIOD` has passed since it ended, so the authority gets the
+/// first chance to settle
+pub fn validate_crank(poll: &Poll, now: i64) -> Result<()> {
+    require!(
+        poll.resolution.is_some() || poll.settlement_commitment.is_some(),
+        ErrorCode::NoOnChainResolution
+    );
+    let crank_at = poll.end_timestamp.saturating_add(CRANK_GRACE_PERIOD);
+    require_ctx!(
+        now >= crank_at,
+        ErrorCode::CrankTooEarly,
+        now = now,
+        crank_at = crank_at
+    );
+    Ok(())
+}
+
+/// Suspend betting on an active poll. A scheduled poll whose start time has
+/// passed is opened first.
+pub fn suspend(poll: &mut Poll, now: i64) -> Result<()> {
+    open_scheduled(poll, now)?;
+    require!(
+        poll.status == PollStatus::Active,
+        ErrorCode::PollNotActive
+    );
+
+    poll.status = PollStatus::Suspended;
+    Ok(())
+}
+
+/// Reopen a suspended poll
+pub fn resume(poll: &mut Poll) -> Result<()> {
+    require!(
+        poll.status == PollStatus::Suspended,
+        ErrorCode::PollNotSuspended
+    );
+
+    poll.status = PollStatus::Active;
+    Ok(())
+}
+
+/// Cancel an active, scheduled, or suspended poll for abuse and forfeit its
+/// creation bond. Returns the bond, which the caller moves to the treasury.
+pub fn slash(poll: &mut Poll, now: i64) -> Result<u64> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+
+    poll.status = PollStatus::Cancelled;
+    poll.finalized_at = now;
+    Ok(std::mem::take(&mut poll.creation_bond))
+}
+
+/// Cancel a poll nobody settled within its resolution deadline after it
+/// ended, so its bids can be refunded. A proposed settlement is no longer
+/// unresolved: it finalizes or is decided by its arbiter.
+pub fn expire_unresolved(poll: &mut Poll, now: i64) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Active | PollStatus::Scheduled | PollStatus::Suspended
+        ),
+        ErrorCode::PollNotActive
+    );
+    require!(
+        poll.resolution_deadline > 0,
+        ErrorCode::NoResolutionDeadline
+    );
+    let deadline = poll.end_timestamp.saturating_add(poll.resolution_deadline);
+    require_ctx!(
+        now > deadline,
+        ErrorCode::ResolutionDeadlineNotReached,
+        now = now,
+        deadline = deadline
+    );
+
+    poll.status = PollStatus::Cancelled;
+    poll.finalized_at = now;
+    Ok(())
+}
+
+/// Validate that `poll` pays refunds: it was cancelled or settled as a draw
+pub fn validate_refundable(poll: &Poll) -> Result<()> {
+    require!(
+        matches!(poll.status, PollStatus::Cancelled | PollStatus::Voided),
+        ErrorCode::PollNotCancelled
+    );
+    Ok(())
+}
+
+/// End of the dispute window of a pending settlement
+pub fn dispute_ends_at(poll: &Poll) -> i64 {
+    poll.settlement_proposed_at.saturating_add(poll.dispute_period)
+}
+
+/// Record a challenge against a pending settlement while its window is open
+pub fn challenge(poll: &mut Poll, now: i64) -> Result<()> {
+    require!(
+        poll.status == PollStatus::PendingSettlement,
+        ErrorCode::SettlementNotPending
+    );
+    require!(!poll.challenged, ErrorCode::SettlementAlreadyChallenged);
+    require_ctx!(
+        now < dispute_ends_at(poll),
+        ErrorCode::DisputeWindowClosed,
+        now = now,
+        dispute_ends_at = dispute_ends_at(poll)
+    );
+
+    poll.challenged = true;
+    Ok(())
+}
+
+/// Finalize an unchallenged settlement once its dispute window has closed
+pub fn finalize_settlement(poll: &mut Poll, now: i64) -> Result<()> {
+    require!(
+        poll.status == PollStatus::PendingSettlement,
+        ErrorCode::SettlementNotPending
+    );
+    require!(!poll.challenged, ErrorCode::SettlementChallenged);
+    require_ctx!(
+        now >= dispute_ends_at(poll),
+        ErrorCode::DisputeWindowOpen,
+        now = now,
+        dispute_ends_at = dispute_ends_at(poll)
+    );
+
+    poll.status = PollStatus::Settled;
+    poll.finalized_at = now;
+    Ok(())
+}
+
+/// Finalize a challenged settlement with the arbiter's `winning_option`.
+/// Returns whether the proposed winner was overturned.
+pub fn resolve_challenge(poll: &mut Poll, winning_option: u8, now: i64) -> Result<bool> {
+    require!(
+        poll.status == PollStatus::PendingSettlement,
+        ErrorCode::SettlementNotPending
+    );
+    require!(poll.challenged, ErrorCode::SettlementNotChallenged);
+    validate_outcome(poll, winning_option)?;
+
+    let overturned = poll.winner != Some(winning_option);
+    poll.winner = Some(winning_option);
+    poll.status = PollStatus::Settled;
+    poll.finalized_at = now;
+    Ok(overturned)
+}
+
+/// Validate that a bid no longer holds a claim on its poll: it was paid,
+/// refunded, exited, or lost, or its poll was expired or closed with the
+/// claim unpaid. Bids still active on a finalized poll lost.
+pub fn validate_bid_close(poll: &Poll, status: BidStatus, option: u8) -> Result<()> {
+    let closable = match status {
+        BidStatus::Active => match poll.status {
+            PollStatus::Settled => poll.winner != Some(option),
+            PollStatus::Expired | PollStatus::Finalized | PollStatus::Closed => true,
+            _ => false,
+        },
+        // The rest of the payout is forfeit once the claim period is over
+        BidStatus::PartiallyClaimed => {
+            matches!(poll.status, PollStatus::Expired | PollStatus::Closed)
+        }
+        _ => true,
+    };
+    require!(closable, ErrorCode::BidNotClosable);
+    Ok(())
+}
+
+/// Claim `amount` of a winning bid's unclaimed payout, or all of it when
+/// `None`. The bid is `Won` once nothing is left and `PartiallyClaimed` until
+/// then. Returns the amount to pay.
+pub fn claim_part(bid: &mut Bid, amount: Option<u64>) -> Result<u64> {
+    let amount = match amount {
+        Some(amount) => {
+            require_ctx!(
+                (1..=bid.unclaimed).contains(&amount),
+                ErrorCode::InvalidClaimAmount,
+                amount = amount,
+                unclaimed = bid.unclaimed
+            );
+            amount
+        }
+        None => bid.unclaimed,
+    };
+    bid.unclaimed -= amount;
+    bid.status = if bid.unclaimed == 0 {
+        BidStatus::Won
+    } else {
+        BidStatus::PartiallyClaimed
+    };
+    Ok(amount)
+}
+
+/// Add a payment to a bid's bettor, and the fee or tip taken from it, to the
+/// bid's claim accounting
+pub fn record_payment(bid: &mut Bid, amount: u64, fee: u64, now: i64) -> Result<()> {
+    bid.claimed_amount = safe_add(bid.claimed_amount, amount)?;
+    bid.fee_paid = safe_add(bid.fee_paid, fee)?;
+    bid.claimed_at = now;
+    Ok(())
+}
+
+/// Mark an active bid that lost a settled poll as `Lost`
+pub fn mark_lost(poll: &Poll, bid: &mut Bid) -> Result<()> {
+    require!(
+        poll.status == PollStatus::Settled,
+        ErrorCode::PollNotSettled
+    );
+    require!(
+        bid.status == BidStatus::Active,
+        ErrorCode::BidAlreadyClaimed
+    );
+    require!(poll.winner != Some(bid.option), ErrorCode::BidNotLost);
+
+    bid.status = BidStatus::Lost;
+    Ok(())
+}
+
+/// Validate that a settled, cancelled, or voided poll's claim period is over,
+/// or that the poll already expired or was finalized
+pub fn validate_poll_close(poll: &Poll, now: i64) -> Result<()> {
+    if matches!(poll.status, PollStatus::Expired | PollStatus::Finalized) {
+        return Ok(());
+    }
+    validate_claim_period_over(poll, now)
+}
+
+/// Expire a settled, cancelled, or voided poll whose claim period is over.
+/// Its unclaimed funds, fees included, are swept to the treasury, so every
+/// claim is rejected from now on.
+pub fn expire(poll: &mut Poll, now: i64) -> Result<()> {
+    validate_claim_period_over(poll, now)?;
+
+    poll.status = PollStatus::Expired;
+    poll.accrued_fees = 0;
+    poll.creator_fees = 0;
+    Ok(())
+}
+
+/// Count a new bid on `option` towards the claims `finalize` waits for. Polls
+/// created before version 7 have no counts, and nothing is counted for them.
+pub fn count_bid(poll: &mut Poll, option: u8) -> Result<()> {
+    if let Some(count) = poll.bid_counts.get_mut(option as usize) {
+        *count = count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+    }
+    Ok(())
+}
+
+/// Stop counting a bid that left the pool by an exit or cancellation
+fn uncount_bid(poll: &mut Poll, option: u8) {
+    if let Some(count) = poll.bid_counts.get_mut(option as usize) {
+        *count = count.saturating_sub(1);
+    }
+}
+
+/// Count a winning bid paid in full
+pub fn count_claim(poll: &mut Poll) -> Result<()> {
+    poll.claimed_count = poll
+        .claimed_count
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+/// Finalize a settled poll before its claim period is over, once every bid on
+/// the winning outcome has been paid in full. Fees must be swept or claimed
+/// and house liquidity withdrawn first, so what's left in the vault is
+/// rounding dust. Outcome-token polls and polls created before version 7,
+/// whose bids weren't counted, wait for `expire` instead.
+pub fn finalize(poll: &mut Poll) -> Result<()> {
+    require!(
+        poll.status == PollStatus::Settled,
+        ErrorCode::PollNotSettled
+    );
+    require!(!poll.outcome_tokens, ErrorCode::OutcomeTokenMarket);
+    let winning_bids = poll
+        .winner
+        .and_then(|winner| poll.bid_counts.get(winner as usize))
+        .copied()
+        .ok_or_else(|| error!(ErrorCode::ClaimsNotTracked))?;
+    require_ctx!(
+        poll.claimed_count >= winning_bids
+            && poll.accrued_fees == 0
+            && poll.creator_fees == 0
+            && poll.lp_liquidity == 0,
+        ErrorCode::ClaimsOutstanding,
+        claimed = poll.claimed_count,
+        winning_bids = winning_bids,
+        accrued_fees = poll.accrued_fees,
+        creator_fees = poll.creator_fees,
+        lp_liquidity = poll.lp_liquidity
+    );
+
+    poll.status = PollStatus::Finalized;
+    Ok(())
+}
+
+/// Check that registry page `page` follows `previous`, the page before it
+pub fn validate_registry_page(page: u32, previous: Option<u32>) -> Result<()> {
+    require!(
+        page == 0 || previous == Some(page - 1),
+        ErrorCode::InvalidRegistryPage
+    );
+    Ok(())
+}
+
+/// Append a poll to a registry page with room and record the page on the
+/// poll. Finalized polls have left the registry for good.
+pub fn list_poll(poll: &mut Poll, key: Pubkey, registry: &mut PollRegistry) -> Result<()> {
+    require!(poll.registry_page.is_none(), ErrorCode::PollAlreadyListed);
+    require!(
+        poll.status != PollStatus::Finalized,
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        registry.polls.len() < REGISTRY_PAGE_SIZE,
+        ErrorCode::RegistryPageFull,
+        page = registry.page
+    );
+    registry.polls.push(key);
+    poll.registry_page = Some(registry.page);
+    Ok(())
+}
+
+/// Take a poll off its registry page, if it's listed. The page's last entry
+/// fills the gap.
+pub fn unlist_poll(
+    poll: &mut Poll,
+    key: Pubkey,
+    registry: Option<&mut PollRegistry>,
+) -> Result<()> {
+    let Some(page) = poll.registry_page else {
+        return Ok(());
+    };
+    let registry = registry.ok_or_else(|| error!(ErrorCode::RegistryRequired))?;
+    let index = registry
+        .polls
+        .iter()
+        .position(|listed| *listed == key)
+        .filter(|_| registry.page == page)
+        .ok_or_else(|| error!(ErrorCode::RegistryMismatch))?;
+    registry.polls.swap_remove(index);
+    poll.registry_page = None;
+    Ok(())
+}
+
+fn validate_claim_period_over(poll: &Poll, now: i64) -> Result<()> {
+    require!(
+        matches!(
+            poll.status,
+            PollStatus::Settled | PollStatus::Cancelled | PollStatus::Voided
+        ),
+        ErrorCode::PollNotFinalized
+    );
+    let closes_at = poll.finalized_at.saturating_add(poll.claim_period);
+    require_ctx!(
+        now >= closes_at,
+        ErrorCode::ClaimGracePeriod,
+        now = now,
+        closes_at = closes_at
+    );
+    Ok(())
+}
+
+/// Strip a poll down to its closed tombstone: every instruction but
+/// `close_bid` rejects it. The creation bond is returned with the freed rent.
+pub fn close_poll(poll: &mut Poll) {
+    poll.status = PollStatus::Closed;
+    poll.creation_bond = 0;
+}
+
+/// Propose `new_authority` as the next holder of a key held by `current`,
+/// to take over once it accepts; `None` withdraws a pending proposal
+pub fn propose_transfer(
+    pending: &mut Option<Pubkey>,
+    current: Pubkey,
+    new_authority: Option<Pubkey>,
+) -> Result<()> {
+    require!(
+        new_authority != Some(current) && new_authority != Some(Pubkey::default()),
+        ErrorCode::InvalidNewAuthority
+    );
+
+    *pending = new_authority;
+    Ok(())
+}
+
+/// Accept a pending transfer as `signer`, which must be the proposed key
+pub fn accept_transfer(pending: &mut Option<Pubkey>, signer: Pubkey) -> Result<()> {
+    require!(*pending == Some(signer), ErrorCode::NotPendingAuthority);
+
+    *pending = None;
+    Ok(())
+}
+
+/// Set up a user's profile the first time it is used
+pub fn init_profile(profile: &mut UserProfile, user: Pubkey, bump: u8) {
+    if profile.user == Pubkey::default() {
+        profile.user = user;
+        profile.bump = bump;
+    }
+}
+
+/// Approve `delegate` to claim the profile user's winnings, or revoke the
+/// approval with `None`
+pub fn set_claim_delegate(profile: &mut UserProfile, delegate: Option<Pubkey>) -> Result<()> {
+    require!(
+        delegate != Some(profile.user),
+        ErrorCode::InvalidClaimDelegate
+    );
+    profile.claim_delegate = delegate;
+    Ok(())
+}
+
+/// Count a bid of `amount` in its bettor's profile, and in their epoch
+/// volume if it is placed during the config's epoch
+pub fn profile_bid(
+    profile: &mut UserProfile,
+    config: &Config,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    profile.bids_placed = profile
+        .bids_placed
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    profile.volume = safe_add(profile.volume, amount)?;
+    if enter_epoch(profile, config, now) {
+        profile.epoch_volume = safe_add(profile.epoch_volume, amount)?;
+    }
+    Ok(())
+}
+
+/// Count a claim that paid `payout` for a bid of `amount`: a win, or a refund
+/// of a cancelled or voided poll. Net P&L, and epoch P&L during the config's
+/// epoch, move by `payout - amount`.
+pub fn profile_claim(
+    profile: &mut UserProfile,
+    config: &Config,
+    amount: u64,
+    payout: u64,
+    won: bool,
+    now: i64,
+) -> Result<()> {
+    profile.claims = profile.claims.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+    if won {
+        profile.wins = profile.wins.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+    }
+    let pnl = i64::try_from(i128::from(payout) - i128::from(amount))
+        .map_err(|_| ErrorCode::MathOverflow)?;
+    profile.net_pnl = profile.net_pnl.checked_add(pnl).ok_or(ErrorCode::MathOverflow)?;
+    if enter_epoch(profile, config, now) {
+        profile.epoch_pnl = profile.epoch_pnl.checked_add(pnl).ok_or(ErrorCode::MathOverflow)?;
+    }
+    Ok(())
+}
+
+/// Count a losing bid of `amount`, recorded when the bid is closed
+pub fn profile_loss(
+    profile: &mut UserProfile,
+    config: &Config,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    profile.losses = profile.losses.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+    let amount = i64::try_from(amount).map_err(|_| ErrorCode::MathOverflow)?;
+    profile.net_pnl = profile.net_pnl.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+    if enter_epoch(profile, config, now) {
+        profile.epoch_pnl = profile.epoch_pnl.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+    }
+    Ok(())
+}
+
+/// Move a profile's epoch counters to the config's latest epoch if `now` is
+/// inside it, starting them from zero. Returns whether activity at `now`
+/// counts toward that epoch.
+fn enter_epoch(profile: &mut UserProfile, config: &Config, now: i64) -> bool {
+    let open = config.epoch_id > 0 && (config.epoch_start..config.epoch_end).contains(&now);
+    if open && profile.epoch_id != config.epoch_id {
+        profile.epoch_id = config.epoch_id;
+        profile.epoch_volume = 0;
+        profile.epoch_pnl = 0;
+    }
+    open
+}
+
+/// Validate a new leaderboard epoch: the id after the config's latest, a
+/// window that hasn't started, 1 to `MAX_EPOCH_WINNERS` nonzero rank weights
+/// summing to the whole pool, and a reward pool. The previous epoch's scores
+/// must be past submission, since the new epoch resets profiles' counters.
+pub fn validate_epoch(
+    config: &Config,
+    epoch_id: u32,
+    start_timestamp: i64,
+    end_timestamp: i64,
+    weights: &[u16],
+    reward_pool: u64,
+    now: i64,
+) -> Result<()> {
+    require_ctx!(
+        now >= config.epoch_end.saturating_add(EPOCH_SUBMISSION_PERIOD),
+        ErrorCode::EpochStillOpen,
+        previous_end = config.epoch_end,
+        now = now
+    );
+    let total_weight: u64 = weights.iter().map(|weight| u64::from(*weight)).sum();
+    require_ctx!(
+        config.epoch_id.checked_add(1) == Some(epoch_id)
+            && now <= start_timestamp
+            && start_timestamp < end_timestamp
+            && (1..=MAX_EPOCH_WINNERS).contains(&weights.len())
+            && weights.iter().all(|weight| *weight > 0)
+            && total_weight == BPS_DENOMINATOR
+            && reward_pool > 0,
+        ErrorCode::InvalidEpoch,
+        epoch_id = epoch_id,
+        latest = config.epoch_id,
+        start = start_timestamp,
+        end = end_timestamp,
+        winners = weights.len(),
+        total_weight = total_weight
+    );
+    Ok(())
+}
+
+/// A profile's score on `epoch` by its metric: 0 if the profile's counters
+/// are for another epoch
+pub fn epoch_score(epoch: &Epoch, profile: &UserProfile) -> i64 {
+    if profile.epoch_id != epoch.epoch_id {
+        return 0;
+    }
+    match epoch.metric {
+        EpochMetric::Volume => i64::try_from(profile.epoch_volume).unwrap_or(i64::MAX),
+        EpochMetric::Pnl => profile.epoch_pnl,
+    }
+}
+
+/// Enter a profile's positive score on an ended epoch's leaderboard, below
+/// any equal score already there, dropping the lowest entry once every rank
+/// is filled. Scores are final from the end, and can be submitted until
+/// `EPOCH_SUBMISSION_PERIOD` after it. Returns the score and its rank.
+pub fn submit_epoch_score(epoch: &mut Epoch, profile: &UserProfile, now: i64) -> Result<(i64, u8)> {
+    let closes = epoch.end_timestamp.saturating_add(EPOCH_SUBMISSION_PERIOD);
+    require_ctx!(
+        (epoch.end_timestamp..closes).contains(&now),
+        ErrorCode::EpochSubmissionClosed,
+        end = epoch.end_timestamp,
+        closes = closes,
+        now = now
+    );
+    let score = epoch_score(epoch, profile);
+    require!(score > 0, ErrorCode::NoEpochScore);
+    require!(
+        epoch.leaderboard.iter().all(|entry| entry.user != profile.user),
+        ErrorCode::AlreadyOnLeaderboard
+    );
+    let rank = epoch.leaderboard.partition_point(|entry| entry.score >= score);
+    require_ctx!(
+        rank < epoch.weights.len(),
+        ErrorCode::ScoreBelowLeaderboard,
+        score = score,
+        lowest = epoch.leaderboard.last().map_or(0, |entry| entry.score)
+    );
+
+    epoch.leaderboard.insert(
+        rank,
+        EpochEntry {
+            user: profile.user,
+            score,
+            reward: 0,
+            claimed: false,
+        },
+    );
+    epoch.leaderboard.truncate(epoch.weights.len());
+    Ok((score, rank as u8))
+}
+
+/// Fix each ranked user's reward at their rank's weight of the pool (rounded
+/// down) once scores can no longer be submitted. Returns what's left of the
+/// pool, unfilled ranks' shares and rounding dust, for the treasury.
+pub fn finalize_epoch(epoch: &mut Epoch, now: i64) -> Result<u64> {
+    require!(!epoch.finalized, ErrorCode::EpochAlreadyFinalized);
+    require_ctx!(
+        now >= epoch.end_timestamp.saturating_add(EPOCH_SUBMISSION_PERIOD),
+        ErrorCode::EpochNotFinalizable,
+        end = epoch.end_timestamp,
+        now = now
+    );
+
+    let mut distributed = 0;
+    for (entry, weight) in epoch.leaderboard.iter_mut().zip(&epoch.weights) {
+        entry.reward = Bps::new((*weight).into()).try_apply(epoch.reward_pool, Rounding::Down)?;
+        distributed = safe_add(distributed, entry.reward)?;
+    }
+    epoch.finalized = true;
+    safe_sub(epoch.reward_pool, distributed)
+}
+
+/// Mark `user`'s reward from a finalized epoch paid. Returns their rank and
+/// reward.
+pub fn claim_epoch_reward(epoch: &mut Epoch, user: Pubkey) -> Result<(u8, u64)> {
+    require!(epoch.finalized, ErrorCode::EpochNotFinalized);
+    let rank = epoch
+        .leaderboard
+        .iter()
+        .position(|entry| entry.user == user)
+        .ok_or(ErrorCode::NotOnLeaderboard)?;
+    let entry = &mut epoch.leaderboard[rank];
+    require!(!entry.claimed, ErrorCode::EpochRewardAlreadyClaimed);
+
+    entry.claimed = true;
+    let reward = entry.reward;
+    epoch.paid = safe_add(epoch.paid, reward)?;
+    Ok((rank as u8, reward))
+}
+
+/// Bucket of a scalar poll that `value` falls in: outcome `i` covers values
+/// from `bounds[i - 1]` up to but excluding `bounds[i]`, and the first and
+/// last buckets are open-ended
+pub fn scalar_outcome(poll: &Poll, value: i64) -> Result<u8> {
+    match &poll.kind {
+        PollKind::Scalar { bounds } => Ok(bounds.partition_point(|bound| *bound <= value) as u8),
+        PollKind::Categorical | PollKind::Jackpot => err!(ErrorCode::NotScalarMarket),
+    }
+}
+
+/// Winning outcome of an oracle market: 0 if `price` satisfies the poll's
+/// resolution source, 1 otherwise, or the bucket the price falls in on a
+/// scalar poll. The price must come from the poll's feed and be published at
+/// most `ORACLE_PRICE_WINDOW` seconds after the poll ended.
+pub fn oracle_outcome(poll: &Poll, price: &OraclePrice) -> Result<u8> {
+    let source = poll.resolution.ok_or(ErrorCode::NoResolutionSource)?;
+    require!(
+        price.feed_id == source.feed_id && price.exponent == source.exponent,
+        ErrorCode::OracleFeedMismatch
+    );
+    require_ctx!(
+        (poll.end_timestamp..=poll.end_timestamp.saturating_add(ORACLE_PRICE_WINDOW))
+            .contains(&price.publish_time),
+        ErrorCode::OraclePriceOutsideWindow,
+        publish_time = price.publish_time,
+        end_timestamp = poll.end_timestamp,
+        window = ORACLE_PRICE_WINDOW
+    );
+
+    if let PollKind::Scalar { .. } = poll.kind {
+        return scalar_outcome(poll, price.price);
+    }
+    let holds = match source.comparison {
+        PriceComparison::Above => price.price > source.threshold,
+        PriceComparison::AtOrAbove => price.price >= source.threshold,
+        PriceComparison::Below => price.price < source.threshold,
+        PriceComparison::AtOrBelow => price.price <= source.threshold,
+    };
+    Ok(if holds { 0 } else { 1 })
+}
+
+/// Fee adjustment a bid placed at `now` locks in under the poll's incentive
+/// curve: minus the early bonus in the early window, plus the late fee in the
+/// late window, and 0 otherwise or without a curve
+pub fn fee_adjust_bps(poll: &Poll, now: i64) -> i16 {
+    match poll.incentives {
+        Some(curve) if now < poll.start_timestamp.saturating_add(curve.early_window) => {
+            -(curve.early_bonus_bps as i16)
+        }
+        Some(curve) if now >= poll.end_timestamp.saturating_sub(curve.late_window) => {
+            curve.late_fee_bps as i16
+        }
+        _ => 0,
+    }
+}
+
+/// `fee_bps` moved by a bid's `fee_adjust_bps`, floored at zero
+pub fn adjusted_fee_bps(fee_bps: u16, fee_adjust_bps: i16) -> u16 {
+    (i32::from(fee_bps) + i32::from(fee_adjust_bps)).clamp(0, u16::MAX.into()) as u16
+}
+
+/// Payout of a stake bought at `odds`: `amount * BPS_DENOMINATOR / odds`,
+/// rounded down
+pub fn potential_win(amount: u64, odds: u64) -> Result<u64> {
+    payout_at_odds(amount, Bps::new(odds))
+}
+
+/// Reprice every outcome of the poll from its current stakes
+pub fn update_amm_odds(poll: &mut Poll) -> Result<()> {
+    let (total_pool, outcome_count) = (poll.total_pool, poll.outcomes.len());
+    let virtual_liquidity = poll.virtual_liquidity;
+    for outcome in poll.outcomes.iter_mut() {
+        outcome.odds = amm_odds(outcome.stake, total_pool, outcome_count, virtual_liquidity)?;
+    }
+    Ok(())
+}
+
+/// AMM odds of one of `outcome_count` outcomes from its stake
+/// Formula: odds = (stake + b) / (total_pool + outcome_count * b) with
+/// smoothing, where `b` is the poll's virtual liquidity per outcome. A larger
+/// `b` makes each bid move the odds less; 0 prices on raw stake ratios.
+pub fn amm_odds(
+    stake: u64,
+    total_pool: u64,
+    outcome_count: usize,
+    virtual_liquidity: u64,
+) -> Result<u64> {
+    let stake = safe_add(stake, virtual_liquidity)?;
+    let total_pool = safe_add(safe_mul(outcome_count as u64, virtual_liquidity)?, total_pool)?;
+    if total_pool == 0 {
+        // Equal odds for every outcome
+        return Ok(Bps::try_from_ratio(1, outcome_count as u64, Rounding::Down)?.raw());
+    }
+
+    // Probability = stake / total (in basis points), smoothed to prevent
+    // extreme odds (kept between 5% and 95%)
+    let prob = clamped_odds(
+        stake,
+        total_pool,
+        Bps::new(MIN_ODDS_BPS),
+        Bps::new(MAX_ODDS_BPS),
+    )?;
+    Ok(prob.raw())
+}
+
+/// Split a winning bid's potential win into (payout, platform_fee) at
+/// `fee_bps`. Fees round up so the payout never exceeds what the odds promised.
+pub fn split_platform_fee(potential_win: u64, fee_bps: u16) -> Result<(u64, u64)> {
+    let platform_fee = fee(potential_win, Bps::new(fee_bps.into()))?;
+    let payout = safe_sub(potential_win, platform_fee)?;
+    Ok((payout, platform_fee))
+}
+
+/// Gross win of a winning bid before fees: the `potential_win` locked in at
+/// purchase for fixed-odds polls, or `amount / winning_stake * total_pool`
+/// (rounded down) for pari-mutuel polls
+pub fn gross_win(poll: &Poll, amount: u64, potential_win: u64) -> Result<u64> {
+    match (poll.payout_mode, poll.winner) {
+        (PayoutMode::PariMutuel, Some(winner)) => {
+            let winning_stake = poll.outcomes[winner as usize].stake;
+            safe_mul_div(amount, poll.total_pool, winning_stake, Rounding::Down)
+        }
+        _ => Ok(potential_win),
+    }
+}
+
+/// Final (payout, platform_fee) of a bid of `amount` on `option` once the
+/// poll's winner is known, at the poll's fee moved by the bid's
+/// `fee_adjust_bps`; losing bids get nothing
+pub fn settled_payout(
+    poll: &Poll,
+    option: u8,
+    amount: u64,
+    potential_win: u64,
+    fee_adjust_bps: i16,
+) -> Result<(u64, u64)> {
+    if poll.winner == Some(option) {
+        let fee_bps = adjusted_fee_bps(poll.fee_bps, fee_adjust_bps);
+        split_platform_fee(gross_win(poll, amount, potential_win)?, fee_bps)
+    } else {
+        Ok((0, 0))
+    }
+}
+
+/// Turn on odds history for a poll that has taken no bids, recording its
+/// opening odds as the first snapshot
+pub fn enable_odds_history(
+    poll: &mut Poll,
+    history: &mut OddsHistory,
+    interval: i64,
+    now: i64,
+) -> Result<()> {
+    require!(
+        matches!(poll.status, PollStatus::Active | PollStatus::Scheduled),
+        ErrorCode::PollNotActive
+    );
+    require_ctx!(
+        interval > 0,
+        ErrorCode::InvalidOddsHistoryInterval,
+        interval = interval
+    );
+    require_ctx!(
+        poll.next_bid_index == 0 && poll.total_pool == 0,
+        ErrorCode::PollHasBids,
+        next_bid_index = poll.next_bid_index,
+        total_pool = poll.total_pool
+    );
+
+    poll.odds_history = true;
+    history.interval = interval;
+    history.head = 0;
+    history.count = 0;
+    record_odds(history, poll, now);
+    Ok(())
+}
+
+/// Append a snapshot of the poll's odds and pool to its history, overwriting
+/// the oldest once full, unless the latest is under `interval` seconds old.
+/// Returns whether a snapshot was taken.
+pub fn record_odds(history: &mut OddsHistory, poll: &Poll, now: i64) -> bool {
+    let capacity = ODDS_HISTORY_CAPACITY as u32;
+    if history.count > 0 {
+        let latest = &history.snapshots[((history.head + capacity - 1) % capacity) as usize];
+        if now < latest.timestamp.saturating_add(history.interval) {
+            return false;
+        }
+    }
+
+    let mut odds = [0; MAX_OUTCOMES];
+    for (odds, outcome) in odds.iter_mut().zip(&poll.outcomes) {
+        *odds = outcome.odds.min(BPS_DENOMINATOR) as u16;
+    }
+    history.snapshots[history.head as usize] = OddsSnapshot {
+        timestamp: now,
+        total_pool: poll.total_pool,
+        odds,
+    };
+    history.head = (history.head + 1) % capacity;
+    history.count = (history.count + 1).min(capacity);
+    true
+}
+
+/// Validate a limit order escrowing `amount` for a bid on `option` that fills
+/// once the outcome's odds come down to `max_odds`
+pub fn validate_limit_order(
+    poll: &Poll,
+    amount: u64,
+    option: u8,
+    max_odds: u64,
+    now: i64,
+) -> Result<()> {
+    // Orders escrow SOL and fill as regular bids, without a UserStake account
+    require!(poll.mint.is_none(), ErrorCode::DenominationMismatch);
+    require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+    require!(!poll.outcome_tokens, ErrorCode::OutcomeTokenMarket);
+    validate_no_user_cap(poll)?;
+    validate_no_bid_cooldown(poll)?;
+    validate_bid(poll, amount, now)?;
+    validate_outcome(poll, option)?;
+    require_ctx!(
+        (MIN_ODDS_BPS..=MAX_ODDS_BPS).contains(&max_odds),
+        ErrorCode::InvalidLimitOdds,
+        max_odds = max_odds,
+        min = MIN_ODDS_BPS,
+        max = MAX_ODDS_BPS
+    );
+    Ok(())
+}
+
+/// Fill a limit order as a bid at the odds `record_bid` prices it at, late-bid
+/// premium and vig included, which must be at or below the order's limit.
+/// Returns the odds the bid was priced at and its locked-in potential win.
+pub fn match_order(poll: &mut Poll, order: &PendingOrder, now: i64) -> Result<(u64, u64)> {
+    validate_no_user_cap(poll)?;
+    validate_no_bid_cooldown(poll)?;
+    validate_outcome(poll, order.option)?;
+    let odds = bid_odds(poll, order.amount, order.option, now)?;
+    require_ctx!(
+        odds <= order.max_odds,
+        ErrorCode::LimitNotReached,
+        odds = odds,
+        max_odds = order.max_odds
+    );
+
+    record_bid(poll, order.amount, order.option, now)
+}
+
+/// Validate one leg of a parlay staking `amount`: the poll must take a bid of
+/// that size on `option` right now. Returns the leg with the odds such a bid
+/// is priced at, late-bid premium and vig included, locked in.
+pub fn parlay_leg(
+    poll: &Poll,
+    poll_key: Pubkey,
+    option: u8,
+    amount: u64,
+    now: i64,
+) -> Result<ParlayLeg> {
+    require!(poll.adapter.is_none(), ErrorCode::AdapterMarket);
+    validate_ungated(poll)?;
+    validate_bid(poll, amount, now)?;
+    validate_outcome(poll, option)?;
+
+    Ok(ParlayLeg {
+        poll: poll_key,
+        option,
+        odds: bid_odds(poll, amount, option, now)?,
+
+    })
+}
+
+/// Gross win of a parlay staking `amount` on `legs`: the stake divided by
+/// each leg's odds in turn, so the combined odds are their product. Legs must
+/// be on distinct polls, `MIN_PARLAY_LEGS` to `MAX_PARLAY_LEGS` of them.
+pub fn parlay_potential_win(amount: u64, legs: &[ParlayLeg]) -> Result<u64> {
+    require_ctx!(
+        (MIN_PARLAY_LEGS..=MAX_PARLAY_LEGS).contains(&legs.len()),
+        ErrorCode::InvalidParlayLegs,
+        count = legs.len(),
+        min = MIN_PARLAY_LEGS,
+        max = MAX_PARLAY_LEGS
+    );
+    for (i, leg) in legs.iter().enumerate() {
+        require!(
+            legs[..i].iter().all(|other| other.poll != leg.poll),
+            ErrorCode::InvalidParlayLegs
+        );
+    }
+
+    legs.iter().try_fold(amount, |win, leg| {
+        mul_div(win, BPS_DENOMINATOR, leg.odds, Rounding::Down)
+            .ok_or_else(|| error!(ErrorCode::InvalidParlayLegs))
+    })
+}
+
+/// How one leg of a parlay stands on its poll
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParlayLegResult {
+    Pending,
+    Won,
+    Lost,
+    /// The poll was cancelled or voided; the leg drops out of the parlay
+    Void,
+}
+
+/// Result of `leg` given its poll's final state. Expired polls keep their winner.
+pub fn parlay_leg_result(poll: &Poll, leg: &ParlayLeg) -> ParlayLegResult {
+    match poll.status {
+        PollStatus::Settled | PollStatus::Expired | PollStatus::Finalized => match poll.winner {
+            Some(winner) if winner == leg.option => ParlayLegResult::Won,
+            Some(_) => ParlayLegResult::Lost,
+            None => ParlayLegResult::Void,
+        },
+        PollStatus::Cancelled | PollStatus::Voided => ParlayLegResult::Void,
+        _ => ParlayLegResult::Pending,
+    }
+}
+
+/// Final (payout, platform_fee) of a parlay whose legs stand at `results`.
+/// Every leg must be decided and none lost. Void legs drop out of the
+/// combined odds; if every leg is void the stake is refunded without a fee.
+pub fn parlay_payout(parlay: &Parlay, results: &[ParlayLegResult]) -> Result<(u64, u64)> {
+    require!(
+        !results.contains(&ParlayLegResult::Pending),
+        ErrorCode::ParlayNotResolved
+    );
+    require!(
+        !results.contains(&ParlayLegResult::Lost),
+        ErrorCode::ParlayLegLost
+    );
+
+    let won = parlay
+        .legs
+        .iter()
+        .zip(results)
+        .filter(|(_, result)| **result == ParlayLegResult::Won);
+    let mut gross = parlay.amount;
+    let mut any_won = false;
+    for (leg, _) in won {
+        gross = safe_mul_div(gross, BPS_DENOMINATOR, leg.odds, Rounding::Down)?;
+        any_won = true;
+    }
+
+    if any_won {
+        split_platform_fee(gross, parlay.fee_bps)
+    } else {
+        Ok((parlay.amount, 0))
+    }
+}
+
+/// Platform fee (basis points) charged to a bettor with lifetime `volume`: the
+/// highest tier of the poll's schedule the volume reaches, never more than the
+/// poll's base `fee_bps`
+pub fn tiered_fee_bps(poll: &Poll, volume: u64) -> u16 {
+    poll.fee_tiers
+        .iter()
+        .rev()
+        .find(|tier| volume >= tier.min_volume)
+        .map_or(poll.fee_bps, |tier| tier.fee_bps.min(poll.fee_bps))
+}
+
+/// `settled_payout` with the fee tier of a bettor with lifetime `volume`
+pub fn tiered_payout(
+    poll: &Poll,
+    option: u8,
+    amount: u64,
+    potential_win: u64,
+    fee_adjust_bps: i16,
+    volume: u64,
+) -> Result<(u64, u64)> {
+    if poll.winner == Some(option) {
+        tiered_split(poll, gross_win(poll, amount, potential_win)?, fee_adjust_bps, volume)
+    } else {
+        Ok((0, 0))
+    }
+}
+
+/// Split a winning bid's `gross_win` into (payout, platform_fee) at the fee
+/// tier of a bettor with lifetime `volume`, moved by the bid's `fee_adjust_bps`
+pub fn tiered_split(
+    poll: &Poll,
+    gross_win: u64,
+    fee_adjust_bps: i16,
+    volume: u64,
+) -> Result<(u64, u64)> {
+    let fee_bps = adjusted_fee_bps(tiered_fee_bps(poll, volume), fee_adjust_bps);
+    split_platform_fee(gross_win, fee_bps)
+}
+
+/// Quote a bid of `amount` on `option` at `now` exactly as `record_bid` would
+/// price it, for a bettor with lifetime `volume`, without changing the poll.
+/// The fee and payout assume the bid wins at its locked-in potential win.
+pub fn quote_bid(poll: &Poll, amount: u64, option: u8, volume: u64, now: i64) -> Result<BidQuote> {
+    let amount = fill_amount(poll, amount, option, now)?;
+    let mut after = poll.clone();
+    let (odds, potential_win) = record_bid(&mut after, amount, option, now)?;
+    let fee_bps = adjusted_fee_bps(tiered_fee_bps(poll, volume), fee_adjust_bps(poll, now));
+    let (payout, fee) = split_platform_fee(potential_win, fee_bps)?;
+
+    Ok(BidQuote {
+        version: VIEW_VERSION,
+        amount,
+        option,
+        odds,
+        post_trade_odds: after.outcomes.iter().map(|outcome| outcome.odds).collect(),
+        potential_win,
+        fee,
+        payout,
+    })
+}
+
+/// A poll's outcomes priced per share. An outcome's shares are the wins
+/// locked in on it (`total_potential_liability`), since a bid's potential win
+/// is its stake over the price it paid.
+pub fn market_state(poll: &Poll, key: Pubkey) -> Result<MarketState> {
+    let shares: Vec<u64> = poll
+        .outcomes
+        .iter()
+        .map(|outcome| outcome.total_potential_liability)
+        .collect();
+    let max_payout = match poll.payout_mode {
+        PayoutMode::FixedOdds => shares.iter().copied().max().unwrap_or(0),
+        PayoutMode::PariMutuel => poll.total_pool,
+    };
+
+    Ok(MarketState {
+        version: VIEW_VERSION,
+        poll: key,
+        status: poll.status,
+        payout_mode: poll.payout_mode,
+        implied_probabilities: poll.outcomes.iter().map(|outcome| outcome.odds).collect(),
+        share_prices: poll
+            .outcomes
+            .iter()
+            .map(|outcome| odds_with_vig(poll, outcome.odds))
+            .collect::<Result<_>>()?,
+        shares,
+        total_pool: poll.total_pool,
+        max_payout,
+    })
+}
+
+/// Bring a poll written by an earlier release up to `POLL_VERSION`. Returns
+/// the version it was at.
+pub fn migrate_poll(poll: &mut Poll) -> Result<u8> {
+    let from = poll.version;
+    require_ctx!(
+        from < POLL_VERSION,
+        ErrorCode::AccountUpToDate,
+        version = from,
+        current = POLL_VERSION
+    );
+    // What each version added; new fields stay zero on an older poll:
+    // 1: `version`
+    // 2: `incentives` (none)
+    // 3: `resolution_deadline` (no deadline)
+    // 4: `swept_fees`, `fee_share_bps` (earlier sweeps earn no fee share)
+    // 5: `cancel_window`, `cancel_fee_bps` (no cancellation)
+    // 6: `max_price_impact_bps` (no limit)
+    // 7: `bid_counts`, `claimed_count` (earlier bids unknown, so no early
+    //    finalization)
+    // 8: `min_seconds_between_bids` (no cooldown)
+    // 9: `registry_page` (unset until `list_poll`)
+    // 10: `damping_window`, `damping_bps` (no damping)
+    // 11: `vig_bps` (no vig)
+    // 12: vault seeds `["vault", poll]` (the handler moves lamports and bump)
+    // 13: `treasury_swept` (earlier sweeps uncounted)
+    // A version that needs anything else sets it here. Code reading a field
+    // checks `version` first.
+    poll.version = POLL_VERSION;
+    Ok(from)
+}
+
+/// Bring a bid written by an earlier release up to `BID_VERSION`. Returns the
+/// version it was at.
+pub fn migrate_bid(bid: &mut Bid) -> Result<u8> {
+    let from = bid.version;
+    require_ctx!(
+        from < BID_VERSION,
+        ErrorCode::AccountUpToDate,
+        version = from,
+        current = BID_VERSION
+    );
+    // What each version added; new fields stay zero on an older bid:
+    // 2: `claimed_amount`, `fee_paid`, `claimed_at` (earlier payments unrecorded)
+    // 3: `fee_adjust_bps` (no incentives)
+    // 4: `memo` (none)
+    // 5: `placed_at` (can't be cancelled)
+    // 6: `credit` (no credit owed)
+    // 7: `usd_notional_at_purchase` (no USD value)
+    bid.version = BID_VERSION;
+    Ok(from)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::LAMPORTS_PER_SOL;
+
+    fn active_bid(amount: u64, odds: u64, potential_win: u64) -> Bid {
+        Bid {
+            bettor: Pubkey::new_unique(),
+            poll: Pubkey::default(),
+            amount,
+            option: 0,
+            odds_at_purchase: odds,
+            potential_win,
+            status: BidStatus::Active,
+            timestamp: 1,
+            index: 0,
+            bump: 0,
+            referrer: None,
+            unclaimed: 0,
+            version: BID_VERSION,
+            claimed_amount: 0,
+            fee_paid: 0,
+            claimed_at: 0,
+            fee_adjust_bps: 0,
+            memo: None,
+            placed_at: 0,
+            credit: 0,
+            usd_notional_at_purchase: 0,
+        }
+    }
+
+    pub(crate) fn active_poll(end_timestamp: i64) -> Poll {
+        Poll {
+            authority: Pubkey::default(),
+            poll_id: "poll".to_string(),
+            title_hash: text_hash("Will it rain?"),
+            outcomes: new_outcomes(&["Yes".to_string(), "No".to_string()]).unwrap(),
+            total_pool: 0,
+            end_timestamp,
+            status: PollStatus::Active,
+            winner: None,
+            vault_bump: 0,
+            bump: 0,
+            next_bid_index: 0,
+            accrued_fees: 0,
+            adapter: None,
+            mint: None,
+            payout_mode: PayoutMode::FixedOdds,
+            resolution: None,
+            dispute_period: 0,
+            settlement_proposed_at: 0,
+            arbiter: None,
+            challenged: false,
+            fee_bps: 200,
+            min_bet: MIN_BET_AMOUNT,
+            max_bet: MAX_BET_AMOUNT,
+            finalized_at: 0,
+            creation_bond: 0,
+            creator_fee_bps: 0,
+            creator_fees: 0,
+            start_timestamp: 0,
+            initial_end_timestamp: end_timestamp,
+            deadline_extension: None,
+            extension_count: 0,
+            virtual_liquidity: 0,
+            lp_liquidity: 0,
+            lp_fee_bps: 0,
+            lp_fees: 0,
+            outcome_tokens: false,
+            claim_period: CLOSE_GRACE_PERIOD,
+            pending_authority: None,
+            max_total_pool: None,
+            max_stake_per_option: None,
+            max_stake_per_user: None,
+            settlement_commitment: None,
+            committed_at: 0,
+            referral_fee_bps: 0,
+            fee_tiers: Vec::new(),
+            kind: PollKind::Categorical,
+            crank_bounty: 0,
+            event_seq: 0,
+            gate: None,
+            council: None,
+            result_signer: None,
+            community_votes: Vec::new(),
+            total_fees: 0,
+            odds_history: false,
+            version: POLL_VERSION,
+            incentives: None,
+            resolution_deadline: 0,
+            swept_fees: 0,
+            fee_share_bps: 0,
+            cancel_window: 0,
+            cancel_fee_bps: 0,
+            max_price_impact_bps: 0,
+            bid_counts: vec![0; 2],
+            claimed_count: 0,
+            min_seconds_between_bids: 0,
+            registry_page: None,
+            damping_window: 0,
+            damping_bps: 0,
+            vig_bps: 0,
+            treasury_swept: 0,
+        }
+    }
+
+    #[test]
+    fn metadata_limits() {
+        let outcomes = vec![String::new(); 2];
+        assert!(validate_poll_metadata(&"a".repeat(64), "", &outcomes).is_ok());
+        assert_eq!(
+            validate_poll_metadata(&"a".repeat(65), "", &outcomes),
+            Err(ErrorCode::PollIdTooLong.into())
+        );
+        assert_eq!(
+            validate_poll_metadata("", "", &[String::new(), "b".repeat(129)]),
+            Err(ErrorCode::OptionTextTooLong.into())
+        );
+        assert!(validate_poll_metadata("", "", &vec![String::new(); MAX_OUTCOMES]).is_ok());
+        assert_eq!(
+            validate_poll_metadata("", "", &vec![String::new(); MAX_OUTCOMES + 1]),
+            Err(ErrorCode::InvalidOutcomeCount.into())
+        );
+        assert_eq!(
+            validate_poll_metadata("", "", &outcomes[..1]),
+            Err(ErrorCode::InvalidOutcomeCount.into())
+        );
+    }
+
+    #[test]
+    fn poll_metadata_limits() {
+        let tags = vec!["a".repeat(MAX_METADATA_TAG_LEN); MAX_METADATA_TAGS];
+        let criteria = "c".repeat(MAX_RESOLUTION_CRITERIA_LEN);
+        assert!(validate_metadata(&"u".repeat(MAX_METADATA_URI_LEN), &tags, &criteria).is_ok());
+        assert!(validate_metadata("", &[], "").is_ok());
+        assert_eq!(
+            validate_metadata(&"u".repeat(MAX_METADATA_URI_LEN + 1), &[], ""),
+            Err(ErrorCode::MetadataUriTooLong.into())
+        );
+        for invalid in [
+            vec!["a".to_string(); MAX_METADATA_TAGS + 1],
+            vec![String::new()],
+            vec!["a".repeat(MAX_METADATA_TAG_LEN + 1)],
+        ] {
+            assert_eq!(
+                validate_metadata("", &invalid, ""),
+                Err(ErrorCode::InvalidMetadataTags.into())
+            );
+        }
+        assert_eq!(
+            validate_metadata("", &[], &"c".repeat(MAX_RESOLUTION_CRITERIA_LEN + 1)),
+            Err(ErrorCode::ResolutionCriteriaTooLong.into())
+        );
+    }
+
+    #[test]
+    fn config_limits() {
+        let config = |fee_bps, creator_fee_bps, lp_fee_bps, min_bet, max_bet| {
+            validate_config(
+                fee_bps,
+                creator_fee_bps,
+                lp_fee_bps,
+                0,
+                min_bet,
+                max_bet,
+                CLOSE_GRACE_PERIOD,
+            )
+        };
+        assert!(config(MAX_FEE_BPS, 0, 0, MIN_BET_AMOUNT, MAX_BET_AMOUNT).is_ok());
+        assert_eq!(
+            config(MAX_FEE_BPS + 1, 0, 0, MIN_BET_AMOUNT, MAX_BET_AMOUNT),
+            Err(ErrorCode::InvalidFeeBps.into())
+        );
+        // The creator and LP shares split the platform fee, at most all of it
+        assert!(config(0, 6_000, 4_000, MIN_BET_AMOUNT, MAX_BET_AMOUNT).is_ok());
+        assert_eq!(
+            config(0, 6_000, 4_001, MIN_BET_AMOUNT, MAX_BET_AMOUNT),
+            Err(ErrorCode::InvalidFeeBps.into())
+        );
+        // ...and so does the referral share
+        assert_eq!(
+            validate_config(0, 6_000, 3_000, 1_001, MIN_BET_AMOUNT, MAX_BET_AMOUNT, CLOSE_GRACE_PERIOD),
+            Err(ErrorCode::InvalidFeeBps.into())
+        );
+        assert_eq!(
+            config(0, 0, 0, MIN_BET_AMOUNT - 1, MAX_BET_AMOUNT),
+            Err(ErrorCode::InvalidBetLimits.into())
+        );
+        assert_eq!(
+            config(0, 0, 0, MAX_BET_AMOUNT, MIN_BET_AMOUNT),
+            Err(ErrorCode::InvalidBetLimits.into())
+        );
+        // Bettors always get at least the cluster's grace period to claim
+        assert_eq!(
+            validate_config(0, 0, 0, 0, MIN_BET_AMOUNT, MAX_BET_AMOUNT, CLOSE_GRACE_PERIOD - 1),
+            Err(ErrorCode::InvalidClaimPeriod.into())
+        );
+    }
+
+    #[test]
+    fn polls_override_bet_limits_and_fee_within_config() {
+        let config = Config {
+            admin: Pubkey::default(),
+            treasury: Pubkey::default(),
+            fee_bps: 200,
+            min_bet: MIN_BET_AMOUNT,
+            max_bet: 100 * MIN_BET_AMOUNT,
+            bump: 0,
+            creation_bond: 0,
+            creator_fee_bps: 0,
+            lp_fee_bps: 0,
+            claim_period: CLOSE_GRACE_PERIOD,
+            pending_admin: None,
+            paused: false,
+            referral_fee_bps: 0,
+            fee_tiers: Vec::new(),
+            event_seq: 0,
+            usd_feed_id: [0; 32],
+            max_bid_usd: 0,
+            epoch_id: 0,
+            epoch_start: 0,
+            epoch_end: 0,
+        };
+        assert_eq!(
+            poll_limits(&config, &PollLimits::default()),
+            Ok((MIN_BET_AMOUNT, 100 * MIN_BET_AMOUNT, 200))
+        );
+        let high_stakes = PollLimits {
+            min_bet: Some(10 * MIN_BET_AMOUNT),
+            max_bet: None,
+            fee_bps: Some(MAX_FEE_BPS),
+        };
+        assert_eq!(
+            poll_limits(&config, &high_stakes),
+            Ok((10 * MIN_BET_AMOUNT, 100 * MIN_BET_AMOUNT, MAX_FEE_BPS))
+        );
+
+        for invalid in [
+            PollLimits { min_bet: Some(MIN_BET_AMOUNT - 1), ..PollLimits::default() },
+            PollLimits { max_bet: Some(100 * MIN_BET_AMOUNT + 1), ..PollLimits::default() },
+            PollLimits {
+                min_bet: Some(50 * MIN_BET_AMOUNT),
+                max_bet: Some(40 * MIN_BET_AMOUNT),
+                fee_bps: None,
+            },
+        ] {
+            assert_eq!(
+                poll_limits(&config, &invalid),
+                Err(ErrorCode::InvalidBetLimits.into())
+            );
+        }
+        // The fee can't undercut the platform's
+        for fee_bps in [199, MAX_FEE_BPS + 1] {
+            let limits = PollLimits {
+                fee_bps: Some(fee_bps),
+                ..PollLimits::default()
+            };
+            assert_eq!(
+                poll_limits(&config, &limits),
+                Err(ErrorCode::InvalidFeeBps.into())
+            );
+        }
+    }
+
+    #[test]
+    fn end_timestamp_respects_min_duration() {
+        assert!(validate_end_timestamp(1_000 + MIN_POLL_DURATION, 1_000).is_ok());
+        assert_eq!(
+            validate_end_timestamp(1_000 + MIN_POLL_DURATION - 1, 1_000),
+            Err(ErrorCode::InvalidEndTime.into())
+        );
+    }
+
+    #[test]
+    fn template_rounds_follow_the_schedule() {
+        const DAY: i64 = 24 * 60 * 60;
+        assert!(validate_template_prefix(&"a".repeat(MAX_TEMPLATE_PREFIX_LEN)).is_ok());
+        assert_eq!(
+            validate_template_prefix(&"a".repeat(MAX_TEMPLATE_PREFIX_LEN + 1)),
+            Err(ErrorCode::TemplatePrefixTooLong.into())
+        );
+        // The longest prefix and round still make a valid poll id
+        let poll_id = template_poll_id(&"a".repeat(MAX_TEMPLATE_PREFIX_LEN), u64::MAX);
+        assert!(validate_poll_metadata(&poll_id, "", &vec![String::new(); 2]).is_ok());
+
+        assert!(validate_template_schedule(1_000, DAY, MIN_POLL_DURATION).is_ok());
+        assert_eq!(
+            validate_template_schedule(1_000, 0, DAY),
+            Err(ErrorCode::InvalidTemplateSchedule.into())
+        );
+        assert_eq!(
+            validate_template_schedule(1_000, DAY, MIN_POLL_DURATION - 1),
+            Err(ErrorCode::InvalidEndTime.into())
+        );
+
+        let template = PollTemplate {
+            authority: Pubkey::default(),
+            id_prefix: "btc-daily".to_string(),
+            title: "Will BTC close green today?".to_string(),
+            outcomes: vec!["Yes".to_string(), "No".to_string()],
+            mint: None,
+            payout_mode: PayoutMode::PariMutuel,
+            resolution: None,
+            dispute_period: 0,
+            arbiter: None,
+            deadline_extension: None,
+            virtual_liquidity: 0,
+            kind: PollKind::Categorical,
+            limits: PollLimits::default(),
+            first_start: 1_000,
+            period: DAY,
+            duration: DAY - 60,
+            bump: 0,
+            event_seq: 0,
+        };
+        assert_eq!(
+            template_round(&template, 0),
+            Ok(("btc-daily-0".to_string(), 1_000, 1_000 + DAY - 60))
+        );
+        assert_eq!(
+            template_round(&template, 7),
+            Ok(("btc-daily-7".to_string(), 1_000 + 7 * DAY, 1_000 + 8 * DAY - 60))
+        );
+        assert_eq!(
+            template_round(&template, u64::MAX),
+            Err(ErrorCode::InvalidTemplateSchedule.into())
+        );
+    }
+
+    #[test]
+    fn scheduled_poll_opens_at_start_time() {
+        let mut poll = active_poll(1_000);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        poll.status = PollStatus::Scheduled;
+        poll.start_timestamp = 500;
+
+        assert_eq!(
+            record_bid(&mut poll, MIN_BET_AMOUNT, 0, 499),
+            Err(ErrorCode::PollNotStarted.into())
+        );
+        assert!(poll.status == PollStatus::Scheduled);
+        assert_eq!(poll.total_pool, 0);
+
+        // The first bid after the start time opens the poll
+        record_bid(&mut poll, MIN_BET_AMOUNT, 0, 500).unwrap();
+        assert!(poll.status == PollStatus::Active);
+        assert_eq!(poll.total_pool, MIN_BET_AMOUNT);
+
+        // A scheduled poll that never took a bid can still be settled
+        let mut poll = active_poll(1_000);
+        poll.status = PollStatus::Scheduled;
+        poll.start_timestamp = 500;
+        settle(&mut poll, 1, 1_000).unwrap();
+        assert!(poll.status == PollStatus::Settled);
+    }
+
+    #[test]
+    fn end_time_moves_within_guardrails() {
+        let mut poll = active_poll(10_000);
+
+        // Shortened, but no closer than the minimum duration from now
+        assert_eq!(
+            update_end_time(&mut poll, 1_000 + MIN_POLL_DURATION - 1, 1_000),
+            Err(ErrorCode::InvalidEndTime.into())
+        );
+        assert_eq!(
+            update_end_time(&mut poll, 1_000 + MIN_POLL_DURATION, 1_000),
+            Ok(10_000)
+        );
+
+        // Pushed back at most MAX_END_EXTENSION past the original end, even
+        // across several updates
+        update_end_time(&mut poll, 10_000 + MAX_END_EXTENSION, 1_000).unwrap();
+        assert_eq!(
+            update_end_time(&mut poll, 10_000 + MAX_END_EXTENSION + 1, 1_000),
+            Err(ErrorCode::InvalidEndTime.into())
+        );
+
+        // An ended poll keeps its end time
+        let end = poll.end_timestamp;
+        assert_eq!(
+            update_end_time(&mut poll, end + 1, end),
+            Err(ErrorCode::PollEnded.into())
+        );
+    }
+
+    #[test]
+    fn suspended_polls_take_no_bids_until_resumed() {
+        let mut poll = active_poll(10_000);
+        assert_eq!(resume(&mut poll), Err(ErrorCode::PollNotSuspended.into()));
+        suspend(&mut poll, 1_000).unwrap();
+        assert_eq!(suspend(&mut poll, 1_000), Err(ErrorCode::PollNotActive.into()));
+
+        assert_eq!(
+            validate_bid(&poll, MIN_BET_AMOUNT, 1_000),
+            Err(ErrorCode::PollNotActive.into())
+        );
+        assert_eq!(
+            settle(&mut poll.clone(), 0, 10_000),
+            Err(ErrorCode::PollNotActive.into())
+        );
+        // The authority can make up for the lost time while suspended
+        update_end_time(&mut poll, 12_000, 1_000).unwrap();
+
+        resume(&mut poll).unwrap();
+        assert!(poll.status == PollStatus::Active);
+        assert!(validate_bid(&poll, MIN_BET_AMOUNT, 1_000).is_ok());
+    }
+
+    #[test]
+    fn late_bids_extend_the_deadline() {
+        let extension = DeadlineExtension {
+            window: 60,
+            duration: 120,
+            max_extensions: 2,
+        };
+        assert!(validate_deadline_extension(Some(&extension)).is_ok());
+        assert_eq!(
+            validate_deadline_extension(Some(&DeadlineExtension {
+                duration: MAX_END_EXTENSION,
+                ..extension
+            })),
+            Err(ErrorCode::InvalidDeadlineExtension.into())
+        );
+
+        let mut poll = active_poll(1_000);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        poll.deadline_extension = Some(extension);
+
+        record_bid(&mut poll, MIN_BET_AMOUNT, 0, 939).unwrap();
+        assert_eq!(poll.end_timestamp, 1_000);
+
+        record_bid(&mut poll, MIN_BET_AMOUNT, 0, 940).unwrap();
+        assert_eq!(poll.end_timestamp, 1_120);
+        record_bid(&mut poll, MIN_BET_AMOUNT, 1, 1_119).unwrap();
+        assert_eq!(poll.end_timestamp, 1_240);
+
+        // Extensions used up: the next late bid leaves the end alone
+        record_bid(&mut poll, MIN_BET_AMOUNT, 1, 1_239).unwrap();
+        assert_eq!(poll.end_timestamp, 1_240);
+        assert_eq!(poll.extension_count, 2);
+    }
+
+    #[test]
+    fn lp_liquidity_backs_fixed_odds_and_takes_the_residual() {
+        let mut poll = active_poll(100);
+        poll.lp_fee_bps = 5_000;
+        poll.virtual_liquidity = 10 * MIN_BET_AMOUNT;
+
+        // Without backing, the first fixed-odds bid can't be covered
+        assert_eq!(
+            record_bid(&mut poll, MIN_BET_AMOUNT, 0, 1),
+            Err(ErrorCode::InsufficientPoolLiquidity.into())
+        );
+        assert_eq!(
+            provide_liquidity(&mut poll, 0, 1),
+            Err(ErrorCode::InvalidLiquidityAmount.into())
+        );
+        provide_liquidity(&mut poll, 10 * MIN_BET_AMOUNT, 1).unwrap();
+        assert_eq!(poll.outcomes[0].odds, 5_000);
+
+        let (_, win) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 1).unwrap();
+        assert_eq!(win, 2 * MIN_BET_AMOUNT);
+        record_bid(&mut poll, MIN_BET_AMOUNT, 1, 1).unwrap();
+        assert_eq!(
+            provide_liquidity(&mut poll, MIN_BET_AMOUNT, 100),
+            Err(ErrorCode::PollEnded.into())
+        );
+
+        let mut position = LpPosition {
+            poll: Pubkey::default(),
+            provider: Pubkey::default(),
+            amount: 4 * MIN_BET_AMOUNT,
+            fees_claimed: 0,
+            withdrawn: false,
+            bump: 0,
+        };
+        assert_eq!(
+            lp_withdrawal(&poll, &mut position),
+            Err(ErrorCode::PollNotFinalized.into())
+        );
+
+        // Outcome 0 wins: the LPs pay its locked-in win out of the pool
+        settle(&mut poll, 0, 100).unwrap();
+        let equity = 12 * MIN_BET_AMOUNT - win;
+        assert_eq!(lp_equity(&poll), Ok(equity));
+        accrue_fee(&mut poll, 1_000).unwrap();
+        assert_eq!(poll.lp_fees, 500);
+
+        let share = equity * 4 / 10 + 200;
+        assert_eq!(lp_withdrawal(&poll, &mut position), Ok(share));
+        assert_eq!(
+            lp_withdrawal(&poll, &mut position),
+            Err(ErrorCode::NothingToWithdraw.into())
+        );
+        // Fees accrued after a withdrawal can be collected later
+        accrue_fee(&mut poll, 1_000).unwrap();
+        assert_eq!(lp_withdrawal(&poll, &mut position), Ok(200));
+    }
+
+    #[test]
+    fn increased_bid_blends_its_odds() {
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        poll.virtual_liquidity = 10 * MIN_BET_AMOUNT;
+
+        let (odds, potential_win) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 1).unwrap();
+        let mut bid = active_bid(MIN_BET_AMOUNT, odds, potential_win);
+
+        // The increase is priced at the odds the first stake moved to
+        let moved_odds = poll.outcomes[0].odds;
+        assert_eq!(increase_bid(&mut poll, &mut bid, MIN_BET_AMOUNT, 2), Ok(moved_odds));
+        let added_win = super::potential_win(MIN_BET_AMOUNT, moved_odds).unwrap();
+        assert_eq!(bid.amount, 2 * MIN_BET_AMOUNT);
+        assert_eq!(bid.potential_win, potential_win + added_win);
+        assert!(odds < bid.odds_at_purchase && bid.odds_at_purchase < moved_odds);
+        assert_eq!(poll.outcomes[0].stake, 2 * MIN_BET_AMOUNT);
+
+        bid.status = BidStatus::Exited;
+        assert_eq!(
+            increase_bid(&mut poll, &mut bid, MIN_BET_AMOUNT, 3),
+            Err(ErrorCode::BidAlreadyClaimed.into())
+        );
+    }
+
+    #[test]
+    fn transferred_bid_changes_hands_before_settlement() {
+        let mut poll = active_poll(100);
+        let mut bid = active_bid(MIN_BET_AMOUNT, 20_000, 2 * MIN_BET_AMOUNT);
+        let owner = bid.bettor;
+        let new_bettor = Pubkey::new_unique();
+
+        for invalid in [owner, Pubkey::default()] {
+            assert_eq!(
+                transfer_bid(&poll, &mut bid, invalid),
+                Err(ErrorCode::InvalidNewBettor.into())
+            );
+        }
+        assert_eq!(transfer_bid(&poll, &mut bid, new_bettor), Ok(()));
+        assert_eq!(bid.bettor, new_bettor);
+
+        bid.status = BidStatus::Exited;
+        assert_eq!(
+            transfer_bid(&poll, &mut bid, owner),
+            Err(ErrorCode::BidAlreadyClaimed.into())
+        );
+
+        bid.status = BidStatus::Active;
+        poll.status = PollStatus::Settled;
+        assert_eq!(
+            transfer_bid(&poll, &mut bid, owner),
+            Err(ErrorCode::PollNotActive.into())
+        );
+    }
+
+    #[test]
+    fn outcome_tokens_replace_bids() {
+        let mut poll = active_poll(100);
+        poll.lp_liquidity = 10 * MIN_BET_AMOUNT;
+        assert_eq!(
+            record_outcome_token_bid(&mut poll, MIN_BET_AMOUNT, 0, 1),
+            Err(ErrorCode::NotOutcomeTokenMarket.into())
+        );
+        assert_eq!(enable_outcome_tokens(&mut poll), Ok(()));
+        assert_eq!(
+            record_bid(&mut poll, MIN_BET_AMOUNT, 0, 1),
+            Err(ErrorCode::OutcomeTokenMarket.into())
+        );
+
+        // Fixed-odds tokens are the locked-in potential win
+        let (odds, tokens) = record_outcome_token_bid(&mut poll, MIN_BET_AMOUNT, 0, 1).unwrap();
+        assert_eq!(Ok(tokens), potential_win(MIN_BET_AMOUNT, odds));
+        assert_eq!(
+            enable_outcome_tokens(&mut poll),
+            Err(ErrorCode::PollHasBids.into())
+        );
+        assert_eq!(
+            redeem_outcome_tokens(&mut poll, 0, tokens),
+            Err(ErrorCode::PollNotFinalized.into())
+        );
+
+        // A cancelled poll refunds the stake behind the tokens
+        let mut cancelled = poll.clone();
+        cancelled.status = PollStatus::Cancelled;
+        assert_eq!(
+            redeem_outcome_tokens(&mut cancelled, 0, tokens),
+            Ok((MIN_BET_AMOUNT, 0))
+        );
+
+        settle(&mut poll, 0, 100).unwrap();
+        assert_eq!(
+            redeem_outcome_tokens(&mut poll, 0, tokens),
+            split_platform_fee(tokens, poll.fee_bps)
+        );
+        assert_eq!(
+            redeem_outcome_tokens(&mut poll, 1, tokens),
+            Err(ErrorCode::BidDidNotWin.into())
+        );
+        assert_eq!(
+            redeem_outcome_tokens(&mut poll, 0, 0),
+            Err(ErrorCode::InvalidTokenAmount.into())
+        );
+    }
+
+    #[test]
+    fn pari_mutuel_outcome_tokens_are_stake() {
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        enable_outcome_tokens(&mut poll).unwrap();
+
+        let (_, tokens) = record_outcome_token_bid(&mut poll, MIN_BET_AMOUNT, 0, 1).unwrap();
+        assert_eq!(tokens, MIN_BET_AMOUNT);
+        record_outcome_token_bid(&mut poll, 3 * MIN_BET_AMOUNT, 1, 1).unwrap();
+
+        settle(&mut poll, 0, 100).unwrap();
+        assert_eq!(
+            redeem_outcome_tokens(&mut poll, 0, tokens),
+            split_platform_fee(4 * MIN_BET_AMOUNT, poll.fee_bps)
+        );
+    }
+
+    #[test]
+    fn profile_tracks_volume_and_net_pnl() {
+        let mut profile = UserProfile {
+            user: Pubkey::default(),
+            bids_placed: 0,
+            volume: 0,
+            wins: 0,
+            losses: 0,
+            claims: 0,
+            net_pnl: 0,
+            bump: 0,
+            claim_delegate: None,
+            event_seq: 0,
+            epoch_id: 0,
+            epoch_volume: 0,
+            epoch_pnl: 0,
+        };
+        let user = Pubkey::new_unique();
+        init_profile(&mut profile, user, 254);
+        init_profile(&mut profile, Pubkey::new_unique(), 1);
+        assert_eq!((profile.user, profile.bump), (user, 254));
+
+        assert_eq!(
+            set_claim_delegate(&mut profile, Some(user)),
+            Err(ErrorCode::InvalidClaimDelegate.into())
+        );
+        let operator = Pubkey::new_unique();
+        set_claim_delegate(&mut profile, Some(operator)).unwrap();
+        assert_eq!(profile.claim_delegate, Some(operator));
+        set_claim_delegate(&mut profile, None).unwrap();
+        assert_eq!(profile.claim_delegate, None);
+
+        // Epoch 1 counts activity from 100 to 200
+        let mut config = test_config();
+        (config.epoch_id, config.epoch_start, config.epoch_end) = (1, 100, 200);
+        for (amount, now) in [(100, 50), (200, 100), (300, 199)] {
+            profile_bid(&mut profile, &config, amount, now).unwrap();
+        }
+        assert_eq!((profile.bids_placed, profile.volume), (3, 600));
+        assert_eq!((profile.epoch_id, profile.epoch_volume), (1, 500));
+
+        profile_claim(&mut profile, &config, 100, 180, true, 150).unwrap();
+        // A scaled-down refund is a loss without counting as one
+        profile_claim(&mut profile, &config, 200, 150, false, 150).unwrap();
+        profile_loss(&mut profile, &config, 300, 200).unwrap();
+        assert_eq!((profile.wins, profile.losses, profile.claims), (1, 1, 2));
+        assert_eq!(profile.net_pnl, 80 - 50 - 300);
+        assert_eq!(profile.epoch_pnl, 80 - 50);
+
+        // The next epoch's first activity starts its counters from zero
+        (config.epoch_id, config.epoch_start, config.epoch_end) = (2, 300, 400);
+        profile_bid(&mut profile, &config, 100, 300).unwrap();
+        assert_eq!((profile.epoch_id, profile.epoch_volume, profile.epoch_pnl), (2, 100, 0));
+    }
+
+    fn test_config() -> Config {
+        Config {
+            admin: Pubkey::default(),
+            treasury: Pubkey::default(),
+            fee_bps: 200,
+            min_bet: MIN_BET_AMOUNT,
+            max_bet: MAX_BET_AMOUNT,
+            bump: 0,
+            creation_bond: 0,
+            creator_fee_bps: 0,
+            lp_fee_bps: 0,
+            claim_period: CLOSE_GRACE_PERIOD,
+            pending_admin: None,
+            paused: false,
+            referral_fee_bps: 0,
+            fee_tiers: Vec::new(),
+            event_seq: 0,
+            usd_feed_id: [0; 32],
+            max_bid_usd: 0,
+            epoch_id: 0,
+            epoch_start: 0,
+            epoch_end: 0,
+        }
+    }
+
+    #[test]
+    fn epochs_open_one_at_a_time_with_weights_summing_to_the_pool() {
+        let mut config = test_config();
+        let now = 2 * EPOCH_SUBMISSION_PERIOD;
+        let weights = [6_000, 3_000, 1_000];
+        assert_eq!(
+            validate_epoch(&config, 1, now, now + 100, &weights, 1_000, now),
+            Ok(())
+        );
+        for (epoch_id, start, end, weights, pool) in [
+            (2, now, now + 100, &weights[..], 1_000),
+            (1, now - 1, now + 100, &weights[..], 1_000),
+            (1, now, now, &weights[..], 1_000),
+            (1, now, now + 100, &weights[..2], 1_000),
+            (1, now, now + 100, &[5_000, 5_000, 0][..], 1_000),
+            (1, now, now + 100, &[1_000; MAX_EPOCH_WINNERS + 1][..], 1_000),
+            (1, now, now + 100, &weights[..], 0),
+        ] {
+            assert_eq!(
+                validate_epoch(&config, epoch_id, start, end, weights, pool, now),
+                Err(ErrorCode::InvalidEpoch.into())
+            );
+        }
+
+        // The next epoch waits until the last one's scores are in
+        (config.epoch_id, config.epoch_end) = (1, now);
+        let later = now + EPOCH_SUBMISSION_PERIOD;
+        assert_eq!(
+            validate_epoch(&config, 2, later, later + 100, &weights, 1_000, later - 1),
+            Err(ErrorCode::EpochStillOpen.into())
+        );
+        assert_eq!(
+            validate_epoch(&config, 2, later, later + 100, &weights, 1_000, later),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn epoch_leaderboard_ranks_and_pays_the_top_scores() {
+        let mut epoch = Epoch {
+            epoch_id: 1,
+            start_timestamp: 0,
+            end_timestamp: 100,
+            metric: EpochMetric::Volume,
+            reward_pool: 1_001,
+            weights: vec![6_000, 3_000, 1_000],
+            leaderboard: Vec::new(),
+            finalized: false,
+            paid: 0,
+            event_seq: 0,
+            bump: 0,
+        };
+        let mut config = test_config();
+        (config.epoch_id, config.epoch_start, config.epoch_end) = (1, 0, 100);
+        let profiles: Vec<UserProfile> = [300, 500, 100, 500, 0]
+            .into_iter()
+            .map(|volume| {
+                let mut profile = UserProfile {
+                    user: Pubkey::new_unique(),
+                    bids_placed: 0,
+                    volume: 0,
+                    wins: 0,
+                    losses: 0,
+                    claims: 0,
+                    net_pnl: 0,
+                    bump: 0,
+                    claim_delegate: None,
+                    event_seq: 0,
+                    epoch_id: 0,
+                    epoch_volume: 0,
+                    epoch_pnl: 0,
+                };
+                profile_bid(&mut profile, &config, volume, 50).unwrap();
+                profile
+            })
+            .collect();
+
+        // Scores are final only once the epoch ends
+        assert_eq!(
+            submit_epoch_score(&mut epoch, &profiles[0], 99),
+            Err(ErrorCode::EpochSubmissionClosed.into())
+        );
+        assert_eq!(submit_epoch_score(&mut epoch, &profiles[0], 100), Ok((300, 0)));
+        assert_eq!(submit_epoch_score(&mut epoch, &profiles[1], 100), Ok((500, 0)));
+        assert_eq!(submit_epoch_score(&mut epoch, &profiles[2], 100), Ok((100, 2)));
+        // Ties rank below the earlier submission, pushing the lowest off
+        assert_eq!(submit_epoch_score(&mut epoch, &profiles[3], 100), Ok((500, 1)));
+        let ranked: Vec<Pubkey> = epoch.leaderboard.iter().map(|entry| entry.user).collect();
+        assert_eq!(ranked, [profiles[1].user, profiles[3].user, profiles[0].user]);
+        assert_eq!(
+            submit_epoch_score(&mut epoch, &profiles[2], 100),
+            Err(ErrorCode::ScoreBelowLeaderboard.into())
+        );
+        assert_eq!(
+            submit_epoch_score(&mut epoch, &profiles[1], 100),
+            Err(ErrorCode::AlreadyOnLeaderboard.into())
+        );
+        assert_eq!(
+            submit_epoch_score(&mut epoch, &profiles[4], 100),
+            Err(ErrorCode::NoEpochScore.into())
+        );
+
+        let closes = 100 + EPOCH_SUBMISSION_PERIOD;
+        assert_eq!(
+            claim_epoch_reward(&mut epoch, profiles[1].user),
+            Err(ErrorCode::EpochNotFinalized.into())
+        );
+        assert_eq!(
+            finalize_epoch(&mut epoch, closes - 1),
+            Err(ErrorCode::EpochNotFinalizable.into())
+        );
+        // 60%, 30%, and 10% of 1001, rounded down; the dust goes back
+        assert_eq!(finalize_epoch(&mut epoch, closes), Ok(1));
+        assert_eq!(
+            finalize_epoch(&mut epoch, closes),
+            Err(ErrorCode::EpochAlreadyFinalized.into())
+        );
+
+        assert_eq!(claim_epoch_reward(&mut epoch, profiles[3].user), Ok((1, 300)));
+        assert_eq!(
+            claim_epoch_reward(&mut epoch, profiles[3].user),
+            Err(ErrorCode::EpochRewardAlreadyClaimed.into())
+        );
+        assert_eq!(
+            claim_epoch_reward(&mut epoch, profiles[2].user),
+            Err(ErrorCode::NotOnLeaderboard.into())
+        );
+        assert_eq!(claim_epoch_reward(&mut epoch, profiles[1].user), Ok((0, 600)));
+        assert_eq!(epoch.paid, 900);
+    }
+
+    #[test]
+    fn authority_transfer_needs_acceptance() {
+        let current = Pubkey::new_unique();
+        let next = Pubkey::new_unique();
+        let mut pending = None;
+
+        for invalid in [current, Pubkey::default()] {
+            assert_eq!(
+                propose_transfer(&mut pending, current, Some(invalid)),
+                Err(ErrorCode::InvalidNewAuthority.into())
+            );
+        }
+        assert_eq!(
+            accept_transfer(&mut pending, next),
+            Err(ErrorCode::NotPendingAuthority.into())
+        );
+
+        propose_transfer(&mut pending, current, Some(next)).unwrap();
+        assert_eq!(
+            accept_transfer(&mut pending, current),
+            Err(ErrorCode::NotPendingAuthority.into())
+        );
+        assert_eq!(accept_transfer(&mut pending, next), Ok(()));
+        assert_eq!(pending, None);
+
+        // A withdrawn proposal can't be accepted
+        propose_transfer(&mut pending, current, Some(next)).unwrap();
+        propose_transfer(&mut pending, current, None).unwrap();
+        assert_eq!(
+            accept_transfer(&mut pending, next),
+            Err(ErrorCode::NotPendingAuthority.into())
+        );
+    }
+
+    #[test]
+    fn pool_option_and_user_caps() {
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        assert_eq!(
+            set_caps(&mut poll, Some(0), None, None),
+            Err(ErrorCode::InvalidBetCap.into())
+        );
+        set_caps(
+            &mut poll,
+            Some(5 * MIN_BET_AMOUNT),
+            Some(3 * MIN_BET_AMOUNT),
+            Some(2 * MIN_BET_AMOUNT),
+        )
+        .unwrap();
+
+        record_bid(&mut poll, 3 * MIN_BET_AMOUNT, 0, 1).unwrap();
+        assert_eq!(
+            record_bid(&mut poll, MIN_BET_AMOUNT, 0, 1),
+            Err(ErrorCode::BetCapExceeded.into())
+        );
+        // A bid past a cap fills up to it, unless nothing is left
+        assert_eq!(
+            fill_amount(&poll, 4 * MIN_BET_AMOUNT, 1, 1),
+            Ok(2 * MIN_BET_AMOUNT)
+        );
+        assert_eq!(
+            fill_amount(&poll, MIN_BET_AMOUNT, 0, 1),
+            Err(ErrorCode::BetCapExceeded.into())
+        );
+        record_bid(&mut poll, 2 * MIN_BET_AMOUNT, 1, 1).unwrap();
+        assert_eq!(
+            record_bid(&mut poll, MIN_BET_AMOUNT, 1, 1),
+            Err(ErrorCode::BetCapExceeded.into())
+        );
+        assert_eq!(poll.total_pool, 5 * MIN_BET_AMOUNT);
+
+        let mut user_stake = UserStake {
+            poll: Pubkey::default(),
+            user: Pubkey::default(),
+            stake: 0,
+            bump: 0,
+        };
+        record_user_stake(&poll, &mut user_stake, 2 * MIN_BET_AMOUNT).unwrap();
+        assert_eq!(
+            record_user_stake(&poll, &mut user_stake, 1),
+            Err(ErrorCode::BetCapExceeded.into())
+        );
+        assert_eq!(
+            validate_no_user_cap(&poll),
+            Err(ErrorCode::UserStakeRequired.into())
+        );
+
+        // Lifting the caps reopens the poll
+        set_caps(&mut poll, None, None, None).unwrap();
+        record_bid(&mut poll, MIN_BET_AMOUNT, 1, 1).unwrap();
+        assert!(validate_no_user_cap(&poll).is_ok());
+    }
+
+    #[test]
+    fn positions_list_bids_while_there_is_room() {
+        let mut position = UserPollPosition {
+            poll: Pubkey::default(),
+            user: Pubkey::default(),
+            stakes: [0; MAX_OUTCOMES],
+            bid_count: 0,
+            bid_indices: Vec::new(),
+            bump: 0,
+            last_bid_ts: 0,
+        };
+        let user = Pubkey::new_unique();
+        init_position(&mut position, Pubkey::new_unique(), user, 7);
+        init_position(&mut position, Pubkey::new_unique(), Pubkey::new_unique(), 9);
+        assert_eq!(position.user, user);
+        assert_eq!(position.bump, 7);
+
+        for index in 0..MAX_POSITION_BIDS as u64 + 2 {
+            record_position(&mut position, 1, 100, Some(index)).unwrap();
+        }
+        record_position(&mut position, 2, 50, None).unwrap();
+        assert_eq!(position.stakes[1], 100 * (MAX_POSITION_BIDS as u64 + 2));
+        assert_eq!(position.stakes[2], 50);
+        assert_eq!(position.bid_count, MAX_POSITION_BIDS as u32 + 2);
+        assert_eq!(position.bid_indices.len(), MAX_POSITION_BIDS);
+        assert_eq!(position.bid_indices[MAX_POSITION_BIDS - 1], MAX_POSITION_BIDS as u64 - 1);
+    }
+
+    #[test]
+    fn bid_cooldown_spaces_out_a_users_bids() {
+        let mut poll = active_poll(i64::MAX);
+        let mut position = UserPollPosition {
+            poll: Pubkey::default(),
+            user: Pubkey::new_unique(),
+            stakes: [0; MAX_OUTCOMES],
+            bid_count: 0,
+            bid_indices: Vec::new(),
+            bump: 0,
+            last_bid_ts: 0,
+        };
+        // Off by default: back-to-back bids go through
+        record_bid_time(&poll, &mut position, 10).unwrap();
+        record_bid_time(&poll, &mut position, 10).unwrap();
+        assert!(validate_no_bid_cooldown(&poll).is_ok());
+
+        assert_eq!(
+            set_bid_cooldown(&mut poll, MAX_BID_COOLDOWN + 1),
+            Err(ErrorCode::InvalidBidCooldown.into())
+        );
+        set_bid_cooldown(&mut poll, 30).unwrap();
+        assert_eq!(
+            record_bid_time(&poll, &mut position, 39),
+            Err(ErrorCode::BidCooldown.into())
+        );
+        record_bid_time(&poll, &mut position, 40).unwrap();
+        assert_eq!(position.last_bid_ts, 40);
+        assert_eq!(
+            validate_no_bid_cooldown(&poll),
+            Err(ErrorCode::PositionRequired.into())
+        );
+        let order = PendingOrder {
+            poll: Pubkey::default(),
+            bettor: Pubkey::new_unique(),
+            order_id: 0,
+            amount: MIN_BET_AMOUNT,
+            option: 0,
+            max_odds: MAX_ODDS_BPS,
+            created_at: 0,
+            bump: 0,
+        };
+        assert_eq!(
+            match_order(&mut poll, &order, 40),
+            Err(ErrorCode::PositionRequired.into())
+        );
+
+        // A new user's first bid isn't held back
+        position.last_bid_ts = 0;
+        record_bid_time(&poll, &mut position, 41).unwrap();
+    }
+
+    #[test]
+    fn gated_polls_admit_allowlisted_bettors_holders_and_attested_users() {
+        let mut poll = active_poll(i64::MAX);
+        let bettors: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = bettors.iter().map(allowlist_leaf).collect();
+        // Three leaves: the third is paired with the parent of the first two
+        let parent = allowlist_node(&leaves[0], &leaves[1]);
+        let root = allowlist_node(&parent, &leaves[2]);
+        set_gate(&mut poll, Some(AccessGate::Allowlist { root })).unwrap();
+
+        let proof = [leaves[1], leaves[2]];
+        assert!(validate_gate(&poll, bettors[0], &proof, None, None, 0).is_ok());
+        let proof = [leaves[0], leaves[2]];
+        assert!(validate_gate(&poll, bettors[1], &proof, None, None, 0).is_ok());
+        assert!(validate_gate(&poll, bettors[2], &[parent], None, None, 0).is_ok());
+        assert_eq!(
+            validate_gate(&poll, Pubkey::new_unique(), &[parent], None, None, 0),
+            Err(ErrorCode::NotAllowlisted.into())
+        );
+        assert_eq!(
+            validate_gate(&poll, bettors[0], &[leaves[1]], None, None, 0),
+            Err(ErrorCode::NotAllowlisted.into())
+        );
+        assert_eq!(validate_ungated(&poll), Err(ErrorCode::GatedPoll.into()));
+
+        let mint = Pubkey::new_unique();
+        assert_eq!(
+            set_gate(&mut poll, Some(AccessGate::TokenHolding { mint, min_amount: 0 })),
+            Err(ErrorCode::InvalidAccessGate.into())
+        );
+        set_gate(&mut poll, Some(AccessGate::TokenHolding { mint, min_amount: 5 })).unwrap();
+        let holding = TokenHolding {
+            owner: bettors[0],
+            mint,
+            amount: 5,
+        };
+        assert!(validate_gate(&poll, bettors[0], &[], Some(holding), None, 0).is_ok());
+        assert_eq!(
+            validate_gate(&poll, bettors[0], &[], None, None, 0),
+            Err(ErrorCode::GateTokenRequired.into())
+        );
+        assert_eq!(
+            validate_gate(&poll, bettors[1], &[], Some(holding), None, 0),
+            Err(ErrorCode::GateTokenRequired.into())
+        );
+        let short = TokenHolding {
+            amount: 4,
+            ..holding
+        };
+        assert_eq!(
+            validate_gate(&poll, bettors[0], &[], Some(short), None, 0),
+            Err(ErrorCode::InsufficientGateHolding.into())
+        );
+
+        let attestor = Pubkey::new_unique();
+        assert_eq!(
+            set_gate(
+                &mut poll,
+                Some(AccessGate::Attestation {
+                    attestor: Pubkey::default()
+                })
+            ),
+            Err(ErrorCode::InvalidAccessGate.into())
+        );
+        set_gate(&mut poll, Some(AccessGate::Attestation { attestor })).unwrap();
+        let attestation = Attestation {
+            attestor,
+            user: bettors[0],
+            issued_at: 0,
+            expires_at: 100,
+            bump: 0,
+            event_seq: 0,
+        };
+        assert!(validate_gate(&poll, bettors[0], &[], None, Some(&attestation), 99).is_ok());
+        assert_eq!(
+            validate_gate(&poll, bettors[0], &[], None, Some(&attestation), 100),
+            Err(ErrorCode::AttestationExpired.into())
+        );
+        // Another user's credential, or one from another attestor, doesn't count
+        assert_eq!(
+            validate_gate(&poll, bettors[1], &[], None, Some(&attestation), 0),
+            Err(ErrorCode::AttestationRequired.into())
+        );
+        let foreign = Attestation {
+            attestor: Pubkey::new_unique(),
+            expires_at: 0,
+            ..attestation
+        };
+        assert_eq!(
+            validate_gate(&poll, bettors[0], &[], None, Some(&foreign), 0),
+            Err(ErrorCode::AttestationRequired.into())
+        );
+        assert_eq!(
+            validate_gate(&poll, bettors[0], &[], Some(holding), None, 0),
+            Err(ErrorCode::AttestationRequired.into())
+        );
+        assert_eq!(validate_attestation_expiry(0, 50), Ok(()));
+        assert_eq!(validate_attestation_expiry(51, 50), Ok(()));
+        assert_eq!(
+            validate_attestation_expiry(50, 50),
+            Err(ErrorCode::InvalidAttestation.into())
+        );
+
+        set_gate(&mut poll, None).unwrap();
+        assert!(validate_gate(&poll, bettors[1], &[], None, None, 0).is_ok());
+        assert!(validate_ungated(&poll).is_ok());
+    }
+
+    #[test]
+    fn bid_window_and_amount() {
+        let poll = active_poll(100);
+        assert!(validate_bid(&poll, MIN_BET_AMOUNT, 99).is_ok());
+        assert_eq!(
+            validate_bid(&poll, MIN_BET_AMOUNT, 100),
+            Err(ErrorCode::PollEnded.into())
+        );
+        assert_eq!(
+            validate_bid(&poll, MIN_BET_AMOUNT - 1, 0),
+            Err(ErrorCode::InvalidBetAmount.into())
+        );
+        assert_eq!(
+            validate_bid(&poll, MAX_BET_AMOUNT + 1, 0),
+            Err(ErrorCode::InvalidBetAmount.into())
+        );
+    }
+
+    #[test]
+    fn record_bid_prices_at_pre_bid_odds_and_moves_odds() {
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        let (odds, win) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0).unwrap();
+        assert_eq!(odds, 5000);
+        assert_eq!(win, MIN_BET_AMOUNT * 2);
+        assert_eq!(poll.total_pool, MIN_BET_AMOUNT);
+        assert_eq!((poll.outcomes[0].odds, poll.outcomes[1].odds), (9500, 500));
+
+        let (odds, _) = record_bid(&mut poll, MIN_BET_AMOUNT, 1, 0).unwrap();
+        assert_eq!(odds, 500);
+        assert_eq!((poll.outcomes[0].odds, poll.outcomes[1].odds), (5000, 5000));
+    }
+
+    #[test]
+    fn outcome_labels_are_hashed() {
+        let outcomes = new_outcomes(&["Yes".to_string(), "No".to_string()]).unwrap();
+        assert_eq!(outcomes[0].text_hash, text_hash("Yes"));
+        assert_eq!(outcomes[1].text_hash, text_hash("No"));
+        assert_ne!(text_hash("Yes"), text_hash("yes"));
+    }
+
+    #[test]
+    fn multi_outcome_bids_reprice_every_outcome() {
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        poll.outcomes = new_outcomes(&[String::new(), String::new(), String::new(), String::new()])
+            .unwrap();
+        assert!(poll.outcomes.iter().all(|outcome| outcome.odds == 2500));
+
+        record_bid(&mut poll, MIN_BET_AMOUNT, 2, 0).unwrap();
+        record_bid(&mut poll, MIN_BET_AMOUNT, 3, 0).unwrap();
+        let odds: Vec<u64> = poll.outcomes.iter().map(|outcome| outcome.odds).collect();
+        assert_eq!(odds, vec![500, 500, 5000, 5000]);
+
+        assert_eq!(
+            record_bid(&mut poll, MIN_BET_AMOUNT, 4, 0),
+            Err(ErrorCode::InvalidOutcome.into())
+        );
+        assert_eq!(
+            settle(&mut poll, 4, 100),
+            Err(ErrorCode::InvalidOutcome.into())
+        );
+    }
+
+    #[test]
+    fn fixed_odds_bids_must_be_covered_by_the_pool() {
+        let mut poll = active_poll(100);
+        // Nothing backs a fixed-odds win on an empty pool
+        assert_eq!(
+            record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0),
+            Err(ErrorCode::InsufficientPoolLiquidity.into())
+        );
+        assert_eq!(poll.total_pool, 0);
+
+        // 9 units already staked on outcome 1 back a 2x win on outcome 0
+        poll.outcomes[1].stake = 9 * MIN_BET_AMOUNT;
+        poll.total_pool = 9 * MIN_BET_AMOUNT;
+        update_amm_odds(&mut poll).unwrap();
+        poll.outcomes[0].odds = 5000;
+        let (_, win) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0).unwrap();
+        assert_eq!(poll.outcomes[0].total_potential_liability, win);
+
+        // Another 10x win on top of the first is more than the 11-unit pool holds
+        assert_eq!(poll.outcomes[0].odds, 1000);
+        assert_eq!(
+            record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0),
+            Err(ErrorCode::InsufficientPoolLiquidity.into())
+        );
+        assert_eq!(poll.total_pool, 10 * MIN_BET_AMOUNT);
+        // Even the part of it the pool could back is under the minimum bet
+        assert_eq!(
+            fill_amount(&poll, MIN_BET_AMOUNT, 0, 0),
+            Err(ErrorCode::InsufficientPoolLiquidity.into())
+        );
+
+        // With 10 units of LP backing, 2 units' 10x win fits: 2 + 20 <= 20 + 2
+        poll.lp_liquidity = 10 * MIN_BET_AMOUNT;
+        let filled = fill_amount(&poll, 5 * MIN_BET_AMOUNT, 0, 0).unwrap();
+        assert_eq!(filled, 2 * MIN_BET_AMOUNT);
+        record_bid(&mut poll, filled, 0, 0).unwrap();
+    }
+
+    #[test]
+    fn adapter_polls_reject_amm_bids() {
+        let mut poll = active_poll(100);
+        poll.adapter = Some(Pubkey::new_unique());
+        assert_eq!(
+            record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0),
+            Err(ErrorCode::AdapterMarket.into())
+        );
+        assert_eq!(poll.total_pool, 0);
+    }
+
+    #[test]
+    fn exit_pays_current_value_and_reprices() {
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        let (_, win) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0).unwrap();
+        record_bid(&mut poll, 3 * MIN_BET_AMOUNT, 0, 0).unwrap();
+        record_bid(&mut poll, 4 * MIN_BET_AMOUNT, 1, 0).unwrap();
+
+        // Bought at 50% and still at 50%: the stake comes back, less the fee
+        let odds = poll.outcomes[0].odds;
+        let value = Bps::new(odds).apply(win, Rounding::Down).unwrap();
+        let (payout, fee) = exit_position(&mut poll, 0, MIN_BET_AMOUNT, win, 1).unwrap();
+        assert_eq!(value, MIN_BET_AMOUNT);
+        assert_eq!(Ok((payout, fee)), split_platform_fee(value, poll.fee_bps));
+        assert_eq!(poll.total_pool, 8 * MIN_BET_AMOUNT - value);
+        assert_eq!(poll.accrued_fees, fee);
+        assert_eq!(poll.outcomes[0].stake, 3 * MIN_BET_AMOUNT);
+        assert_eq!(
+            poll.outcomes[0].odds,
+            amm_odds(3 * MIN_BET_AMOUNT, poll.total_pool, 2, 0).unwrap()
+        );
+
+        assert_eq!(
+            exit_position(&mut poll, 0, MIN_BET_AMOUNT, win, 100),
+            Err(ErrorCode::PollEnded.into())
+        );
+    }
+
+    #[test]
+    fn fixed_odds_exits_must_leave_wins_covered() {
+        let mut poll = active_poll(100);
+        poll.outcomes[1].stake = 9 * MIN_BET_AMOUNT;
+        poll.total_pool = 9 * MIN_BET_AMOUNT;
+        update_amm_odds(&mut poll).unwrap();
+        poll.outcomes[0].odds = 5000;
+        let (_, win) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0).unwrap();
+
+        // Bought at 50%, sold at 10%: a loss, and nothing else needs covering
+        let mut exited = poll.clone();
+        let (payout, fee) = exit_position(&mut exited, 0, MIN_BET_AMOUNT, win, 1).unwrap();
+        assert_eq!(payout + fee, win / 10);
+        assert_eq!(exited.outcomes[0].total_potential_liability, 0);
+
+        // Once outcome 1 also has wins to cover, draining the pool is rejected
+        poll.outcomes[1].total_potential_liability = poll.total_pool;
+        assert_eq!(
+            exit_position(&mut poll, 0, MIN_BET_AMOUNT, win, 1),
+            Err(ErrorCode::InsufficientPoolLiquidity.into())
+        );
+    }
+
+    #[test]
+    fn finalize_waits_for_every_winning_claim() {
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        let (_, win) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0).unwrap();
+        record_bid(&mut poll, 3 * MIN_BET_AMOUNT, 0, 0).unwrap();
+        record_bid(&mut poll, 4 * MIN_BET_AMOUNT, 1, 0).unwrap();
+        // Stake added to a bid isn't a new bid, and an exited bid is owed nothing
+        let mut bid = active_bid(4 * MIN_BET_AMOUNT, 5_000, 8 * MIN_BET_AMOUNT);
+        bid.option = 1;
+        increase_bid(&mut poll, &mut bid, MIN_BET_AMOUNT, 0).unwrap();
+        exit_position(&mut poll, 0, MIN_BET_AMOUNT, win, 1).unwrap();
+        assert_eq!(poll.bid_counts, vec![1, 1]);
+
+        assert_eq!(finalize(&mut poll), Err(ErrorCode::PollNotSettled.into()));
+        settle(&mut poll, 0, 100).unwrap();
+        assert_eq!(
+            finalize(&mut poll.clone()),
+            Err(ErrorCode::ClaimsOutstanding.into())
+        );
+        count_claim(&mut poll).unwrap();
+        // The exit fee is still unswept
+        assert_eq!(
+            finalize(&mut poll.clone()),
+            Err(ErrorCode::ClaimsOutstanding.into())
+        );
+        poll.accrued_fees = 0;
+        finalize(&mut poll).unwrap();
+        assert!(poll.status == PollStatus::Finalized);
+        assert!(validate_poll_close(&poll, 100).is_ok());
+        assert!(validate_bid_close(&poll, BidStatus::Active, 1).is_ok());
+
+        // A migrated poll's earlier bids weren't counted
+        let mut legacy = active_poll(100);
+        legacy.payout_mode = PayoutMode::PariMutuel;
+        legacy.bid_counts = Vec::new();
+        record_bid(&mut legacy, MIN_BET_AMOUNT, 0, 0).unwrap();
+        settle(&mut legacy, 0, 100).unwrap();
+        assert_eq!(finalize(&mut legacy), Err(ErrorCode::ClaimsNotTracked.into()));
+    }
+
+    #[test]
+    fn token_2022_transfer_fees_are_charged_on_stakes() {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token_2022::spl_token_2022::extension::{
+            non_transferable::NonTransferable, transfer_fee::TransferFee,
+            BaseStateWithExtensionsMut, StateWithExtensionsMut,
+        };
+
+        let base = Mint2022 { decimals: 6, is_initialized: true, ..Default::default() };
+        let mut classic = vec![0; Mint2022::LEN];
+        Mint2022::pack(base, &mut classic).unwrap();
+        assert!(validate_stake_mint(&classic).is_ok());
+        assert!(!has_transfer_fee(&classic).unwrap());
+        assert_eq!(transfer_fee(&classic, 100_000, 0).unwrap(), 0);
+
+        // 1% up to 5,000 base units
+        let len = ExtensionType::try_calculate_account_len::<Mint2022>(&[
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let mut with_fee = vec![0; len];
+        let mut state =
+            StateWithExtensionsMut::<Mint2022>::unpack_uninitialized(&mut with_fee).unwrap();
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: 5_000.into(),
+            transfer_fee_basis_points: 100.into(),
+        };
+        config.older_transfer_fee = fee;
+        config.newer_transfer_fee = fee;
+        state.base = base;
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        assert!(validate_stake_mint(&with_fee).is_ok());
+        assert!(has_transfer_fee(&with_fee).unwrap());
+        assert_eq!(transfer_fee(&with_fee, 100_000, 0).unwrap(), 1_000);
+        assert_eq!(transfer_fee(&with_fee, 100_001, 0).unwrap(), 1_001);
+        assert_eq!(transfer_fee(&with_fee, 10_000_000, 0).unwrap(), 5_000);
+
+        let len = ExtensionType::try_calculate_account_len::<Mint2022>(&[
+            ExtensionType::NonTransferable,
+        ])
+        .unwrap();
+        let mut soulbound = vec![0; len];
+        let mut state =
+            StateWithExtensionsMut::<Mint2022>::unpack_uninitialized(&mut soulbound).unwrap();
+        state.init_extension::<NonTransferable>(true).unwrap();
+        state.base = base;
+        state.pack_base();
+        state.init_account_type().unwrap();
+        assert_eq!(
+            validate_stake_mint(&soulbound),
+            Err(ErrorCode::UnsupportedMint.into())
+        );
+    }
+
+    #[test]
+    fn price_impact_limit_trims_large_bids() {
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        assert_eq!(
+            set_max_price_impact(&mut poll, 10_001),
+            Err(ErrorCode::InvalidPriceImpact.into())
+        );
+        set_max_price_impact(&mut poll, 500).unwrap();
+
+        // An empty pool without virtual liquidity jumps to the odds bounds
+        assert_eq!(
+            fill_amount(&poll, MIN_BET_AMOUNT, 0, 0),
+            Err(ErrorCode::PriceImpactTooHigh.into())
+        );
+
+        poll.virtual_liquidity = 10 * MIN_BET_AMOUNT;
+        update_amm_odds(&mut poll).unwrap();
+        assert_eq!(fill_amount(&poll, MIN_BET_AMOUNT, 0, 0), Ok(MIN_BET_AMOUNT));
+
+        // 50% to 55% takes 2.22 minimum bets of the requested 20
+        let filled = fill_amount(&poll, 20 * MIN_BET_AMOUNT, 0, 0).unwrap();
+        assert!(filled > 2 * MIN_BET_AMOUNT && filled < 3 * MIN_BET_AMOUNT);
+        assert_eq!(
+            record_bid(&mut poll.clone(), filled + 1, 0, 0),
+            Err(ErrorCode::PriceImpactTooHigh.into())
+        );
+        record_bid(&mut poll, filled, 0, 0).unwrap();
+        assert_eq!(poll.outcomes[0].odds, 5_500);
+
+        let quote = quote_bid(&poll, 20 * MIN_BET_AMOUNT, 1, 0, 0).unwrap();
+        assert!(quote.amount < 20 * MIN_BET_AMOUNT);
+        assert!(quote.post_trade_odds[1] - poll.outcomes[1].odds <= 500);
+    }
+
+    #[test]
+    fn bids_cancel_within_the_window_for_a_fee() {
+        let mut poll = active_poll(1_000);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        let (odds, win) = record_bid(&mut poll, 2 * MIN_BET_AMOUNT, 0, 10).unwrap();
+        record_bid(&mut poll, 2 * MIN_BET_AMOUNT, 1, 10).unwrap();
+        let mut bid = active_bid(2 * MIN_BET_AMOUNT, odds, win);
+        bid.placed_at = 10;
+
+        // Off until the authority sets a window
+        assert_eq!(
+            cancel_position(&mut poll.clone(), &bid, 20),
+            Err(ErrorCode::CancelWindowClosed.into())
+        );
+        assert_eq!(
+            set_cancel_window(&mut poll, MAX_CANCEL_WINDOW + 1, 100),
+            Err(ErrorCode::InvalidCancelWindow.into())
+        );
+        assert_eq!(
+            set_cancel_window(&mut poll, 300, MAX_CANCEL_FEE_BPS + 1),
+            Err(ErrorCode::InvalidCancelWindow.into())
+        );
+        set_cancel_window(&mut poll, 300, 100).unwrap();
+
+        assert_eq!(
+            cancel_position(&mut poll.clone(), &bid, 311),
+            Err(ErrorCode::CancelWindowClosed.into())
+        );
+        // A bid from before version 5 has no placement time
+        let mut legacy = bid.clone();
+        legacy.placed_at = 0;
+        assert_eq!(
+            cancel_position(&mut poll.clone(), &legacy, 20),
+            Err(ErrorCode::CancelWindowClosed.into())
+        );
+
+        let (refund, fee) = cancel_position(&mut poll, &bid, 310).unwrap();
+        assert_eq!(Ok((refund, fee)), split_platform_fee(2 * MIN_BET_AMOUNT, 100));
+        assert_eq!(poll.accrued_fees, fee);
+        assert_eq!(poll.total_pool, 2 * MIN_BET_AMOUNT);
+        assert_eq!(poll.outcomes[0].stake, 0);
+        assert_eq!(poll.outcomes[0].total_potential_liability, 0);
+        assert_eq!(Ok(poll.outcomes[0].odds), amm_odds(0, poll.total_pool, 2, 0));
+    }
+
+    #[test]
+    fn refunds_scale_down_when_pool_is_short() {
+        let mut poll = active_poll(100);
+        poll.outcomes[0].stake = 3;
+        poll.outcomes[1].stake = 1;
+        poll.total_pool = 4;
+        assert_eq!(refund_amount(&poll, 3), Ok(3));
+
+        poll.total_pool = 2;
+        assert_eq!(refund_amount(&poll, 3), Ok(1));
+        assert_eq!(refund_amount(&poll, 1), Ok(0));
+
+        poll.lp_liquidity = u64::MAX;
+        assert_eq!(refund_amount(&poll, 1), Err(ErrorCode::MathOverflow.into()));
+
+        // Pushed refunds tip the cranker, never more than the refund
+        assert_eq!(refund_tip(MIN_BET_AMOUNT), (MIN_BET_AMOUNT - REFUND_TIP, REFUND_TIP));
+        assert_eq!(refund_tip(1), (0, 1));
+    }
+
+    #[test]
+    fn settle_only_after_end() {
+        let mut poll = active_poll(100);
+        assert_eq!(
+            settle(&mut poll, 1, 99),
+            Err(ErrorCode::PollNotEnded.into())
+        );
+        settle(&mut poll, 1, 100).unwrap();
+        assert!(poll.status == PollStatus::Settled);
+        assert_eq!(
+            settle(&mut poll, 0, 200),
+            Err(ErrorCode::PollNotActive.into())
+        );
+    }
+
+    #[test]
+    fn council_settles_once_threshold_votes_agree() {
+        let mut poll = active_poll(100);
+        let resolvers: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        for (keys, threshold) in [
+            (Vec::new(), 1),
+            (resolvers.clone(), 0),
+            (resolvers.clone(), 4),
+            (vec![resolvers[0], resolvers[0]], 1),
+            (vec![Pubkey::new_unique(); MAX_RESOLVERS + 1], 1),
+        ] {
+            assert_eq!(
+                set_resolvers(&mut poll, keys, threshold),
+                Err(ErrorCode::InvalidResolvers.into())
+            );
+        }
+        set_resolvers(&mut poll, resolvers.clone(), 2).unwrap();
+        assert_eq!(
+            set_resolvers(&mut poll, resolvers.clone(), 2),
+            Err(ErrorCode::SettlerAlreadySet.into())
+        );
+        assert_eq!(
+            validate_authority_settlement(&poll),
+            Err(ErrorCode::ResolverVoteRequired.into())
+        );
+
+        assert_eq!(
+            vote_settlement(&mut poll, resolvers[0], 1, 99),
+            Err(ErrorCode::PollNotEnded.into())
+        );
+        assert_eq!(
+            vote_settlement(&mut poll, Pubkey::new_unique(), 1, 100),
+            Err(ErrorCode::NotResolver.into())
+        );
+        assert_eq!(vote_settlement(&mut poll, resolvers[0], 1, 100), Ok(1));
+        // Disagreeing votes don't settle, and a repeated vote counts once
+        assert_eq!(vote_settlement(&mut poll, resolvers[1], 0, 100), Ok(1));
+        assert_eq!(vote_settlement(&mut poll, resolvers[0], 1, 100), Ok(1));
+        assert!(poll.status == PollStatus::Active);
+        assert_eq!(vote_settlement(&mut poll, resolvers[2], 0, 100), Ok(2));
+        assert!(poll.status == PollStatus::Settled);
+        assert_eq!(poll.winner, Some(0));
+        assert_eq!(
+            vote_settlement(&mut poll, resolvers[1], 1, 200),
+            Err(ErrorCode::PollNotActive.into())
+        );
+
+        // Councils are set before the first bid
+        let mut poll = active_poll(100);
+        poll.next_bid_index = 1;
+        assert_eq!(
+            set_resolvers(&mut poll, resolvers, 1),
+            Err(ErrorCode::PollHasBids.into())
+        );
+    }
+
+    #[test]
+    fn bettors_settle_abandoned_polls_by_stake_vote() {
+        let mut poll = active_poll(100);
+        poll.total_pool = 1_000;
+        let opens_at = 100 + COMMUNITY_VOTE_DELAY;
+
+        assert_eq!(
+            vote_outcome(&mut poll, &active_bid(200, 5_000, 400), 1, opens_at - 1),
+            Err(ErrorCode::CommunityVoteTooEarly.into())
+        );
+        let mut exited = active_bid(200, 5_000, 400);
+        exited.status = BidStatus::Exited;
+        assert_eq!(
+            vote_outcome(&mut poll, &exited, 1, opens_at),
+            Err(ErrorCode::BidCannotVote.into())
+        );
+        assert_eq!(
+            validate_resolved(&poll),
+            Err(ErrorCode::PollNotFinalized.into())
+        );
+
+        // Short of quorum, then short of a supermajority
+        assert_eq!(
+            vote_outcome(&mut poll, &active_bid(200, 5_000, 400), 1, opens_at),
+            Ok(200)
+        );
+        assert_eq!(
+            vote_outcome(&mut poll, &active_bid(150, 5_000, 300), 0, opens_at),
+            Ok(150)
+        );
+        assert!(poll.status == PollStatus::Active);
+        assert_eq!(
+            vote_outcome(&mut poll, &active_bid(200, 5_000, 400), 1, opens_at),
+            Ok(400)
+        );
+        assert!(poll.status == PollStatus::Settled);
+        assert_eq!(poll.winner, Some(1));
+        assert_eq!(poll.community_votes, vec![150, 400]);
+        assert_eq!(
+            vote_outcome(&mut poll, &active_bid(100, 5_000, 200), 0, opens_at),
+            Err(ErrorCode::PollNotActive.into())
+        );
+        assert!(validate_resolved(&poll).is_ok());
+    }
+
+    #[test]
+    fn signed_results_settle_only_their_poll() {
+        let poll_key = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let mut poll = active_poll(100);
+        let signed = SignedMessage {
+            signer,
+            message: result_message(&poll_key, 1),
+        };
+        assert_eq!(
+            validate_signed_result(&poll, poll_key, 1, &signed),
+            Err(ErrorCode::NoResultSigner.into())
+        );
+
+        set_result_signer(&mut poll, signer).unwrap();
+        assert_eq!(
+            set_resolvers(&mut poll, vec![signer], 1),
+            Err(ErrorCode::SettlerAlreadySet.into())
+        );
+        assert_eq!(
+            validate_authority_settlement(&poll),
+            Err(ErrorCode::ResultSignatureRequired.into())
+        );
+        assert!(validate_signed_result(&poll, poll_key, 1, &signed).is_ok());
+        for (key, winner, signed) in [
+            (poll_key, 0, signed.clone()),
+            (Pubkey::new_unique(), 1, signed.clone()),
+            (poll_key, 1, SignedMessage { signer: Pubkey::new_unique(), ..signed }),
+        ] {
+            assert_eq!(
+                validate_signed_result(&poll, key, winner, &signed),
+                Err(ErrorCode::InvalidResultSignature.into())
+            );
+        }
+    }
+
+    #[test]
+    fn commit_reveal_settlement() {
+        let salt = [7u8; 32];
+        let mut poll = active_poll(100);
+        assert_eq!(
+            reveal_settlement(&mut poll, 1, &salt, 200),
+            Err(ErrorCode::NoSettlementCommitment.into())
+        );
+        assert_eq!(
+            commit_settlement(&mut poll, settlement_hash(1, &salt), 99),
+            Err(ErrorCode::PollNotEnded.into())
+        );
+        commit_settlement(&mut poll, settlement_hash(1, &salt), 100).unwrap();
+
+        // The committed poll can only be settled by revealing
+        assert_eq!(
+            settle(&mut poll.clone(), 0, 100),
+            Err(ErrorCode::SettlementAwaitingReveal.into())
+        );
+        assert_eq!(
+            settle_void(&mut poll.clone(), 100),
+            Err(ErrorCode::SettlementAwaitingReveal.into())
+        );
+        let reveal_at = 100 + SETTLEMENT_REVEAL_DELAY;
+        assert_eq!(
+            reveal_settlement(&mut poll, 1, &salt, reveal_at - 1),
+            Err(ErrorCode::RevealTooEarly.into())
+        );
+        assert_eq!(
+            reveal_settlement(&mut poll, 0, &salt, reveal_at),
+            Err(ErrorCode::SettlementHashMismatch.into())
+        );
+        assert_eq!(
+            reveal_settlement(&mut poll, 1, &[8u8; 32], reveal_at),
+            Err(ErrorCode::SettlementHashMismatch.into())
+        );
+        reveal_settlement(&mut poll, 1, &salt, reveal_at).unwrap();
+        assert!(poll.status == PollStatus::Settled);
+        assert_eq!(poll.winner, Some(1));
+        assert_eq!(poll.settlement_commitment, None);
+    }
+
+    #[test]
+    fn disputed_settlement_lifecycle() {
+        let mut poll = active_poll(100);
+        poll.dispute_period = 50;
+        settle(&mut poll, 0, 110).unwrap();
+        assert!(poll.status == PollStatus::PendingSettlement);
+        assert_eq!(dispute_ends_at(&poll), 160);
+        assert_eq!(
+            finalize_settlement(&mut poll, 159),
+            Err(ErrorCode::DisputeWindowOpen.into())
+        );
+
+        // Unchallenged: anyone finalizes after the window
+        let mut unchallenged = poll.clone();
+        finalize_settlement(&mut unchallenged, 160).unwrap();
+        assert!(unchallenged.status == PollStatus::Settled);
+        assert_eq!(unchallenged.winner, Some(0));
+
+        // Challenged: only the arbiter's resolution finalizes
+        assert_eq!(
+            resolve_challenge(&mut poll, 1, 200),
+            Err(ErrorCode::SettlementNotChallenged.into())
+        );
+        challenge(&mut poll, 159).unwrap();
+        assert_eq!(
+            challenge(&mut poll, 159),
+            Err(ErrorCode::SettlementAlreadyChallenged.into())
+        );
+        assert_eq!(
+            finalize_settlement(&mut poll, 500),
+            Err(ErrorCode::SettlementChallenged.into())
+        );
+        assert_eq!(resolve_challenge(&mut poll, 1, 600), Ok(true));
+        assert_eq!(poll.finalized_at, 600);
+        assert!(poll.status == PollStatus::Settled);
+        assert_eq!(poll.winner, Some(1));
+
+        let mut late = active_poll(100);
+        late.dispute_period = 50;
+        settle(&mut late, 0, 100).unwrap();
+        assert_eq!(
+            challenge(&mut late, 150),
+            Err(ErrorCode::DisputeWindowClosed.into())
+        );
+        assert_eq!(
+            validate_dispute_period(MAX_DISPUTE_PERIOD + 1),
+            Err(ErrorCode::InvalidDisputePeriod.into())
+        );
+    }
+
+    #[test]
+    fn void_settlement_opens_refunds() {
+        let mut poll = active_poll(100);
+        assert_eq!(
+            validate_refundable(&poll),
+            Err(ErrorCode::PollNotCancelled.into())
+        );
+        assert_eq!(
+            settle_void(&mut poll, 99),
+            Err(ErrorCode::PollNotEnded.into())
+        );
+
+        settle_void(&mut poll, 100).unwrap();
+        assert!(poll.status == PollStatus::Voided);
+        assert_eq!(poll.winner, None);
+        assert!(validate_refundable(&poll).is_ok());
+        // Stakes are still owed, so no bid can be closed before its refund
+        assert_eq!(
+            validate_bid_close(&poll, BidStatus::Active, 0),
+            Err(ErrorCode::BidNotClosable.into())
+        );
+        assert!(validate_poll_close(&poll, 100 + CLOSE_GRACE_PERIOD).is_ok());
+        assert_eq!(
+            settle(&mut poll, 0, 100),
+            Err(ErrorCode::PollNotActive.into())
+        );
+    }
+
+    #[test]
+    fn unresolved_poll_expires_after_its_deadline() {
+        assert!(validate_resolution_deadline(0).is_ok());
+        assert!(validate_resolution_deadline(CRANK_GRACE_PERIOD).is_ok());
+        assert_eq!(
+            validate_resolution_deadline(MAX_RESOLUTION_DEADLINE + 1),
+            Err(ErrorCode::InvalidResolutionDeadline.into())
+        );
+        assert_eq!(
+            validate_resolution_deadline(-1),
+            Err(ErrorCode::InvalidResolutionDeadline.into())
+        );
+
+        let mut poll = active_poll(100);
+        assert_eq!(
+            expire_unresolved(&mut poll, 1_000),
+            Err(ErrorCode::NoResolutionDeadline.into())
+        );
+        poll.resolution_deadline = 50;
+        assert_eq!(
+            expire_unresolved(&mut poll, 150),
+            Err(ErrorCode::ResolutionDeadlineNotReached.into())
+        );
+        assert!(expire_unresolved(&mut poll, 151).is_ok());
+        assert!(poll.status == PollStatus::Cancelled);
+        assert_eq!(poll.finalized_at, 151);
+        assert!(validate_refundable(&poll).is_ok());
+
+        // A settled poll is resolved, however late
+        let mut poll = active_poll(100);
+        poll.resolution_deadline = 50;
+        settle(&mut poll, 0, 200).unwrap();
+        assert_eq!(
+            expire_unresolved(&mut poll, 300),
+            Err(ErrorCode::PollNotActive.into())
+        );
+    }
+
+    #[test]
+    fn creator_share_of_fees_and_slashed_bond() {
+        let mut poll = active_poll(100);
+        poll.creator_fee_bps = 2_500;
+        accrue_fee(&mut poll, 1_003).unwrap();
+        // The creator's share rounds down; the treasury keeps the remainder
+        assert_eq!(poll.creator_fees, 250);
+        assert_eq!(poll.accrued_fees, 753);
+        assert_eq!(poll.total_fees, 1_003);
+
+        poll.creation_bond = 5_000;
+        assert_eq!(slash(&mut poll, 50), Ok(5_000));
+        assert!(poll.status == PollStatus::Cancelled);
+        assert_eq!(poll.finalized_at, 50);
+        assert_eq!(poll.creation_bond, 0);
+        assert_eq!(slash(&mut poll, 60), Err(ErrorCode::PollNotActive.into()));
+    }
+
+    #[test]
+    fn resolution_notes_need_a_uri_before_settlement() {
+        let mut poll = active_poll(100);
+        assert!(validate_resolution_note(&poll, "ipfs://evidence").is_ok());
+        assert_eq!(
+            validate_resolution_note(&poll, ""),
+            Err(ErrorCode::InvalidResolutionNote.into())
+        );
+        assert_eq!(
+            validate_resolution_note(&poll, &"u".repeat(MAX_METADATA_URI_LEN + 1)),
+            Err(ErrorCode::InvalidResolutionNote.into())
+        );
+
+        poll.status = PollStatus::PendingSettlement;
+        assert_eq!(
+            validate_resolution_note(&poll, "ipfs://evidence"),
+            Err(ErrorCode::PollNotActive.into())
+        );
+    }
+
+    #[test]
+    fn events_group_unresolved_polls() {
+        assert!(validate_event_id(&"e".repeat(64)).is_ok());
+        assert_eq!(
+            validate_event_id(&"e".repeat(65)),
+            Err(ErrorCode::EventIdTooLong.into())
+        );
+
+        let mut event = MarketEvent {
+            authority: Pubkey::new_unique(),
+            event_id: "final".to_string(),
+            status: EventStatus::Open,
+            polls: Vec::new(),
+            total_volume: 0,
+            event_seq: 0,
+            bump: 0,
+        };
+        let poll = active_poll(100);
+        let key = Pubkey::new_unique();
+        assert!(add_event_poll(&mut event, key, &poll).is_ok());
+        assert_eq!(
+            add_event_poll(&mut event, key, &poll),
+            Err(ErrorCode::PollAlreadyInEvent.into())
+        );
+        for _ in 1..MAX_EVENT_POLLS {
+            add_event_poll(&mut event, Pubkey::new_unique(), &poll).unwrap();
+        }
+        assert_eq!(
+            add_event_poll(&mut event, Pubkey::new_unique(), &poll),
+            Err(ErrorCode::EventFull.into())
+        );
+
+        // A poll settled on its own is skipped by `settle_event`
+        let mut settled = active_poll(100);
+        settle(&mut settled, 0, 100).unwrap();
+        assert!(!event_poll_unresolved(&settled));
+        event.polls.clear();
+        assert_eq!(
+            add_event_poll(&mut event, Pubkey::new_unique(), &settled),
+            Err(ErrorCode::PollNotActive.into())
+        );
+
+        event.status = EventStatus::Settled;
+        assert_eq!(
+            add_event_poll(&mut event, Pubkey::new_unique(), &poll),
+            Err(ErrorCode::EventSettled.into())
+        );
+    }
+
+    #[test]
+    fn fee_shares_earn_from_later_sweeps() {
+        let mut poll = active_poll(100);
+        poll.swept_fees = 1_000;
+        assert_eq!(grant_fee_share(&mut poll, 2_500), Ok(1_000));
+        assert_eq!(
+            grant_fee_share(&mut poll, 7_501),
+            Err(ErrorCode::InvalidFeeShare.into())
+        );
+        assert_eq!(
+            grant_fee_share(&mut poll, 0),
+            Err(ErrorCode::InvalidFeeShare.into())
+        );
+        assert_eq!(grant_fee_share(&mut poll, 7_500), Ok(1_000));
+        assert_eq!(poll.fee_share_bps, 10_000);
+
+        let mut share = FeeShare {
+            poll: Pubkey::default(),
+            party: Pubkey::default(),
+            share_bps: 2_500,
+            swept_at_grant: 1_000,
+            claimed: 0,
+            bump: 0,
+        };
+        // Sweeps before the grant don't count
+        assert_eq!(
+            claim_fee_share(&mut share, 1_000),
+            Err(ErrorCode::NoFeesToSweep.into())
+        );
+        assert_eq!(claim_fee_share(&mut share, 1_403), Ok(100));
+        // Rounding is settled on the running total, not each claim
+        assert_eq!(claim_fee_share(&mut share, 1_405), Ok(1));
+        assert_eq!(share.claimed, 101);
+
+        // The vault holds back the shares' part of each sweep, rounded up,
+        // until it is claimed
+        let mut vault = FeeVault {
+            total_swept: 0,
+            bump: 0,
+            event_seq: 0,
+            fee_share_reserve: 0,
+        };
+        reserve_fee_shares(&mut vault, &poll, 405).unwrap();
+        assert_eq!(vault.fee_share_reserve, 405);
+        poll.fee_share_bps = 2_500;
+        reserve_fee_shares(&mut vault, &poll, 3).unwrap();
+        assert_eq!(vault.fee_share_reserve, 406);
+        assert_eq!(treasury_available(&vault, 1_000), 594);
+        release_fee_share(&mut vault, 101);
+        assert_eq!(treasury_available(&vault, 1_000), 695);
+        release_fee_share(&mut vault, 1_000);
+        assert_eq!((vault.fee_share_reserve, treasury_available(&vault, 1_000)), (0, 1_000));
+
+        let mut token_poll = active_poll(100);
+        token_poll.mint = Some(Pubkey::new_unique());
+        assert_eq!(
+            grant_fee_share(&mut token_poll, 100),
+            Err(ErrorCode::DenominationMismatch.into())
+        );
+    }
+
+    #[test]
+    fn treasury_withdrawals_wait_out_the_timelock() {
+        let mut stats = TreasuryStats {
+            total_fees: 0,
+            total_insurance: 0,
+            total_withdrawn: 0,
+            sweep_count: 0,
+            withdraw_delay: 0,
+            pending: None,
+            recent_sweeps: Vec::new(),
+            withdrawals: Vec::new(),
+            event_seq: 0,
+            bump: 0,
+            total_remainders: 0,
+            pending_treasury: None,
+        };
+        let mut poll = active_poll(100);
+        let polls: Vec<Pubkey> = (0..=TREASURY_HISTORY_LEN).map(|_| Pubkey::new_unique()).collect();
+        for (i, key) in polls.iter().enumerate() {
+            assert_eq!(record_sweep(&mut stats, &mut poll, *key, 100, 10, i as i64), Ok(()));
+        }
+        assert_eq!(stats.total_fees, 100 * polls.len() as u64);
+        assert_eq!(stats.total_insurance, 10 * polls.len() as u64);
+        // The oldest sweep falls out of the history, not the totals
+        assert_eq!(stats.recent_sweeps.len(), TREASURY_HISTORY_LEN);
+        assert_eq!(stats.recent_sweeps[0].poll, polls[1]);
+        assert_eq!(poll.treasury_swept, stats.total_fees);
+
+        // A vault remainder counts as a sweep without insurance; an empty
+        // vault doesn't count
+        record_remainder(&mut stats, &mut poll, polls[0], 50, 20).unwrap();
+        record_remainder(&mut stats, &mut poll, polls[0], 0, 21).unwrap();
+        assert_eq!(
+            (stats.total_fees, stats.total_remainders, stats.sweep_count),
+            (100 * polls.len() as u64 + 50, 50, polls.len() as u64 + 1)
+        );
+        assert_eq!(poll.treasury_swept, stats.total_fees);
+        assert_eq!(stats.recent_sweeps.last().map(|sweep| sweep.amount), Some(50));
+
+        // Without a timelock a withdrawal unlocks at once
+        let treasury = Pubkey::new_unique();
+        // Only the config's treasury can be paid
+        assert_eq!(
+            queue_treasury_withdrawal(&mut stats, 500, Pubkey::new_unique(), treasury, 10),
+            Err(ErrorCode::InvalidTreasury.into())
+        );
+        assert_eq!(queue_treasury_withdrawal(&mut stats, 500, treasury, treasury, 10), Ok(10));
+        assert_eq!(
+            execute_treasury_withdrawal(&mut stats, Pubkey::new_unique(), 10),
+            Err(ErrorCode::TreasuryDestinationMismatch.into())
+        );
+        assert_eq!(execute_treasury_withdrawal(&mut stats, treasury, 10), Ok(500));
+
+        assert_eq!(set_treasury_timelock(&mut stats, 3_600), Ok(()));
+        for delay in [3_599, MAX_TREASURY_DELAY + 1] {
+            assert_eq!(
+                set_treasury_timelock(&mut stats, delay),
+                Err(ErrorCode::InvalidTreasuryTimelock.into())
+            );
+        }
+        assert_eq!(
+            queue_treasury_withdrawal(&mut stats, 0, treasury, treasury, 20),
+            Err(ErrorCode::InvalidTreasuryWithdrawal.into())
+        );
+        assert_eq!(queue_treasury_withdrawal(&mut stats, 300, treasury, treasury, 20), Ok(3_620));
+        assert_eq!(
+            queue_treasury_withdrawal(&mut stats, 300, treasury, treasury, 20),
+            Err(ErrorCode::TreasuryWithdrawalPending.into())
+        );
+        assert_eq!(
+            execute_treasury_withdrawal(&mut stats, treasury, 3_619),
+            Err(ErrorCode::TreasuryTimelocked.into())
+        );
+        assert_eq!(execute_treasury_withdrawal(&mut stats, treasury, 3_620), Ok(300));
+        assert_eq!(stats.total_withdrawn, 800);
+        assert_eq!(
+            stats.withdrawals.iter().map(|w| w.timestamp).collect::<Vec<_>>(),
+            [10, 3_620]
+        );
+
+        assert_eq!(
+            queue_treasury_withdrawal(&mut stats, 300, treasury, treasury, 4_000),
+            Ok(7_600)
+        );
+        assert_eq!(
+            cancel_treasury_withdrawal(&mut stats).map(|w| w.amount),
+            Ok(300)
+        );
+        assert_eq!(
+            execute_treasury_withdrawal(&mut stats, treasury, 7_600),
+            Err(ErrorCode::NoTreasuryWithdrawal.into())
+        );
+
+        // Moving the treasury waits out the same timelock
+        let next = Pubkey::new_unique();
+        assert_eq!(
+            queue_treasury_change(&mut stats, treasury, treasury, 4_000),
+            Err(ErrorCode::InvalidTreasury.into())
+        );
+        assert_eq!(queue_treasury_change(&mut stats, next, treasury, 4_000), Ok(7_600));
+        assert_eq!(
+            queue_treasury_change(&mut stats, next, treasury, 4_000),
+            Err(ErrorCode::TreasuryChangePending.into())
+        );
+        assert_eq!(
+            execute_treasury_change(&mut stats, 7_599),
+            Err(ErrorCode::TreasuryTimelocked.into())
+        );
+        assert_eq!(execute_treasury_change(&mut stats, 7_600), Ok(next));
+        assert_eq!(
+            execute_treasury_change(&mut stats, 7_600),
+            Err(ErrorCode::NoTreasuryChange.into())
+        );
+    }
+
+    #[test]
+    fn balances_fund_stakes_and_withdrawals() {
+        let user = Pubkey::new_unique();
+        let mut balance = UserBalance {
+            user: Pubkey::default(),
+            balance: 0,
+            event_seq: 0,
+            bump: 0,
+        };
+        init_balance(&mut balance, user, 254);
+        credit_balance(&mut balance, 500).unwrap();
+        // A later deposit keeps the original owner
+        init_balance(&mut balance, Pubkey::new_unique(), 1);
+        credit_balance(&mut balance, 250).unwrap();
+        assert_eq!((balance.user, balance.bump, balance.balance), (user, 254, 750));
+
+        assert_eq!(debit_balance(&mut balance, 700), Ok(()));
+        assert_eq!(
+            debit_balance(&mut balance, 51),
+            Err(ErrorCode::InsufficientBalance.into())
+        );
+        assert_eq!(debit_balance(&mut balance, 50), Ok(()));
+        assert_eq!(balance.balance, 0);
+    }
+
+    #[test]
+    fn late_odds_moves_are_damped_and_priced_in() {
+        let mut poll = active_poll(1_000);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        assert_eq!(
+            set_odds_damping(&mut poll, 100, 0),
+            Err(ErrorCode::InvalidOddsDamping.into())
+        );
+        assert_eq!(
+            set_odds_damping(&mut poll, MAX_DAMPING_WINDOW + 1, 5_000),
+            Err(ErrorCode::InvalidOddsDamping.into())
+        );
+        set_odds_damping(&mut poll, 100, 10_000).unwrap();
+
+        // Nothing is held back before the window opens, and all of the move
+        // at the end
+        assert_eq!(damping_bps(&poll, 900), Ok(0));
+        assert_eq!(damping_bps(&poll, 950), Ok(5_000));
+        assert_eq!(damping_bps(&poll, 1_000), Ok(10_000));
+
+        // Early bids price and move odds as usual
+        let mut early = poll.clone();
+        let (odds, _) = record_bid(&mut early, MIN_BET_AMOUNT, 0, 900).unwrap();
+        assert_eq!(odds, 5_000);
+        assert_eq!(early.outcomes[0].odds, 9_500);
+
+        // Limit orders and parlay legs are priced with the premium too, so an
+        // order limited to the current odds doesn't fill late
+        let order = PendingOrder {
+            poll: Pubkey::new_unique(),
+            bettor: Pubkey::new_unique(),
+            order_id: 0,
+            amount: MIN_BET_AMOUNT,
+            option: 0,
+            max_odds: 5_000,
+            created_at: 900,
+            bump: 0,
+        };
+        assert_eq!(
+            match_order(&mut poll.clone(), &order, 950),
+            Err(ErrorCode::LimitNotReached.into())
+        );
+        let leg = parlay_leg(&poll, Pubkey::new_unique(), 0, MIN_BET_AMOUNT, 950).unwrap();
+        assert_eq!(leg.odds, 7_250);
+
+        // Halfway through the window a bid moves odds half as far, and pays
+        // for the half it didn't make
+        let (odds, _) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 950).unwrap();
+
+        assert_eq!(odds, 7_250);
+        assert_eq!(poll.outcomes[0].odds, 7_250);
+        assert_eq!(poll.outcomes[1].odds, 2_750);
+    }
+
+    #[test]
+    fn vig_is_priced_into_bid_odds() {
+        let mut poll = active_poll(1_000);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        assert_eq!(
+            set_poll_vig(&mut poll, MAX_VIG_BPS + 1),
+            Err(ErrorCode::InvalidVig.into())
+        );
+        set_poll_vig(&mut poll, 500).unwrap();
+
+        // 5% on even odds prices the bid at 52.5%, but the AMM odds carry no vig
+        let (odds, win) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 900).unwrap();
+        assert_eq!(odds, 5_250);
+        assert_eq!(Ok(win), potential_win(MIN_BET_AMOUNT, 5_250));
+        assert_eq!(poll.outcomes[0].odds, 9_500);
+
+        // Near-certain outcomes are capped at returning the stake
+        assert_eq!(odds_with_vig(&poll, 9_600), Ok(BPS_DENOMINATOR));
+        set_poll_vig(&mut poll, 0).unwrap();
+        assert_eq!(odds_with_vig(&poll, 9_600), Ok(9_600));
+    }
+
+    #[test]
+    fn registry_pages_list_live_polls() {
+        assert_eq!(validate_registry_page(0, None), Ok(()));
+        assert_eq!(validate_registry_page(2, Some(1)), Ok(()));
+        for (page, previous) in [(1, None), (2, Some(0))] {
+            assert_eq!(
+                validate_registry_page(page, previous),
+                Err(ErrorCode::InvalidRegistryPage.into())
+            );
+        }
+
+        let mut registry = PollRegistry {
+            page: 3,
+            polls: Vec::new(),
+            bump: 0,
+        };
+        let (mut poll, mut other) = (active_poll(100), active_poll(100));
+        let (key, other_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        list_poll(&mut poll, key, &mut registry).unwrap();
+        list_poll(&mut other, other_key, &mut registry).unwrap();
+        assert_eq!(poll.registry_page, Some(3));
+        assert_eq!(
+            list_poll(&mut poll, key, &mut registry),
+            Err(ErrorCode::PollAlreadyListed.into())
+        );
+
+        // A listed poll can't leave without its page
+        assert_eq!(
+            unlist_poll(&mut poll, key, None),
+            Err(ErrorCode::RegistryRequired.into())
+        );
+        let mut wrong_page = PollRegistry {
+            page: 4,
+            polls: vec![key],
+            bump: 0,
+        };
+        assert_eq!(
+            unlist_poll(&mut poll, key, Some(&mut wrong_page)),
+            Err(ErrorCode::RegistryMismatch.into())
+        );
+        unlist_poll(&mut poll, key, Some(&mut registry)).unwrap();
+        assert_eq!((poll.registry_page, registry.polls.clone()), (None, vec![other_key]));
+        // Unlisted polls pass through
+        assert_eq!(unlist_poll(&mut poll, key, None), Ok(()));
+
+        // A full page turns new polls away
+        registry.polls = vec![Pubkey::new_unique(); REGISTRY_PAGE_SIZE];
+        assert_eq!(
+            list_poll(&mut poll, key, &mut registry),
+            Err(ErrorCode::RegistryPageFull.into())
+        );
+        // and finalized polls stay off the registry
+        registry.polls.clear();
+        poll.status = PollStatus::Finalized;
+        assert_eq!(
+            list_poll(&mut poll, key, &mut registry),
+            Err(ErrorCode::PollNotActive.into())
+        );
+    }
+
+    #[test]
+    fn bet_credit_principal_returns_to_the_promo_vault() {
+        let user = Pubkey::new_unique();
+        let mut vault = PromoVault {
+            outstanding: 0,
+            total_granted: 0,
+            total_returned: 0,
+            event_seq: 0,
+            bump: 0,
+        };
+        let mut credit = BetCredit {
+            user: Pubkey::default(),
+            balance: 0,
+            granted: 0,
+            event_seq: 0,
+            bump: 0,
+        };
+        init_credit(&mut credit, user, 254);
+        // An empty vault is topped up in full
+        assert_eq!(credit_top_up(&vault, 0, 1_000), 1_000);
+        grant_credit(&mut vault, &mut credit, 1_000).unwrap();
+        assert_eq!((credit.user, credit.balance, vault.outstanding), (user, 1_000, 1_000));
+
+        assert_eq!(
+            spend_credit(&mut vault, &mut credit, 1_001),
+            Err(ErrorCode::InsufficientCredit.into())
+        );
+        assert_eq!(spend_credit(&mut vault, &mut credit, 600), Ok(()));
+        assert_eq!((credit.balance, vault.outstanding), (400, 400));
+
+        // A winning payout repays the principal first; partial claims repay
+        // it across payments
+        let mut bid = active_bid(600, 20_000, 1_200);
+        bid.credit = 600;
+        assert_eq!(return_credit(&mut vault, &mut bid, 500), Ok(500));
+        assert_eq!(return_credit(&mut vault, &mut bid, 700), Ok(100));
+        assert_eq!(return_credit(&mut vault, &mut bid, 700), Ok(0));
+        assert_eq!((bid.credit, vault.total_returned), (0, 600));
+
+        // Returned principal backs later grants before the admin pays in:
+        // 1,000 above rent, 400 of it still owed to outstanding credit
+        assert_eq!(credit_top_up(&vault, 1_000, 500), 0);
+        assert_eq!(credit_top_up(&vault, 1_000, 800), 200);
+    }
+
+    #[test]
+    fn volume_fee_tiers() {
+        let tier = |min_volume, fee_bps| FeeTier { min_volume, fee_bps };
+        let tiers = vec![tier(10 * LAMPORTS_PER_SOL, 150), tier(100 * LAMPORTS_PER_SOL, 100)];
+        assert!(validate_fee_tiers(&tiers).is_ok());
+        assert!(validate_fee_tiers(&[]).is_ok());
+        for invalid in [
+            vec![tier(1, 150), tier(1, 100)],
+            vec![tier(1, 100), tier(2, 100)],
+            vec![tier(1, MAX_FEE_BPS + 1)],
+            vec![tier(1, 100); MAX_FEE_TIERS + 1],
+        ] {
+            assert_eq!(
+                validate_fee_tiers(&invalid),
+                Err(ErrorCode::InvalidFeeTiers.into())
+            );
+        }
+
+        let mut poll = active_poll(100);
+        poll.fee_tiers = tiers;
+        assert_eq!(tiered_fee_bps(&poll, 10 * LAMPORTS_PER_SOL - 1), 200);
+        assert_eq!(tiered_fee_bps(&poll, 10 * LAMPORTS_PER_SOL), 150);
+        assert_eq!(tiered_fee_bps(&poll, 500 * LAMPORTS_PER_SOL), 100);
+        // Tiers only discount: a base fee below a tier wins
+        poll.fee_bps = 120;
+        assert_eq!(tiered_fee_bps(&poll, 10 * LAMPORTS_PER_SOL), 120);
+
+        poll.winner = Some(0);
+        assert_eq!(
+            tiered_payout(&poll, 0, 1_000, 2_000, 0, 500 * LAMPORTS_PER_SOL),
+            Ok((1_980, 20))
+        );
+        assert_eq!(
+            tiered_payout(&poll, 1, 1_000, 2_000, 0, 500 * LAMPORTS_PER_SOL),
+            Ok((0, 0))
+        );
+    }
+
+    #[test]
+    fn quote_matches_recorded_bid() {
+        let mut poll = active_poll(100);
+        poll.fee_tiers = vec![FeeTier {
+            min_volume: 10 * LAMPORTS_PER_SOL,
+            fee_bps: 100,
+        }];
+        poll.lp_liquidity = 100 * MIN_BET_AMOUNT;
+        record_bid(&mut poll, 4 * MIN_BET_AMOUNT, 1, 0).unwrap();
+
+        let quote = quote_bid(&poll, MIN_BET_AMOUNT, 0, 0, 0).unwrap();
+        let before = poll.clone();
+        let (odds, potential_win) = record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0).unwrap();
+        assert_eq!(quote.odds, odds);
+        assert_eq!(quote.potential_win, potential_win);
+        assert_eq!(quote.post_trade_odds, vec![poll.outcomes[0].odds, poll.outcomes[1].odds]);
+        assert_eq!(Ok((quote.payout, quote.fee)), split_platform_fee(potential_win, 200));
+
+        // The bettor's fee tier applies
+        let quote = quote_bid(&before, MIN_BET_AMOUNT, 0, 10 * LAMPORTS_PER_SOL, 0).unwrap();
+        assert_eq!(Ok((quote.payout, quote.fee)), split_platform_fee(potential_win, 100));
+
+        // A bid `place_bid` would reject can't be quoted
+        assert_eq!(
+            quote_bid(&before, MIN_BET_AMOUNT, 2, 0, 0),
+            Err(ErrorCode::InvalidOutcome.into())
+        );
+        assert_eq!(
+            quote_bid(&before, MIN_BET_AMOUNT, 0, 0, 100),
+            Err(ErrorCode::PollEnded.into())
+        );
+    }
+
+    #[test]
+    fn market_state_counts_shares_at_the_price_paid() {
+        let mut poll = active_poll(100);
+        poll.lp_liquidity = 100 * MIN_BET_AMOUNT;
+        let (_, yes) = record_bid(&mut poll, 2 * MIN_BET_AMOUNT, 0, 0).unwrap();
+        let (_, no) = record_bid(&mut poll, MIN_BET_AMOUNT, 1, 0).unwrap();
+        poll.vig_bps = 500;
+
+        let state = market_state(&poll, Pubkey::default()).unwrap();
+        // The first bid bought at 50%, two shares per lamport staked, and the
+        // second at the 5% floor, twenty
+        assert_eq!(yes, 4 * MIN_BET_AMOUNT);
+        assert_eq!(no, 20 * MIN_BET_AMOUNT);
+        assert_eq!(state.shares, vec![yes, no]);
+        assert_eq!(state.max_payout, no);
+        let odds: Vec<u64> = poll.outcomes.iter().map(|outcome| outcome.odds).collect();
+        assert_eq!(state.implied_probabilities, odds);
+        assert_eq!(
+            state.share_prices,
+            vec![
+                odds_with_vig(&poll, odds[0]).unwrap(),
+                odds_with_vig(&poll, odds[1]).unwrap()
+            ]
+        );
+        assert!(state.share_prices[0] > odds[0]);
+
+        // Pari-mutuel winners split the pool, however many shares they hold
+        poll.payout_mode = PayoutMode::PariMutuel;
+        let state = market_state(&poll, Pubkey::default()).unwrap();
+        assert_eq!(state.max_payout, 3 * MIN_BET_AMOUNT);
+    }
+
+    #[test]
+    fn odds_history_keeps_spaced_snapshots_in_a_ring() {
+        let mut poll = active_poll(100_000);
+        let mut history: OddsHistory = bytemuck::Zeroable::zeroed();
+        assert_eq!(
+            enable_odds_history(&mut poll, &mut history, 0, 10),
+            Err(ErrorCode::InvalidOddsHistoryInterval.into())
+        );
+        enable_odds_history(&mut poll, &mut history, 60, 10).unwrap();
+        assert!(poll.odds_history);
+        assert_eq!(history.count, 1);
+        assert_eq!(history.snapshots[0].timestamp, 10);
+        assert_eq!(history.snapshots[0].odds[..2], [5_000, 5_000]);
+        assert_eq!(history.snapshots[0].odds[2], 0);
+
+        // Too soon after the opening snapshot
+        poll.total_pool = 1_000;
+        assert!(!record_odds(&mut history, &poll, 69));
+        assert!(record_odds(&mut history, &poll, 70));
+        assert_eq!(history.snapshots[1].total_pool, 1_000);
+
+        // Once full, the oldest snapshot is overwritten
+        let capacity = ODDS_HISTORY_CAPACITY as i64;
+        for i in 2..=capacity {
+            assert!(record_odds(&mut history, &poll, 10 + 60 * i));
+        }
+        assert_eq!(history.count, ODDS_HISTORY_CAPACITY as u32);
+        assert_eq!(history.head, 1);
+        assert_eq!(history.snapshots[0].timestamp, 10 + 60 * capacity);
+
+        let mut poll = active_poll(100);
+        poll.next_bid_index = 1;
+        assert_eq!(
+            enable_odds_history(&mut poll, &mut bytemuck::Zeroable::zeroed(), 60, 10),
+            Err(ErrorCode::PollHasBids.into())
+        );
+    }
+
+    #[test]
+    fn limit_orders_fill_once_odds_reach_the_limit() {
+        let mut poll = active_poll(100);
+        assert!(validate_limit_order(&poll, MIN_BET_AMOUNT, 0, 4_000, 10).is_ok());
+        assert_eq!(
+            validate_limit_order(&poll, MIN_BET_AMOUNT, 0, MAX_ODDS_BPS + 1, 10),
+            Err(ErrorCode::InvalidLimitOdds.into())
+        );
+        assert_eq!(
+            validate_limit_order(&poll, MIN_BET_AMOUNT, 2, 4_000, 10),
+            Err(ErrorCode::InvalidOutcome.into())
+        );
+        poll.mint = Some(Pubkey::new_unique());
+        assert_eq!(
+            validate_limit_order(&poll, MIN_BET_AMOUNT, 0, 4_000, 10),
+            Err(ErrorCode::DenominationMismatch.into())
+        );
+
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        let order = PendingOrder {
+            poll: Pubkey::new_unique(),
+            bettor: Pubkey::new_unique(),
+            order_id: 0,
+            amount: MIN_BET_AMOUNT,
+            option: 0,
+            max_odds: 4_000,
+            created_at: 10,
+            bump: 0,
+        };
+        // Even odds are above the limit until the other side is backed
+        assert_eq!(
+            match_order(&mut poll, &order, 20),
+            Err(ErrorCode::LimitNotReached.into())
+        );
+        record_bid(&mut poll, 2 * MIN_BET_AMOUNT, 1, 20).unwrap();
+        let odds = poll.outcomes[0].odds;
+        assert!(odds <= 4_000);
+        assert_eq!(
+            match_order(&mut poll, &order, 30),
+            Ok((odds, potential_win(MIN_BET_AMOUNT, odds).unwrap()))
+        );
+        assert_eq!(poll.outcomes[0].stake, MIN_BET_AMOUNT);
+    }
+
+    #[test]
+    fn accounts_migrate_to_the_current_version_once() {
+        let mut poll = active_poll(100);
+        poll.version = 0;
+        assert_eq!(migrate_poll(&mut poll), Ok(0));
+        assert_eq!(poll.version, POLL_VERSION);
+        assert_eq!(
+            migrate_poll(&mut poll),
+            Err(ErrorCode::AccountUpToDate.into())
+        );
+
+        let mut bid = active_bid(MIN_BET_AMOUNT, 5_000, 2 * MIN_BET_AMOUNT);
+        bid.version = 0;
+        assert_eq!(migrate_bid(&mut bid), Ok(0));
+        assert_eq!(
+            migrate_bid(&mut bid),
+            Err(ErrorCode::AccountUpToDate.into())
+        );
+    }
+
+    #[test]
+    fn parlay_odds_and_results() {
+        let leg = |odds| ParlayLeg {
+            poll: Pubkey::new_unique(),
+            option: 0,
+            odds,
+        };
+        let legs = vec![leg(5_000), leg(2_500)];
+        // 50% x 25% = 12.5%: 1 SOL wins 8 SOL
+        assert_eq!(parlay_potential_win(LAMPORTS_PER_SOL, &legs), Ok(8 * LAMPORTS_PER_SOL));
+        assert_eq!(
+            parlay_potential_win(LAMPORTS_PER_SOL, &legs[..1]),
+            Err(ErrorCode::InvalidParlayLegs.into())
+        );
+        assert_eq!(
+            parlay_potential_win(LAMPORTS_PER_SOL, &vec![leg(5_000); MAX_PARLAY_LEGS + 1]),
+            Err(ErrorCode::InvalidParlayLegs.into())
+        );
+        assert_eq!(
+            parlay_potential_win(LAMPORTS_PER_SOL, &[legs[0], legs[0]]),
+            Err(ErrorCode::InvalidParlayLegs.into())
+        );
+
+        let mut poll = active_poll(100);
+        let odds = |now| {
+            parlay_leg(&poll, Pubkey::new_unique(), 1, MIN_BET_AMOUNT, now).map(|leg| leg.odds)
+        };
+        assert_eq!(odds(99), Ok(poll.outcomes[1].odds));
+        assert_eq!(odds(100), Err(ErrorCode::PollEnded.into()));
+        assert_eq!(parlay_leg_result(&poll, &legs[0]), ParlayLegResult::Pending);
+        settle(&mut poll, 0, 100).unwrap();
+        assert_eq!(parlay_leg_result(&poll, &legs[0]), ParlayLegResult::Won);
+        settle_void(&mut active_poll(100), 100).unwrap();
+
+        let parlay = Parlay {
+            bettor: Pubkey::new_unique(),
+            parlay_id: 0,
+            amount: LAMPORTS_PER_SOL,
+            potential_win: 8 * LAMPORTS_PER_SOL,
+            fee_bps: 200,
+            timestamp: 0,
+            legs,
+            bump: 0,
+        };
+        use ParlayLegResult::*;
+        assert_eq!(
+            parlay_payout(&parlay, &[Won, Won]),
+            Ok((7_840_000_000, 160_000_000))
+        );
+        // A void leg drops out of the odds; all void refunds the stake
+        assert_eq!(
+            parlay_payout(&parlay, &[Void, Won]),
+            Ok((3_920_000_000, 80_000_000))
+        );
+        assert_eq!(parlay_payout(&parlay, &[Void, Void]), Ok((LAMPORTS_PER_SOL, 0)));
+        assert_eq!(
+            parlay_payout(&parlay, &[Won, Pending]),
+            Err(ErrorCode::ParlayNotResolved.into())
+        );
+        assert_eq!(
+            parlay_payout(&parlay, &[Won, Lost]),
+            Err(ErrorCode::ParlayLegLost.into())
+        );
+    }
+
+    #[test]
+    fn referrer_share_comes_out_of_treasury() {
+        let mut poll = active_poll(100);
+        poll.creator_fee_bps = 2_500;
+        poll.referral_fee_bps = 1_000;
+        assert_eq!(accrue_referred_fee(&mut poll, 1_003), Ok(100));
+        // The creator's share is unchanged; the treasury keeps what's left
+        assert_eq!(poll.creator_fees, 250);
+        assert_eq!(poll.accrued_fees, 653);
+
+        let bettor = Pubkey::new_unique();
+        assert!(validate_referrer(None, bettor).is_ok());
+        assert!(validate_referrer(Some(Pubkey::new_unique()), bettor).is_ok());
+        assert_eq!(
+            validate_referrer(Some(bettor), bettor),
+            Err(ErrorCode::InvalidReferrer.into())
+        );
+    }
+
+    #[test]
+    fn insurance_takes_its_share_and_covers_shortfalls() {
+        assert_eq!(insurance_share(1_003, 2_000), Ok(200));
+        assert_eq!(insurance_share(1_003, 0), Ok(0));
+
+        // A vault that can pay needs no cover
+        assert_eq!(insurance_cover(1_000, 1_000, 0), Ok(0));
+        assert_eq!(insurance_cover(1_000, 600, 0), Ok(0));
+        assert_eq!(insurance_cover(600, 1_000, 400), Ok(400));
+        assert_eq!(
+            insurance_cover(600, 1_000, 399),
+            Err(ErrorCode::VaultShortfall.into())
+        );
+    }
+
+    #[test]
+    fn only_resolved_bids_and_polls_close() {
+        let mut poll = active_poll(100);
+        assert_eq!(
+            validate_bid_close(&poll, BidStatus::Active, 0),
+            Err(ErrorCode::BidNotClosable.into())
+        );
+        assert!(validate_bid_close(&poll, BidStatus::Exited, 0).is_ok());
+        assert_eq!(
+            validate_poll_close(&poll, 1_000),
+            Err(ErrorCode::PollNotFinalized.into())
+        );
+
+        settle(&mut poll, 1, 100).unwrap();
+        assert_eq!(poll.finalized_at, 100);
+        assert!(validate_bid_close(&poll, BidStatus::Active, 0).is_ok());
+        assert_eq!(
+            validate_bid_close(&poll, BidStatus::Active, 1),
+            Err(ErrorCode::BidNotClosable.into())
+        );
+        assert!(validate_bid_close(&poll, BidStatus::Won, 1).is_ok());
+        assert_eq!(
+            validate_bid_close(&poll, BidStatus::PartiallyClaimed, 1),
+            Err(ErrorCode::BidNotClosable.into())
+        );
+
+        assert_eq!(
+            validate_poll_close(&poll, 100 + CLOSE_GRACE_PERIOD - 1),
+            Err(ErrorCode::ClaimGracePeriod.into())
+        );
+        validate_poll_close(&poll, 100 + CLOSE_GRACE_PERIOD).unwrap();
+
+        // Unclaimed winners are forfeited once the poll is closed
+        let mut expired = poll.clone();
+        close_poll(&mut poll);
+        assert!(poll.status == PollStatus::Closed);
+        assert!(validate_bid_close(&poll, BidStatus::Active, 1).is_ok());
+        assert!(validate_bid_close(&poll, BidStatus::PartiallyClaimed, 1).is_ok());
+
+        // ...or swept to the treasury once the poll expires
+        expired.accrued_fees = 30;
+        expired.creator_fees = 10;
+        assert_eq!(
+            expire(&mut expired, 100 + CLOSE_GRACE_PERIOD - 1),
+            Err(ErrorCode::ClaimGracePeriod.into())
+        );
+        expire(&mut expired, 100 + CLOSE_GRACE_PERIOD).unwrap();
+        assert!(expired.status == PollStatus::Expired);
+        assert_eq!((expired.accrued_fees, expired.creator_fees), (0, 0));
+        assert!(validate_bid_close(&expired, BidStatus::Active, 1).is_ok());
+        assert!(validate_poll_close(&expired, 0).is_ok());
+        assert_eq!(
+            expire(&mut expired, 100 + CLOSE_GRACE_PERIOD),
+            Err(ErrorCode::PollNotFinalized.into())
+        );
+    }
+
+    #[test]
+    fn partial_claims_draw_down_the_payout() {
+        let mut bid = active_bid(MIN_BET_AMOUNT, 5_000, 2 * MIN_BET_AMOUNT);
+        bid.unclaimed = 1_000;
+        for invalid in [0, 1_001] {
+            assert_eq!(
+                claim_part(&mut bid, Some(invalid)),
+                Err(ErrorCode::InvalidClaimAmount.into())
+            );
+        }
+        assert_eq!(claim_part(&mut bid, Some(400)), Ok(400));
+        assert!(bid.status == BidStatus::PartiallyClaimed);
+        assert_eq!(bid.unclaimed, 600);
+        assert_eq!(claim_part(&mut bid, None), Ok(600));
+        assert!(bid.status == BidStatus::Won);
+        assert_eq!(bid.unclaimed, 0);
+
+        // A full claim in one go
+        bid.unclaimed = 1_000;
+        assert_eq!(claim_part(&mut bid, Some(1_000)), Ok(1_000));
+        assert!(bid.status == BidStatus::Won);
+    }
+
+    #[test]
+    fn payments_accumulate_on_the_bid() {
+        let mut bid = active_bid(MIN_BET_AMOUNT, 5_000, 2 * MIN_BET_AMOUNT);
+        record_payment(&mut bid, 400, 20, 100).unwrap();
+        record_payment(&mut bid, 600, 0, 250).unwrap();
+        assert_eq!(bid.claimed_amount, 1_000);
+        assert_eq!(bid.fee_paid, 20);
+        assert_eq!(bid.claimed_at, 250);
+
+        // Totals past u64 fail instead of panicking
+        bid.claimed_amount = u64::MAX;
+        assert_eq!(
+            record_payment(&mut bid, 1, 0, 300),
+            Err(ErrorCode::MathOverflow.into())
+        );
+    }
+
+    #[test]
+    fn losing_bids_are_marked_lost() {
+        let mut poll = active_poll(100);
+        let mut bid = active_bid(MIN_BET_AMOUNT, 5_000, 2 * MIN_BET_AMOUNT);
+        assert_eq!(
+            mark_lost(&poll, &mut bid),
+            Err(ErrorCode::PollNotSettled.into())
+        );
+
+        settle(&mut poll, 0, 100).unwrap();
+        assert_eq!(mark_lost(&poll, &mut bid), Err(ErrorCode::BidNotLost.into()));
+        bid.option = 1;
+        assert_eq!(mark_lost(&poll, &mut bid), Ok(()));
+        assert!(bid.status == BidStatus::Lost);
+        assert_eq!(
+            mark_lost(&poll, &mut bid),
+            Err(ErrorCode::BidAlreadyClaimed.into())
+        );
+    }
+
+    #[test]
+    fn oracle_outcome_from_price_in_window() {
+        let mut poll = active_poll(100);
+        let price = |price, publish_time| OraclePrice {
+            feed_id: [1; 32],
+            price,
+            exponent: -8,
+            publish_time,
+        };
+        assert_eq!(
+            oracle_outcome(&poll, &price(0, 100)),
+            Err(ErrorCode::NoResolutionSource.into())
+        );
+
+        poll.resolution = Some(ResolutionSource {
+            feed_id: [1; 32],
+            threshold: 200_00000000,
+            exponent: -8,
+            comparison: PriceComparison::AtOrAbove,
+        });
+        assert_eq!(oracle_outcome(&poll, &price(200_00000000, 100)), Ok(0));
+        assert_eq!(oracle_outcome(&poll, &price(199_99999999, 160)), Ok(1));
+        assert_eq!(
+            oracle_outcome(&poll, &price(200_00000000, 99)),
+            Err(ErrorCode::OraclePriceOutsideWindow.into())
+        );
+        assert_eq!(
+            oracle_outcome(&poll, &price(200_00000000, 161)),
+            Err(ErrorCode::OraclePriceOutsideWindow.into())
+        );
+        assert_eq!(
+            oracle_outcome(&poll, &OraclePrice { exponent: -6, ..price(200_000000, 100) }),
+            Err(ErrorCode::OracleFeedMismatch.into())
+        );
+
+        assert_eq!(
+            validate_resolution_source(poll.resolution.as_ref(), &poll.kind, 3),
+            Err(ErrorCode::InvalidResolutionSource.into())
+        );
+
+        // A scalar oracle market settles on the bucket the price falls in
+        poll.kind = PollKind::Scalar {
+            bounds: vec![100_00000000, 200_00000000],
+        };
+        assert!(validate_resolution_source(poll.resolution.as_ref(), &poll.kind, 3).is_ok());
+        assert_eq!(oracle_outcome(&poll, &price(199_99999999, 100)), Ok(1));
+        assert_eq!(oracle_outcome(&poll, &price(200_00000000, 100)), Ok(2));
+    }
+
+    #[test]
+    fn scalar_buckets() {
+        let scalar = |bounds: Vec<i64>| PollKind::Scalar { bounds };
+        let fixed = PayoutMode::FixedOdds;
+        assert!(validate_poll_kind(&PollKind::Categorical, fixed, 2).is_ok());
+        assert!(validate_poll_kind(&scalar(vec![10, 20]), fixed, 3).is_ok());
+        assert_eq!(
+            validate_poll_kind(&scalar(vec![10, 20]), fixed, 2),
+            Err(ErrorCode::InvalidScalarBounds.into())
+        );
+        assert_eq!(
+            validate_poll_kind(&scalar(vec![20, 20]), fixed, 3),
+            Err(ErrorCode::InvalidScalarBounds.into())
+        );
+
+        let mut poll = active_poll(100);
+        assert_eq!(scalar_outcome(&poll, 0), Err(ErrorCode::NotScalarMarket.into()));
+        poll.kind = scalar(vec![10, 20]);
+        assert_eq!(scalar_outcome(&poll, i64::MIN), Ok(0));
+        assert_eq!(scalar_outcome(&poll, 9), Ok(0));
+        assert_eq!(scalar_outcome(&poll, 10), Ok(1));
+        assert_eq!(scalar_outcome(&poll, 19), Ok(1));
+        assert_eq!(scalar_outcome(&poll, 20), Ok(2));
+        assert_eq!(scalar_outcome(&poll, i64::MAX), Ok(2));
+    }
+
+    #[test]
+    fn crank_needs_on_chain_result_and_grace_period() {
+        let mut poll = active_poll(100);
+        let crank_at = 100 + CRANK_GRACE_PERIOD;
+        assert_eq!(
+            validate_crank(&poll, crank_at),
+            Err(ErrorCode::NoOnChainResolution.into())
+        );
+
+        poll.settlement_commitment = Some(settlement_hash(0, &[7; 32]));
+        assert_eq!(
+            validate_crank(&poll, crank_at - 1),
+            Err(ErrorCode::CrankTooEarly.into())
+        );
+        assert!(validate_crank(&poll, crank_at).is_ok());
+    }
+
+    #[test]
+    fn fee_and_payout_split() {
+        assert_eq!(split_platform_fee(1_000_000, 200), Ok((980_000, 20_000)));
+        assert_eq!(split_platform_fee(1, 200), Ok((0, 1)));
+        assert_eq!(split_platform_fee(1_000_000, 0), Ok((1_000_000, 0)));
+        let mut poll = active_poll(100);
+        assert_eq!(settled_payout(&poll, 1, 1, 1_000_000, 0), Ok((0, 0)));
+        poll.winner = Some(0);
+        assert_eq!(settled_payout(&poll, 0, 1, 1_000_000, 0), Ok((980_000, 20_000)));
+        assert_eq!(settled_payout(&poll, 1, 1, 1_000_000, 0), Ok((0, 0)));
+    }
+
+    #[test]
+    fn incentives_move_the_fee_of_early_and_late_bids() {
+        let curve = IncentiveCurve {
+            early_window: 100,
+            early_bonus_bps: 150,
+            late_window: 50,
+            late_fee_bps: 100,
+        };
+        assert!(validate_incentives(Some(&curve), 200, 0, 150).is_ok());
+        for invalid in [
+            IncentiveCurve { early_bonus_bps: 201, ..curve },
+            IncentiveCurve { late_fee_bps: MAX_FEE_BPS - 199, ..curve },
+            IncentiveCurve { early_window: -1, ..curve },
+            IncentiveCurve { late_window: 51, ..curve },
+        ] {
+            assert_eq!(
+                validate_incentives(Some(&invalid), 200, 0, 150),
+                Err(ErrorCode::InvalidIncentiveCurve.into())
+            );
+        }
+
+        let mut poll = active_poll(1_000);
+        assert_eq!(fee_adjust_bps(&poll, 0), 0);
+        poll.incentives = Some(curve);
+        assert_eq!(fee_adjust_bps(&poll, 99), -150);
+        assert_eq!(fee_adjust_bps(&poll, 100), 0);
+        assert_eq!(fee_adjust_bps(&poll, 950), 100);
+
+        // The bonus comes out of the 2% fee; the late fee adds to it
+        poll.winner = Some(0);
+        assert_eq!(settled_payout(&poll, 0, 1, 1_000_000, -150), Ok((995_000, 5_000)));
+        assert_eq!(settled_payout(&poll, 0, 1, 1_000_000, 100), Ok((970_000, 30_000)));
+        assert_eq!(adjusted_fee_bps(100, -150), 0);
+
+        // A ledger entry settles with the adjustment it locked in
+        let entry = crate::LedgerBid {
+            bettor: Pubkey::new_unique(),
+            amount: 1,
+            potential_win: 1_000_000,
+            odds_at_purchase: 5_000,
+            timestamp: 10,
+            option: 0,
+            status: BidStatus::Active as u8,
+            fee_adjust_bps: -150,
+            _padding: [0; 4],
+        };
+        let bid = entry.to_bid(Pubkey::default(), 3);
+        assert_eq!((bid.fee_adjust_bps, bid.index), (-150, 3));
+        assert_eq!(tiered_split(&poll, 1_000_000, bid.fee_adjust_bps, 0), Ok((995_000, 5_000)));
+
+        // An increase keeps the less favorable adjustment
+        let mut poll = active_poll(1_000);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        poll.incentives = Some(curve);
+        let mut bid = active_bid(MIN_BET_AMOUNT, 5_000, 2 * MIN_BET_AMOUNT);
+        bid.fee_adjust_bps = -150;
+        increase_bid(&mut poll, &mut bid, MIN_BET_AMOUNT, 500).unwrap();
+        assert_eq!(bid.fee_adjust_bps, 0);
+    }
+
+    #[test]
+    fn pari_mutuel_splits_pool_by_winning_stake() {
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0).unwrap();
+        record_bid(&mut poll, 3 * MIN_BET_AMOUNT, 0, 0).unwrap();
+        record_bid(&mut poll, 4 * MIN_BET_AMOUNT, 1, 0).unwrap();
+        settle(&mut poll, 0, 100).unwrap();
+
+        // 1/4 and 3/4 of an 8-unit pool, whatever odds were locked in
+        let (payout, fee) = settled_payout(&poll, 0, MIN_BET_AMOUNT, 1, 0).unwrap();
+        assert_eq!(payout + fee, 2 * MIN_BET_AMOUNT);
+        let (payout, fee) = settled_payout(&poll, 0, 3 * MIN_BET_AMOUNT, 1, 0).unwrap();
+        assert_eq!(payout + fee, 6 * MIN_BET_AMOUNT);
+        assert_eq!(fee, Bps::new(poll.fee_bps.into()).apply(6 * MIN_BET_AMOUNT, Rounding::Up).unwrap());
+        assert_eq!(settled_payout(&poll, 1, 4 * MIN_BET_AMOUNT, 1, 0), Ok((0, 0)));
+    }
+
+    #[test]
+    fn jackpot_polls_are_pari_mutuel() {
+        let jackpot = PollKind::Jackpot;
+        assert!(validate_poll_kind(&jackpot, PayoutMode::PariMutuel, 3).is_ok());
+        assert_eq!(
+            validate_poll_kind(&jackpot, PayoutMode::FixedOdds, 3),
+            Err(ErrorCode::JackpotPayoutMode.into())
+        );
+
+        let mut poll = active_poll(100);
+        poll.kind = jackpot;
+        poll.payout_mode = PayoutMode::PariMutuel;
+        assert_eq!(scalar_outcome(&poll, 0), Err(ErrorCode::NotScalarMarket.into()));
+        record_bid(&mut poll, MIN_BET_AMOUNT, 0, 0).unwrap();
+        record_bid(&mut poll, 3 * MIN_BET_AMOUNT, 1, 0).unwrap();
+        settle(&mut poll, 0, 100).unwrap();
+
+        // The only winner takes the whole pool less the fee
+        let (payout, fee) = settled_payout(&poll, 0, MIN_BET_AMOUNT, 1, 0).unwrap();
+        assert_eq!(payout + fee, 4 * MIN_BET_AMOUNT);
+        assert_eq!(settled_payout(&poll, 1, 3 * MIN_BET_AMOUNT, 1, 0), Ok((0, 0)));
+    }
+
+    #[test]
+    fn amm_odds_clamped() {
+        assert_eq!(amm_odds(0, 0, 2, 0), Ok(5000));
+        assert_eq!(amm_odds(0, 0, 3, 0), Ok(3333));
+        assert_eq!(amm_odds(1, 1, 2, 0), Ok(9500));
+        assert_eq!(amm_odds(0, 1, 2, 0), Ok(500));
+        assert_eq!(amm_odds(3, 4, 2, 0), Ok(7500));
+    }
+
+    #[test]
+    fn virtual_liquidity_smooths_early_odds() {
+        // Unstaked outcomes start at equal odds either way
+        assert_eq!(amm_odds(0, 0, 2, 100), Ok(5000));
+        // The first bid of 10 moves a b = 100 market from 50% to 52.38%,
+        // instead of straight to the 95% cap
+        assert_eq!(amm_odds(10, 10, 2, 100), Ok(5238));
+        assert_eq!(amm_odds(0, 10, 2, 100), Ok(4761));
+        assert_eq!(amm_odds(10, 10, 2, 0), Ok(9500));
+        // Deep markets converge on the raw stake ratio
+        assert_eq!(amm_odds(3_000_000, 4_000_000, 2, 100), Ok(7499));
+
+        assert!(validate_virtual_liquidity(MAX_VIRTUAL_LIQUIDITY).is_ok());
+        assert_eq!(
+            validate_virtual_liquidity(MAX_VIRTUAL_LIQUIDITY + 1),
+            Err(ErrorCode::InvalidVirtualLiquidity.into())
+        );
+    }
+
+    #[test]
+    fn usd_limit_values_sol_bids_at_a_fresh_price() {
+        let mut config = Config {
+            admin: Pubkey::default(),
+            treasury: Pubkey::default(),
+            fee_bps: 0,
+            min_bet: MIN_BET_AMOUNT,
+            max_bet: MAX_BET_AMOUNT,
+            bump: 0,
+            creation_bond: 0,
+            creator_fee_bps: 0,
+            lp_fee_bps: 0,
+            claim_period: CLOSE_GRACE_PERIOD,
+            pending_admin: None,
+            paused: false,
+            referral_fee_bps: 0,
+            fee_tiers: Vec::new(),
+            event_seq: 0,
+            usd_feed_id: [0; 32],
+            max_bid_usd: 0,
+            epoch_id: 0,
+            epoch_start: 0,
+            epoch_end: 0,
+        };
+        // $150.00 per SOL with Pyth's SOL/USD exponent
+        let price = OraclePrice {
+            feed_id: [4; 32],
+            price: 150_00000000,
+            exponent: -8,
+            publish_time: 1_000,
+        };
+        assert_eq!(usd_notional(2 * LAMPORTS_PER_SOL, &price), Ok(300_000000));
+        assert_eq!(usd_notional(1, &price), Ok(0));
+        assert_eq!(
+            usd_notional(LAMPORTS_PER_SOL, &OraclePrice { price: 0, ..price }),
+            Err(ErrorCode::InvalidUsdPrice.into())
+        );
+
+        // Without a limit, a bid needs no price and is valued at 0
+        assert_eq!(usd_bid_notional(&config, LAMPORTS_PER_SOL, None, 1_000), Ok(0));
+        assert!(validate_no_usd_limit(&config).is_ok());
+        assert_eq!(
+            validate_usd_limits(&[0; 32], 500_000000),
+            Err(ErrorCode::InvalidUsdLimits.into())
+        );
+        assert!(validate_usd_limits(&[0; 32], 0).is_ok());
+
+        assert!(validate_usd_limits(&[4; 32], 500_000000).is_ok());
+        config.usd_feed_id = [4; 32];
+        config.max_bid_usd = 500_000000;
+        assert_eq!(
+            usd_bid_notional(&config, LAMPORTS_PER_SOL, None, 1_000),
+            Err(ErrorCode::UsdPriceRequired.into())
+        );
+        assert_eq!(
+            validate_no_usd_limit(&config),
+            Err(ErrorCode::UsdPriceRequired.into())
+        );
+        let other_feed = OraclePrice { feed_id: [5; 32], ..price };
+        assert_eq!(
+            usd_bid_notional(&config, LAMPORTS_PER_SOL, Some(&other_feed), 1_000),
+            Err(ErrorCode::UsdFeedMismatch.into())
+        );
+        let last_fresh = price.publish_time + USD_PRICE_MAX_AGE;
+        assert_eq!(
+            usd_bid_notional(&config, LAMPORTS_PER_SOL, Some(&price), last_fresh + 1),
+            Err(ErrorCode::InvalidUsdPrice.into())
+        );
+
+        // $500 buys at most 3.33 SOL at $150
+        assert_eq!(
+            usd_bid_notional(&config, 3 * LAMPORTS_PER_SOL, Some(&price), last_fresh),
+            Ok(450_000000)
+        );
+        assert_eq!(
+            usd_bid_notional(&config, 4 * LAMPORTS_PER_SOL, Some(&price), 1_000),
+            Err(ErrorCode::UsdLimitExceeded.into())
+        );
+    }
+}
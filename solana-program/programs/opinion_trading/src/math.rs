@@ -0,0 +1,216 @@
+//! Basis-point fixed-point math for odds, probabilities, and fees.
+//!
+//! Every ratio in the program is a `Bps` value scaled by `BPS_DENOMINATOR`
+//! (10_000 = 100%). Intermediate products are computed in u128 and rounding is
+//! always explicit: amounts owed to bettors round down and amounts owed to the
+//! platform round up, so the vault never promises more than it collects.
+//!
+//! The `safe_*` helpers and the `try_*` methods fail with
+//! `ErrorCode::MathOverflow` instead of returning `None`, for instruction
+//! paths that would otherwise end in `unwrap()`.
+
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, BPS_DENOMINATOR};
+
+/// Direction to round a fixed-point result
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// A ratio expressed in basis points
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bps(u64);
+
+impl Bps {
+    pub const ZERO: Bps = Bps(0);
+    pub const ONE: Bps = Bps(BPS_DENOMINATOR);
+
+    pub const fn new(raw: u64) -> Self {
+        Bps(raw)
+    }
+
+    pub const fn raw(self) -> u64 {
+        self.0
+    }
+
+    /// `numerator / denominator` as a ratio in basis points
+    pub fn from_ratio(numerator: u64, denominator: u64, rounding: Rounding) -> Option<Self> {
+        mul_div(numerator, BPS_DENOMINATOR, denominator, rounding).map(Bps)
+    }
+
+    /// Restrict the ratio to `[min, max]`
+    pub fn clamp(self, min: Bps, max: Bps) -> Self {
+        Ord::clamp(self, min, max)
+    }
+
+    /// `amount * self`, e.g. the fee owed on `amount`
+    pub fn apply(self, amount: u64, rounding: Rounding) -> Option<u64> {
+        mul_div(amount, self.0, BPS_DENOMINATOR, rounding)
+    }
+
+    /// `amount / self`, e.g. the payout of a stake bought at probability `self`
+    pub fn invert(self, amount: u64, rounding: Rounding) -> Option<u64> {
+        mul_div(amount, BPS_DENOMINATOR, self.0, rounding)
+    }
+
+    /// `from_ratio`, failing with `MathOverflow`
+    pub fn try_from_ratio(numerator: u64, denominator: u64, rounding: Rounding) -> Result<Self> {
+        safe_mul_div(numerator, BPS_DENOMINATOR, denominator, rounding).map(Bps)
+    }
+
+    /// `apply`, failing with `MathOverflow`
+    pub fn try_apply(self, amount: u64, rounding: Rounding) -> Result<u64> {
+        safe_mul_div(amount, self.0, BPS_DENOMINATOR, rounding)
+    }
+
+    /// `invert`, failing with `MathOverflow`
+    pub fn try_invert(self, amount: u64, rounding: Rounding) -> Result<u64> {
+        safe_mul_div(amount, BPS_DENOMINATOR, self.0, rounding)
+    }
+}
+
+/// `a * b / c` with a u128 intermediate. `None` on division by zero or when
+/// the result does not fit in a u64.
+pub fn mul_div(a: u64, b: u64, c: u64, rounding: Rounding) -> Option<u64> {
+    if c == 0 {
+        return None;
+    }
+    let product = (a as u128).checked_mul(b as u128)?;
+    let divisor = c as u128;
+    let quotient = match rounding {
+        Rounding::Down => product / divisor,
+        Rounding::Up => product.div_ceil(divisor),
+    };
+    u64::try_from(quotient).ok()
+}
+
+/// `mul_div`, failing with `MathOverflow` on division by zero or a result
+/// past u64
+pub fn safe_mul_div(a: u64, b: u64, c: u64, rounding: Rounding) -> Result<u64> {
+    mul_div(a, b, c, rounding).ok_or_else(|| error!(ErrorCode::MathOverflow))
+}
+
+/// `a + b`, failing with `MathOverflow`
+pub fn safe_add(a: u64, b: u64) -> Result<u64> {
+    a.checked_add(b).ok_or_else(|| error!(ErrorCode::MathOverflow))
+}
+
+/// `a * b`, failing with `MathOverflow`
+pub fn safe_mul(a: u64, b: u64) -> Result<u64> {
+    a.checked_mul(b).ok_or_else(|| error!(ErrorCode::MathOverflow))
+}
+
+/// `a - b`, failing with `MathOverflow` when `b` is larger
+pub fn safe_sub(a: u64, b: u64) -> Result<u64> {
+    a.checked_sub(b).ok_or_else(|| error!(ErrorCode::MathOverflow))
+}
+
+/// The fee at `rate` on `amount`, rounded up in the platform's favour
+pub fn fee(amount: u64, rate: Bps) -> Result<u64> {
+    rate.try_apply(amount, Rounding::Up)
+}
+
+/// The payout of `amount` staked at `odds`, rounded down in the vault's
+/// favour
+pub fn payout_at_odds(amount: u64, odds: Bps) -> Result<u64> {
+    odds.try_invert(amount, Rounding::Down)
+}
+
+/// Probability of an outcome holding `stake` of `total`, clamped to
+/// `[min, max]`
+pub fn clamped_odds(stake: u64, total: u64, min: Bps, max: Bps) -> Result<Bps> {
+    Ok(Bps::try_from_ratio(stake, total, Rounding::Down)?.clamp(min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_rounds_in_requested_direction() {
+        assert_eq!(mul_div(10, 1, 3, Rounding::Down), Some(3));
+        assert_eq!(mul_div(10, 1, 3, Rounding::Up), Some(4));
+        assert_eq!(mul_div(9, 1, 3, Rounding::Up), Some(3));
+    }
+
+    #[test]
+    fn mul_div_rejects_zero_divisor_and_overflow() {
+        assert_eq!(mul_div(1, 1, 0, Rounding::Down), None);
+        assert_eq!(mul_div(u64::MAX, 2, 1, Rounding::Down), None);
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX, Rounding::Down), Some(u64::MAX));
+    }
+
+    #[test]
+    fn fees_round_up_and_payouts_round_down() {
+        let fee_rate = Bps::new(200);
+        // 2% of 1_234_567 is 24_691.34
+        assert_eq!(fee_rate.apply(1_234_567, Rounding::Up), Some(24_692));
+        assert_eq!(fee_rate.apply(1_234_567, Rounding::Down), Some(24_691));
+
+        // A stake of 1_000_000 at 30% pays 3_333_333.33
+        let odds = Bps::new(3_000);
+        assert_eq!(odds.invert(1_000_000, Rounding::Down), Some(3_333_333));
+    }
+
+    #[test]
+    fn ratio_and_clamp() {
+        let prob = Bps::from_ratio(1, 3, Rounding::Down).unwrap();
+        assert_eq!(prob, Bps::new(3_333));
+        assert_eq!(Bps::from_ratio(1, 0, Rounding::Down), None);
+
+        let min = Bps::new(500);
+        let max = Bps::new(9_500);
+        assert_eq!(Bps::ZERO.clamp(min, max), min);
+        assert_eq!(Bps::ONE.clamp(min, max), max);
+        assert_eq!(prob.clamp(min, max), prob);
+    }
+
+    #[test]
+    fn invert_by_zero_odds_is_none() {
+        assert_eq!(Bps::ZERO.invert(1, Rounding::Down), None);
+    }
+
+    #[test]
+    fn safe_math_fails_with_math_overflow_at_the_u64_boundary() {
+        let overflow = Err(ErrorCode::MathOverflow.into());
+        assert_eq!(safe_mul_div(u64::MAX, u64::MAX, u64::MAX, Rounding::Up), Ok(u64::MAX));
+        assert_eq!(safe_mul_div(u64::MAX, 2, 1, Rounding::Down), overflow);
+        assert_eq!(safe_mul_div(1, 1, 0, Rounding::Down), overflow);
+        // (2^65 - 1) / 2 is u64::MAX rounded down but past it rounded up
+        let a = 1_190_112_520_884_487_201;
+        assert_eq!(safe_mul_div(a, 31, 2, Rounding::Down), Ok(u64::MAX));
+        assert_eq!(safe_mul_div(a, 31, 2, Rounding::Up), overflow);
+
+        assert_eq!(safe_add(u64::MAX - 1, 1), Ok(u64::MAX));
+        assert_eq!(safe_add(u64::MAX, 1), overflow);
+        assert_eq!(safe_mul(u64::MAX, 1), Ok(u64::MAX));
+        assert_eq!(safe_mul(u64::MAX / 2 + 1, 2), overflow);
+        assert_eq!(safe_sub(1, 1), Ok(0));
+        assert_eq!(safe_sub(0, 1), overflow);
+    }
+
+    #[test]
+    fn fee_and_odds_helpers_at_the_u64_boundary() {
+        // A 100% fee on the largest amount is the amount; 2% rounds up
+        assert_eq!(fee(u64::MAX, Bps::ONE), Ok(u64::MAX));
+        assert_eq!(fee(u64::MAX, Bps::new(200)), Ok(u64::MAX / 50 + 1));
+        assert_eq!(fee(0, Bps::ONE), Ok(0));
+
+        // Odds below 100% pay more than the stake, which can't exceed u64
+        assert_eq!(payout_at_odds(u64::MAX, Bps::ONE), Ok(u64::MAX));
+        assert_eq!(
+            payout_at_odds(u64::MAX, Bps::new(9_999)),
+            Err(ErrorCode::MathOverflow.into())
+        );
+        assert_eq!(payout_at_odds(1, Bps::ZERO), Err(ErrorCode::MathOverflow.into()));
+
+        let (min, max) = (Bps::new(500), Bps::new(9_500));
+        assert_eq!(clamped_odds(u64::MAX, u64::MAX, min, max), Ok(max));
+        assert_eq!(clamped_odds(0, u64::MAX, min, max), Ok(min));
+        assert_eq!(clamped_odds(1, 3, min, max), Ok(Bps::new(3_333)));
+        assert_eq!(clamped_odds(1, 0, min, max), Err(ErrorCode::MathOverflow.into()));
+    }
+}
@@ -0,0 +1,132 @@
+//! Oracle resolution for price-based markets.
+//!
+//! A poll created with a `ResolutionSource` is settled by anyone calling
+//! `settle_poll_with_oracle` with a Pyth pull-oracle price update account
+//! (`PriceUpdateV2`, owned by `cluster::PYTH_RECEIVER_PROGRAM_ID`). Price
+//! update accounts are posted on demand rather than living at a fixed address,
+//! so the poll pins the feed id instead of an account. The core only reads the
+//! fields it needs and decodes the account by hand, so no Pyth SDK (and its
+//! Anchor version) is pulled in.
+
+use anchor_lang::prelude::*;
+
+use crate::cluster::PYTH_RECEIVER_PROGRAM_ID;
+use crate::ErrorCode;
+
+/// `sha256("account:PriceUpdateV2")[..8]`
+pub const PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Seconds after `end_timestamp` within which the settlement price must have
+/// been published
+pub const ORACLE_PRICE_WINDOW: i64 = 60;
+
+/// Seconds a SOL/USD price may trail the clock when it values a bid against
+/// the config's USD limit
+pub const USD_PRICE_MAX_AGE: i64 = 60;
+
+/// Lowest SOL/USD price exponent a bid can be valued at; Pyth's SOL/USD feed
+/// uses -8
+pub const MIN_USD_PRICE_EXPONENT: i32 = -16;
+
+/// Wormhole verification of a posted price update
+#[derive(AnchorDeserialize)]
+enum VerificationLevel {
+    Partial {
+        #[allow(dead_code)]
+        num_signatures: u8,
+    },
+    Full,
+}
+
+/// Leading fields of Pyth's `PriceUpdateV2`; the EMA fields and posted slot
+/// that follow are not read
+#[derive(AnchorDeserialize)]
+struct PriceUpdateHeader {
+    _write_authority: Pubkey,
+    verification_level: VerificationLevel,
+    feed_id: [u8; 32],
+    price: i64,
+    _conf: u64,
+    exponent: i32,
+    publish_time: i64,
+}
+
+/// A verified price read from a price update account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+/// Read a fully verified price from a Pyth `PriceUpdateV2` account
+pub fn read_price(price_update: &AccountInfo) -> Result<OraclePrice> {
+    require_keys_eq!(
+        *price_update.owner,
+        PYTH_RECEIVER_PROGRAM_ID,
+        ErrorCode::InvalidOracleAccount
+    );
+    let data = price_update.try_borrow_data()?;
+    decode_price(&data)
+}
+
+/// Decode a price update's account data; split out of `read_price` so the
+/// layout is testable without an `AccountInfo`
+pub fn decode_price(data: &[u8]) -> Result<OraclePrice> {
+    require!(
+        data.get(..8) == Some(&PRICE_UPDATE_DISCRIMINATOR[..]),
+        ErrorCode::InvalidOracleAccount
+    );
+    let header = PriceUpdateHeader::deserialize(&mut &data[8..])
+        .map_err(|_| error!(ErrorCode::InvalidOracleAccount))?;
+    require!(
+        matches!(header.verification_level, VerificationLevel::Full),
+        ErrorCode::InvalidOracleAccount
+    );
+
+    Ok(OraclePrice {
+        feed_id: header.feed_id,
+        price: header.price,
+        exponent: header.exponent,
+        publish_time: header.publish_time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price_update(verification: &[u8], publish_time: i64) -> Vec<u8> {
+        let mut data = PRICE_UPDATE_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&[7; 32]); // write authority
+        data.extend_from_slice(verification);
+        data.extend_from_slice(&[9; 32]); // feed id
+        data.extend_from_slice(&20_012_345_678i64.to_le_bytes());
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&(-8i32).to_le_bytes());
+        data.extend_from_slice(&publish_time.to_le_bytes());
+        data.extend_from_slice(&[0; 40]); // prev publish time, EMA, posted slot
+        data
+    }
+
+    #[test]
+    fn decodes_fully_verified_updates_only() {
+        let price = decode_price(&price_update(&[1], 1_700_000_000)).unwrap();
+        assert_eq!(price.feed_id, [9; 32]);
+        assert_eq!(price.price, 20_012_345_678);
+        assert_eq!(price.exponent, -8);
+        assert_eq!(price.publish_time, 1_700_000_000);
+
+        assert_eq!(
+            decode_price(&price_update(&[0, 5], 1_700_000_000)),
+            Err(ErrorCode::InvalidOracleAccount.into())
+        );
+        let mut wrong_account = price_update(&[1], 0);
+        wrong_account[0] ^= 1;
+        assert_eq!(
+            decode_price(&wrong_account),
+            Err(ErrorCode::InvalidOracleAccount.into())
+        );
+    }
+}
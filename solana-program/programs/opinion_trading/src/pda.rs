@@ -0,0 +1,84 @@
+//! Addresses of the program's PDAs.
+//!
+//! Callers building instructions, in particular other programs placing bids
+//! and claiming through CPI, derive the accounts here rather than repeating
+//! the seeds. Each helper returns the address and its bump.
+
+use anchor_lang::prelude::*;
+
+use crate::ID;
+
+/// The global config
+pub fn config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], &ID)
+}
+
+/// The poll created with `poll_id`
+pub fn poll(poll_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"poll", poll_id.as_bytes()], &ID)
+}
+
+/// The SOL vault escrowing the stakes of `poll`
+pub fn vault(poll: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", poll.as_ref()], &ID)
+}
+
+/// The SOL vault of the poll created with `poll_id` before poll version 12,
+/// which `migrate_poll` empties into `vault`
+pub fn legacy_vault(poll_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", poll_id.as_bytes()], &ID)
+}
+
+/// The bid `bettor` placed on `poll` at `bid_index` (the poll's
+/// `next_bid_index` when it was placed)
+pub fn bid(poll: &Pubkey, bettor: &Pubkey, bid_index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"bid", poll.as_ref(), bettor.as_ref(), &bid_index.to_le_bytes()],
+        &ID,
+    )
+}
+
+/// The betting profile of `user`
+pub fn profile(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"profile", user.as_ref()], &ID)
+}
+
+/// The total `user` has staked on `poll`
+pub fn user_stake(poll: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"user_stake", poll.as_ref(), user.as_ref()], &ID)
+}
+
+/// The bids `user` has placed on `poll`
+pub fn position(poll: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pos", poll.as_ref(), user.as_ref()], &ID)
+}
+
+/// The event created with `event_id`
+pub fn event(event_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"event", event_id.as_bytes()], &ID)
+}
+
+/// The internal balance of `user`
+pub fn balance(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"balance", user.as_ref()], &ID)
+}
+
+/// The promotional bet credit granted to `user`
+pub fn credit(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"credit", user.as_ref()], &ID)
+}
+
+/// The promo vault backing every bet credit
+pub fn promo_vault() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"promo_vault"], &ID)
+}
+
+/// Page `page` of the poll registry
+pub fn poll_registry(page: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"poll_registry", &page.to_le_bytes()], &ID)
+}
+
+/// The credential `attestor` issued to `user` for attestation-gated polls
+pub fn attestation(attestor: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"attestation", attestor.as_ref(), user.as_ref()], &ID)
+}
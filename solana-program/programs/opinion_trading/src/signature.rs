@@ -0,0 +1,148 @@
+//! Signed results for markets resolved off-chain.
+//!
+//! A poll with a `result_signer` is settled by anyone calling
+//! `settle_with_signature` in a transaction whose previous instruction is an
+//! ed25519 program instruction verifying the signer's signature over
+//! `result_message(poll, winning_option)`. The runtime checks the signature
+//! before the program runs; the program only reads that instruction back from
+//! the instructions sysvar to confirm who signed what, so the signing key
+//! never has to sign a transaction or hold SOL.
+
+use anchor_lang::prelude::*;
+use solana_instructions_sysvar::get_instruction_relative;
+use solana_sdk_ids::ed25519_program;
+
+use crate::ErrorCode;
+
+/// Domain prefix of a signed result, so the signature can't be replayed as
+/// any other message the key signs
+pub const RESULT_MESSAGE_PREFIX: &[u8] = b"yukti_result";
+
+/// Ed25519 program data: signature count and padding, then one 14-byte
+/// offsets entry per signature
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+
+/// Offsets entries with this instruction index point into the ed25519
+/// instruction's own data
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// A message and the key whose signature over it the runtime verified
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedMessage {
+    pub signer: Pubkey,
+    pub message: Vec<u8>,
+}
+
+/// The message a poll's result signer signs to settle it with
+/// `winning_option`
+pub fn result_message(poll: &Pubkey, winning_option: u8) -> Vec<u8> {
+    [RESULT_MESSAGE_PREFIX, poll.as_ref(), &[winning_option]].concat()
+}
+
+/// Read the message verified by the ed25519 instruction just before the
+/// current one
+pub fn read_signed_message(instructions: &AccountInfo) -> Result<SignedMessage> {
+    let verify = get_instruction_relative(-1, instructions)
+        .map_err(|_| error!(ErrorCode::InvalidResultSignature))?;
+    require_keys_eq!(
+        verify.program_id,
+        ed25519_program::ID,
+        ErrorCode::InvalidResultSignature
+    );
+    decode_ed25519(&verify.data)
+}
+
+/// Decode an ed25519 program instruction verifying exactly one signature
+/// over data in the instruction itself; split out of `read_signed_message`
+/// so the layout is testable without the sysvar
+pub fn decode_ed25519(data: &[u8]) -> Result<SignedMessage> {
+    require!(data.first() == Some(&1), ErrorCode::InvalidResultSignature);
+    let offsets = data
+        .get(SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN)
+        .ok_or_else(|| error!(ErrorCode::InvalidResultSignature))?;
+    let field = |i: usize| u16::from_le_bytes([offsets[2 * i], offsets[2 * i + 1]]);
+    // signature_offset, signature_instruction_index, public_key_offset,
+    // public_key_instruction_index, message_data_offset, message_data_size,
+    // message_instruction_index
+    require!(
+        field(1) == CURRENT_INSTRUCTION
+            && field(3) == CURRENT_INSTRUCTION
+            && field(6) == CURRENT_INSTRUCTION,
+        ErrorCode::InvalidResultSignature
+    );
+    let public_key = usize::from(field(2));
+    let message = usize::from(field(4));
+
+    let signer = data
+        .get(public_key..public_key + 32)
+        .ok_or_else(|| error!(ErrorCode::InvalidResultSignature))?;
+    let message = data
+        .get(message..message + usize::from(field(5)))
+        .ok_or_else(|| error!(ErrorCode::InvalidResultSignature))?;
+
+    Ok(SignedMessage {
+        signer: Pubkey::try_from(signer).unwrap(),
+        message: message.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Data of an ed25519 instruction laid out like the SDK's
+    /// `new_ed25519_instruction`: offsets, key, signature, message
+    fn verify_data(signer: &Pubkey, message: &[u8], instruction_index: u16) -> Vec<u8> {
+        let public_key = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN;
+        let signature = public_key + 32;
+        let message_offset = signature + 64;
+        let mut data = vec![1, 0];
+        for field in [
+            signature,
+            usize::from(instruction_index),
+            public_key,
+            usize::from(instruction_index),
+            message_offset,
+            message.len(),
+            usize::from(instruction_index),
+        ] {
+            data.extend_from_slice(&(field as u16).to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[3; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn decodes_single_inline_signatures_only() {
+        let signer = Pubkey::new_unique();
+        let message = result_message(&Pubkey::new_unique(), 1);
+        assert_eq!(
+            decode_ed25519(&verify_data(&signer, &message, CURRENT_INSTRUCTION)),
+            Ok(SignedMessage {
+                signer,
+                message: message.clone(),
+            })
+        );
+
+        // Key or message read from another instruction
+        assert_eq!(
+            decode_ed25519(&verify_data(&signer, &message, 0)),
+            Err(ErrorCode::InvalidResultSignature.into())
+        );
+        let mut two_signatures = verify_data(&signer, &message, CURRENT_INSTRUCTION);
+        two_signatures[0] = 2;
+        assert_eq!(
+            decode_ed25519(&two_signatures),
+            Err(ErrorCode::InvalidResultSignature.into())
+        );
+        let mut truncated = verify_data(&signer, &message, CURRENT_INSTRUCTION);
+        truncated.pop();
+        assert_eq!(
+            decode_ed25519(&truncated),
+            Err(ErrorCode::InvalidResultSignature.into())
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Off-chain simulation of the program's pricing and payouts.
+//!
+//! Built with the `no-entrypoint` feature, so a backend or risk engine can
+//! link the crate as a library and price bids, replay order flow, and
+//! project settlements with the exact rules the instructions run. Every
+//! function here is pure: it takes plain numbers or a `Poll` decoded with
+//! `Poll::try_deserialize`, never reads the clock or another account, and
+//! delegates to `logic` and `math` rather than restating them.
+
+use anchor_lang::prelude::*;
+
+use crate::math::safe_add;
+use crate::{logic, Poll};
+
+/// A bid replayed against a poll by `simulate_bid`
+#[derive(Clone)]
+pub struct SimulatedBid {
+    /// The part of the requested amount that fills under the poll's caps and
+    /// price impact limit
+    pub amount: u64,
+    /// Odds the bid is priced at (basis points)
+    pub odds: u64,
+    pub potential_win: u64,
+    /// The poll once the bid is recorded, for chaining further bids
+    pub poll: Poll,
+}
+
+/// AMM odds of every outcome of a market holding `stakes`, as
+/// `update_amm_odds` prices a poll whose pool is the sum of its stakes
+pub fn amm_odds(stakes: &[u64], virtual_liquidity: u64) -> Result<Vec<u64>> {
+    let total_pool = stakes.iter().try_fold(0, |total, &stake| safe_add(total, stake))?;
+    stakes
+        .iter()
+        .map(|&stake| logic::amm_odds(stake, total_pool, stakes.len(), virtual_liquidity))
+        .collect()
+}
+
+/// Payout of `amount` staked at `odds` before fees, rounded down
+pub fn potential_win(amount: u64, odds: u64) -> Result<u64> {
+    logic::potential_win(amount, odds)
+}
+
+/// (payout, platform_fee) of a winning `potential_win` at `fee_bps` moved by
+/// the bid's `fee_adjust_bps`
+pub fn platform_fee(potential_win: u64, fee_bps: u16, fee_adjust_bps: i16) -> Result<(u64, u64)> {
+    logic::split_platform_fee(potential_win, logic::adjusted_fee_bps(fee_bps, fee_adjust_bps))
+}
+
+/// Record a bid of `amount` on `option` at `now` against a copy of `poll`,
+/// filling and pricing it as `place_bid` would
+pub fn simulate_bid(poll: &Poll, amount: u64, option: u8, now: i64) -> Result<SimulatedBid> {
+    let amount = logic::fill_amount(poll, amount, option, now)?;
+    let mut after = poll.clone();
+    let (odds, potential_win) = logic::record_bid(&mut after, amount, option, now)?;
+    Ok(SimulatedBid {
+        amount,
+        odds,
+        potential_win,
+        poll: after,
+    })
+}
+
+/// (payout, platform_fee) of a bid on `option` if the poll settles on
+/// `winner`; losing bids get nothing
+pub fn simulate_payout(
+    poll: &Poll,
+    winner: u8,
+    option: u8,
+    amount: u64,
+    potential_win: u64,
+    fee_adjust_bps: i16,
+) -> Result<(u64, u64)> {
+    let mut settled = poll.clone();
+    settled.winner = Some(winner);
+    logic::settled_payout(&settled, option, amount, potential_win, fee_adjust_bps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::tests::active_poll;
+    use crate::{PayoutMode, LAMPORTS_PER_SOL, MAX_ODDS_BPS, MIN_ODDS_BPS};
+
+    // A fixed-odds poll with LP liquidity to back the first bids' wins
+    fn backed_poll() -> Poll {
+        let mut poll = active_poll(100);
+        poll.lp_liquidity = 10 * LAMPORTS_PER_SOL;
+        poll
+    }
+
+    #[test]
+    fn amm_odds_match_a_repriced_poll() {
+        assert_eq!(amm_odds(&[0, 0], 0), Ok(vec![5_000, 5_000]));
+        assert_eq!(amm_odds(&[0, 0, 0], 0), Ok(vec![3_333, 3_333, 3_333]));
+        assert_eq!(amm_odds(&[3, 1], 0), Ok(vec![7_500, 2_500]));
+        assert_eq!(amm_odds(&[1, 0], 0), Ok(vec![MAX_ODDS_BPS, MIN_ODDS_BPS]));
+        // Virtual liquidity smooths the same stakes towards even odds
+        assert_eq!(amm_odds(&[3, 1], 2), Ok(vec![6_250, 3_750]));
+
+        let mut poll = backed_poll();
+        poll.virtual_liquidity = LAMPORTS_PER_SOL;
+        let sim = simulate_bid(&poll, LAMPORTS_PER_SOL, 0, 1).unwrap();
+        let stakes: Vec<u64> = sim.poll.outcomes.iter().map(|outcome| outcome.stake).collect();
+        let odds: Vec<u64> = sim.poll.outcomes.iter().map(|outcome| outcome.odds).collect();
+        assert_eq!(amm_odds(&stakes, poll.virtual_liquidity), Ok(odds));
+        assert!(amm_odds(&[u64::MAX, 1], 0).is_err());
+    }
+
+    #[test]
+    fn potential_win_and_fee_round_against_the_bettor() {
+        // 1_000_000 at 30% is 3_333_333.33
+        assert_eq!(potential_win(1_000_000, 3_000), Ok(3_333_333));
+        assert!(potential_win(1, 0).is_err());
+
+        // 2% of 1_234_567 is 24_691.34
+        assert_eq!(platform_fee(1_234_567, 200, 0), Ok((1_209_875, 24_692)));
+        assert_eq!(platform_fee(1_000, 200, 100), Ok((970, 30)));
+        assert_eq!(platform_fee(1_000, 200, -300), Ok((1_000, 0)));
+    }
+
+    #[test]
+    fn simulated_bids_match_the_quote_and_leave_the_poll_alone() {
+        let poll = backed_poll();
+        let sim = simulate_bid(&poll, LAMPORTS_PER_SOL, 0, 1).unwrap();
+        let quote = logic::quote_bid(&poll, LAMPORTS_PER_SOL, 0, 0, 1).unwrap();
+        assert_eq!(
+            (sim.amount, sim.odds, sim.potential_win),
+            (quote.amount, quote.odds, quote.potential_win)
+        );
+        assert_eq!(poll.total_pool, 0);
+        assert_eq!(sim.poll.total_pool, LAMPORTS_PER_SOL);
+
+        // Chained bids see the earlier ones' odds and fill only what the
+        // pool can back
+        let next = simulate_bid(&sim.poll, LAMPORTS_PER_SOL, 1, 2).unwrap();
+        assert_eq!(next.odds, sim.poll.outcomes[1].odds);
+        assert!(next.amount < LAMPORTS_PER_SOL);
+        assert_eq!(next.poll.total_pool, LAMPORTS_PER_SOL + next.amount);
+
+        assert!(simulate_bid(&poll, LAMPORTS_PER_SOL, 2, 1).is_err());
+        assert!(simulate_bid(&poll, LAMPORTS_PER_SOL, 0, 100).is_err());
+    }
+
+    #[test]
+    fn simulated_payouts_follow_the_payout_mode() {
+        let poll = backed_poll();
+        let bid = simulate_bid(&poll, LAMPORTS_PER_SOL, 0, 1).unwrap();
+        let win = bid.potential_win;
+        let fee = win.div_ceil(50);
+        assert_eq!(
+            simulate_payout(&bid.poll, 0, 0, bid.amount, win, 0),
+            Ok((win - fee, fee))
+        );
+        assert_eq!(simulate_payout(&bid.poll, 1, 0, bid.amount, win, 0), Ok((0, 0)));
+
+        // Pari-mutuel winners split the whole pool pro rata
+        let mut poll = active_poll(100);
+        poll.payout_mode = PayoutMode::PariMutuel;
+        let first = simulate_bid(&poll, LAMPORTS_PER_SOL, 0, 1).unwrap();
+        let second = simulate_bid(&first.poll, 3 * LAMPORTS_PER_SOL, 1, 1).unwrap();
+        let pool = 4 * LAMPORTS_PER_SOL;
+        let (payout, fee) =
+            simulate_payout(&second.poll, 0, 0, first.amount, first.potential_win, 0).unwrap();
+        assert_eq!((payout + fee, fee), (pool, pool / 50));
+    }
+}
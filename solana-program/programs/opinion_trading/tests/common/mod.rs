@@ -0,0 +1,552 @@
+//! Shared setup for the program tests.
+//!
+//! The program runs as its SBF build under `solana-program-test`. Anchor's
+//! CPIs only run on the Solana target, which rules out a native processor, so
+//! the tests are behind the `test-sbf` feature: `cargo test-sbf` builds the
+//! program and turns it on, and a plain `cargo test` skips them.
+//! `initialize_config` checks the upgrade authority of the program's
+//! `ProgramData`, which a program loaded at genesis doesn't have, so the
+//! config is written directly at genesis; every other account is created
+//! through the program's own instructions.
+
+// Each test binary uses its own subset of the helpers
+#![allow(dead_code)]
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{
+    system_program, AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas,
+};
+use opinion_trading::{cluster, pda, Config, PayoutMode, PollKind, PollLimits, ID};
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+// Platform fee the test config charges on winning payouts (basis points)
+pub const FEE_BPS: u16 = 200;
+
+// Lamports each funded wallet starts with
+pub const STARTING_BALANCE: u64 = 100 * LAMPORTS_PER_SOL;
+
+/// The poll created with a given id, with the PDAs derived from it
+pub struct Market {
+    pub poll_id: String,
+    pub poll: Pubkey,
+    pub vault: Pubkey,
+}
+
+impl Market {
+    pub fn new(poll_id: &str) -> Self {
+        let poll = pda::poll(poll_id).0;
+        Market {
+            poll_id: poll_id.to_string(),
+            poll,
+            vault: pda::vault(&poll).0,
+        }
+    }
+}
+
+// Where `cargo build-sbf` writes the program, for a plain `cargo test`;
+// `cargo test-sbf` sets `SBF_OUT_DIR` itself
+const DEPLOY_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy");
+
+/// Start a test validator with the program, a config charging `FEE_BPS` with
+/// no creation bond, page 0 of the poll registry, and each of `wallets`
+/// funded with `STARTING_BALANCE`
+pub async fn start(wallets: &[&Keypair]) -> ProgramTestContext {
+    start_with_admin(&Keypair::new(), wallets).await
+}
+
+/// `start` with `admin` as the config admin, funded like `wallets`
+pub async fn start_with_admin(admin: &Keypair, wallets: &[&Keypair]) -> ProgramTestContext {
+    // `solana-program-test` reads `BPF_OUT_DIR` before `SBF_OUT_DIR`
+    let out_dir = match std::env::var("BPF_OUT_DIR").or_else(|_| std::env::var("SBF_OUT_DIR")) {
+        Ok(dir) => dir,
+        Err(_) => {
+            std::env::set_var("SBF_OUT_DIR", DEPLOY_DIR);
+            DEPLOY_DIR.to_string()
+        }
+    };
+    let program = std::path::Path::new(&out_dir).join("opinion_trading.so");
+    assert!(
+        program.exists(),
+        "{} not found: build the program with `cargo build-sbf` first, or run `cargo test-sbf`",
+        program.display()
+    );
+    let mut program_test = ProgramTest::new("opinion_trading", ID, None);
+    program_test.prefer_bpf(true);
+
+    let (config_key, bump) = pda::config();
+    let config = Config {
+        admin: admin.pubkey(),
+        treasury: Pubkey::new_unique(),
+        fee_bps: FEE_BPS,
+        min_bet: cluster::MIN_BET_AMOUNT,
+        max_bet: cluster::MAX_BET_AMOUNT,
+        bump,
+        creation_bond: 0,
+        creator_fee_bps: 0,
+        lp_fee_bps: 0,
+        claim_period: cluster::CLOSE_GRACE_PERIOD,
+        pending_admin: None,
+        paused: false,
+        referral_fee_bps: 0,
+        fee_tiers: Vec::new(),
+        event_seq: 0,
+        usd_feed_id: [0; 32],
+        max_bid_usd: 0,
+        epoch_id: 0,
+        epoch_start: 0,
+        epoch_end: 0,
+    };
+    program_test.add_account(config_key, program_account(&config, Config::LEN));
+
+    for wallet in std::iter::once(&admin).chain(wallets) {
+        program_test.add_account(
+            wallet.pubkey(),
+            Account::new(STARTING_BALANCE, 0, &system_program::ID),
+        );
+    }
+
+    let mut context = program_test.start_with_context().await;
+
+    let (registry, _) = pda::poll_registry(0);
+    let init_registry = Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::InitRegistryPage {
+            registry,
+            previous: None,
+            payer: context.payer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::InitRegistryPage { page: 0 }.data(),
+    };
+    send(&mut context, &[init_registry], &[]).await.unwrap();
+
+    context
+}
+
+/// An account owned by the program holding `account`, padded to `space`
+fn program_account<T: AccountSerialize>(account: &T, space: usize) -> Account {
+    let mut data = Vec::with_capacity(space);
+    account.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+
+    let mut program_account = Account::new(Rent::default().minimum_balance(space), 0, &ID);
+    program_account.data = data;
+    program_account
+}
+
+/// Send `instructions` in one transaction paid by the context's payer
+pub async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    // A fresh blockhash, so a retried instruction isn't a duplicate transaction
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// Assert a single-instruction transaction failed with the program or Anchor
+/// error `code`
+pub fn assert_error(result: Result<(), BanksClientError>, code: impl Into<u32>) {
+    let error = result.expect_err("transaction should have failed");
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(code.into()))
+    );
+}
+
+/// Deserialize the program account at `key`
+pub async fn fetch<T: AccountDeserialize>(context: &mut ProgramTestContext, key: Pubkey) -> T {
+    let account = context
+        .banks_client
+        .get_account(key)
+        .await
+        .unwrap()
+        .expect("account should exist");
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+/// Lamports held by `key`, 0 if it doesn't exist
+pub async fn balance(context: &mut ProgramTestContext, key: Pubkey) -> u64 {
+    context.banks_client.get_balance(key).await.unwrap()
+}
+
+/// The validator's current unix time
+pub async fn now(context: &mut ProgramTestContext) -> i64 {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp
+}
+
+/// Move the validator's clock to `unix_timestamp`
+pub async fn warp_to(context: &mut ProgramTestContext, unix_timestamp: i64) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
+
+/// Set the lamports held by `key`, e.g. to leave a vault short
+pub async fn set_balance(context: &mut ProgramTestContext, key: Pubkey, lamports: u64) {
+    let mut account = context
+        .banks_client
+        .get_account(key)
+        .await
+        .unwrap()
+        .expect("account should exist");
+    account.lamports = lamports;
+    context.set_account(&key, &account.into());
+}
+
+/// The event authority `emit_cpi!` signs with
+pub fn event_authority() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &ID).0
+}
+
+/// Create a two-outcome SOL poll ending an hour from now
+pub async fn create_poll(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    poll_id: &str,
+    payout_mode: PayoutMode,
+) -> Market {
+    let market = Market::new(poll_id);
+    let end_timestamp = now(context).await + 60 * 60;
+
+    let instruction = Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::InitializePoll {
+            poll: market.poll,
+            vault: market.vault,
+            config: pda::config().0,
+            registry: pda::poll_registry(0).0,
+            mint: None,
+            token_vault: None,
+            authority: authority.pubkey(),
+            token_program: None,
+            associated_token_program: None,
+            system_program: system_program::ID,
+            #[cfg(feature = "cpi-events")]
+            event_authority: event_authority(),
+            #[cfg(feature = "cpi-events")]
+            program: ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::InitializePoll {
+            poll_id: poll_id.to_string(),
+            title: format!("Test poll {poll_id}"),
+            outcomes: vec!["Yes".to_string(), "No".to_string()],
+            end_timestamp,
+            payout_mode,
+            resolution: None,
+            dispute_period: 0,
+            arbiter: None,
+            start_timestamp: 0,
+            deadline_extension: None,
+            virtual_liquidity: 0,
+            kind: PollKind::Categorical,
+            limits: PollLimits {
+                min_bet: None,
+                max_bet: None,
+                fee_bps: None,
+            },
+            incentives: None,
+            resolution_deadline: 0,
+        }
+        .data(),
+    };
+    send(context, &[instruction], &[authority]).await.unwrap();
+
+    market
+}
+
+/// `place_bid` by `bettor` at the poll's next bid index
+pub async fn place_bid_ix(
+    context: &mut ProgramTestContext,
+    market: &Market,
+    bettor: &Keypair,
+    amount: u64,
+    option: u8,
+) -> (Instruction, Pubkey) {
+    let poll: opinion_trading::Poll = fetch(context, market.poll).await;
+    let bid_index = poll.next_bid_index;
+    let (bid, _) = pda::bid(&market.poll, &bettor.pubkey(), bid_index);
+
+    let instruction = Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::PlaceBid {
+            poll: market.poll,
+            config: pda::config().0,
+            vault: market.vault,
+            bid,
+            profile: pda::profile(&bettor.pubkey()).0,
+            user_stake: pda::user_stake(&market.poll, &bettor.pubkey()).0,
+            position: pda::position(&market.poll, &bettor.pubkey()).0,
+            referrer: None,
+            gate_token: None,
+            attestation: None,
+            odds_history: None,
+            sol_usd_price: None,
+            bettor: bettor.pubkey(),
+            payer: bettor.pubkey(),
+            system_program: system_program::ID,
+            #[cfg(feature = "cpi-events")]
+            event_authority: event_authority(),
+            #[cfg(feature = "cpi-events")]
+            program: ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::PlaceBid {
+            amount,
+            option,
+            timestamp: 0,
+            bid_index,
+            allowlist_proof: Vec::new(),
+            memo: None,
+        }
+        .data(),
+    };
+    (instruction, bid)
+}
+
+/// Place a bid and return its address
+pub async fn place_bid(
+    context: &mut ProgramTestContext,
+    market: &Market,
+    bettor: &Keypair,
+    amount: u64,
+    option: u8,
+) -> Pubkey {
+    let (instruction, bid) = place_bid_ix(context, market, bettor, amount, option).await;
+    send(context, &[instruction], &[bettor]).await.unwrap();
+    bid
+}
+
+/// `settle_poll` signed by `authority`
+pub fn settle_poll_ix(market: &Market, authority: &Keypair, winning_option: u8) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::SettlePoll {
+            poll: market.poll,
+            config: pda::config().0,
+            resolution_note: None,
+            authority: authority.pubkey(),
+            event_authority: event_authority(),
+            program: ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::SettlePoll { winning_option }.data(),
+    }
+}
+
+/// `cancel_poll` signed by `authority`
+pub fn cancel_poll_ix(market: &Market, authority: &Keypair) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::CancelPoll {
+            poll: market.poll,
+            authority: authority.pubkey(),
+            event_authority: event_authority(),
+            program: ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::CancelPoll {}.data(),
+    }
+}
+
+/// The insurance fund covering vault shortfalls
+pub fn insurance_fund() -> Pubkey {
+    Pubkey::find_program_address(&[b"insurance_fund"], &ID).0
+}
+
+/// The fee vault `sweep_fees` pays into
+pub fn fee_vault() -> Pubkey {
+    Pubkey::find_program_address(&[b"fee_vault"], &ID).0
+}
+
+/// The ledger `sweep_fees` and `withdraw_treasury` record into
+pub fn treasury_stats() -> Pubkey {
+    Pubkey::find_program_address(&[b"treasury_stats"], &ID).0
+}
+
+/// Create the fee vault, its untimelocked treasury ledger, and an insurance
+/// fund taking `insurance_fee_bps` of swept fees, holding `deposit` beyond rent
+pub async fn init_fee_accounts(
+    context: &mut ProgramTestContext,
+    admin: &Keypair,
+    insurance_fee_bps: u16,
+    deposit: u64,
+) {
+    let config = pda::config().0;
+    let init_fee_vault = Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::InitializeFeeVault {
+            config,
+            fee_vault: fee_vault(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::InitializeFeeVault {}.data(),
+    };
+    let init_treasury_stats = Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::InitializeTreasuryStats {
+            config,
+            treasury_stats: treasury_stats(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::InitializeTreasuryStats { withdraw_delay: 0 }.data(),
+    };
+    let init_insurance_fund = Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::InitializeInsuranceFund {
+            config,
+            insurance_fund: insurance_fund(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::InitializeInsuranceFund {
+            fee_bps: insurance_fee_bps,
+        }
+        .data(),
+    };
+    let deposit_insurance = Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::ManageInsuranceFund {
+            config,
+            insurance_fund: insurance_fund(),
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+            #[cfg(feature = "cpi-events")]
+            event_authority: event_authority(),
+            #[cfg(feature = "cpi-events")]
+            program: ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::DepositInsurance { amount: deposit }.data(),
+    };
+    for instruction in [
+        init_fee_vault,
+        init_treasury_stats,
+        init_insurance_fund,
+        deposit_insurance,
+    ] {
+        send(context, &[instruction], &[admin]).await.unwrap();
+    }
+}
+
+/// `sweep_fees` of a settled poll into the fee vault and insurance fund
+pub fn sweep_fees_ix(market: &Market, authority: &Keypair) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::SweepFees {
+            poll: market.poll,
+            vault: market.vault,
+            fee_vault: fee_vault(),
+            treasury_stats: treasury_stats(),
+            insurance_fund: insurance_fund(),
+            authority: authority.pubkey(),
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::SweepFees {}.data(),
+    }
+}
+
+/// `withdraw_treasury` of `amount` from the fee vault to `destination`
+pub fn withdraw_treasury_ix(admin: &Keypair, amount: u64, destination: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::WithdrawTreasury {
+            config: pda::config().0,
+            fee_vault: fee_vault(),
+            treasury_stats: treasury_stats(),
+            destination,
+            admin: admin.pubkey(),
+            event_authority: event_authority(),
+            program: ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::WithdrawTreasury {
+            amount,
+            destination,
+        }
+        .data(),
+    }
+}
+
+/// `claim_winnings` of the whole payout of `bid` to the bettor's wallet,
+/// drawing any vault shortfall from the insurance fund if it's passed
+pub fn claim_winnings_ix(
+    market: &Market,
+    bid: Pubkey,
+    bettor: &Keypair,
+    insurance_fund: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::ClaimWinnings {
+            poll: market.poll,
+            config: pda::config().0,
+            vault: market.vault,
+            bid,
+            profile: pda::profile(&bettor.pubkey()).0,
+            referrer: None,
+            insurance_fund,
+            promo_vault: None,
+            bettor: bettor.pubkey(),
+            claim_delegate: None,
+            destination: bettor.pubkey(),
+            payer: bettor.pubkey(),
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::ClaimWinnings { amount: None }.data(),
+    }
+}
+
+/// `claim_refund` of `bid` on a cancelled poll to the bettor's wallet
+pub fn claim_refund_ix(market: &Market, bid: Pubkey, bettor: &Keypair) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: opinion_trading::accounts::ClaimRefund {
+            poll: market.poll,
+            config: pda::config().0,
+            vault: market.vault,
+            bid,
+            profile: pda::profile(&bettor.pubkey()).0,
+            promo_vault: None,
+            bettor: bettor.pubkey(),
+            destination: bettor.pubkey(),
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: ID,
+        }
+        .to_account_metas(None),
+        data: opinion_trading::instruction::ClaimRefund {}.data(),
+    }
+}
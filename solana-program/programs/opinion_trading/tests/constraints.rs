@@ -0,0 +1,100 @@
+//! Negative tests for the account constraints tying bids, vaults, and
+//! authorities to the poll an instruction acts on.
+
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use common::*;
+use opinion_trading::{ErrorCode, PayoutMode};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+
+/// Two polls with one bid each by `bettor` on outcome 0, ended
+async fn two_polls_with_bids(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    bettor: &Keypair,
+) -> ((Market, Pubkey), (Market, Pubkey)) {
+    let a = create_poll(context, authority, "poll-a", PayoutMode::PariMutuel).await;
+    let b = create_poll(context, authority, "poll-b", PayoutMode::PariMutuel).await;
+    let bid_a = place_bid(context, &a, bettor, LAMPORTS_PER_SOL, 0).await;
+    let bid_b = place_bid(context, &b, bettor, LAMPORTS_PER_SOL, 0).await;
+
+    let end = now(context).await + 60 * 60;
+    warp_to(context, end).await;
+    ((a, bid_a), (b, bid_b))
+}
+
+#[solana_program_test::tokio::test(crate = "solana_program_test::tokio")]
+async fn claim_rejects_a_bid_or_vault_from_another_poll() {
+    let authority = Keypair::new();
+    let bettor = Keypair::new();
+    let mut context = start(&[&authority, &bettor]).await;
+    let ((a, bid_a), (b, bid_b)) = two_polls_with_bids(&mut context, &authority, &bettor).await;
+    for market in [&a, &b] {
+        send(&mut context, &[settle_poll_ix(market, &authority, 0)], &[&authority])
+            .await
+            .unwrap();
+    }
+
+    let claim = claim_winnings_ix(&a, bid_b, &bettor, None);
+    let result = send(&mut context, &[claim], &[&bettor]).await;
+    assert_error(result, ErrorCode::BidPollMismatch);
+
+    // Poll A with poll B's vault
+    let mixed = Market {
+        vault: b.vault,
+        ..Market::new(&a.poll_id)
+    };
+    let claim = claim_winnings_ix(&mixed, bid_a, &bettor, None);
+    let result = send(&mut context, &[claim], &[&bettor]).await;
+    assert_error(result, AnchorErrorCode::ConstraintSeeds);
+
+    let claim = claim_winnings_ix(&a, bid_a, &bettor, None);
+    send(&mut context, &[claim], &[&bettor]).await.unwrap();
+}
+
+#[solana_program_test::tokio::test(crate = "solana_program_test::tokio")]
+async fn refund_rejects_a_bid_from_another_poll() {
+    let authority = Keypair::new();
+    let bettor = Keypair::new();
+    let mut context = start(&[&authority, &bettor]).await;
+    let a = create_poll(&mut context, &authority, "poll-a", PayoutMode::PariMutuel).await;
+    let b = create_poll(&mut context, &authority, "poll-b", PayoutMode::PariMutuel).await;
+    let bid_a = place_bid(&mut context, &a, &bettor, LAMPORTS_PER_SOL, 0).await;
+    let bid_b = place_bid(&mut context, &b, &bettor, LAMPORTS_PER_SOL, 0).await;
+    send(&mut context, &[cancel_poll_ix(&a, &authority)], &[&authority])
+        .await
+        .unwrap();
+
+    let result = send(&mut context, &[claim_refund_ix(&a, bid_b, &bettor)], &[&bettor]).await;
+    assert_error(result, ErrorCode::BidPollMismatch);
+
+    send(&mut context, &[claim_refund_ix(&a, bid_a, &bettor)], &[&bettor])
+        .await
+        .unwrap();
+}
+
+#[solana_program_test::tokio::test(crate = "solana_program_test::tokio")]
+async fn only_the_poll_authority_settles_or_cancels() {
+    let authority = Keypair::new();
+    let bettor = Keypair::new();
+    let stranger = Keypair::new();
+    let mut context = start(&[&authority, &bettor, &stranger]).await;
+    let ((a, _), (b, _)) = two_polls_with_bids(&mut context, &authority, &bettor).await;
+
+    let result = send(&mut context, &[settle_poll_ix(&a, &stranger, 0)], &[&stranger]).await;
+    assert_error(result, ErrorCode::Unauthorized);
+    let result = send(&mut context, &[cancel_poll_ix(&b, &stranger)], &[&stranger]).await;
+    assert_error(result, ErrorCode::Unauthorized);
+
+    send(&mut context, &[settle_poll_ix(&a, &authority, 0)], &[&authority])
+        .await
+        .unwrap();
+    send(&mut context, &[cancel_poll_ix(&b, &authority)], &[&authority])
+        .await
+        .unwrap();
+}
@@ -0,0 +1,195 @@
+//! End-to-end payout tests: polls are created, bid on from several wallets,
+//! settled or cancelled, and claimed, checking every lamport that moves.
+
+#![cfg(feature = "test-sbf")]
+
+mod common;
+
+use common::*;
+use opinion_trading::{
+    pda, Bid, BidStatus, Config, ErrorCode, InsuranceFund, PayoutMode, Poll, TreasuryStats,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+// Bounds AMM odds are clamped to (basis points)
+const MIN_ODDS_BPS: u64 = 500;
+const MAX_ODDS_BPS: u64 = 9_500;
+
+#[solana_program_test::tokio::test(crate = "solana_program_test::tokio")]
+async fn pari_mutuel_winners_split_the_pool_less_the_fee() {
+    let admin = Keypair::new();
+    let authority = Keypair::new();
+    let [alice, bob, carol] = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let mut context = start_with_admin(&admin, &[&authority, &alice, &bob, &carol]).await;
+    init_fee_accounts(&mut context, &admin, 0, 0).await;
+    let market = create_poll(&mut context, &authority, "round-trip", PayoutMode::PariMutuel).await;
+
+    let alice_bid = place_bid(&mut context, &market, &alice, 3 * LAMPORTS_PER_SOL, 0).await;
+    let bob_bid = place_bid(&mut context, &market, &bob, LAMPORTS_PER_SOL, 0).await;
+    let carol_bid = place_bid(&mut context, &market, &carol, 2 * LAMPORTS_PER_SOL, 1).await;
+
+    let poll: Poll = fetch(&mut context, market.poll).await;
+    assert_eq!(poll.total_pool, 6 * LAMPORTS_PER_SOL);
+    assert_eq!(
+        balance(&mut context, market.vault).await,
+        poll.crank_bounty + 6 * LAMPORTS_PER_SOL
+    );
+
+    warp_to(&mut context, poll.end_timestamp).await;
+    send(&mut context, &[settle_poll_ix(&market, &authority, 0)], &[&authority])
+        .await
+        .unwrap();
+
+    // Alice staked 3 of the 4 SOL on the winner: 4.5 SOL gross, less 2%
+    let before = balance(&mut context, alice.pubkey()).await;
+    let claim = claim_winnings_ix(&market, alice_bid, &alice, None);
+    send(&mut context, &[claim], &[&alice]).await.unwrap();
+    assert_eq!(balance(&mut context, alice.pubkey()).await - before, 4_410_000_000);
+
+    // Bob: 1.5 SOL gross, less 2%
+    let before = balance(&mut context, bob.pubkey()).await;
+    let claim = claim_winnings_ix(&market, bob_bid, &bob, None);
+    send(&mut context, &[claim], &[&bob]).await.unwrap();
+    assert_eq!(balance(&mut context, bob.pubkey()).await - before, 1_470_000_000);
+
+    let bid: Bid = fetch(&mut context, alice_bid).await;
+    assert!(bid.status == BidStatus::Won);
+
+    // A second claim pays nothing more
+    let claim = claim_winnings_ix(&market, alice_bid, &alice, None);
+    let result = send(&mut context, &[claim], &[&alice]).await;
+    assert_error(result, ErrorCode::BidAlreadyClaimed);
+
+    let claim = claim_winnings_ix(&market, carol_bid, &carol, None);
+    let result = send(&mut context, &[claim], &[&carol]).await;
+    assert_error(result, ErrorCode::BidDidNotWin);
+
+    // The fees are all that's left besides the crank bounty
+    let poll: Poll = fetch(&mut context, market.poll).await;
+    assert_eq!(poll.accrued_fees, 120_000_000);
+    assert_eq!(
+        balance(&mut context, market.vault).await,
+        poll.crank_bounty + poll.accrued_fees
+    );
+
+    let before = balance(&mut context, fee_vault()).await;
+    send(&mut context, &[sweep_fees_ix(&market, &authority)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(balance(&mut context, fee_vault()).await - before, 120_000_000);
+    assert_eq!(balance(&mut context, market.vault).await, poll.crank_bounty);
+
+    let stats: TreasuryStats = fetch(&mut context, treasury_stats()).await;
+    assert_eq!((stats.total_fees, stats.sweep_count), (120_000_000, 1));
+    assert_eq!(stats.recent_sweeps[0].poll, market.poll);
+
+    // No timelock: the withdrawal is paid to the config's treasury at once
+    // and kept in the history
+    let config: Config = fetch(&mut context, pda::config().0).await;
+    let destination = config.treasury;
+    let withdraw = withdraw_treasury_ix(&admin, 100_000_000, destination);
+    send(&mut context, &[withdraw], &[&admin]).await.unwrap();
+    assert_eq!(balance(&mut context, destination).await, 100_000_000);
+    let stats: TreasuryStats = fetch(&mut context, treasury_stats()).await;
+    assert_eq!(stats.total_withdrawn, 100_000_000);
+    assert_eq!(stats.withdrawals[0].destination, destination);
+    assert!(stats.pending.is_none());
+}
+
+#[solana_program_test::tokio::test(crate = "solana_program_test::tokio")]
+async fn cancelled_poll_refunds_every_stake_once() {
+    let authority = Keypair::new();
+    let [alice, bob] = [Keypair::new(), Keypair::new()];
+    let mut context = start(&[&authority, &alice, &bob]).await;
+    let market = create_poll(&mut context, &authority, "refunds", PayoutMode::PariMutuel).await;
+
+    let alice_bid = place_bid(&mut context, &market, &alice, LAMPORTS_PER_SOL, 0).await;
+    let bob_bid = place_bid(&mut context, &market, &bob, 2 * LAMPORTS_PER_SOL, 1).await;
+    send(&mut context, &[cancel_poll_ix(&market, &authority)], &[&authority])
+        .await
+        .unwrap();
+
+    for (bettor, bid, amount) in [
+        (&alice, alice_bid, LAMPORTS_PER_SOL),
+        (&bob, bob_bid, 2 * LAMPORTS_PER_SOL),
+    ] {
+        let before = balance(&mut context, bettor.pubkey()).await;
+        let refund = claim_refund_ix(&market, bid, bettor);
+        send(&mut context, &[refund], &[bettor]).await.unwrap();
+        assert_eq!(balance(&mut context, bettor.pubkey()).await - before, amount);
+
+        let refund = claim_refund_ix(&market, bid, bettor);
+        let result = send(&mut context, &[refund], &[bettor]).await;
+        assert_error(result, ErrorCode::BidAlreadyClaimed);
+    }
+
+    let poll: Poll = fetch(&mut context, market.poll).await;
+    assert_eq!(balance(&mut context, market.vault).await, poll.crank_bounty);
+}
+
+#[solana_program_test::tokio::test(crate = "solana_program_test::tokio")]
+async fn vault_shortfall_is_drawn_from_the_insurance_fund() {
+    let admin = Keypair::new();
+    let authority = Keypair::new();
+    let [alice, bob] = [Keypair::new(), Keypair::new()];
+    let mut context = start_with_admin(&admin, &[&authority, &alice, &bob]).await;
+    let market = create_poll(&mut context, &authority, "shortfall", PayoutMode::PariMutuel).await;
+
+    let alice_bid = place_bid(&mut context, &market, &alice, LAMPORTS_PER_SOL, 0).await;
+    place_bid(&mut context, &market, &bob, LAMPORTS_PER_SOL, 1).await;
+    let poll: Poll = fetch(&mut context, market.poll).await;
+    warp_to(&mut context, poll.end_timestamp).await;
+    send(&mut context, &[settle_poll_ix(&market, &authority, 0)], &[&authority])
+        .await
+        .unwrap();
+
+    // Leave the vault with half the pool; Alice is owed 1.96 SOL
+    let left = LAMPORTS_PER_SOL;
+    set_balance(&mut context, market.vault, left).await;
+    let claim = claim_winnings_ix(&market, alice_bid, &alice, None);
+    let result = send(&mut context, &[claim], &[&alice]).await;
+    assert_error(result, ErrorCode::VaultShortfall);
+
+    init_fee_accounts(&mut context, &admin, 0, 5 * LAMPORTS_PER_SOL).await;
+    let before = balance(&mut context, alice.pubkey()).await;
+    let claim = claim_winnings_ix(&market, alice_bid, &alice, Some(insurance_fund()));
+    send(&mut context, &[claim], &[&alice]).await.unwrap();
+    assert_eq!(balance(&mut context, alice.pubkey()).await - before, 1_960_000_000);
+
+    let fund: InsuranceFund = fetch(&mut context, insurance_fund()).await;
+    assert_eq!(fund.total_covered, 1_960_000_000 - left);
+    assert_eq!(balance(&mut context, market.vault).await, 0);
+}
+
+#[solana_program_test::tokio::test(crate = "solana_program_test::tokio")]
+async fn odds_are_clamped_and_bids_priced_at_the_clamp() {
+    let authority = Keypair::new();
+    let [alice, bob] = [Keypair::new(), Keypair::new()];
+    let mut context = start(&[&authority, &alice, &bob]).await;
+    let market = create_poll(&mut context, &authority, "clamped", PayoutMode::PariMutuel).await;
+
+    // The first bid buys at even odds and takes the whole pool
+    let first = place_bid(&mut context, &market, &alice, 10 * LAMPORTS_PER_SOL, 0).await;
+    let bid: Bid = fetch(&mut context, first).await;
+    assert_eq!(bid.odds_at_purchase, 5_000);
+    assert_eq!(bid.potential_win, 20 * LAMPORTS_PER_SOL);
+
+    let poll: Poll = fetch(&mut context, market.poll).await;
+    assert_eq!(poll.outcomes[0].odds, MAX_ODDS_BPS);
+    assert_eq!(poll.outcomes[1].odds, MIN_ODDS_BPS);
+
+    let favourite = place_bid(&mut context, &market, &bob, LAMPORTS_PER_SOL, 0).await;
+    let bid: Bid = fetch(&mut context, favourite).await;
+    assert_eq!(bid.odds_at_purchase, MAX_ODDS_BPS);
+    assert_eq!(bid.potential_win, 1_052_631_578);
+
+    let long_shot = place_bid(&mut context, &market, &bob, LAMPORTS_PER_SOL, 1).await;
+    let bid: Bid = fetch(&mut context, long_shot).await;
+    assert_eq!(bid.odds_at_purchase, MIN_ODDS_BPS);
+    assert_eq!(bid.potential_win, 20 * LAMPORTS_PER_SOL);
+
+    // 11 of 12 SOL and 1 of 12, inside the clamp
+    let poll: Poll = fetch(&mut context, market.poll).await;
+    assert_eq!(poll.outcomes[0].odds, 9_166);
+    assert_eq!(poll.outcomes[1].odds, 833);
+}
@@ -0,0 +1,147 @@
+//! Yukti periphery program.
+//!
+//! Product features that never custody funds (promotions, gamification,
+//! referral attribution) live here and compose with the `opinion_trading` core
+//! through CPI. The core keeps escrow, settlement, and claims; shipping changes
+//! in this program does not touch the audited money paths.
+
+use anchor_lang::prelude::*;
+use opinion_trading::program::OpinionTrading;
+
+declare_id!("5yEMM94xATt7aWQ9YZCu1d8ePqCJswBUSc1BYguRB2rq");
+
+#[program]
+pub mod yukti_periphery {
+    use super::*;
+
+    /// Place a bid on the core program through the periphery
+    /// Periphery features hook in around this CPI without touching core state
+    pub fn place_bid(
+        ctx: Context<PlaceBid>,
+        amount: u64,
+        option: u8,
+        timestamp: i64,
+        bid_index: u64,
+        allowlist_proof: Vec<[u8; 32]>,
+        memo: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let cpi_context = CpiContext::new(
+            ctx.accounts.core_program.to_account_info(),
+            opinion_trading::cpi::accounts::PlaceBid {
+                poll: ctx.accounts.poll.to_account_info(),
+                config: ctx.accounts.config.to_account_info(),
+                vault: ctx.accounts.vault.to_account_info(),
+                bid: ctx.accounts.bid.to_account_info(),
+                profile: ctx.accounts.profile.to_account_info(),
+                user_stake: ctx.accounts.user_stake.to_account_info(),
+                position: ctx.accounts.position.to_account_info(),
+                referrer: None,
+                gate_token: None,
+                attestation: None,
+                odds_history: ctx
+                    .accounts
+                    .odds_history
+                    .as_ref()
+                    .map(|odds_history| odds_history.to_account_info()),
+                sol_usd_price: ctx
+                    .accounts
+                    .sol_usd_price
+                    .as_ref()
+                    .map(|sol_usd_price| sol_usd_price.to_account_info()),
+                bettor: ctx.accounts.bettor.to_account_info(),
+                payer: ctx.accounts.bettor.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                #[cfg(feature = "cpi-events")]
+                event_authority: ctx.accounts.core_event_authority.to_account_info(),
+                #[cfg(feature = "cpi-events")]
+                program: ctx.accounts.core_program.to_account_info(),
+            },
+        );
+        opinion_trading::cpi::place_bid(
+            cpi_context,
+            amount,
+            option,
+            timestamp,
+            bid_index,
+            allowlist_proof,
+            memo,
+        )?;
+
+        emit!(BidRouted {
+            poll: ctx.accounts.poll.key(),
+            bid: ctx.accounts.bid.key(),
+            bettor: ctx.accounts.bettor.key(),
+            amount,
+            option,
+        });
+
+        Ok(())
+    }
+}
+
+// =============================================================================
+// ACCOUNT STRUCTS
+// =============================================================================
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    /// CHECK: Validated by the core program
+    pub poll: UncheckedAccount<'info>,
+
+    /// CHECK: Config PDA checked via seeds by the core program
+    pub config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Vault PDA checked via seeds by the core program
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Bid PDA initialized by the core program
+    pub bid: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: UserProfile PDA checked via seeds by the core program
+    pub profile: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: UserStake PDA checked via seeds by the core program
+    pub user_stake: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: UserPollPosition PDA checked via seeds by the core program
+    pub position: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: The poll's OddsHistory, checked by the core program; required
+    /// when the poll keeps one
+    pub odds_history: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Pyth SOL/USD price update, checked by the core program;
+    /// required while the core config sets a USD bid limit
+    pub sol_usd_price: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub core_program: Program<'info, OpinionTrading>,
+
+    /// CHECK: The core's event authority PDA, checked by the core program
+    #[cfg(feature = "cpi-events")]
+    pub core_event_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// =============================================================================
+// EVENTS
+// =============================================================================
+
+#[event]
+pub struct BidRouted {
+    pub poll: Pubkey,
+    pub bid: Pubkey,
+    pub bettor: Pubkey,
+    pub amount: u64,
+    pub option: u8,
+}